@@ -0,0 +1,199 @@
+/// Longest chunk handed to an engine in one call; longer input is split.
+pub const MAX_CHUNK_WORDS: usize = 77;
+
+/// Characters that end a sentence in the languages we voice.
+const SENTENCE_TERMINATORS: &[char] = &['.', '!', '?', ';', '。', '！', '？', '；', '…', '\n'];
+/// Closing marks that stay attached to the sentence they end.
+const SENTENCE_CLOSERS: &[char] = &['"', '\'', ')', '”', '’', '」', '』', '）'];
+
+/// Splits `text` into chunks of at most `max_words` words, cutting between
+/// sentences wherever possible. Scripts written without spaces (Chinese
+/// characters and Japanese kana) count every character as a word; any other
+/// run of letters, including Hangul, counts per whitespace-separated word, so
+/// mixed text keeps the spaces between its Latin or Korean words. Sentences
+/// longer than `max_words` are cut mid-sentence.
+pub fn split_into_chunks(text: &str, max_words: usize) -> Vec<String> {
+    let max_words = max_words.max(1);
+    let mut chunks = Vec::new();
+    let mut current: Vec<Token> = Vec::new();
+    for sentence in split_sentences(tokenize(text)) {
+        for piece in sentence.chunks(max_words) {
+            if !current.is_empty() && current.len() + piece.len() > max_words {
+                chunks.push(render(&current));
+                current.clear();
+            }
+            current.extend_from_slice(piece);
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(render(&current));
+    }
+    chunks
+}
+
+/// One counted word: a single character of a per-character script, or a
+/// whitespace-delimited run of anything else. Punctuation stays attached to
+/// the word before it.
+#[derive(Clone, Debug)]
+struct Token {
+    text: String,
+    /// Whether whitespace separated this token from the previous one.
+    spaced: bool,
+    /// Set when a newline follows, which ends the sentence like a full stop.
+    line_end: bool,
+}
+
+impl Token {
+    fn ends_sentence(&self) -> bool {
+        self.line_end
+            || self
+                .text
+                .trim_end_matches(SENTENCE_CLOSERS)
+                .ends_with(SENTENCE_TERMINATORS)
+    }
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    // Whether the last token may still grow: a word until whitespace, a
+    // per-character token only by the punctuation that follows it.
+    let mut open = false;
+    let mut spaced = false;
+    for ch in text.chars() {
+        if ch.is_whitespace() {
+            if ch == '\n' {
+                if let Some(last) = tokens.last_mut() {
+                    last.line_end = true;
+                }
+            }
+            open = false;
+            spaced = true;
+            continue;
+        }
+        let per_char = counts_per_char(ch);
+        let attach = open
+            && !per_char
+            && tokens.last().is_some_and(|last| {
+                !last.text.chars().next().is_some_and(counts_per_char) || !ch.is_alphanumeric()
+            });
+        match tokens.last_mut() {
+            Some(last) if attach => last.text.push(ch),
+            _ => tokens.push(Token {
+                text: ch.to_string(),
+                spaced: spaced && !tokens.is_empty(),
+                line_end: false,
+            }),
+        }
+        open = true;
+        spaced = false;
+    }
+    tokens
+}
+
+fn split_sentences(tokens: Vec<Token>) -> Vec<Vec<Token>> {
+    let mut sentences = Vec::new();
+    let mut current = Vec::new();
+    for token in tokens {
+        let ends = token.ends_sentence();
+        current.push(token);
+        if ends {
+            sentences.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        sentences.push(current);
+    }
+    sentences
+}
+
+/// Joins tokens with a space wherever the input had whitespace.
+fn render(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    for (index, token) in tokens.iter().enumerate() {
+        if index > 0 && token.spaced {
+            out.push(' ');
+        }
+        out.push_str(&token.text);
+    }
+    out
+}
+
+/// Chinese characters and Japanese kana. Hangul is left out: Korean puts
+/// spaces between words.
+fn counts_per_char(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{3040}'..='\u{30ff}' | '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '\u{f900}'..='\u{faff}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_text_is_one_chunk() {
+        assert_eq!(
+            split_into_chunks("  Hello there. How are you?  ", 77),
+            vec!["Hello there. How are you?"]
+        );
+        assert!(split_into_chunks("   ", 77).is_empty());
+    }
+
+    #[test]
+    fn packs_whole_sentences_per_chunk() {
+        let chunks = split_into_chunks("One two three. Four five! Six seven eight?", 5);
+        assert_eq!(
+            chunks,
+            vec!["One two three. Four five!", "Six seven eight?"]
+        );
+    }
+
+    #[test]
+    fn cuts_overlong_sentences() {
+        let chunks = split_into_chunks("a b c d e f g", 3);
+        assert_eq!(chunks, vec!["a b c", "d e f", "g"]);
+    }
+
+    #[test]
+    fn counts_characters_for_chinese() {
+        let chunks = split_into_chunks("你好。今天天气很好！我们出去玩吧。", 10);
+        assert_eq!(chunks, vec!["你好。今天天气很好！", "我们出去玩吧。"]);
+    }
+
+    #[test]
+    fn keeps_spaces_between_latin_words_in_chinese_text() {
+        assert_eq!(
+            split_into_chunks("我喜欢 Rust programming 语言。", 77),
+            vec!["我喜欢 Rust programming 语言。"]
+        );
+        assert_eq!(
+            split_into_chunks("我喜欢 Rust programming 语言。", 4),
+            vec!["我喜欢 Rust", "programming 语言。"]
+        );
+        assert_eq!(
+            split_into_chunks("今天用GPU跑。Then we rest.", 6),
+            vec!["今天用GPU跑。", "Then we rest."]
+        );
+        assert_eq!(
+            split_into_chunks("你好。Hello world", 2),
+            vec!["你好。", "Hello world"]
+        );
+    }
+
+    #[test]
+    fn counts_hangul_by_word() {
+        assert_eq!(
+            split_into_chunks("안녕하세요 여러분. 오늘 날씨가 좋네요.", 3),
+            vec!["안녕하세요 여러분.", "오늘 날씨가 좋네요."]
+        );
+    }
+
+    #[test]
+    fn does_not_split_inside_numbers() {
+        assert_eq!(
+            split_into_chunks("Pi is 3.14 roughly. Yes", 4),
+            vec!["Pi is 3.14 roughly.", "Yes"]
+        );
+    }
+}
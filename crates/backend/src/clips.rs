@@ -0,0 +1,155 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use parking_lot::Mutex;
+use uuid::Uuid;
+
+#[derive(Clone, Debug)]
+pub struct StoredClip {
+    pub format: &'static str,
+    pub audio: Bytes,
+}
+
+struct Entry {
+    created_at: Instant,
+    clip: StoredClip,
+}
+
+#[derive(Default)]
+struct Entries {
+    clips: HashMap<Uuid, Entry>,
+    /// Request ids oldest first, for expiry and eviction.
+    order: VecDeque<Uuid>,
+    bytes: usize,
+}
+
+impl Entries {
+    fn pop_oldest(&mut self) {
+        if let Some(entry) = self
+            .order
+            .pop_front()
+            .and_then(|request_id| self.clips.remove(&request_id))
+        {
+            self.bytes -= entry.clip.audio.len();
+        }
+    }
+}
+
+/// Audio of recent `/api/tts` responses keyed by their `request_id`, so
+/// `GET /api/tts/:request_id/audio` can serve a clip again without
+/// re-synthesizing it. Clips are dropped `ttl` after they were stored, or
+/// earlier, oldest first, to keep the total under `max_bytes`.
+pub struct ClipStore {
+    ttl: Duration,
+    max_bytes: usize,
+    entries: Mutex<Entries>,
+}
+
+impl ClipStore {
+    pub fn new(ttl: Duration, max_bytes: usize) -> Self {
+        Self {
+            ttl,
+            max_bytes,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Clips larger than `max_bytes` on their own are not kept.
+    pub fn insert(&self, request_id: Uuid, clip: StoredClip) {
+        let now = Instant::now();
+        let size = clip.audio.len();
+        let mut entries = self.entries.lock();
+        if let Some(previous) = entries.clips.remove(&request_id) {
+            entries.bytes -= previous.clip.audio.len();
+            entries.order.retain(|id| *id != request_id);
+        }
+        if size > self.max_bytes {
+            return;
+        }
+        while let Some(oldest) = entries.order.front() {
+            let expired = now.duration_since(entries.clips[oldest].created_at) >= self.ttl;
+            if !expired && entries.bytes + size <= self.max_bytes {
+                break;
+            }
+            entries.pop_oldest();
+        }
+        entries.bytes += size;
+        entries.order.push_back(request_id);
+        entries.clips.insert(
+            request_id,
+            Entry {
+                created_at: now,
+                clip,
+            },
+        );
+    }
+
+    pub fn get(&self, request_id: Uuid) -> Option<StoredClip> {
+        let entries = self.entries.lock();
+        entries
+            .clips
+            .get(&request_id)
+            .filter(|entry| entry.created_at.elapsed() < self.ttl)
+            .map(|entry| entry.clip.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(len: usize) -> StoredClip {
+        StoredClip {
+            format: "audio/wav",
+            audio: Bytes::from(vec![0u8; len]),
+        }
+    }
+
+    #[test]
+    fn returns_stored_clips_by_request_id() {
+        let store = ClipStore::new(Duration::from_secs(60), 1024);
+        let id = Uuid::new_v4();
+        store.insert(id, clip(16));
+        let stored = store.get(id).unwrap();
+        assert_eq!((stored.format, stored.audio.len()), ("audio/wav", 16));
+        assert!(store.get(Uuid::new_v4()).is_none());
+    }
+
+    #[test]
+    fn expired_clips_are_not_served_and_are_pruned() {
+        let store = ClipStore::new(Duration::from_millis(20), 1024);
+        let old = Uuid::new_v4();
+        store.insert(old, clip(16));
+        std::thread::sleep(Duration::from_millis(40));
+        assert!(store.get(old).is_none());
+
+        let fresh = Uuid::new_v4();
+        store.insert(fresh, clip(16));
+        let entries = store.entries.lock();
+        assert!(!entries.clips.contains_key(&old));
+        assert_eq!(entries.bytes, 16);
+    }
+
+    #[test]
+    fn evicts_the_oldest_clips_past_max_bytes() {
+        let store = ClipStore::new(Duration::from_secs(60), 100);
+        let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        for id in &ids {
+            store.insert(*id, clip(40));
+        }
+        assert!(store.get(ids[0]).is_none());
+        assert!(store.get(ids[1]).is_some());
+        assert!(store.get(ids[2]).is_some());
+
+        let oversized = Uuid::new_v4();
+        store.insert(oversized, clip(101));
+        assert!(store.get(oversized).is_none());
+        assert!(store.get(ids[2]).is_some());
+        assert_eq!(store.entries.lock().bytes, 80);
+    }
+}
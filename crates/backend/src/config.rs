@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
@@ -6,7 +7,7 @@ use danmaku::config::DanmakuConfig;
 use danmaku_gateway::config::GatewayConfig as DanmakuGatewayConfig;
 use serde::Deserialize;
 use shimmy::model_registry::ModelEntry;
-use tts_engine::{F5EngineConfig, IndexTtsEngineConfig};
+use tts_engine::{EngineKind, F5EngineConfig, IndexTtsEngineConfig};
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct AppConfig {
@@ -16,6 +17,14 @@ pub struct AppConfig {
     pub api: ApiConfig,
     #[serde(default)]
     pub default_voice: Option<String>,
+    /// Voice used when a request names only an engine. Engines without an
+    /// entry use `default_voice` if it belongs to them, else their first voice.
+    #[serde(default)]
+    pub default_voices: HashMap<EngineKind, String>,
+    /// Engine the UI preselects and requests without `voice_id` or `engine`
+    /// use; unset keeps the voice-first behaviour.
+    #[serde(default)]
+    pub default_engine: Option<EngineKind>,
     pub f5: F5EngineConfig,
     #[serde(default)]
     pub index_tts: Option<IndexTtsEngineConfig>,
@@ -25,18 +34,184 @@ pub struct AppConfig {
     pub danmaku: Option<DanmakuConfig>,
     #[serde(default)]
     pub danmaku_gateway: Option<DanmakuGatewayConfig>,
+    #[serde(default)]
+    pub fallback: FallbackConfig,
+    #[serde(default)]
+    pub voice_overrides: VoiceOverridesConfig,
+    #[serde(default)]
+    pub estimate: EstimateConfig,
+    #[serde(default)]
+    pub chunking: ChunkingConfig,
+}
+
+/// How text longer than one engine call is synthesized: split on sentence
+/// boundaries, rendered chunk by chunk and joined into one clip.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ChunkingConfig {
+    /// Off truncates long input to a single chunk instead.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Chunks rendered per request; text past the last one is dropped.
+    #[serde(default = "default_max_chunks")]
+    pub max_chunks: usize,
+    /// Silence inserted between chunks.
+    #[serde(default = "default_chunk_gap_ms")]
+    pub gap_ms: u32,
+    /// Overlap between chunks when `gap_ms` is 0.
+    #[serde(default = "default_chunk_crossfade_ms")]
+    pub crossfade_ms: u32,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_chunks: default_max_chunks(),
+            gap_ms: default_chunk_gap_ms(),
+            crossfade_ms: default_chunk_crossfade_ms(),
+        }
+    }
+}
+
+/// Speaking-rate model behind `estimate_only` requests to `/api/tts`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct EstimateConfig {
+    /// Characters (excluding whitespace) spoken per second at speed 1.0.
+    #[serde(default = "default_chars_per_sec")]
+    pub chars_per_sec: f32,
+    /// Per-language rates keyed by primary subtag, e.g. `zh` or `en`.
+    #[serde(default)]
+    pub languages: HashMap<String, f32>,
+    /// Per-voice rates; these win over `languages`.
+    #[serde(default)]
+    pub voices: HashMap<String, f32>,
+}
+
+impl Default for EstimateConfig {
+    fn default() -> Self {
+        Self {
+            chars_per_sec: default_chars_per_sec(),
+            languages: HashMap::new(),
+            voices: HashMap::new(),
+        }
+    }
+}
+
+impl EstimateConfig {
+    /// Rate for `voice_id`, falling back to its language and then the default.
+    pub fn chars_per_sec_for(&self, voice_id: &str, language: Option<&str>) -> f32 {
+        let primary = language
+            .and_then(|tag| tag.split(['-', '_']).next())
+            .map(str::to_ascii_lowercase);
+        self.voices
+            .get(voice_id)
+            .or_else(|| primary.and_then(|lang| self.languages.get(&lang)))
+            .copied()
+            .filter(|rate| *rate > 0.0)
+            .unwrap_or(self.chars_per_sec)
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct VoiceOverridesConfig {
+    /// Orphaned override clips younger than this survive the startup cleanup,
+    /// e.g. to allow restoring `overrides.json` from a backup. 0 removes every
+    /// unreferenced clip.
+    #[serde(default)]
+    pub orphan_retention_secs: u64,
+    /// Longest override reference text per engine, in characters. Long
+    /// transcripts degrade cloning; engines not listed accept any length.
+    #[serde(default)]
+    pub max_reference_text_chars: HashMap<EngineKind, usize>,
+    /// What happens to reference text over the limit.
+    #[serde(default)]
+    pub reference_text_overflow: ReferenceTextOverflow,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceTextOverflow {
+    /// Refuse the upload with a 400.
+    #[default]
+    Reject,
+    /// Keep the first `max_reference_text_chars` characters and flag the
+    /// response with `reference_text_truncated`.
+    Truncate,
+}
+
+/// Where to retry when a voice's engine fails mid-synthesis.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct FallbackConfig {
+    /// Explicit voice -> fallback voice pairs; these win over `engine`.
+    #[serde(default)]
+    pub voices: HashMap<String, String>,
+    /// Engine to fall back to for voices without an explicit pair, picking a
+    /// voice with the same language when there is one.
+    #[serde(default)]
+    pub engine: Option<EngineKind>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct ApiConfig {
     #[serde(default = "default_max_parallel")]
     pub max_parallel: usize,
+    /// Shared secret required to open the danmaku websockets, passed as
+    /// `?token=` or as a `Sec-WebSocket-Protocol` entry. Unset means open.
+    #[serde(default)]
+    pub ws_token: Option<String>,
+    /// Exact origins allowed to call `/api` with credentials. Unset allows
+    /// any origin without credentials.
+    #[serde(default)]
+    pub allowed_origins: Option<Vec<String>>,
+    /// Largest `/api/tts` body accepted before replying 413.
+    #[serde(default = "default_max_request_bytes")]
+    pub max_request_bytes: usize,
+    /// Gzip clips on `/api/danmaku/stream` for clients that connect with
+    /// `?compress=gzip`. Off ignores the request and always sends raw audio.
+    #[serde(default = "default_true")]
+    pub ws_compression: bool,
+    /// Gzip/brotli JSON responses under `/api` for clients that send
+    /// `Accept-Encoding`. Audio bodies and the websockets are never touched.
+    #[serde(default = "default_true")]
+    pub http_compression: bool,
+    /// Bearer token for `/api/admin/*`. Unset disables the admin endpoints.
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    /// How long `/api/ready` waits for its probe synthesis before failing.
+    #[serde(default = "default_ready_timeout_secs")]
+    pub ready_timeout_secs: u64,
+    /// How long a `/api/ready` result is reused before probing again.
+    #[serde(default = "default_ready_cache_secs")]
+    pub ready_cache_secs: u64,
+    /// Most `/api/tts` jobs that may be queued or running at once while
+    /// every synthesis slot is busy; further requests get `503`.
+    #[serde(default = "default_max_pending_jobs")]
+    pub max_pending_jobs: usize,
+    /// How long `/api/tts` audio stays downloadable from
+    /// `GET /api/tts/:request_id/audio`. `0` keeps nothing.
+    #[serde(default)]
+    pub clip_retention_secs: u64,
+    /// Most audio bytes kept for `clip_retention_secs`; the oldest clips are
+    /// dropped first once a new one would exceed it.
+    #[serde(default = "default_clip_retention_max_bytes")]
+    pub clip_retention_max_bytes: usize,
 }
 
 impl Default for ApiConfig {
     fn default() -> Self {
         Self {
             max_parallel: default_max_parallel(),
+            ws_token: None,
+            allowed_origins: None,
+            max_request_bytes: default_max_request_bytes(),
+            ws_compression: true,
+            http_compression: true,
+            admin_token: None,
+            ready_timeout_secs: default_ready_timeout_secs(),
+            ready_cache_secs: default_ready_cache_secs(),
+            max_pending_jobs: default_max_pending_jobs(),
+            clip_retention_secs: 0,
+            clip_retention_max_bytes: default_clip_retention_max_bytes(),
         }
     }
 }
@@ -119,10 +294,9 @@ impl ShimmyAdditionalModel {
         }
     }
 
-    fn rebase(&mut self, base: &Path) -> Result<()> {
+    fn rebase(&mut self, base: &Path, missing: &mut Vec<String>) {
         let label = format!("Shimmy model {} base path", self.name);
-        self.base_path = normalize_required(base, &self.base_path, &label)?;
-        Ok(())
+        self.base_path = normalize_required(base, &self.base_path, &label, missing);
     }
 }
 
@@ -134,8 +308,59 @@ fn default_max_parallel() -> usize {
     2
 }
 
+fn default_max_request_bytes() -> usize {
+    64 * 1024
+}
+
+fn default_max_pending_jobs() -> usize {
+    64
+}
+
+fn default_clip_retention_max_bytes() -> usize {
+    256 * 1024 * 1024
+}
+
+fn default_ready_timeout_secs() -> u64 {
+    15
+}
+
+fn default_ready_cache_secs() -> u64 {
+    5
+}
+
+fn default_max_chunks() -> usize {
+    8
+}
+
+fn default_chunk_gap_ms() -> u32 {
+    120
+}
+
+fn default_chunk_crossfade_ms() -> u32 {
+    10
+}
+
+fn default_chars_per_sec() -> f32 {
+    4.5
+}
+
+fn default_true() -> bool {
+    true
+}
+
 impl AppConfig {
     pub fn load(path: PathBuf) -> Result<(Self, PathBuf)> {
+        let (app_cfg, config_dir, missing) = Self::load_collecting(path)?;
+        if !missing.is_empty() {
+            anyhow::bail!("{}", missing.join("; "));
+        }
+        Ok((app_cfg, config_dir))
+    }
+
+    /// Like [`load`](Self::load), but paths that do not exist are returned
+    /// as problems instead of failing, so they can all be reported at once.
+    /// Such paths are left as the absolute path that was looked for.
+    pub fn load_collecting(path: PathBuf) -> Result<(Self, PathBuf, Vec<String>)> {
         let config_dir = path
             .parent()
             .map(|p| p.to_path_buf())
@@ -152,14 +377,24 @@ impl AppConfig {
         let mut app_cfg: AppConfig = cfg
             .try_deserialize()
             .context("failed to deserialize configuration")?;
-        app_cfg.rebase_paths(&config_dir)?;
-        Ok((app_cfg, config_dir))
+        if let Some(ref gateway) = app_cfg.danmaku_gateway {
+            gateway
+                .validate()
+                .context("invalid danmaku_gateway configuration")?;
+        }
+        let mut missing = Vec::new();
+        app_cfg.rebase_paths(&config_dir, &mut missing)?;
+        Ok((app_cfg, config_dir, missing))
     }
 
-    fn rebase_paths(&mut self, base: &Path) -> Result<()> {
+    fn rebase_paths(&mut self, base: &Path, missing: &mut Vec<String>) -> Result<()> {
         // Top-level F5 paths
-        self.f5.python_package_path =
-            normalize_required(base, &self.f5.python_package_path, "F5 python package path")?;
+        self.f5.python_package_path = normalize_required(
+            base,
+            &self.f5.python_package_path,
+            "F5 python package path",
+            missing,
+        );
         if let Some(ref mut ckpt) = self.f5.ckpt_file {
             *ckpt = normalize_optional(base, ckpt)?;
         }
@@ -175,11 +410,13 @@ impl AppConfig {
 
         for profile in &mut self.f5.voices {
             let label = format!("reference audio for voice {}", profile.id);
-            profile.reference_audio = normalize_required(base, &profile.reference_audio, &label)?;
+            for clip in &mut profile.reference_audio {
+                *clip = normalize_required(base, clip, &label, missing);
+            }
         }
 
         for extra in &mut self.shimmy.extra_models {
-            extra.rebase(base)?;
+            extra.rebase(base, missing);
         }
 
         if let Some(ref mut index_cfg) = self.index_tts {
@@ -187,21 +424,33 @@ impl AppConfig {
                 base,
                 &index_cfg.python_package_path,
                 "IndexTTS python package path",
-            )?;
-            index_cfg.config_file =
-                normalize_required(base, &index_cfg.config_file, "IndexTTS config file path")?;
-            index_cfg.model_dir =
-                normalize_required(base, &index_cfg.model_dir, "IndexTTS model directory")?;
+                missing,
+            );
+            index_cfg.config_file = normalize_required(
+                base,
+                &index_cfg.config_file,
+                "IndexTTS config file path",
+                missing,
+            );
+            index_cfg.model_dir = normalize_required(
+                base,
+                &index_cfg.model_dir,
+                "IndexTTS model directory",
+                missing,
+            );
 
             for voice in &mut index_cfg.voices {
                 let label = format!("reference audio for IndexTTS voice {}", voice.id);
-                voice.reference_audio = normalize_required(base, &voice.reference_audio, &label)?;
+                for clip in &mut voice.reference_audio {
+                    *clip = normalize_required(base, clip, &label, missing);
+                }
                 if let Some(ref mut emo_audio) = voice.emo_audio {
                     *emo_audio = normalize_required(
                         base,
                         emo_audio,
                         &format!("emotion audio for IndexTTS voice {}", voice.id),
-                    )?;
+                        missing,
+                    );
                 }
             }
         }
@@ -214,11 +463,20 @@ impl AppConfig {
     }
 }
 
-fn normalize_required(base: &Path, path: &Path, label: &str) -> Result<PathBuf> {
+/// Canonical form of `path`, or the absolute path looked for after
+/// recording it in `missing`.
+fn normalize_required(base: &Path, path: &Path, label: &str, missing: &mut Vec<String>) -> PathBuf {
     let candidate = absolute_path(base, path);
-    candidate
-        .canonicalize()
-        .with_context(|| format!("{label} not found at {}", candidate.display()))
+    match candidate.canonicalize() {
+        Ok(canonical) => canonical,
+        Err(err) => {
+            missing.push(format!(
+                "{label} not found at {} ({err})",
+                candidate.display()
+            ));
+            candidate
+        }
+    }
 }
 
 fn normalize_optional(base: &Path, path: &Path) -> Result<PathBuf> {
@@ -1,39 +1,91 @@
-use std::collections::{HashMap, VecDeque};
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::time::Instant;
 
 use anyhow::{anyhow, bail, Context, Result};
 use async_trait::async_trait;
 use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
 use base64::Engine;
+use flate2::{write::GzEncoder, Compression};
+use futures::{SinkExt, StreamExt};
 use parking_lot::Mutex;
 use rand::{distributions::Alphanumeric, Rng};
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, watch};
 use tokio::{
-    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
-    net::TcpStream,
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, Lines},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpStream,
+    },
     task::JoinHandle,
     time::{sleep, Duration},
 };
-use tracing::{error, info, trace};
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tracing::{debug, error, info, trace, warn};
+use uuid::Uuid;
 
-use danmaku::message::{NormalizedMessage, Platform};
-use danmaku::twitch::{parse_ping, parse_privmsg};
+use danmaku::bilibili::{
+    auth_packet, decode_packets, heartbeat_packet, parse_notification, parse_room_input, Operation,
+};
+use danmaku::message::{NormalizedMessage, Platform, Priority};
+use danmaku::twitch::{
+    parse_channel_event, parse_channel_input, parse_notice, parse_ping, parse_privmsg, RoomState,
+    TwitchChannelEvent,
+};
 use danmaku_gateway::{
-    config::GatewayConfig, filter::FilteredMessage, MessageFilter, MessageQueue,
+    config::{GatewayConfig, TtsConfig},
+    filter::FilteredMessage,
+    ChatAggregator, DropReason, FilterStats, MessageFilter, MessageQueue, PriorityQueue,
 };
-use tts_engine::{EngineKind, TtsRequest};
+use tts_engine::{wrap_wav, EngineKind, Stinger, TtsRequest, VoiceDescriptor};
 
+use crate::session::SessionRecorder;
 use crate::synth::Synthesizer;
 
 const TWITCH_IRC_HOST: &str = "irc.chat.twitch.tv";
 const TWITCH_IRC_PORT: u16 = 6667;
 const SOCKS_PROXY_ENV: &str = "SOCKS5_PROXY";
 const ALL_PROXY_ENV: &str = "ALL_PROXY";
-const DEFAULT_TTS_NFE_STEP: u32 = 16;
+const TWITCH_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Silence tolerated on the IRC socket before we PING Twitch ourselves.
+/// Twitch PINGs roughly every five minutes, so this stays well under that.
+pub const DEFAULT_TWITCH_READ_TIMEOUT: Duration = Duration::from_secs(120);
+/// How long our own PING may go unanswered before the connection is dropped.
+const TWITCH_PONG_TIMEOUT: Duration = Duration::from_secs(15);
+pub const DEFAULT_TWITCH_MAX_RECONNECT_ATTEMPTS: u32 = 10;
+const TWITCH_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// A connection that stayed up this long resets the reconnect budget.
+const TWITCH_STABLE_SESSION: Duration = Duration::from_secs(60);
+const BILIBILI_ROOM_INIT_URL: &str = "https://api.live.bilibili.com/room/v1/Room/room_init";
+const BILIBILI_DANMU_INFO_URL: &str =
+    "https://api.live.bilibili.com/xlive/web-room/v1/index/getDanmuInfo";
+const BILIBILI_DEFAULT_WS_URL: &str = "wss://broadcastlv.chat.bilibili.com/sub";
+const BILIBILI_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+const BILIBILI_HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+// whatlang is shaky on short chat lines; below this keep the channel voice.
+const AUTO_LANGUAGE_MIN_CONFIDENCE: f64 = 0.6;
+/// Username shown on clips injected through [`DanmakuService::say`].
+pub const ANNOUNCEMENT_USERNAME: &str = "主播";
+
+/// `bytes` gzipped, or `None` when compression fails or does not make them
+/// smaller, so clients are only told about gzip when it saved bytes.
+pub fn gzip_if_smaller(bytes: &[u8]) -> Option<Vec<u8>> {
+    use std::io::Write as _;
+
+    let mut encoder = GzEncoder::new(Vec::with_capacity(bytes.len() / 2), Compression::fast());
+    encoder.write_all(bytes).ok()?;
+    encoder
+        .finish()
+        .ok()
+        .filter(|compressed| compressed.len() < bytes.len())
+}
 
 #[derive(Debug, Clone)]
 pub struct PlaybackItem {
+    pub packet_id: Uuid,
+    pub priority: Priority,
     pub platform: Platform,
     pub channel: String,
     pub username: String,
@@ -42,6 +94,78 @@ pub struct PlaybackItem {
     pub sample_rate: u32,
     pub audio: Arc<Vec<u8>>,
     pub color: Option<String>,
+    pub peak: f32,
+    pub rms: f32,
+    /// Injected by the streamer through `/api/danmaku/say`, not from chat.
+    pub announcement: bool,
+    /// `audio` gzipped; see [`gzipped_audio`](Self::gzipped_audio).
+    gzipped: Arc<OnceLock<Option<Vec<u8>>>>,
+}
+
+impl PlaybackItem {
+    /// `audio` gzipped for listeners that asked for compression, or `None`
+    /// when that saves nothing. Compressed once per clip on the blocking pool
+    /// and shared by every clone, however many listeners want it.
+    pub async fn gzipped_audio(&self) -> Option<&[u8]> {
+        if self.gzipped.get().is_none() {
+            let (gzipped, audio) = (self.gzipped.clone(), self.audio.clone());
+            tokio::task::spawn_blocking(move || {
+                gzipped.get_or_init(|| gzip_if_smaller(&audio));
+            })
+            .await
+            .ok();
+        }
+        self.gzipped.get().and_then(Option::as_deref)
+    }
+
+    /// Playback length from the WAV header, whatever its channel count, bit
+    /// depth or extra chunks. Zero if the payload is not a readable WAV.
+    pub fn estimated_duration(&self) -> Duration {
+        match hound::WavReader::new(std::io::Cursor::new(self.audio.as_slice())) {
+            Ok(reader) => Duration::from_secs_f64(
+                f64::from(reader.duration()) / f64::from(reader.spec().sample_rate.max(1)),
+            ),
+            Err(_) => Duration::ZERO,
+        }
+    }
+}
+
+/// Start was refused because `queue.max_channels` watchers are already live.
+#[derive(Debug, thiserror::Error)]
+#[error("已达到最大频道数 ({0})")]
+pub struct ChannelLimitReached(pub usize);
+
+#[derive(Debug, thiserror::Error)]
+#[error("请输入正确的 Twitch 用户名或频道链接")]
+pub struct InvalidChannel;
+
+#[derive(Debug, thiserror::Error)]
+#[error("频道 '{0}' 未在播报中")]
+pub struct ChannelNotActive(pub String);
+
+/// Twitch refused the session in a way reconnecting cannot fix.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+struct FatalTwitchNotice(String);
+
+/// Sent when a watcher gives up on its channel for good.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "type", rename = "channel_failed")]
+pub struct ChannelFailure {
+    pub platform: Platform,
+    pub channel: String,
+    pub reason: String,
+}
+
+/// Tells stream clients to stop the clip playing for `channel`.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "type", rename = "skip")]
+pub struct SkipControl {
+    pub channel: String,
+    /// Clip to stop; `None` stops whatever the client is playing.
+    pub packet_id: Option<Uuid>,
+    /// Whether the clip was still in the replay buffer and got dropped.
+    pub removed: bool,
 }
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
@@ -52,6 +176,20 @@ pub struct StartRequest {
     pub voice_id: Option<String>,
     #[serde(default)]
     pub engine: Option<String>,
+    #[serde(flatten)]
+    pub stingers: StingerOverrides,
+}
+
+/// Per-channel replacements for `[tts] danmaku_prefix_audio` and
+/// `danmaku_suffix_audio`: the name of a WAV file in `[tts]
+/// danmaku_stinger_dir`, or an empty string for none. Omitted fields keep
+/// the configured sound.
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct StingerOverrides {
+    #[serde(default)]
+    pub prefix_audio: Option<String>,
+    #[serde(default)]
+    pub suffix_audio: Option<String>,
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -66,35 +204,315 @@ pub struct StopRequest {
     pub channel: String,
 }
 
+#[derive(Debug, serde::Deserialize)]
+pub struct SayRequest {
+    pub channel: String,
+    pub text: String,
+    /// Defaults to the channel's voice.
+    #[serde(default)]
+    pub voice_id: Option<String>,
+    /// Engine to render `voice_id` on; defaults to the channel's engine when
+    /// it has the voice.
+    #[serde(default)]
+    pub engine: Option<EngineKind>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct SayResponse {
+    pub channel: String,
+    pub packet_id: Uuid,
+}
+
 #[derive(Debug, serde::Serialize)]
 pub struct StopResponse {
     pub status: String,
     pub channel: Option<String>,
 }
 
+#[derive(Debug, serde::Serialize)]
+pub struct DanmakuStatus {
+    pub active_channels: Vec<String>,
+    pub pending_messages: usize,
+    pub in_flight: usize,
+    pub playback_depth: usize,
+    pub playback_capacity: usize,
+    pub playback_dropped: u64,
+    pub listeners: usize,
+    pub draining: bool,
+    pub filter: FilterStats,
+}
+
 #[derive(Clone, Debug)]
 pub struct TwitchAuth {
     pub username: String,
     pub oauth_token: String,
 }
 
+#[derive(Clone, Debug)]
+pub struct BilibiliAuth {
+    pub uid: u64,
+    pub cookie: String,
+}
+
+/// Chat presence for one channel, built from JOIN/PART/ROOMSTATE.
+///
+/// Twitch batches membership events and stops sending them for chats with
+/// more than ~1000 users, so `chatters` is an approximation.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct ChannelPresence {
+    pub channel: String,
+    pub chatters: usize,
+    pub room_state: RoomState,
+}
+
+/// Chatters seen and the latest room state, per channel.
+type ChannelPresenceMap = HashMap<String, (HashSet<String>, RoomState)>;
+
+#[derive(Clone)]
+pub struct PresenceTracker {
+    channels: Arc<Mutex<ChannelPresenceMap>>,
+    notifier: broadcast::Sender<ChannelPresence>,
+}
+
+impl PresenceTracker {
+    fn new(capacity: usize) -> Self {
+        let (notifier, _) = broadcast::channel(capacity);
+        Self {
+            channels: Arc::new(Mutex::new(HashMap::new())),
+            notifier,
+        }
+    }
+
+    pub fn apply(&self, event: TwitchChannelEvent) {
+        let presence = {
+            let mut channels = self.channels.lock();
+            let (channel, entry) = match event {
+                TwitchChannelEvent::Join { channel, user } => {
+                    let entry = channels.entry(channel.clone()).or_default();
+                    if !entry.0.insert(user) {
+                        return;
+                    }
+                    (channel, entry)
+                }
+                TwitchChannelEvent::Part { channel, user } => {
+                    let entry = channels.entry(channel.clone()).or_default();
+                    if !entry.0.remove(&user) {
+                        return;
+                    }
+                    (channel, entry)
+                }
+                TwitchChannelEvent::RoomState { channel, state } => {
+                    let entry = channels.entry(channel.clone()).or_default();
+                    entry.1.merge(&state);
+                    (channel, entry)
+                }
+            };
+            ChannelPresence {
+                channel,
+                chatters: entry.0.len(),
+                room_state: entry.1.clone(),
+            }
+        };
+        let _ = self.notifier.send(presence);
+    }
+
+    fn reset(&self, channel: &str) {
+        self.channels.lock().remove(channel);
+    }
+
+    pub fn snapshot(&self) -> Vec<ChannelPresence> {
+        let mut presence: Vec<ChannelPresence> = self
+            .channels
+            .lock()
+            .iter()
+            .map(|(channel, (chatters, room_state))| ChannelPresence {
+                channel: channel.clone(),
+                chatters: chatters.len(),
+                room_state: room_state.clone(),
+            })
+            .collect();
+        presence.sort_by(|a, b| a.channel.cmp(&b.channel));
+        presence
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ChannelPresence> {
+        self.notifier.subscribe()
+    }
+}
+
+/// A channel's slot under `max_channels`, taken before a start awaits
+/// anything so concurrent starts cannot both pass the limit.
+enum Watcher {
+    Starting,
+    Running(JoinHandle<()>),
+}
+
+impl Watcher {
+    fn is_live(&self) -> bool {
+        match self {
+            Self::Starting => true,
+            Self::Running(handle) => !handle.is_finished(),
+        }
+    }
+}
+
+/// Holds a [`Watcher::Starting`] slot; dropping it before
+/// [`run`](Self::run) frees the slot again.
+struct WatcherReservation<'a> {
+    watchers: &'a Mutex<HashMap<String, Watcher>>,
+    channel: String,
+    armed: bool,
+}
+
+impl WatcherReservation<'_> {
+    /// Fills the slot with the running watcher. Fails, aborting `handle`, if
+    /// the channel was stopped while it was starting.
+    fn run(mut self, handle: JoinHandle<()>) -> Result<()> {
+        self.armed = false;
+        let mut watchers = self.watchers.lock();
+        match watchers.get_mut(&self.channel) {
+            Some(slot @ Watcher::Starting) => {
+                *slot = Watcher::Running(handle);
+                Ok(())
+            }
+            _ => {
+                handle.abort();
+                bail!("频道 '{}' 在启动过程中被停止", self.channel)
+            }
+        }
+    }
+}
+
+impl Drop for WatcherReservation<'_> {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        let mut watchers = self.watchers.lock();
+        if matches!(watchers.get(&self.channel), Some(Watcher::Starting)) {
+            watchers.remove(&self.channel);
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 struct ChannelSettings {
+    platform: Platform,
     voice_id: String,
     engine: EngineKind,
+    stingers: Stingers,
+}
+
+/// Sounds played around each chat readout on a channel.
+#[derive(Clone, Default)]
+struct Stingers {
+    prefix: Option<Arc<Stinger>>,
+    suffix: Option<Arc<Stinger>>,
+}
+
+impl std::fmt::Debug for Stingers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Stingers")
+            .field("prefix", &self.prefix.is_some())
+            .field("suffix", &self.suffix.is_some())
+            .finish()
+    }
+}
+
+impl Stingers {
+    fn from_config(config: &TtsConfig) -> Result<Self> {
+        let load = |path: &Option<PathBuf>| {
+            path.as_deref()
+                .map(|path| Stinger::load(path).map(Arc::new))
+                .transpose()
+        };
+        Ok(Self {
+            prefix: load(&config.danmaku_prefix_audio)?,
+            suffix: load(&config.danmaku_suffix_audio)?,
+        })
+    }
+
+    /// These stingers with `overrides` applied, loading named files from
+    /// `dir`. Reads the files, so async callers should run it on a blocking
+    /// thread.
+    fn with_overrides(&self, overrides: &StingerOverrides, dir: Option<&Path>) -> Result<Self> {
+        let pick = |name: Option<&str>, current: &Option<Arc<Stinger>>| match name.map(str::trim) {
+            None => Ok(current.clone()),
+            Some("") => Ok(None),
+            Some(name) => {
+                let path = stinger_path(dir, name)?;
+                Stinger::load(&path).map(|s| Some(Arc::new(s)))
+            }
+        };
+        Ok(Self {
+            prefix: pick(overrides.prefix_audio.as_deref(), &self.prefix)?,
+            suffix: pick(overrides.suffix_audio.as_deref(), &self.suffix)?,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.prefix.is_none() && self.suffix.is_none()
+    }
+}
+
+/// `name` inside the stinger directory. Only plain file names that stay in
+/// the directory are accepted, so callers cannot read or probe other files.
+fn stinger_path(dir: Option<&Path>, name: &str) -> Result<PathBuf> {
+    let Some(dir) = dir else {
+        bail!("未配置 [tts] danmaku_stinger_dir，只能沿用或关闭默认提示音");
+    };
+    let mut components = Path::new(name).components();
+    if !matches!(
+        (components.next(), components.next()),
+        (Some(std::path::Component::Normal(_)), None)
+    ) {
+        bail!("提示音 '{name}' 必须是 danmaku_stinger_dir 中的文件名");
+    }
+    let dir = dir
+        .canonicalize()
+        .with_context(|| format!("提示音目录 {} 不可用", dir.display()))?;
+    let path = dir
+        .join(name)
+        .canonicalize()
+        .ok()
+        .filter(|path| path.starts_with(&dir))
+        .ok_or_else(|| anyhow!("提示音目录中没有 '{name}'"))?;
+    Ok(path)
 }
 
 #[derive(Clone)]
 pub struct DanmakuService {
     queue: Arc<MessageQueue>,
-    playback: Arc<Mutex<VecDeque<PlaybackItem>>>,
-    watchers: Arc<Mutex<HashMap<String, JoinHandle<()>>>>,
+    playback: Arc<Mutex<PriorityQueue<PlaybackItem>>>,
+    playback_capacity: usize,
+    playback_dropped: Arc<AtomicU64>,
+    min_playback_gap: Duration,
+    max_channels: usize,
+    /// When the most recently broadcast clip should finish playing.
+    playback_busy_until: Arc<Mutex<Option<tokio::time::Instant>>>,
+    watchers: Arc<Mutex<HashMap<String, Watcher>>>,
     synthesizer: Synthesizer,
     default_voice: String,
     twitch_connector: Arc<dyn TwitchConnector>,
     twitch_auth: Option<TwitchAuth>,
+    bilibili_connector: Arc<dyn BilibiliConnector>,
+    bilibili_auth: Option<BilibiliAuth>,
     channel_settings: Arc<Mutex<HashMap<String, ChannelSettings>>>,
     playback_notifier: broadcast::Sender<PlaybackItem>,
+    tts_config: TtsConfig,
+    draining: Arc<AtomicBool>,
+    in_flight: Arc<AtomicUsize>,
+    /// Set by [`drain`](Self::drain); the worker then closes its receiver,
+    /// finishes what is already queued and exits.
+    intake_closed: watch::Sender<bool>,
+    worker: Arc<Mutex<Option<JoinHandle<()>>>>,
+    closing: watch::Sender<bool>,
+    presence: PresenceTracker,
+    failures: broadcast::Sender<ChannelFailure>,
+    skips: broadcast::Sender<SkipControl>,
+    recorder: Arc<SessionRecorder>,
+    /// From `[tts] danmaku_prefix_audio` / `danmaku_suffix_audio`.
+    stingers: Stingers,
 }
 
 impl DanmakuService {
@@ -104,11 +522,15 @@ impl DanmakuService {
         gateway_config: GatewayConfig,
         twitch_auth: Option<TwitchAuth>,
         twitch_connector: Arc<dyn TwitchConnector>,
+        bilibili_auth: Option<BilibiliAuth>,
+        bilibili_connector: Arc<dyn BilibiliConnector>,
     ) -> Result<Arc<Self>> {
+        gateway_config.validate()?;
+        let stingers = Stingers::from_config(&gateway_config.tts)?;
         let filter = MessageFilter::new(gateway_config.filter.clone())?;
         let (queue_inner, mut rx) = MessageQueue::new(filter, gateway_config.queue.clone());
         let queue = Arc::new(queue_inner);
-        let playback = Arc::new(Mutex::new(VecDeque::new()));
+        let playback = Arc::new(Mutex::new(PriorityQueue::new()));
         let watchers = Arc::new(Mutex::new(HashMap::new()));
         let selected_voice = gateway_config
             .tts
@@ -118,33 +540,131 @@ impl DanmakuService {
 
         let notifier_capacity = gateway_config.queue.capacity.max(64);
         let (playback_notifier, _) = broadcast::channel(notifier_capacity);
+        let (closing, _) = watch::channel(false);
+        let (intake_closed, _) = watch::channel(false);
+        let (failures, mut failure_rx) = broadcast::channel(16);
+        let (skips, _) = broadcast::channel(16);
 
         let service = Arc::new(Self {
             queue: queue.clone(),
             playback: playback.clone(),
+            playback_capacity: gateway_config.queue.playback_capacity.max(1),
+            playback_dropped: Arc::new(AtomicU64::new(0)),
+            min_playback_gap: Duration::from_millis(gateway_config.queue.min_playback_gap_ms),
+            max_channels: gateway_config.queue.max_channels,
+            playback_busy_until: Arc::new(Mutex::new(None)),
             watchers,
             synthesizer,
             default_voice: selected_voice,
             twitch_connector,
             twitch_auth,
+            bilibili_connector,
+            bilibili_auth,
             channel_settings: Arc::new(Mutex::new(HashMap::new())),
             playback_notifier,
+            tts_config: gateway_config.tts.clone(),
+            draining: Arc::new(AtomicBool::new(false)),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            intake_closed,
+            worker: Arc::new(Mutex::new(None)),
+            closing,
+            presence: PresenceTracker::new(64),
+            failures,
+            skips,
+            recorder: Arc::new(SessionRecorder::new(&gateway_config.recording)),
+            stingers,
         });
 
-        let worker_service = service.clone();
+        // Watchers that give up leave; forget their channel so it can be
+        // started again.
+        let cleanup_service = service.clone();
         tokio::spawn(async move {
-            while let Some(filtered) = rx.recv().await {
-                if let Err(err) = worker_service.process_filtered(filtered).await {
-                    error!(%err, "failed to process danmaku message");
+            loop {
+                match failure_rx.recv().await {
+                    Ok(failure) => {
+                        cleanup_service.watchers.lock().remove(&failure.channel);
+                        cleanup_service
+                            .channel_settings
+                            .lock()
+                            .remove(&failure.channel);
+                        cleanup_service.presence.reset(&failure.channel);
+                        cleanup_service.recorder.clear(&failure.channel);
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+
+        let worker_service = service.clone();
+        let aggregator = ChatAggregator::from_config(&gateway_config.queue);
+        let mut intake_closed = worker_service.intake_closed.subscribe();
+        let worker = tokio::spawn(async move {
+            loop {
+                // Queued messages are taken before the close signal, so the
+                // loop only ends once the closed channel is empty.
+                let filtered = tokio::select! {
+                    biased;
+                    message = rx.recv() => match message {
+                        Some(message) => message,
+                        None => break,
+                    },
+                    _ = intake_closed.wait_for(|closed| *closed) => {
+                        rx.close();
+                        continue;
+                    }
+                };
+                worker_service.in_flight.fetch_add(1, Ordering::SeqCst);
+                let process = |filtered: FilteredMessage| {
+                    let service = &worker_service;
+                    async move {
+                        if let Err(err) = service.process_filtered(filtered).await {
+                            error!(%err, "failed to process danmaku message");
+                        }
+                    }
+                };
+                // A short message waits out the aggregation window so a
+                // burst of identical ones can be read as a single line;
+                // longer messages arriving meanwhile are read right away.
+                let batch = match &aggregator {
+                    Some(aggregator) if aggregator.is_candidate(&filtered) => {
+                        let batch = aggregator.collect_window(filtered, &mut rx, &process).await;
+                        debug!(
+                            target = "ishowtts::danmaku",
+                            readouts = batch.len(),
+                            "aggregation window closed"
+                        );
+                        batch
+                    }
+                    _ => vec![filtered],
+                };
+                for filtered in batch {
+                    process(filtered).await;
                 }
+                worker_service.in_flight.fetch_sub(1, Ordering::SeqCst);
             }
         });
+        *service.worker.lock() = Some(worker);
 
         Ok(service)
     }
 
+    /// The configured stingers with a channel's `overrides` applied.
+    async fn channel_stingers(&self, overrides: &StingerOverrides) -> Result<Stingers> {
+        if overrides.prefix_audio.is_none() && overrides.suffix_audio.is_none() {
+            return Ok(self.stingers.clone());
+        }
+        let stingers = self.stingers.clone();
+        let overrides = overrides.clone();
+        let dir = self.tts_config.danmaku_stinger_dir.clone();
+        tokio::task::spawn_blocking(move || stingers.with_overrides(&overrides, dir.as_deref()))
+            .await
+            .context("stinger loading task failed")?
+    }
+
     fn resolve_channel_settings(
         &self,
+        platform: Platform,
         voice_id: Option<&str>,
         engine: Option<EngineKind>,
     ) -> Result<ChannelSettings> {
@@ -156,49 +676,80 @@ impl DanmakuService {
             .voice_descriptor(&resolved_voice)
             .ok_or_else(|| anyhow!("音色 '{resolved_voice}' 未配置"))?;
 
-        if let Some(requested_engine) = engine {
-            if descriptor.engine != requested_engine {
-                bail!(
-                    "音色 '{}' 属于引擎 '{}'，与选择的 '{}' 不匹配",
-                    resolved_voice,
-                    descriptor.engine,
-                    requested_engine
-                );
+        // The voice id is authoritative; a stale engine selection in the UI
+        // should not stop the channel unless the config asks for strictness.
+        let descriptor = match engine {
+            Some(requested_engine) if descriptor.engine != requested_engine => {
+                match self
+                    .synthesizer
+                    .resolve_voice(&resolved_voice, Some(requested_engine))
+                {
+                    Some(on_requested) => on_requested,
+                    None if self.tts_config.correct_engine_mismatch => {
+                        warn!(
+                            target = "ishowtts::danmaku",
+                            voice = %resolved_voice,
+                            requested_engine = %requested_engine,
+                            engine = %descriptor.engine,
+                            "voice does not belong to the requested engine; using the voice's engine"
+                        );
+                        descriptor
+                    }
+                    None => bail!(
+                        "音色 '{}' 属于引擎 '{}'，与选择的 '{}' 不匹配",
+                        resolved_voice,
+                        descriptor.engine,
+                        requested_engine
+                    ),
+                }
             }
-        }
+            _ => descriptor,
+        };
 
         Ok(ChannelSettings {
+            platform,
             voice_id: resolved_voice,
             engine: descriptor.engine,
+            stingers: self.stingers.clone(),
         })
     }
 
     pub async fn enqueue(&self, message: &NormalizedMessage) -> Result<bool> {
+        if self.is_draining() {
+            return Ok(false);
+        }
         self.queue.enqueue(message).await
     }
 
+    /// Runs the message through the filter only; nothing is queued or synthesized.
+    pub fn filter_test(&self, message: &NormalizedMessage) -> Result<FilteredMessage, DropReason> {
+        self.queue.filter().filter(message)
+    }
+
     pub async fn start_twitch(
         &self,
         user_input: &str,
         voice_id: Option<String>,
         engine: Option<EngineKind>,
+        stingers: &StingerOverrides,
     ) -> Result<String> {
-        let channel = parse_twitch_channel(user_input)
-            .ok_or_else(|| anyhow!("请输入正确的 Twitch 用户名或频道链接"))?;
-
-        {
-            let mut watchers = self.watchers.lock();
-            if let Some(handle) = watchers.get(&channel) {
-                if !handle.is_finished() {
-                    bail!("该频道已经在播报中");
-                }
-                watchers.remove(&channel);
-            }
+        if self.is_draining() {
+            bail!("服务正在关闭，暂不接受新的频道");
         }
+        let channel = parse_channel_input(user_input).ok_or(InvalidChannel)?;
+        let reservation = self.reserve_watcher(&channel)?;
+
+        let mut settings =
+            self.resolve_channel_settings(Platform::Twitch, voice_id.as_deref(), engine)?;
+        settings.stingers = self.channel_stingers(stingers).await?;
+        self.twitch_connector
+            .probe(&channel, self.twitch_auth.as_ref())
+            .await
+            .map_err(|err| anyhow!("无法加入 Twitch 频道 {channel}: {err}"))?;
 
         self.purge_playback_for_channel(&channel);
+        self.presence.reset(&channel);
 
-        let settings = self.resolve_channel_settings(voice_id.as_deref(), engine)?;
         {
             let mut active = self.channel_settings.lock();
             active.insert(channel.clone(), settings.clone());
@@ -207,7 +758,13 @@ impl DanmakuService {
         let queue = self.queue.clone();
         let handle = match self
             .twitch_connector
-            .spawn(channel.clone(), queue, self.twitch_auth.clone())
+            .spawn(
+                channel.clone(),
+                queue,
+                self.presence.clone(),
+                self.twitch_auth.clone(),
+                self.failures.clone(),
+            )
             .await
             .with_context(|| format!("failed to start twitch watcher for {channel}"))
         {
@@ -218,18 +775,112 @@ impl DanmakuService {
             }
         };
 
-        self.watchers.lock().insert(channel.clone(), handle);
+        if let Err(err) = reservation.run(handle) {
+            self.channel_settings.lock().remove(&channel);
+            return Err(err);
+        }
         Ok(channel)
     }
 
+    /// Joins a Bilibili live room. The channel key is the real room id, so a
+    /// short room number and its long form map to the same watcher.
+    pub async fn start_bilibili(
+        &self,
+        user_input: &str,
+        voice_id: Option<String>,
+        engine: Option<EngineKind>,
+        stingers: &StingerOverrides,
+    ) -> Result<String> {
+        if self.is_draining() {
+            bail!("服务正在关闭，暂不接受新的频道");
+        }
+        let room = parse_room_input(user_input)
+            .ok_or_else(|| anyhow!("请输入正确的 Bilibili 直播间号或链接"))?;
+        let mut settings =
+            self.resolve_channel_settings(Platform::Bilibili, voice_id.as_deref(), engine)?;
+        settings.stingers = self.channel_stingers(stingers).await?;
+        let room_id = self
+            .bilibili_connector
+            .resolve_room(room, self.bilibili_auth.as_ref())
+            .await
+            .map_err(|err| anyhow!("无法进入 Bilibili 直播间 {room}: {err}"))?;
+        let channel = room_id.to_string();
+        let reservation = self.reserve_watcher(&channel)?;
+
+        self.purge_playback_for_channel(&channel);
+        self.channel_settings
+            .lock()
+            .insert(channel.clone(), settings);
+
+        let handle = match self
+            .bilibili_connector
+            .spawn(room_id, self.queue.clone(), self.bilibili_auth.clone())
+            .await
+            .with_context(|| format!("failed to start bilibili watcher for room {room_id}"))
+        {
+            Ok(handle) => handle,
+            Err(err) => {
+                self.channel_settings.lock().remove(&channel);
+                return Err(err);
+            }
+        };
+
+        if let Err(err) = reservation.run(handle) {
+            self.channel_settings.lock().remove(&channel);
+            return Err(err);
+        }
+        Ok(channel)
+    }
+
+    /// Takes `channel`'s watcher slot, checking that it is not already
+    /// watched and that fewer than `max_channels` watchers are starting or
+    /// running ([`ChannelLimitReached`] otherwise), under one lock. Finished
+    /// watchers do not count.
+    fn reserve_watcher(&self, channel: &str) -> Result<WatcherReservation<'_>> {
+        let mut watchers = self.watchers.lock();
+        if let Some(watcher) = watchers.get(channel) {
+            if watcher.is_live() {
+                bail!("该频道已经在播报中");
+            }
+            watchers.remove(channel);
+        }
+        if self.max_channels > 0
+            && watchers
+                .values()
+                .filter(|watcher| watcher.is_live())
+                .count()
+                >= self.max_channels
+        {
+            return Err(ChannelLimitReached(self.max_channels).into());
+        }
+        watchers.insert(channel.to_string(), Watcher::Starting);
+        Ok(WatcherReservation {
+            watchers: &self.watchers,
+            channel: channel.to_string(),
+            armed: true,
+        })
+    }
+
     pub fn stop_twitch(&self, user_input: &str) -> Result<Option<String>> {
-        let channel = parse_twitch_channel(user_input)
-            .ok_or_else(|| anyhow!("请输入正确的 Twitch 用户名或频道链接"))?;
+        let channel = parse_channel_input(user_input).ok_or(InvalidChannel)?;
+        Ok(self.stop_channel(Platform::Twitch, channel))
+    }
 
-        let handle_opt = self.watchers.lock().remove(&channel);
+    /// Stops by room id. Short room numbers are not resolved here, so pass
+    /// the channel returned by [`Self::start_bilibili`].
+    pub fn stop_bilibili(&self, user_input: &str) -> Result<Option<String>> {
+        let room = parse_room_input(user_input)
+            .ok_or_else(|| anyhow!("请输入正确的 Bilibili 直播间号或链接"))?;
+        Ok(self.stop_channel(Platform::Bilibili, room.to_string()))
+    }
+
+    fn stop_channel(&self, platform: Platform, channel: String) -> Option<String> {
+        let watcher = self.watchers.lock().remove(&channel);
         let mut changed = false;
-        if let Some(handle) = handle_opt {
-            handle.abort();
+        if let Some(watcher) = watcher {
+            if let Watcher::Running(handle) = watcher {
+                handle.abort();
+            }
             changed = true;
         }
 
@@ -243,16 +894,19 @@ impl DanmakuService {
         if self.purge_playback_for_channel(&channel) {
             changed = true;
         }
+        self.presence.reset(&channel);
+        self.recorder.clear(&channel);
 
         if changed {
             info!(
                 target = "ishowtts::danmaku",
+                ?platform,
                 %channel,
-                "stopped twitch channel"
+                "stopped danmaku channel"
             );
-            Ok(Some(channel))
+            Some(channel)
         } else {
-            Ok(None)
+            None
         }
     }
 
@@ -278,33 +932,97 @@ impl DanmakuService {
             return Ok(());
         }
 
-        let sanitized = filtered.sanitized_text.clone();
-        let speaker = filtered.source.username.trim();
-        let spoken_text = if speaker.is_empty() {
-            sanitized.clone()
-        } else {
-            format!("{speaker} says: {sanitized}")
+        let spoken_text = self
+            .tts_config
+            .render_spoken_text(&filtered.source.username, &filtered.spoken_text);
+        let voice_id = self.voice_for_text(&channel_settings, &filtered.spoken_text);
+        self.synthesize_to_playback(filtered, &channel_settings, spoken_text, voice_id, false)
+            .await?;
+        Ok(())
+    }
+
+    /// Streamer announcement for an active channel: spoken as written, with
+    /// `voice_id` or the channel's voice, and queued like a chat message.
+    pub async fn say(
+        &self,
+        channel: &str,
+        text: &str,
+        voice_id: Option<&str>,
+        engine: Option<EngineKind>,
+    ) -> Result<Uuid> {
+        let channel = channel.trim().to_ascii_lowercase();
+        let active = self.channel_settings.lock().get(&channel).cloned();
+        let Some(active) = active else {
+            return Err(ChannelNotActive(channel).into());
         };
+        let settings = match voice_id {
+            Some(voice_id) => {
+                // Stay on the channel's engine when it has the voice too.
+                let engine = engine.or_else(|| {
+                    self.synthesizer
+                        .resolve_voice(voice_id, Some(active.engine))
+                        .map(|descriptor| descriptor.engine)
+                });
+                self.resolve_channel_settings(active.platform, Some(voice_id), engine)?
+            }
+            None => active,
+        };
+        let text = text.trim().to_string();
+        let source = NormalizedMessage::new_text(
+            settings.platform.clone(),
+            channel.clone(),
+            None,
+            ANNOUNCEMENT_USERNAME,
+            Priority::Moderator,
+            text.clone(),
+            serde_json::Value::Null,
+        );
+        let filtered = FilteredMessage {
+            source,
+            display_text: text.clone(),
+            spoken_text: text.clone(),
+            priority: Priority::Moderator,
+            accepted_at: chrono::Utc::now(),
+        };
+        let voice_id = settings.voice_id.clone();
+        self.synthesize_to_playback(filtered, &settings, text, voice_id, true)
+            .await?
+            .ok_or_else(|| ChannelNotActive(channel).into())
+    }
 
+    /// Synthesizes `spoken_text` and pushes the clip into the playback queue
+    /// and broadcast. `None` when the channel stopped in the meantime.
+    async fn synthesize_to_playback(
+        &self,
+        filtered: FilteredMessage,
+        channel_settings: &ChannelSettings,
+        spoken_text: String,
+        voice_id: String,
+        announcement: bool,
+    ) -> Result<Option<Uuid>> {
+        let channel = filtered.source.channel.clone();
         let request = TtsRequest {
             text: spoken_text.clone(),
-            voice_id: channel_settings.voice_id.clone(),
-            speed: None,
+            voice_id: voice_id.clone(),
+            speed: self.tts_config.danmaku_speed,
             target_rms: None,
             cross_fade_duration: None,
             sway_sampling_coef: None,
             cfg_strength: None,
-            nfe_step: Some(DEFAULT_TTS_NFE_STEP),
+            nfe_step: self.synthesizer.default_nfe_step(channel_settings.engine),
             fix_duration: None,
             remove_silence: Some(true),
             seed: None,
+            normalize_output_rms: self.tts_config.normalize_output_rms,
+            no_cache: false,
+            segments: None,
         };
 
         info!(
             target = "ishowtts::danmaku",
             %channel,
             user = %filtered.source.username,
-            voice = %channel_settings.voice_id,
+            voice = %voice_id,
             engine = %channel_settings.engine,
             text = %spoken_text,
             "processing danmaku message"
@@ -314,7 +1032,7 @@ impl DanmakuService {
 
         let response = self
             .synthesizer
-            .synthesize(request)
+            .synthesize_notify(request, Some(channel_settings.engine), || {})
             .await
             .with_context(|| "TTS synthesis failed for danmaku message")?;
 
@@ -327,22 +1045,38 @@ impl DanmakuService {
                 %channel,
                 "dropping synthesized audio for inactive channel"
             );
-            return Ok(());
+            return Ok(None);
         }
 
         let sample_rate = response.sample_rate;
+        let (mut peak, mut rms) = (response.peak, response.rms);
         let audio_base64 = response.audio_base64;
-        let audio_vec = BASE64_STANDARD
+        let mut audio_vec = BASE64_STANDARD
             .decode(audio_base64.as_bytes())
             .context("failed to decode synthesized audio from base64")?;
+        // Stingers mark chat readouts; the streamer's own lines go without.
+        let stingers = &channel_settings.stingers;
+        if !announcement && !stingers.is_empty() {
+            let wrapped = wrap_wav(
+                &audio_vec,
+                stingers.prefix.as_deref(),
+                stingers.suffix.as_deref(),
+            )
+            .context("failed to add stingers to danmaku audio")?;
+            audio_vec = wrapped.wav;
+            peak = wrapped.peak;
+            rms = wrapped.rms;
+        }
         let audio_bytes = audio_vec.len();
         let audio_kb = ((audio_bytes as f64) / 1024.0 * 10.0).round() / 10.0;
 
         let item = PlaybackItem {
+            packet_id: Uuid::new_v4(),
+            priority: filtered.priority,
             platform: filtered.source.platform.clone(),
             channel: filtered.source.channel.clone(),
             username: filtered.source.username.clone(),
-            display_text: sanitized,
+            display_text: filtered.display_text,
             format: "audio/wav".into(),
             sample_rate,
             audio: Arc::new(audio_vec),
@@ -352,17 +1086,54 @@ impl DanmakuService {
                 .get("color")
                 .and_then(|v| v.as_str())
                 .map(|s| s.to_string()),
+            peak,
+            rms,
+            announcement,
+            gzipped: Arc::default(),
         };
 
-        let queue_depth = {
+        let delay = self.reserve_playback_slot(item.estimated_duration());
+        if !delay.is_zero() {
+            debug!(
+                target = "ishowtts::danmaku",
+                %channel,
+                delay_ms = delay.as_millis() as u64,
+                "spacing playback after previous clip"
+            );
+            sleep(delay).await;
+            if !self.is_channel_active(&channel) {
+                return Ok(None);
+            }
+        }
+
+        let (queue_depth, evicted) = {
             let mut playback_queue = self.playback.lock();
-            playback_queue.push_back(item.clone());
-            playback_queue.len()
+            let mut evicted = Vec::new();
+            while playback_queue.len() >= self.playback_capacity {
+                match playback_queue.pop_oldest() {
+                    Some(old) => evicted.push(old),
+                    None => break,
+                }
+            }
+            playback_queue.push(item.priority, item.clone());
+            (playback_queue.len(), evicted)
         };
+        for old in &evicted {
+            self.playback_dropped.fetch_add(1, Ordering::Relaxed);
+            warn!(
+                target = "ishowtts::danmaku",
+                channel = %old.channel,
+                user = %old.username,
+                packet_id = %old.packet_id,
+                capacity = self.playback_capacity,
+                "playback buffer full; dropped oldest clip"
+            );
+        }
         info!(
             target = "ishowtts::danmaku",
             %channel,
             user = %filtered.source.username,
+            priority = ?item.priority,
             queue_depth,
             "playback enqueued"
         );
@@ -374,6 +1145,8 @@ impl DanmakuService {
                 "failed to broadcast playback item"
             );
         }
+        self.recorder
+            .record(&channel, item.audio.clone(), item.estimated_duration());
         let elapsed_ms = started_at.elapsed().as_millis();
         info!(
             target = "ishowtts::danmaku",
@@ -389,11 +1162,50 @@ impl DanmakuService {
             engine_label = %engine_label,
             "tts synthesis complete"
         );
-        Ok(())
+        Ok(Some(item.packet_id))
     }
 }
 
 impl DanmakuService {
+    /// Channel voice, or with `auto_language_voice` a same-engine voice
+    /// tagged with the message's detected language.
+    fn voice_for_text(&self, settings: &ChannelSettings, text: &str) -> String {
+        if !self.tts_config.auto_language_voice {
+            return settings.voice_id.clone();
+        }
+        let Some(detected) = detect_language(text) else {
+            return settings.voice_id.clone();
+        };
+        let voices = self.synthesizer.voices();
+        let voice_id = language_voice(&voices, &settings.voice_id, settings.engine, detected);
+        if voice_id != settings.voice_id {
+            debug!(
+                target = "ishowtts::danmaku",
+                language = detected,
+                channel_voice = %settings.voice_id,
+                voice = %voice_id,
+                "switched voice for detected language"
+            );
+        }
+        voice_id.to_string()
+    }
+
+    /// Books the next broadcast slot for a clip of `duration` and returns how
+    /// long to wait for it. Always zero when no gap is configured.
+    fn reserve_playback_slot(&self, duration: Duration) -> Duration {
+        if self.min_playback_gap.is_zero() {
+            return Duration::ZERO;
+        }
+        let now = tokio::time::Instant::now();
+        let mut busy_until = self.playback_busy_until.lock();
+        let start = match *busy_until {
+            Some(until) if until + self.min_playback_gap > now => until + self.min_playback_gap,
+            _ => now,
+        };
+        *busy_until = Some(start + duration);
+        start - now
+    }
+
     fn is_channel_active(&self, channel: &str) -> bool {
         self.channel_settings.lock().contains_key(channel)
     }
@@ -405,10 +1217,140 @@ impl DanmakuService {
         playback.len() != initial_len
     }
 
+    pub fn recorder(&self) -> &SessionRecorder {
+        &self.recorder
+    }
+
+    pub fn presence(&self) -> &PresenceTracker {
+        &self.presence
+    }
+
+    /// Tells connected clients to stop clip `packet_id` of `channel`, or
+    /// whatever they are playing for it, and drops that clip from the replay
+    /// buffer. The server cannot tell which clip a client is on, so only a
+    /// named clip is removed.
+    pub fn skip(&self, channel: &str, packet_id: Option<Uuid>) -> Result<SkipControl> {
+        let channel = channel.trim().trim_start_matches('#').to_ascii_lowercase();
+        if !self.is_channel_active(&channel) {
+            return Err(ChannelNotActive(channel).into());
+        }
+        let removed = packet_id.is_some_and(|packet_id| {
+            remove_skipped(&mut self.playback.lock(), &channel, packet_id)
+        });
+        let control = SkipControl {
+            channel,
+            packet_id,
+            removed,
+        };
+        info!(
+            target = "ishowtts::danmaku",
+            channel = %control.channel,
+            packet_id = ?control.packet_id,
+            "skipping current clip"
+        );
+        let _ = self.skips.send(control.clone());
+        Ok(control)
+    }
+
+    pub fn subscribe_skips(&self) -> broadcast::Receiver<SkipControl> {
+        self.skips.subscribe()
+    }
+
+    pub fn subscribe_failures(&self) -> broadcast::Receiver<ChannelFailure> {
+        self.failures.subscribe()
+    }
+
     pub fn subscribe_playback(&self) -> broadcast::Receiver<PlaybackItem> {
         self.playback_notifier.subscribe()
     }
 
+    /// Resolves to `true` once [`drain`](Self::drain) wants websocket
+    /// listeners to flush what they have and disconnect.
+    pub fn subscribe_closing(&self) -> watch::Receiver<bool> {
+        self.closing.subscribe()
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Stops intake, lets queued messages finish synthesizing and reach the
+    /// websocket listeners, then asks the listeners to close. Gives up once
+    /// `timeout` has elapsed.
+    pub async fn drain(&self, timeout: Duration) {
+        if self.draining.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        let watchers: Vec<_> = self.watchers.lock().drain().collect();
+        for (_, watcher) in &watchers {
+            if let Watcher::Running(handle) = watcher {
+                handle.abort();
+            }
+        }
+        info!(
+            target = "ishowtts::danmaku",
+            channels = watchers.len(),
+            pending = self.queue.pending(),
+            in_flight = self.in_flight.load(Ordering::SeqCst),
+            "draining danmaku playback"
+        );
+
+        // Waiting for the worker itself, rather than polling the counters,
+        // also covers a message it has received but not yet counted.
+        let _ = self.intake_closed.send(true);
+        let worker = self.worker.lock().take();
+        if let Some(worker) = worker {
+            if tokio::time::timeout_at(deadline, worker).await.is_err() {
+                warn!(
+                    target = "ishowtts::danmaku",
+                    pending = self.queue.pending(),
+                    in_flight = self.in_flight.load(Ordering::SeqCst),
+                    "drain timed out before synthesis finished"
+                );
+            }
+        }
+
+        // Listeners flush whatever is buffered in their receiver before
+        // closing; each one drops its receiver on the way out.
+        let _ = self.closing.send(true);
+        while self.playback_notifier.receiver_count() > 0 {
+            if tokio::time::Instant::now() >= deadline {
+                warn!(
+                    target = "ishowtts::danmaku",
+                    listeners = self.playback_notifier.receiver_count(),
+                    "drain timed out waiting for websocket listeners"
+                );
+                break;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+        info!(target = "ishowtts::danmaku", "danmaku playback drained");
+    }
+
+    /// `tts.spoken_template`, or the gateway default when unset.
+    pub fn spoken_template(&self) -> &str {
+        self.tts_config.spoken_template()
+    }
+
+    pub fn status(&self) -> DanmakuStatus {
+        let mut active_channels: Vec<String> =
+            self.channel_settings.lock().keys().cloned().collect();
+        active_channels.sort();
+        DanmakuStatus {
+            active_channels,
+            pending_messages: self.queue.pending(),
+            in_flight: self.in_flight.load(Ordering::SeqCst),
+            playback_depth: self.playback.lock().len(),
+            playback_capacity: self.playback_capacity,
+            playback_dropped: self.playback_dropped.load(Ordering::Relaxed),
+            listeners: self.playback_notifier.receiver_count(),
+            draining: self.is_draining(),
+            filter: self.queue.filter().stats(),
+        }
+    }
+
     pub fn pending_playback(&self) -> Vec<PlaybackItem> {
         self.playback.lock().iter().cloned().collect()
     }
@@ -420,12 +1362,41 @@ pub trait TwitchConnector: Send + Sync {
         &self,
         channel: String,
         queue: Arc<MessageQueue>,
+        presence: PresenceTracker,
         auth: Option<TwitchAuth>,
+        failures: broadcast::Sender<ChannelFailure>,
     ) -> Result<JoinHandle<()>>;
+
+    /// Checks that `channel` can be joined before a watcher is spawned.
+    async fn probe(&self, channel: &str, auth: Option<&TwitchAuth>) -> Result<()>;
 }
 
-#[derive(Default)]
-pub struct RealTwitchConnector;
+pub struct RealTwitchConnector {
+    read_timeout: Duration,
+    max_reconnect_attempts: u32,
+}
+
+impl RealTwitchConnector {
+    pub fn with_read_timeout(read_timeout: Duration) -> Self {
+        Self {
+            read_timeout,
+            max_reconnect_attempts: DEFAULT_TWITCH_MAX_RECONNECT_ATTEMPTS,
+        }
+    }
+
+    /// Consecutive failed reconnects tolerated before the channel is
+    /// dropped; 0 retries forever.
+    pub fn with_max_reconnect_attempts(mut self, attempts: u32) -> Self {
+        self.max_reconnect_attempts = attempts;
+        self
+    }
+}
+
+impl Default for RealTwitchConnector {
+    fn default() -> Self {
+        Self::with_read_timeout(DEFAULT_TWITCH_READ_TIMEOUT)
+    }
+}
 
 #[async_trait]
 impl TwitchConnector for RealTwitchConnector {
@@ -433,45 +1404,291 @@ impl TwitchConnector for RealTwitchConnector {
         &self,
         channel: String,
         queue: Arc<MessageQueue>,
+        presence: PresenceTracker,
         auth: Option<TwitchAuth>,
+        failures: broadcast::Sender<ChannelFailure>,
     ) -> Result<JoinHandle<()>> {
+        let read_timeout = self.read_timeout;
+        let max_attempts = self.max_reconnect_attempts;
         Ok(tokio::spawn(async move {
+            let mut attempts = 0u32;
             loop {
-                if let Err(err) = twitch_loop(channel.clone(), queue.clone(), auth.clone()).await {
-                    error!(%err, "twitch worker error, retrying in 5s");
-                    sleep(Duration::from_secs(5)).await;
-                } else {
+                let started = Instant::now();
+                let Err(err) = twitch_loop(
+                    channel.clone(),
+                    queue.clone(),
+                    presence.clone(),
+                    auth.clone(),
+                    read_timeout,
+                )
+                .await
+                else {
+                    break;
+                };
+
+                let fatal = err.is::<FatalTwitchNotice>();
+                if started.elapsed() >= TWITCH_STABLE_SESSION {
+                    attempts = 0;
+                }
+                attempts += 1;
+                if fatal || (max_attempts > 0 && attempts > max_attempts) {
+                    error!(
+                        target = "ishowtts::danmaku",
+                        %channel,
+                        %err,
+                        attempts,
+                        fatal,
+                        "giving up on twitch channel"
+                    );
+                    let _ = failures.send(ChannelFailure {
+                        platform: Platform::Twitch,
+                        channel: channel.clone(),
+                        reason: err.to_string(),
+                    });
                     break;
                 }
+                error!(%err, attempts, "twitch worker error, retrying in 5s");
+                sleep(TWITCH_RECONNECT_DELAY).await;
             }
         }))
     }
+
+    async fn probe(&self, channel: &str, auth: Option<&TwitchAuth>) -> Result<()> {
+        probe_twitch(channel, auth).await
+    }
 }
 
-async fn twitch_loop(
-    channel: String,
+#[async_trait]
+pub trait BilibiliConnector: Send + Sync {
+    async fn spawn(
+        &self,
+        room_id: u64,
+        queue: Arc<MessageQueue>,
+        auth: Option<BilibiliAuth>,
+    ) -> Result<JoinHandle<()>>;
+
+    /// Maps a (possibly short) room number to the real room id, failing for
+    /// rooms that do not exist.
+    async fn resolve_room(&self, room: u64, auth: Option<&BilibiliAuth>) -> Result<u64>;
+}
+
+#[derive(Default)]
+pub struct RealBilibiliConnector;
+
+#[async_trait]
+impl BilibiliConnector for RealBilibiliConnector {
+    async fn spawn(
+        &self,
+        room_id: u64,
+        queue: Arc<MessageQueue>,
+        auth: Option<BilibiliAuth>,
+    ) -> Result<JoinHandle<()>> {
+        Ok(tokio::spawn(async move {
+            while let Err(err) = bilibili_loop(room_id, queue.clone(), auth.clone()).await {
+                error!(%err, room_id, "bilibili worker error, retrying in 5s");
+                sleep(Duration::from_secs(5)).await;
+            }
+        }))
+    }
+
+    async fn resolve_room(&self, room: u64, auth: Option<&BilibiliAuth>) -> Result<u64> {
+        let client = bilibili_http_client(auth)?;
+        let data = bilibili_api_get(&client, BILIBILI_ROOM_INIT_URL, room).await?;
+        data.get("room_id")
+            .and_then(serde_json::Value::as_u64)
+            .ok_or_else(|| anyhow!("room_init response missing room_id"))
+    }
+}
+
+fn bilibili_http_client(auth: Option<&BilibiliAuth>) -> Result<reqwest::Client> {
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        reqwest::header::REFERER,
+        reqwest::header::HeaderValue::from_static("https://live.bilibili.com/"),
+    );
+    if let Some(auth) = auth {
+        let cookie = reqwest::header::HeaderValue::from_str(&auth.cookie)
+            .context("bilibili cookie contains invalid characters")?;
+        headers.insert(reqwest::header::COOKIE, cookie);
+    }
+    reqwest::Client::builder()
+        .user_agent("Mozilla/5.0 (X11; Linux x86_64) ishowtts")
+        .default_headers(headers)
+        .timeout(BILIBILI_HTTP_TIMEOUT)
+        .build()
+        .context("failed to build bilibili http client")
+}
+
+/// GETs a live API endpoint and returns its `data`, turning a non-zero
+/// `code` into an error carrying Bilibili's message.
+async fn bilibili_api_get(
+    client: &reqwest::Client,
+    url: &str,
+    room: u64,
+) -> Result<serde_json::Value> {
+    let mut body: serde_json::Value = client
+        .get(url)
+        .query(&[("id", room)])
+        .send()
+        .await
+        .with_context(|| format!("request to {url} failed"))?
+        .error_for_status()?
+        .json()
+        .await
+        .with_context(|| format!("invalid response from {url}"))?;
+    let code = body.get("code").and_then(serde_json::Value::as_i64);
+    if code != Some(0) {
+        let message = body
+            .get("message")
+            .or_else(|| body.get("msg"))
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or("unknown error");
+        bail!("{message} (code {})", code.unwrap_or_default());
+    }
+    Ok(body
+        .get_mut("data")
+        .map(serde_json::Value::take)
+        .unwrap_or_default())
+}
+
+async fn bilibili_loop(
+    room_id: u64,
     queue: Arc<MessageQueue>,
-    auth: Option<TwitchAuth>,
+    auth: Option<BilibiliAuth>,
 ) -> Result<()> {
-    info!(%channel, "connecting to twitch chat");
-    let mut stream = connect_twitch_irc(auth.as_ref()).await?;
-
-    let nick = auth
-        .as_ref()
-        .map(|a| a.username.clone())
-        .unwrap_or_else(|| {
-            format!(
-                "justinfan{}",
-                rand::thread_rng()
-                    .sample_iter(&Alphanumeric)
-                    .take(8)
-                    .map(char::from)
-                    .collect::<String>()
-            )
-            .to_lowercase()
-        });
+    info!(room_id, "connecting to bilibili danmaku");
+    let client = bilibili_http_client(auth.as_ref())?;
+    // The token lets the server show real usernames; joining without one
+    // still works, just anonymised.
+    let (url, key) = match bilibili_api_get(&client, BILIBILI_DANMU_INFO_URL, room_id).await {
+        Ok(info) => {
+            let url = info["host_list"]
+                .get(0)
+                .and_then(|host| {
+                    let name = host.get("host")?.as_str()?;
+                    let port = host.get("wss_port")?.as_u64()?;
+                    Some(format!("wss://{name}:{port}/sub"))
+                })
+                .unwrap_or_else(|| BILIBILI_DEFAULT_WS_URL.to_string());
+            let key = info["token"].as_str().map(str::to_string);
+            (url, key)
+        }
+        Err(err) => {
+            warn!(
+                target = "ishowtts::danmaku",
+                room_id,
+                %err,
+                "getDanmuInfo failed; joining without a token"
+            );
+            (BILIBILI_DEFAULT_WS_URL.to_string(), None)
+        }
+    };
+    let buvid = auth.as_ref().and_then(|auth| {
+        auth.cookie
+            .split(';')
+            .filter_map(|pair| pair.trim().split_once('='))
+            .find(|(name, _)| *name == "buvid3")
+            .map(|(_, value)| value.to_string())
+    });
+
+    let (socket, _) = tokio_tungstenite::connect_async(url.as_str())
+        .await
+        .with_context(|| format!("failed to connect to {url}"))?;
+    let (mut sink, mut stream) = socket.split();
+    sink.send(WsMessage::Binary(auth_packet(
+        room_id,
+        auth.as_ref().map_or(0, |auth| auth.uid),
+        key.as_deref(),
+        buvid.as_deref(),
+    )))
+    .await
+    .context("bilibili auth send failed")?;
+
+    let mut heartbeat = tokio::time::interval(BILIBILI_HEARTBEAT_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = heartbeat.tick() => {
+                sink.send(WsMessage::Binary(heartbeat_packet()))
+                    .await
+                    .context("bilibili heartbeat send failed")?;
+            }
+            message = stream.next() => match message {
+                Some(Ok(WsMessage::Binary(data))) => {
+                    for packet in decode_packets(&data)? {
+                        match packet.operation {
+                            Operation::AuthReply => {
+                                let code = packet.json()?.get("code").and_then(|code| code.as_i64());
+                                if code != Some(0) {
+                                    bail!("bilibili rejected auth for room {room_id} (code {code:?})");
+                                }
+                                info!(target = "ishowtts::danmaku", room_id, "joined bilibili danmaku stream");
+                            }
+                            Operation::Notification => {
+                                let body = match packet.json() {
+                                    Ok(body) => body,
+                                    Err(err) => {
+                                        trace!(target = "ishowtts::danmaku", %err, "unparsed bilibili packet");
+                                        continue;
+                                    }
+                                };
+                                let Some(chat) = parse_notification(room_id, &body) else {
+                                    continue;
+                                };
+                                let normalized = chat.to_normalized();
+                                trace!(
+                                    target = "ishowtts::danmaku",
+                                    room_id,
+                                    user = %normalized.username,
+                                    text = %chat.message,
+                                    "received bilibili chat"
+                                );
+                                if !queue.enqueue(&normalized).await.unwrap_or(false) {
+                                    trace!(
+                                        target = "ishowtts::danmaku",
+                                        room_id,
+                                        user = %normalized.username,
+                                        "message dropped by queue"
+                                    );
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Some(Ok(WsMessage::Close(_))) | None => {
+                    info!(target = "ishowtts::danmaku", room_id, "bilibili closed connection");
+                    return Err(anyhow!("bilibili danmaku stream ended unexpectedly"));
+                }
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(anyhow!("error reading from bilibili: {err}")),
+            }
+        }
+    }
+}
 
-    let pass_line = auth.as_ref().map_or_else(
+struct TwitchSession {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+    /// Login we joined as, so our own JOIN/PART can be ignored.
+    identity: String,
+}
+
+async fn open_twitch_session(channel: &str, auth: Option<&TwitchAuth>) -> Result<TwitchSession> {
+    let mut stream = connect_twitch_irc(auth).await?;
+
+    let nick = auth.map(|a| a.username.clone()).unwrap_or_else(|| {
+        format!(
+            "justinfan{}",
+            rand::thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(8)
+                .map(char::from)
+                .collect::<String>()
+        )
+        .to_lowercase()
+    });
+
+    let pass_line = auth.map_or_else(
         || "PASS SCHMOOPIIE\r\n".to_string(),
         |auth| {
             let token = if auth.oauth_token.starts_with("oauth:") {
@@ -483,7 +1700,6 @@ async fn twitch_loop(
         },
     );
     let user_identity = auth
-        .as_ref()
         .map(|auth| auth.username.as_str())
         .unwrap_or_else(|| nick.as_str());
     let nick_line = format!("NICK {}\r\n", user_identity);
@@ -510,12 +1726,110 @@ async fn twitch_loop(
         .await
         .context("twitch JOIN send failed")?;
 
-    let (reader, mut writer) = stream.into_split();
-    let mut lines = BufReader::new(reader).lines();
+    let (reader, writer) = stream.into_split();
+    Ok(TwitchSession {
+        lines: BufReader::new(reader).lines(),
+        writer,
+        identity: user_identity.to_string(),
+    })
+}
+
+/// Joins `channel` and waits for Twitch to confirm it with `ROOMSTATE`, or
+/// reject it with a `NOTICE`, so bad channels and credentials fail up front.
+async fn probe_twitch(channel: &str, auth: Option<&TwitchAuth>) -> Result<()> {
+    let mut session = open_twitch_session(channel, auth).await?;
+    let result = tokio::time::timeout(
+        TWITCH_PROBE_TIMEOUT,
+        await_join_confirmation(&mut session, channel),
+    )
+    .await
+    .unwrap_or_else(|_| Err(anyhow!("等待 Twitch 确认超时")));
+    session
+        .writer
+        .write_all(format!("PART #{channel}\r\n").as_bytes())
+        .await
+        .ok();
+    result
+}
+
+async fn await_join_confirmation(session: &mut TwitchSession, channel: &str) -> Result<()> {
+    loop {
+        let line = session
+            .lines
+            .next_line()
+            .await?
+            .ok_or_else(|| anyhow!("Twitch 在确认前关闭了连接"))?;
+        if let Some(token) = parse_ping(&line) {
+            session
+                .writer
+                .write_all(format!("PONG :{}\r\n", token).as_bytes())
+                .await?;
+            continue;
+        }
+        if let Ok(Some(notice)) = parse_notice(&line) {
+            if notice.channel.is_none() || notice.channel.as_deref() == Some(channel) {
+                bail!("{}", notice.message);
+            }
+        }
+        if let Ok(Some(TwitchChannelEvent::RoomState {
+            channel: joined, ..
+        })) = parse_channel_event(&line)
+        {
+            if joined == channel {
+                return Ok(());
+            }
+        }
+    }
+}
+
+async fn twitch_loop(
+    channel: String,
+    queue: Arc<MessageQueue>,
+    presence: PresenceTracker,
+    auth: Option<TwitchAuth>,
+    read_timeout: Duration,
+) -> Result<()> {
+    info!(%channel, "connecting to twitch chat");
+    let TwitchSession {
+        mut lines,
+        mut writer,
+        identity,
+    } = open_twitch_session(&channel, auth.as_ref()).await?;
     info!(target = "ishowtts::danmaku", "joined twitch chat stream");
 
+    // A NAT that silently drops the connection leaves `next_line` pending
+    // forever, so quiet periods are probed with our own PING.
+    let mut awaiting_pong = false;
     loop {
-        match lines.next_line().await {
+        let wait = if awaiting_pong {
+            TWITCH_PONG_TIMEOUT
+        } else {
+            read_timeout
+        };
+        let Ok(next) = tokio::time::timeout(wait, lines.next_line()).await else {
+            if awaiting_pong {
+                return Err(anyhow!(
+                    "twitch IRC did not answer PING within {}s",
+                    TWITCH_PONG_TIMEOUT.as_secs()
+                ));
+            }
+            debug!(
+                target = "ishowtts::danmaku",
+                %channel,
+                idle_secs = read_timeout.as_secs(),
+                "twitch IRC idle; sending PING"
+            );
+            writer
+                .write_all(b"PING :tmi.twitch.tv\r\n")
+                .await
+                .map_err(|err| anyhow!("failed to send PING: {err}"))?;
+            awaiting_pong = true;
+            continue;
+        };
+        // Any line, PONG or otherwise, shows the connection is alive.
+        awaiting_pong = false;
+
+        match next {
             Ok(Some(line)) => {
                 trace!(target = "ishowtts::danmaku", %line, "twitch irc line");
                 if let Some(token) = parse_ping(&line) {
@@ -527,6 +1841,13 @@ async fn twitch_loop(
                     }
                     continue;
                 }
+                if let Ok(Some(notice)) = parse_notice(&line) {
+                    let ours =
+                        notice.channel.is_none() || notice.channel.as_deref() == Some(&channel);
+                    if ours && notice.is_fatal() {
+                        return Err(FatalTwitchNotice(notice.message).into());
+                    }
+                }
 
                 match parse_privmsg(&line) {
                     Ok(Some(chat)) => {
@@ -547,7 +1868,16 @@ async fn twitch_loop(
                             );
                         }
                     }
-                    Ok(None) => {}
+                    Ok(None) => match parse_channel_event(&line) {
+                        Ok(Some(TwitchChannelEvent::Join { ref user, .. }))
+                        | Ok(Some(TwitchChannelEvent::Part { ref user, .. }))
+                            if user.eq_ignore_ascii_case(&identity) => {}
+                        Ok(Some(event)) => presence.apply(event),
+                        Ok(None) => {}
+                        Err(err) => {
+                            trace!(target = "ishowtts::danmaku", %err, "unparsed twitch event");
+                        }
+                    },
                     Err(err) => {
                         error!(%err, "failed to parse twitch message");
                     }
@@ -564,26 +1894,54 @@ async fn twitch_loop(
     }
 }
 
-fn parse_twitch_channel(input: &str) -> Option<String> {
-    let trimmed = input.trim();
-    if trimmed.is_empty() {
-        return None;
-    }
-    let lower = trimmed.to_lowercase();
-    let after = if let Some(idx) = lower.find("twitch.tv/") {
-        let rest = &trimmed[idx + "twitch.tv/".len()..];
-        rest.split(|c: char| c == '/' || c == '?' || c == '&')
-            .next()
-            .unwrap_or("")
-    } else {
-        trimmed
+/// `voice_id` when it speaks `language`, else the first voice on `engine`
+/// that does, else `voice_id` anyway.
+fn language_voice<'a>(
+    voices: &'a [VoiceDescriptor],
+    voice_id: &'a str,
+    engine: EngineKind,
+    language: &str,
+) -> &'a str {
+    let speaks = |voice: &VoiceDescriptor| {
+        voice
+            .language
+            .as_deref()
+            .and_then(|tag| tag.split(['-', '_']).next())
+            .map(|primary| primary.eq_ignore_ascii_case(language))
+            .unwrap_or(false)
     };
-    let channel = after.trim_matches('/');
-    if channel.is_empty() {
-        None
-    } else {
-        Some(channel.to_lowercase())
+    if voices
+        .iter()
+        .any(|voice| voice.id == voice_id && speaks(voice))
+    {
+        return voice_id;
     }
+    voices
+        .iter()
+        .find(|voice| voice.engine == engine && speaks(voice))
+        .map_or(voice_id, |voice| voice.id.as_str())
+}
+
+/// ISO 639-1 code of `text`'s language, if detection is confident enough.
+fn detect_language(text: &str) -> Option<&'static str> {
+    let info = whatlang::detect(text)?;
+    if !info.is_reliable() || info.confidence() < AUTO_LANGUAGE_MIN_CONFIDENCE {
+        return None;
+    }
+    use whatlang::Lang;
+    Some(match info.lang() {
+        Lang::Cmn => "zh",
+        Lang::Eng => "en",
+        Lang::Jpn => "ja",
+        Lang::Kor => "ko",
+        Lang::Spa => "es",
+        Lang::Fra => "fr",
+        Lang::Deu => "de",
+        Lang::Rus => "ru",
+        Lang::Por => "pt",
+        Lang::Ita => "it",
+        _ => return None,
+    })
 }
 
 async fn connect_twitch_irc(auth: Option<&TwitchAuth>) -> Result<TcpStream> {
@@ -705,3 +2063,308 @@ async fn connect_via_socks(
 
     Ok(stream)
 }
+
+/// Drops clip `packet_id` of `channel` from the replay buffer so clients
+/// that connect later do not play it again.
+fn remove_skipped(
+    playback: &mut PriorityQueue<PlaybackItem>,
+    channel: &str,
+    packet_id: Uuid,
+) -> bool {
+    playback
+        .remove_first(|item| item.channel == channel && item.packet_id == packet_id)
+        .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn voice(id: &str, engine: EngineKind, language: Option<&str>) -> VoiceDescriptor {
+        VoiceDescriptor {
+            id: id.to_string(),
+            engine,
+            engine_label: engine.as_str().to_string(),
+            language: language.map(str::to_string),
+            reference_text: None,
+        }
+    }
+
+    fn playback_item(audio: Vec<u8>, sample_rate: u32) -> PlaybackItem {
+        PlaybackItem {
+            packet_id: Uuid::new_v4(),
+            priority: Priority::Normal,
+            platform: Platform::Twitch,
+            channel: "chan".into(),
+            username: "alice".into(),
+            display_text: "hi".into(),
+            format: "audio/wav".into(),
+            sample_rate,
+            audio: Arc::new(audio),
+            color: None,
+            peak: 0.0,
+            rms: 0.0,
+            announcement: false,
+            gzipped: Arc::default(),
+        }
+    }
+
+    fn wav(channels: u16, bits_per_sample: u16, sample_rate: u32, frames: u32) -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+        for _ in 0..frames * u32::from(channels) {
+            writer.write_sample(0i32).unwrap();
+        }
+        writer.finalize().unwrap();
+        cursor.into_inner()
+    }
+
+    #[test]
+    fn estimated_duration_reads_the_wav_spec() {
+        let mono = playback_item(wav(1, 16, 24_000, 12_000), 24_000);
+        assert_eq!(mono.estimated_duration(), Duration::from_millis(500));
+        let stereo = playback_item(wav(2, 16, 24_000, 24_000), 24_000);
+        assert_eq!(stereo.estimated_duration(), Duration::from_secs(1));
+        let deep = playback_item(wav(1, 24, 48_000, 12_000), 48_000);
+        assert_eq!(deep.estimated_duration(), Duration::from_millis(250));
+        let garbage = playback_item(vec![0; 100], 24_000);
+        assert_eq!(garbage.estimated_duration(), Duration::ZERO);
+    }
+
+    #[test]
+    fn detects_cjk_latin_and_mixed_text() {
+        assert_eq!(detect_language("今天的直播真好看，大家晚上好"), Some("zh"));
+        assert_eq!(
+            detect_language("what a great stream today, thanks for the fun games everyone"),
+            Some("en")
+        );
+        // The dominant script decides a mixed message.
+        assert_eq!(
+            detect_language("主播今天玩的这个游戏太好玩了 GG"),
+            Some("zh")
+        );
+        assert_eq!(detect_language(""), None);
+        assert_eq!(detect_language("12345 !!!"), None);
+    }
+
+    #[test]
+    fn language_voice_prefers_channel_voice_then_same_engine() {
+        let voices = [
+            voice("walter", EngineKind::F5, Some("en-US")),
+            voice("xiaoming", EngineKind::F5, Some("zh_CN")),
+            voice("index-zh", EngineKind::IndexTts, Some("zh")),
+        ];
+        assert_eq!(
+            language_voice(&voices, "walter", EngineKind::F5, "en"),
+            "walter"
+        );
+        assert_eq!(
+            language_voice(&voices, "walter", EngineKind::F5, "zh"),
+            "xiaoming"
+        );
+        assert_eq!(
+            language_voice(&voices, "index-en", EngineKind::IndexTts, "zh"),
+            "index-zh"
+        );
+        // No voice on the channel's engine speaks Japanese.
+        assert_eq!(
+            language_voice(&voices, "walter", EngineKind::F5, "ja"),
+            "walter"
+        );
+    }
+
+    #[test]
+    fn skip_removes_only_the_named_clip() {
+        let mut playback = PriorityQueue::new();
+        let played = playback_item(wav(1, 16, 16_000, 100), 16_000);
+        let current = playback_item(wav(1, 16, 16_000, 100), 16_000);
+        let current_id = current.packet_id;
+        let played_id = played.packet_id;
+        playback.push(Priority::Normal, played);
+        playback.push(Priority::Normal, current);
+
+        assert!(!remove_skipped(&mut playback, "other", current_id));
+        assert!(!remove_skipped(&mut playback, "chan", Uuid::new_v4()));
+        assert!(remove_skipped(&mut playback, "chan", current_id));
+        assert!(!remove_skipped(&mut playback, "chan", current_id));
+        assert_eq!(playback.pop().map(|item| item.packet_id), Some(played_id));
+        assert!(playback.pop().is_none());
+    }
+
+    #[test]
+    fn stinger_overrides_stay_inside_the_stinger_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("chime.wav"), wav(1, 16, 8_000, 80)).unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret.wav"), wav(1, 16, 8_000, 80)).unwrap();
+
+        let named = |prefix: &str| StingerOverrides {
+            prefix_audio: Some(prefix.to_string()),
+            suffix_audio: Some(String::new()),
+        };
+        let base = Stingers::default();
+        let stingers = base
+            .with_overrides(&named("chime.wav"), Some(dir.path()))
+            .unwrap();
+        assert!(stingers.prefix.is_some() && stingers.suffix.is_none());
+
+        let secret = outside.path().join("secret.wav");
+        for name in [
+            secret.to_str().unwrap(),
+            "../secret.wav",
+            "sub/chime.wav",
+            "missing.wav",
+        ] {
+            assert!(base.with_overrides(&named(name), Some(dir.path())).is_err());
+        }
+        assert!(base.with_overrides(&named("chime.wav"), None).is_err());
+        assert!(base
+            .with_overrides(&StingerOverrides::default(), None)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn gzip_is_used_only_when_smaller() {
+        use std::io::Read as _;
+
+        let silence = wav(1, 16, 16_000, 16_000);
+        let compressed = gzip_if_smaller(&silence).unwrap();
+        assert!(compressed.len() < silence.len());
+        // The same decoder the web client uses.
+        let mut inflated = Vec::new();
+        flate2::read::GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut inflated)
+            .unwrap();
+        assert_eq!(inflated, silence);
+
+        let mut noise = vec![0u8; 4_096];
+        rand::thread_rng().fill(noise.as_mut_slice());
+        assert!(gzip_if_smaller(&noise).is_none());
+    }
+
+    #[tokio::test]
+    async fn clones_share_the_gzipped_audio() {
+        let item = playback_item(wav(1, 16, 16_000, 16_000), 16_000);
+        let clone = item.clone();
+        let first = item.gzipped_audio().await.unwrap();
+        let second = clone.gzipped_audio().await.unwrap();
+        assert!(std::ptr::eq(first, second));
+    }
+
+    struct IdleTwitch;
+
+    #[async_trait]
+    impl TwitchConnector for IdleTwitch {
+        async fn spawn(
+            &self,
+            _channel: String,
+            _queue: Arc<MessageQueue>,
+            _presence: PresenceTracker,
+            _auth: Option<TwitchAuth>,
+            _failures: broadcast::Sender<ChannelFailure>,
+        ) -> Result<JoinHandle<()>> {
+            Ok(tokio::spawn(std::future::pending()))
+        }
+
+        async fn probe(&self, _channel: &str, _auth: Option<&TwitchAuth>) -> Result<()> {
+            // Like a real join check, let other starts run meanwhile.
+            tokio::task::yield_now().await;
+            Ok(())
+        }
+    }
+
+    struct IdleBilibili;
+
+    #[async_trait]
+    impl BilibiliConnector for IdleBilibili {
+        async fn spawn(
+            &self,
+            _room_id: u64,
+            _queue: Arc<MessageQueue>,
+            _auth: Option<BilibiliAuth>,
+        ) -> Result<JoinHandle<()>> {
+            Ok(tokio::spawn(std::future::pending()))
+        }
+
+        async fn resolve_room(&self, room: u64, _auth: Option<&BilibiliAuth>) -> Result<u64> {
+            Ok(room)
+        }
+    }
+
+    fn service(config: GatewayConfig) -> Arc<DanmakuService> {
+        let engine = tts_engine::MockEngine::new(
+            EngineKind::F5,
+            vec![(voice("walter", EngineKind::F5, None), Vec::new())],
+        );
+        let synthesizer = Synthesizer::new(vec![Arc::new(engine)], 1).unwrap();
+        DanmakuService::new(
+            synthesizer,
+            "walter".to_string(),
+            config,
+            None,
+            Arc::new(IdleTwitch),
+            None,
+            Arc::new(IdleBilibili),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn stopping_a_channel_clears_its_recording() {
+        let mut config = GatewayConfig::default();
+        config.recording.record_session = true;
+        let service = service(config);
+        for channel in ["chan", "other"] {
+            service
+                .start_twitch(channel, None, None, &StingerOverrides::default())
+                .await
+                .unwrap();
+            service.recorder().record(
+                channel,
+                Arc::new(wav(1, 16, 16_000, 1_600)),
+                Duration::from_millis(100),
+            );
+        }
+
+        assert_eq!(
+            service.stop_twitch("chan").unwrap().as_deref(),
+            Some("chan")
+        );
+        assert!(service.recorder().export("chan").unwrap().is_none());
+        assert!(service.recorder().export("other").unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn concurrent_starts_respect_max_channels() {
+        let mut config = GatewayConfig::default();
+        config.queue.max_channels = 1;
+        let service = service(config);
+        let stingers = StingerOverrides::default();
+        let (first, second) = tokio::join!(
+            service.start_twitch("first", None, None, &stingers),
+            service.start_twitch("second", None, None, &stingers),
+        );
+        let refused = [first, second]
+            .into_iter()
+            .filter_map(Result::err)
+            .collect::<Vec<_>>();
+        assert_eq!(refused.len(), 1);
+        assert!(refused[0].downcast_ref::<ChannelLimitReached>().is_some());
+        assert_eq!(service.status().active_channels.len(), 1);
+
+        // A refused or stopped start frees its slot.
+        let active = service.status().active_channels[0].clone();
+        service.stop_twitch(&active).unwrap();
+        service
+            .start_twitch("third", None, None, &stingers)
+            .await
+            .unwrap();
+    }
+}
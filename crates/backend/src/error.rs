@@ -0,0 +1,130 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+use crate::i18n::{self, Locale};
+
+/// Error returned by the HTTP handlers, serialized as
+/// `{ "code": "...", "message": "..." }` so clients can branch on `code`
+/// instead of matching message text. Codes with a translation table entry are
+/// rendered in the request's `Accept-Language`; others keep `message`.
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+    /// Values for the `{name}` placeholders of the code's message template.
+    pub args: Vec<(&'static str, String)>,
+}
+
+#[derive(Serialize)]
+struct ApiErrorBody<'a> {
+    code: &'a str,
+    message: &'a str,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Adds a value for the `{name}` placeholder of the localized message.
+    pub fn with_arg(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.args.push((name, value.to_string()));
+        self
+    }
+
+    pub fn voice_not_found(status: StatusCode, voice_id: &str) -> Self {
+        Self::new(status, "voice_not_found", format!("未知音色 '{voice_id}'"))
+            .with_arg("voice_id", voice_id)
+    }
+
+    pub fn engine_mismatch(voice_id: &str, voice_engine: &str, engine: &str) -> Self {
+        Self::new(
+            StatusCode::BAD_REQUEST,
+            "engine_mismatch",
+            format!("音色 '{voice_id}' 属于引擎 '{voice_engine}'，而不是 '{engine}'"),
+        )
+        .with_arg("voice_id", voice_id)
+        .with_arg("voice_engine", voice_engine)
+        .with_arg("engine", engine)
+    }
+
+    pub fn text_empty() -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "text_empty", "文本不能为空")
+    }
+
+    pub fn reference_text_required() -> Self {
+        Self::new(
+            StatusCode::BAD_REQUEST,
+            "reference_text_required",
+            "该引擎的音色必须提供参考文本",
+        )
+    }
+
+    pub fn no_voices() -> Self {
+        Self::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "no_voices",
+            "没有可用的音色：所有引擎都未能加载",
+        )
+    }
+
+    pub fn danmaku_disabled() -> Self {
+        Self::new(
+            StatusCode::NOT_IMPLEMENTED,
+            "danmaku_disabled",
+            "弹幕播报未启用",
+        )
+    }
+}
+
+/// Fallback code for errors that have not been given a specific one yet.
+fn code_for_status(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::BAD_REQUEST => "bad_request",
+        StatusCode::NOT_FOUND => "not_found",
+        StatusCode::CONFLICT => "conflict",
+        StatusCode::NOT_ACCEPTABLE => "not_acceptable",
+        StatusCode::PAYLOAD_TOO_LARGE => "payload_too_large",
+        StatusCode::NOT_IMPLEMENTED => "not_implemented",
+        StatusCode::BAD_GATEWAY => "upstream_error",
+        StatusCode::SERVICE_UNAVAILABLE => "unavailable",
+        _ => "internal_error",
+    }
+}
+
+impl From<(StatusCode, String)> for ApiError {
+    fn from((status, message): (StatusCode, String)) -> Self {
+        Self::new(status, code_for_status(status), message)
+    }
+}
+
+impl ApiError {
+    /// The response body in `locale`. The error itself rides along in the
+    /// response extensions so `localize_errors` can re-render it.
+    pub fn localized_response(&self, locale: Locale) -> Response {
+        let message = i18n::render(self.code, locale, &self.args);
+        let body = ApiErrorBody {
+            code: self.code,
+            message: message.as_deref().unwrap_or(&self.message),
+        };
+        let mut response = (self.status, Json(body)).into_response();
+        response.extensions_mut().insert(self.clone());
+        response
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        self.localized_response(Locale::default())
+    }
+}
@@ -0,0 +1,168 @@
+/// Languages API error messages are available in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Locale {
+    /// Chinese, the language the API has always answered in.
+    #[default]
+    Zh,
+    En,
+}
+
+impl Locale {
+    /// The supported language the client prefers most, by `q` weight, from
+    /// an `Accept-Language` header. Falls back to Chinese.
+    pub fn from_accept_language(header: Option<&str>) -> Self {
+        let Some(header) = header else {
+            return Self::default();
+        };
+        let mut best: Option<(f32, Self)> = None;
+        for entry in header.split(',') {
+            let mut parts = entry.split(';');
+            let tag = parts.next().unwrap_or_default().trim();
+            let weight = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            let primary = tag.split(['-', '_']).next().unwrap_or_default();
+            let locale = if primary.eq_ignore_ascii_case("zh") {
+                Self::Zh
+            } else if primary.eq_ignore_ascii_case("en") {
+                Self::En
+            } else {
+                continue;
+            };
+            if weight > 0.0
+                && best
+                    .map(|(best_weight, _)| weight > best_weight)
+                    .unwrap_or(true)
+            {
+                best = Some((weight, locale));
+            }
+        }
+        best.map(|(_, locale)| locale).unwrap_or_default()
+    }
+}
+
+/// Message template for an error `code`. `{name}` placeholders are filled from
+/// the error's arguments. Codes without an entry keep their original message.
+fn template(code: &str, locale: Locale) -> Option<&'static str> {
+    let (zh, en) = match code {
+        "voice_not_found" => ("未知音色 '{voice_id}'", "Unknown voice '{voice_id}'"),
+        "engine_mismatch" => (
+            "音色 '{voice_id}' 属于引擎 '{voice_engine}'，而不是 '{engine}'",
+            "Voice '{voice_id}' belongs to engine '{voice_engine}', not '{engine}'",
+        ),
+        "text_empty" => ("文本不能为空", "Text must not be empty"),
+        "reference_text_required" => (
+            "该引擎的音色必须提供参考文本",
+            "Voices on this engine need a reference transcript",
+        ),
+        "reference_text_too_long" => (
+            "参考文本共 {chars} 字，超过上限 {max_chars} 字",
+            "Reference text is {chars} characters, over the limit of {max_chars}",
+        ),
+        "danmaku_disabled" => ("弹幕播报未启用", "Danmaku readout is not enabled"),
+        "no_voices" => (
+            "没有可用的音色：所有引擎都未能加载",
+            "No voices available: every engine failed to load",
+        ),
+        "admin_disabled" => (
+            "未配置 api.admin_token，管理接口已禁用",
+            "Admin endpoints are disabled because api.admin_token is not set",
+        ),
+        "unauthorized" => ("缺少或错误的 token", "Missing or invalid token"),
+        "unknown_engine" => ("不支持的模型 '{engine}'", "Unsupported engine '{engine}'"),
+        "invalid_pcm_format" => (
+            "不支持的 PCM 格式 '{format}'，可选 f32 或 s16",
+            "Unsupported PCM format '{format}'; use f32 or s16",
+        ),
+        "invalid_channel" => (
+            "请输入正确的 Twitch 用户名或频道链接",
+            "Enter a valid Twitch username or channel link",
+        ),
+        "channel_limit_reached" => (
+            "已达到最大频道数 ({limit})",
+            "Channel limit reached ({limit})",
+        ),
+        "channel_not_active" => (
+            "频道 '{channel}' 未在播报中",
+            "Channel '{channel}' is not being read out",
+        ),
+        "segments_unsupported" => (
+            "音色 '{voice_id}' 所在的引擎不支持分段合成（仅 IndexTTS）",
+            "Voice '{voice_id}' is on an engine without segmented synthesis (IndexTTS only)",
+        ),
+        "session_recording_disabled" => (
+            "未开启弹幕会话录制（[recording] record_session）",
+            "Danmaku session recording is off ([recording] record_session)",
+        ),
+        "session_empty" => (
+            "频道 '{channel}' 没有录制的音频",
+            "Nothing has been recorded for channel '{channel}'",
+        ),
+        "job_queue_full" => (
+            "排队中的合成任务已达上限 ({limit})，请稍后再试",
+            "Too many queued synthesis jobs ({limit}); try again later",
+        ),
+        "clip_retention_disabled" => (
+            "未开启音频保留（[api] clip_retention_secs）",
+            "Clip retention is off ([api] clip_retention_secs)",
+        ),
+        "idempotency_key_conflict" => (
+            "idempotency_key '{key}' 已用于内容不同的请求",
+            "idempotency_key '{key}' was already used for a different request",
+        ),
+        "clip_not_found" => (
+            "没有请求 '{request_id}' 的音频（音频保留 {retention_secs} 秒）",
+            "No audio for request '{request_id}' (clips are kept for {retention_secs}s)",
+        ),
+        _ => return None,
+    };
+    Some(match locale {
+        Locale::Zh => zh,
+        Locale::En => en,
+    })
+}
+
+/// Renders the message for `code` in `locale`, or `None` when the code has no
+/// translated template.
+pub fn render(code: &str, locale: Locale, args: &[(&'static str, String)]) -> Option<String> {
+    let mut message = template(code, locale)?.to_string();
+    for (name, value) in args {
+        message = message.replace(&format!("{{{name}}}"), value);
+    }
+    Some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_language_picks_highest_weighted_supported_locale() {
+        assert_eq!(Locale::from_accept_language(None), Locale::Zh);
+        assert_eq!(
+            Locale::from_accept_language(Some("en-US,en;q=0.9")),
+            Locale::En
+        );
+        assert_eq!(
+            Locale::from_accept_language(Some("fr-FR, en;q=0.5, zh-CN;q=0.8")),
+            Locale::Zh
+        );
+        assert_eq!(Locale::from_accept_language(Some("de, fr")), Locale::Zh);
+        assert_eq!(Locale::from_accept_language(Some("en;q=0")), Locale::Zh);
+    }
+
+    #[test]
+    fn render_fills_arguments() {
+        let args = [("voice_id", "walter".to_string())];
+        assert_eq!(
+            render("voice_not_found", Locale::En, &args).as_deref(),
+            Some("Unknown voice 'walter'")
+        );
+        assert_eq!(
+            render("voice_not_found", Locale::Zh, &args).as_deref(),
+            Some("未知音色 'walter'")
+        );
+        assert_eq!(render("bad_request", Locale::En, &args), None);
+    }
+}
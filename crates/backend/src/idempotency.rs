@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use tokio::sync::OnceCell;
+
+pub const DEFAULT_IDEMPOTENCY_TTL: Duration = Duration::from_secs(300);
+/// Most keys remembered at once; the oldest is forgotten first.
+pub const DEFAULT_IDEMPOTENCY_MAX_KEYS: usize = 1024;
+
+/// A key was reused within the TTL for a request with a different payload.
+#[derive(Debug, thiserror::Error)]
+#[error("idempotency_key '{0}' 已用于内容不同的请求")]
+pub struct IdempotencyConflict(pub String);
+
+struct Slot<T> {
+    created_at: Instant,
+    /// Hash of the request the key was first used for.
+    fingerprint: u64,
+    cell: Arc<OnceCell<T>>,
+}
+
+/// Short-lived map from client-supplied idempotency keys to the result of the
+/// first request that used them. Concurrent requests with the same key wait on
+/// the same cell, so a retried submit never triggers a second synthesis.
+pub struct IdempotencyCache<T> {
+    ttl: Duration,
+    max_keys: usize,
+    slots: Mutex<HashMap<String, Slot<T>>>,
+}
+
+impl<T> IdempotencyCache<T> {
+    pub fn new(ttl: Duration, max_keys: usize) -> Self {
+        Self {
+            ttl,
+            max_keys: max_keys.max(1),
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the cell for `key`, creating it if missing or expired. Fails
+    /// when the key is still held by a request with another `fingerprint`.
+    pub fn slot(
+        &self,
+        key: &str,
+        fingerprint: u64,
+    ) -> Result<Arc<OnceCell<T>>, IdempotencyConflict> {
+        let now = Instant::now();
+        let mut slots = self.slots.lock();
+        slots.retain(|_, slot| now.duration_since(slot.created_at) < self.ttl);
+        if let Some(slot) = slots.get(key) {
+            if slot.fingerprint != fingerprint {
+                return Err(IdempotencyConflict(key.to_string()));
+            }
+            return Ok(slot.cell.clone());
+        }
+        if slots.len() >= self.max_keys {
+            // Callers already waiting keep their cell; only the key is lost.
+            let oldest = slots
+                .iter()
+                .min_by_key(|(_, slot)| slot.created_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                slots.remove(&oldest);
+            }
+        }
+        let cell = Arc::new(OnceCell::new());
+        slots.insert(
+            key.to_string(),
+            Slot {
+                created_at: now,
+                fingerprint,
+                cell: cell.clone(),
+            },
+        );
+        Ok(cell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_key_and_payload_share_a_cell() {
+        let cache = IdempotencyCache::<u32>::new(DEFAULT_IDEMPOTENCY_TTL, 8);
+        let first = cache.slot("retry", 1).unwrap();
+        first.set(7).unwrap();
+        assert_eq!(cache.slot("retry", 1).unwrap().get(), Some(&7));
+        assert!(cache.slot("other", 1).unwrap().get().is_none());
+    }
+
+    #[test]
+    fn rejects_a_key_reused_for_another_payload() {
+        let cache = IdempotencyCache::<u32>::new(Duration::from_millis(20), 8);
+        cache.slot("retry", 1).unwrap();
+        assert!(matches!(
+            cache.slot("retry", 2),
+            Err(IdempotencyConflict(key)) if key == "retry"
+        ));
+
+        // Once expired, the key is free for any payload.
+        std::thread::sleep(Duration::from_millis(40));
+        assert!(cache.slot("retry", 2).is_ok());
+    }
+
+    #[test]
+    fn forgets_the_oldest_key_past_the_cap() {
+        let cache = IdempotencyCache::<u32>::new(DEFAULT_IDEMPOTENCY_TTL, 2);
+        cache.slot("first", 1).unwrap().set(1).unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+        cache.slot("second", 1).unwrap().set(2).unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+        cache.slot("third", 1).unwrap().set(3).unwrap();
+
+        assert!(cache.slot("first", 1).unwrap().get().is_none());
+        assert_eq!(cache.slot("third", 1).unwrap().get(), Some(&3));
+        assert_eq!(cache.slots.lock().len(), 2);
+    }
+}
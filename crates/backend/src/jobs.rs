@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+/// Finished jobs are kept this long for clients to collect the result.
+pub const DEFAULT_JOB_TTL: Duration = Duration::from_secs(300);
+
+/// Queued and running jobs are never evicted, so new ones are refused past
+/// `[api] max_pending_jobs`.
+#[derive(Debug, thiserror::Error)]
+#[error("排队中的合成任务已达上限 ({0})")]
+pub struct JobQueueFull(pub usize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JobError {
+    pub code: &'static str,
+    pub message: String,
+}
+
+/// What `GET /api/tts/:request_id` reports for a job.
+#[derive(Clone, Debug, Serialize)]
+pub struct JobSnapshot<T> {
+    pub request_id: Uuid,
+    pub status: JobStatus,
+    /// 1-based place among queued jobs; only set while queued.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<JobError>,
+}
+
+struct Job<T> {
+    ticket: u64,
+    status: JobStatus,
+    finished_at: Option<Instant>,
+    result: Option<T>,
+    error: Option<JobError>,
+}
+
+struct Jobs<T> {
+    next_ticket: u64,
+    entries: HashMap<Uuid, Job<T>>,
+}
+
+/// Tracks synthesis requests that were accepted with `202` because every
+/// synthesis slot was busy, so callers can poll for progress.
+pub struct JobTracker<T> {
+    ttl: Duration,
+    max_pending: usize,
+    jobs: Mutex<Jobs<T>>,
+}
+
+impl<T: Clone> JobTracker<T> {
+    pub fn new(ttl: Duration, max_pending: usize) -> Self {
+        Self {
+            ttl,
+            max_pending,
+            jobs: Mutex::new(Jobs {
+                next_ticket: 0,
+                entries: HashMap::new(),
+            }),
+        }
+    }
+
+    pub fn enqueue(&self) -> Result<Uuid, JobQueueFull> {
+        let now = Instant::now();
+        let mut jobs = self.jobs.lock();
+        jobs.entries.retain(|_, job| {
+            job.finished_at
+                .map(|finished| now.duration_since(finished) < self.ttl)
+                .unwrap_or(true)
+        });
+        let pending = jobs
+            .entries
+            .values()
+            .filter(|job| job.finished_at.is_none())
+            .count();
+        if pending >= self.max_pending {
+            return Err(JobQueueFull(self.max_pending));
+        }
+        let id = Uuid::new_v4();
+        let ticket = jobs.next_ticket;
+        jobs.next_ticket += 1;
+        jobs.entries.insert(
+            id,
+            Job {
+                ticket,
+                status: JobStatus::Queued,
+                finished_at: None,
+                result: None,
+                error: None,
+            },
+        );
+        Ok(id)
+    }
+
+    pub fn start(&self, id: Uuid) {
+        if let Some(job) = self.jobs.lock().entries.get_mut(&id) {
+            job.status = JobStatus::Running;
+        }
+    }
+
+    pub fn finish(&self, id: Uuid, outcome: Result<T, ApiError>) {
+        let mut jobs = self.jobs.lock();
+        let Some(job) = jobs.entries.get_mut(&id) else {
+            return;
+        };
+        job.finished_at = Some(Instant::now());
+        match outcome {
+            Ok(result) => {
+                job.status = JobStatus::Done;
+                job.result = Some(result);
+            }
+            Err(err) => {
+                job.status = JobStatus::Failed;
+                job.error = Some(JobError {
+                    code: err.code,
+                    message: err.message,
+                });
+            }
+        }
+    }
+
+    pub fn snapshot(&self, id: Uuid) -> Option<JobSnapshot<T>> {
+        let jobs = self.jobs.lock();
+        let job = jobs.entries.get(&id)?;
+        let position = (job.status == JobStatus::Queued).then(|| {
+            jobs.entries
+                .values()
+                .filter(|other| other.status == JobStatus::Queued && other.ticket <= job.ticket)
+                .count()
+        });
+        Some(JobSnapshot {
+            request_id: id,
+            status: job.status,
+            position,
+            result: job.result.clone(),
+            error: job.error.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(tracker: &JobTracker<u32>, id: Uuid) -> Option<usize> {
+        tracker.snapshot(id).unwrap().position
+    }
+
+    #[test]
+    fn queued_jobs_are_numbered_by_ticket() {
+        let tracker = JobTracker::new(DEFAULT_JOB_TTL, 8);
+        let first = tracker.enqueue().unwrap();
+        let second = tracker.enqueue().unwrap();
+        let third = tracker.enqueue().unwrap();
+        assert_eq!(position(&tracker, first), Some(1));
+        assert_eq!(position(&tracker, third), Some(3));
+
+        tracker.start(first);
+        assert_eq!(position(&tracker, first), None);
+        assert_eq!(position(&tracker, second), Some(1));
+        assert_eq!(position(&tracker, third), Some(2));
+
+        tracker.finish(first, Ok(7));
+        let done = tracker.snapshot(first).unwrap();
+        assert_eq!(done.status, JobStatus::Done);
+        assert_eq!(done.result, Some(7));
+    }
+
+    #[test]
+    fn finished_jobs_expire_after_ttl() {
+        let tracker = JobTracker::new(Duration::from_millis(20), 8);
+        let finished = tracker.enqueue().unwrap();
+        let queued = tracker.enqueue().unwrap();
+        tracker.finish(finished, Ok(1));
+        std::thread::sleep(Duration::from_millis(40));
+
+        // Eviction runs on the next enqueue.
+        tracker.enqueue().unwrap();
+        assert!(tracker.snapshot(finished).is_none());
+        assert!(tracker.snapshot(queued).is_some());
+    }
+
+    #[test]
+    fn refuses_jobs_past_the_pending_cap() {
+        let tracker = JobTracker::new(DEFAULT_JOB_TTL, 2);
+        let first = tracker.enqueue().unwrap();
+        let second = tracker.enqueue().unwrap();
+        tracker.start(second);
+        assert!(matches!(tracker.enqueue(), Err(JobQueueFull(2))));
+
+        // Finished jobs no longer count against the cap.
+        tracker.finish(first, Ok(1));
+        assert!(tracker.enqueue().is_ok());
+    }
+}
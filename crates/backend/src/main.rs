@@ -1,33 +1,68 @@
+mod chunking;
+mod clips;
 mod config;
 mod danmaku;
+mod error;
+mod i18n;
+mod idempotency;
+mod jobs;
+mod progress;
+mod pronunciations;
+mod readiness;
 mod routes;
+mod session;
 mod shimmy_integration;
 mod synth;
+mod url_guard;
 mod voice_overrides;
-
-use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Instant};
+mod waveform;
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::{anyhow, Context, Result};
 use axum::Router;
-use clap::Parser;
-use routes::{build_api_router, build_openai_router, build_shimmy_router, ApiState};
+use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine as _};
+use clap::{Parser, Subcommand};
+use parking_lot::RwLock;
+use routes::{build_api_router, build_openai_router, build_shimmy_router, ApiState, DefaultVoices};
 use shimmy::AppState as ShimmyAppState;
 use shimmy_integration::F5ShimmyEngine;
-use synth::Synthesizer;
+use synth::{check_reference_clip, Synthesizer};
 use tokio::signal;
 use tower_http::trace::{
     DefaultMakeSpan, DefaultOnFailure, DefaultOnRequest, DefaultOnResponse, TraceLayer,
 };
 use tracing::{error, info, warn, Level};
 use tracing_subscriber::{fmt, EnvFilter};
-use tts_engine::{EngineKind, F5Engine, IndexTtsEngine, TtsEngine, VoiceOverrideUpdate};
+use tts_engine::{
+    EngineKind, EngineType, F5Engine, IndexTtsEngine, MockEngine, TtsEngine, VoiceDescriptor,
+};
 use voice_overrides::VoiceOverrideStore;
 
+use clips::ClipStore;
+use idempotency::{IdempotencyCache, DEFAULT_IDEMPOTENCY_MAX_KEYS, DEFAULT_IDEMPOTENCY_TTL};
+use jobs::{JobTracker, DEFAULT_JOB_TTL};
+use progress::{ProgressHub, DEFAULT_PROGRESS_TTL};
+use pronunciations::PronunciationStore;
+use readiness::ReadinessProbe;
+
 use crate::{
     config::AppConfig,
-    danmaku::{DanmakuService, RealTwitchConnector, TwitchAuth},
+    danmaku::{
+        BilibiliAuth, DanmakuService, RealBilibiliConnector, RealTwitchConnector, TwitchAuth,
+        DEFAULT_TWITCH_MAX_RECONNECT_ATTEMPTS, DEFAULT_TWITCH_READ_TIMEOUT,
+    },
 };
-use ::danmaku::TwitchConfig;
+use ::danmaku::{BilibiliConfig, DanmakuConfig, TwitchConfig};
+
+/// Respellings added through `POST /api/pronunciations` are saved here.
+const PRONUNCIATIONS_PATH: &str = "data/pronunciations.toml";
 
 #[derive(Debug, Parser)]
 #[command(
@@ -37,14 +72,42 @@ use ::danmaku::TwitchConfig;
 )]
 struct Cli {
     /// Path to configuration file
-    #[arg(long)]
-    config: PathBuf,
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
     /// Logging level (error|warn|info|debug|trace)
     #[arg(long, default_value = "info")]
     log_level: String,
     /// Warm up frequently used voices during startup
     #[arg(long, default_value_t = false)]
     warmup: bool,
+    /// Refuse to start if any voice's reference audio is missing or unreadable
+    #[arg(long, default_value_t = false)]
+    strict_voices: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Synthesize one line to a WAV file and exit without starting the server
+    Synth {
+        /// Voice id (defaults to the configured default voice)
+        #[arg(long)]
+        voice: Option<String>,
+        /// Text to synthesize
+        #[arg(long)]
+        text: String,
+        /// Output WAV path
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Check the configuration and every path it references, report all
+    /// problems found and exit non-zero if there were any
+    Validate {
+        /// Print the report as JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
 }
 
 #[tokio::main]
@@ -52,7 +115,11 @@ async fn main() -> Result<()> {
     let cli = Cli::parse();
     init_tracing(&cli.log_level)?;
 
-    let (config, _config_dir) = AppConfig::load(cli.config.clone())?;
+    let config_path = cli.config.clone().context("--config is required")?;
+    if let Some(Command::Validate { json }) = cli.command {
+        return run_validate_command(config_path, json).await;
+    }
+    let (config, _config_dir) = AppConfig::load(config_path.clone())?;
     anyhow::ensure!(
         !config.f5.voices.is_empty(),
         "configuration must declare at least one F5 voice profile"
@@ -75,47 +142,44 @@ async fn main() -> Result<()> {
         targets
     };
 
-    let f5_engine = Arc::new(F5Engine::new(config.f5.clone())?);
-    let mut engines: Vec<Arc<dyn TtsEngine>> = Vec::new();
-    let f5_dyn: Arc<dyn TtsEngine> = f5_engine.clone();
-    engines.push(f5_dyn);
+    let pronunciations = Arc::new(PronunciationStore::load(PRONUNCIATIONS_PATH)?);
+    let synthesizer = build_synthesizer(&config, pronunciations.clone())?;
+    let voice_summaries_vec = synthesizer.voices();
 
-    if let Some(index_cfg) = config.index_tts.clone() {
-        let index_engine: Arc<dyn TtsEngine> = Arc::new(IndexTtsEngine::new(index_cfg)?);
-        engines.push(index_engine);
+    if let Some(Command::Synth { voice, text, out }) = cli.command {
+        let overrides_store = VoiceOverrideStore::load("data/voices/overrides")?;
+        apply_existing_overrides(&synthesizer, &overrides_store)?;
+        let voice = voice.unwrap_or_else(|| resolve_default_voice(&config, &voice_summaries_vec));
+        return run_synth_command(&synthesizer, &voice, &text, &out).await;
     }
 
-    let synthesizer = Arc::new(Synthesizer::new(engines, config.api.max_parallel)?);
-    let voice_summaries_vec = synthesizer.voices();
-    anyhow::ensure!(
-        !voice_summaries_vec.is_empty(),
-        "no voice profiles available after engine initialisation"
-    );
-
     if cli.warmup {
         run_warmup(&synthesizer, &warmup_targets).await;
     }
 
     let overrides_store = Arc::new(VoiceOverrideStore::load("data/voices/overrides")?);
     apply_existing_overrides(&synthesizer, &overrides_store)?;
+    run_self_check(&synthesizer, cli.strict_voices).await?;
+    if let Err(err) = overrides_store.collect_garbage(Duration::from_secs(
+        config.voice_overrides.orphan_retention_secs,
+    )) {
+        warn!(target = "ishowtts::backend", %err, "override audio cleanup failed");
+    }
 
-    let default_voice = match config.default_voice.clone() {
-        Some(candidate) => {
-            if voice_summaries_vec.iter().any(|v| v.id == candidate) {
-                candidate
-            } else {
-                let fallback = voice_summaries_vec.first().unwrap().id.clone();
-                warn!(
-                    target = "ishowtts::backend",
-                    configured = %candidate,
-                    fallback = %fallback,
-                    "configured default voice not found; falling back"
-                );
-                fallback
-            }
+    let default_voice = resolve_default_voice(&config, &voice_summaries_vec);
+    let engine_default_voices =
+        resolve_engine_default_voices(&config, &voice_summaries_vec, &default_voice);
+    let default_engine = config.default_engine.filter(|engine| {
+        let loaded = engine_default_voices.contains_key(engine);
+        if !loaded {
+            warn!(
+                target = "ishowtts::backend",
+                engine = %engine,
+                "configured default engine has no voices; ignoring"
+            );
         }
-        None => voice_summaries_vec.first().unwrap().id.clone(),
-    };
+        loaded
+    });
 
     let shimmy_engine = F5ShimmyEngine::new(synthesizer.clone());
 
@@ -134,12 +198,35 @@ async fn main() -> Result<()> {
         .as_ref()
         .and_then(|cfg| cfg.twitch.clone())
         .and_then(|tw_cfg| build_twitch_auth(&tw_cfg));
+    let twitch_read_timeout = config
+        .danmaku
+        .as_ref()
+        .and_then(|cfg| cfg.twitch.as_ref())
+        .and_then(|tw_cfg| tw_cfg.read_timeout_secs)
+        .map(|secs| Duration::from_secs(secs.max(1)))
+        .unwrap_or(DEFAULT_TWITCH_READ_TIMEOUT);
+    let twitch_max_reconnects = config
+        .danmaku
+        .as_ref()
+        .and_then(|cfg| cfg.twitch.as_ref())
+        .and_then(|tw_cfg| tw_cfg.max_reconnect_attempts)
+        .unwrap_or(DEFAULT_TWITCH_MAX_RECONNECT_ATTEMPTS);
+    let bilibili_auth = config
+        .danmaku
+        .as_ref()
+        .and_then(|cfg| cfg.bilibili.as_ref())
+        .and_then(build_bilibili_auth);
     let danmaku_service = match DanmakuService::new(
         (*synthesizer).clone(),
         default_voice.clone(),
         danmaku_gateway_cfg,
         twitch_auth,
-        Arc::new(RealTwitchConnector::default()),
+        Arc::new(
+            RealTwitchConnector::with_read_timeout(twitch_read_timeout)
+                .with_max_reconnect_attempts(twitch_max_reconnects),
+        ),
+        bilibili_auth,
+        Arc::new(RealBilibiliConnector::default()),
     ) {
         Ok(service) => Some(service),
         Err(err) => {
@@ -149,19 +236,88 @@ async fn main() -> Result<()> {
     };
 
     if let Some(danmaku_cfg) = config.danmaku.clone() {
-        if let Some(twitch_cfg) = danmaku_cfg.twitch {
+        if let Some(twitch_cfg) = &danmaku_cfg.twitch {
             if twitch_cfg.enabled && !twitch_cfg.channels.is_empty() {
                 info!(channels = ?twitch_cfg.channels, "danmaku configured for twitch channels");
             }
         }
+        if let Some(bilibili_cfg) = &danmaku_cfg.bilibili {
+            if bilibili_cfg.enabled && !bilibili_cfg.room_ids.is_empty() {
+                info!(rooms = ?bilibili_cfg.room_ids, "danmaku configured for bilibili rooms");
+            }
+        }
+        if let Some(youtube_cfg) = &danmaku_cfg.youtube {
+            if youtube_cfg.enabled && youtube_cfg.auto_start {
+                warn!(
+                    target = "ishowtts::backend",
+                    channel = ?youtube_cfg.channel_id,
+                    "youtube auto_start ignored: YouTube live chat is not supported yet"
+                );
+            }
+        }
+        if let Some(service) = danmaku_service.clone() {
+            tokio::spawn(auto_start_danmaku(service, danmaku_cfg));
+        }
     }
 
+    let drain_service = danmaku_service.clone();
     let api_state = ApiState {
         synthesizer: synthesizer.clone(),
-        default_voice: default_voice.clone(),
+        default_voices: Arc::new(RwLock::new(DefaultVoices {
+            voice: default_voice.clone(),
+            per_engine: engine_default_voices,
+        })),
+        default_engine,
         danmaku: danmaku_service,
         voice_overrides: overrides_store.clone(),
+        pronunciations,
+        voice_overrides_config: Arc::new(config.voice_overrides.clone()),
         shimmy: shimmy_state.clone(),
+        idempotency: Arc::new(IdempotencyCache::new(
+            DEFAULT_IDEMPOTENCY_TTL,
+            DEFAULT_IDEMPOTENCY_MAX_KEYS,
+        )),
+        tts_jobs: Arc::new(JobTracker::new(
+            DEFAULT_JOB_TTL,
+            config.api.max_pending_jobs.max(1),
+        )),
+        clips: (config.api.clip_retention_secs > 0).then(|| {
+            Arc::new(ClipStore::new(
+                Duration::from_secs(config.api.clip_retention_secs),
+                config.api.clip_retention_max_bytes,
+            ))
+        }),
+        ws_token: config
+            .api
+            .ws_token
+            .as_deref()
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+            .map(Arc::from),
+        max_request_bytes: config.api.max_request_bytes,
+        ws_compression: config.api.ws_compression,
+        http_compression: config.api.http_compression,
+        estimate: Arc::new(config.estimate.clone()),
+        admin_token: config
+            .api
+            .admin_token
+            .as_deref()
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+            .map(Arc::from),
+        readiness: Arc::new(ReadinessProbe::new(
+            synthesizer.clone(),
+            default_voice.clone(),
+            default_engine.filter(|engine| {
+                synthesizer
+                    .resolve_voice(&default_voice, Some(*engine))
+                    .is_some()
+            }),
+            Duration::from_secs(config.api.ready_timeout_secs),
+            Duration::from_secs(config.api.ready_cache_secs),
+        )),
+        progress: Arc::new(ProgressHub::new(DEFAULT_PROGRESS_TTL)),
+        config_path: Arc::new(config_path),
     };
 
     let trace_layer = TraceLayer::new_for_http()
@@ -171,7 +327,10 @@ async fn main() -> Result<()> {
         .on_failure(DefaultOnFailure::new().level(Level::WARN));
 
     let app = Router::new()
-        .nest("/api", build_api_router(api_state))
+        .nest(
+            "/api",
+            build_api_router(api_state, config.api.allowed_origins.as_deref())?,
+        )
         .nest("/shimmy", build_shimmy_router(shimmy_state.clone()))
         .nest("/v1", build_openai_router(shimmy_state.clone()))
         .layer(trace_layer);
@@ -187,7 +346,13 @@ async fn main() -> Result<()> {
     info!(target = "ishowtts::backend", %addr, "backend ready");
 
     axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            // Keep serving while the last queued readouts reach the listeners.
+            if let Some(service) = drain_service {
+                service.drain(DANMAKU_DRAIN_TIMEOUT).await;
+            }
+        })
         .await?;
 
     info!(target = "ishowtts::backend", "shutdown complete");
@@ -209,6 +374,8 @@ fn init_tracing(level: &str) -> Result<()> {
     Ok(())
 }
 
+const DANMAKU_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
 async fn shutdown_signal() {
     let ctrl_c = async {
         signal::ctrl_c()
@@ -233,6 +400,260 @@ async fn shutdown_signal() {
     info!(target = "ishowtts::backend", "shutdown signal received");
 }
 
+/// Logs which voices have usable reference audio; with `strict`, any broken
+/// voice aborts startup.
+async fn run_self_check(synthesizer: &Arc<Synthesizer>, strict: bool) -> Result<()> {
+    let check_synthesizer = synthesizer.clone();
+    let report = tokio::task::spawn_blocking(move || check_synthesizer.self_check()).await?;
+    for voice in &report.unhealthy {
+        warn!(
+            target = "ishowtts::backend",
+            voice = %voice.voice_id,
+            engine = %voice.engine,
+            problems = %voice.problems.join("; "),
+            "voice failed reference audio check"
+        );
+    }
+    info!(
+        target = "ishowtts::backend",
+        healthy = report.healthy,
+        total = report.total,
+        "{}/{} voices healthy",
+        report.healthy,
+        report.total
+    );
+    if strict && !report.unhealthy.is_empty() {
+        let broken: Vec<&str> = report
+            .unhealthy
+            .iter()
+            .map(|voice| voice.voice_id.as_str())
+            .collect();
+        anyhow::bail!(
+            "--strict-voices: {} voice(s) have unusable reference audio: {}",
+            broken.len(),
+            broken.join(", ")
+        );
+    }
+    Ok(())
+}
+
+fn build_synthesizer(
+    config: &AppConfig,
+    pronunciations: Arc<PronunciationStore>,
+) -> Result<Arc<Synthesizer>> {
+    let mut engines: Vec<Arc<dyn TtsEngine>> = Vec::new();
+    let f5_engine: Arc<dyn TtsEngine> = match config.f5.engine_type {
+        EngineType::Python => Arc::new(F5Engine::new(config.f5.clone())?),
+        EngineType::Mock => Arc::new(MockEngine::from_f5_config(&config.f5)),
+    };
+    engines.push(f5_engine);
+
+    if let Some(index_cfg) = config.index_tts.clone() {
+        let index_engine: Arc<dyn TtsEngine> = match index_cfg.engine_type {
+            EngineType::Python => Arc::new(IndexTtsEngine::new(index_cfg)?),
+            EngineType::Mock => Arc::new(MockEngine::from_index_tts_config(&index_cfg)),
+        };
+        engines.push(index_engine);
+    }
+
+    let synthesizer = Arc::new(
+        Synthesizer::new(engines, config.api.max_parallel)?
+            .with_fallback(config.fallback.clone())
+            .with_chunking(config.chunking.clone())
+            .with_pronunciations(pronunciations),
+    );
+    anyhow::ensure!(
+        !synthesizer.voices().is_empty(),
+        "no voice profiles available after engine initialisation"
+    );
+    Ok(synthesizer)
+}
+
+fn resolve_default_voice(config: &AppConfig, voices: &[VoiceDescriptor]) -> String {
+    match config.default_voice.clone() {
+        Some(candidate) => {
+            if voices.iter().any(|v| v.id == candidate) {
+                candidate
+            } else {
+                let fallback = voices.first().unwrap().id.clone();
+                warn!(
+                    target = "ishowtts::backend",
+                    configured = %candidate,
+                    fallback = %fallback,
+                    "configured default voice not found; falling back"
+                );
+                fallback
+            }
+        }
+        None => voices.first().unwrap().id.clone(),
+    }
+}
+
+/// Picks a default voice for every engine that has voices: the configured
+/// `default_voices` entry when it names a voice of that engine, otherwise the
+/// global default if it belongs to the engine, otherwise the engine's first
+/// voice.
+fn resolve_engine_default_voices(
+    config: &AppConfig,
+    voices: &[VoiceDescriptor],
+    global_default: &str,
+) -> HashMap<EngineKind, String> {
+    for (engine, candidate) in &config.default_voices {
+        if !voices
+            .iter()
+            .any(|v| v.id == *candidate && v.engine == *engine)
+        {
+            warn!(
+                target = "ishowtts::backend",
+                engine = %engine,
+                configured = %candidate,
+                "configured engine default voice not found on that engine; ignoring"
+            );
+        }
+    }
+
+    let mut defaults = HashMap::new();
+    for voice in voices {
+        if defaults.contains_key(&voice.engine) {
+            continue;
+        }
+        let engine_voices = || voices.iter().filter(|v| v.engine == voice.engine);
+        let chosen = config
+            .default_voices
+            .get(&voice.engine)
+            .filter(|candidate| engine_voices().any(|v| v.id == **candidate))
+            .cloned()
+            .or_else(|| {
+                engine_voices()
+                    .any(|v| v.id == global_default)
+                    .then(|| global_default.to_string())
+            })
+            .unwrap_or_else(|| voice.id.clone());
+        defaults.insert(voice.engine, chosen);
+    }
+    defaults
+}
+
+async fn run_synth_command(
+    synth: &Synthesizer,
+    voice_id: &str,
+    text: &str,
+    out: &std::path::Path,
+) -> Result<()> {
+    anyhow::ensure!(!text.trim().is_empty(), "--text must not be empty");
+    anyhow::ensure!(
+        synth.voice_descriptor(voice_id).is_some(),
+        "unknown voice '{voice_id}'"
+    );
+    let started = Instant::now();
+    let response = synth.synthesize_text(voice_id, text).await?;
+    let wav = BASE64_STANDARD
+        .decode(response.audio_base64.as_bytes())
+        .context("failed to decode synthesized audio")?;
+    std::fs::write(out, &wav).with_context(|| format!("failed to write {}", out.display()))?;
+    info!(
+        target = "ishowtts::backend",
+        voice = %response.voice_id,
+        engine = %response.engine,
+        out = %out.display(),
+        bytes = wav.len(),
+        elapsed_ms = started.elapsed().as_millis(),
+        "synthesized to file"
+    );
+    Ok(())
+}
+
+async fn run_validate_command(config_path: PathBuf, json: bool) -> Result<()> {
+    let path = config_path.clone();
+    let problems = tokio::task::spawn_blocking(move || validate_config(path)).await?;
+    if json {
+        let report = serde_json::json!({
+            "config": config_path,
+            "ok": problems.is_empty(),
+            "problems": problems,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if problems.is_empty() {
+        println!("{}: ok", config_path.display());
+    } else {
+        for problem in &problems {
+            println!("error: {problem}");
+        }
+    }
+    anyhow::ensure!(
+        problems.is_empty(),
+        "{} problem(s) found in {}",
+        problems.len(),
+        config_path.display()
+    );
+    Ok(())
+}
+
+/// Everything wrong with the configuration at `config_path` that would stop
+/// the server from starting or leave a voice without usable reference audio.
+/// Reads and decodes every reference clip.
+fn validate_config(config_path: PathBuf) -> Vec<String> {
+    let (config, _config_dir, mut problems) = match AppConfig::load_collecting(config_path) {
+        Ok(loaded) => loaded,
+        Err(err) => return vec![format!("{err:#}")],
+    };
+    if config.f5.voices.is_empty() {
+        problems.push("configuration must declare at least one F5 voice profile".to_string());
+    }
+
+    let mut directories = vec![("F5 python package path", &config.f5.python_package_path)];
+    let mut voices: Vec<(&str, Vec<&PathBuf>)> = config
+        .f5
+        .voices
+        .iter()
+        .map(|profile| {
+            (
+                profile.id.as_str(),
+                profile.reference_audio.iter().collect(),
+            )
+        })
+        .collect();
+    if let Some(index_cfg) = config.index_tts.as_ref() {
+        directories.push((
+            "IndexTTS python package path",
+            &index_cfg.python_package_path,
+        ));
+        directories.push(("IndexTTS model directory", &index_cfg.model_dir));
+        if index_cfg.config_file.is_dir() {
+            problems.push(format!(
+                "IndexTTS config file path {} is a directory",
+                index_cfg.config_file.display()
+            ));
+        }
+        voices.extend(index_cfg.voices.iter().map(|voice| {
+            let clips = voice
+                .reference_audio
+                .iter()
+                .chain(&voice.emo_audio)
+                .collect();
+            (voice.id.as_str(), clips)
+        }));
+    }
+
+    // Paths that do not exist at all were already reported by the load.
+    for (label, path) in directories {
+        if path.exists() && !path.is_dir() {
+            problems.push(format!("{label} {} is not a directory", path.display()));
+        }
+    }
+    for (voice_id, clips) in voices {
+        if clips.is_empty() {
+            problems.push(format!("voice {voice_id}: no reference audio configured"));
+        }
+        for clip in clips.into_iter().filter(|clip| clip.exists()) {
+            if let Err(problem) = check_reference_clip(clip) {
+                problems.push(format!("voice {voice_id}: {problem}"));
+            }
+        }
+    }
+    problems
+}
+
 async fn run_warmup(synth: &Arc<Synthesizer>, targets: &[(String, EngineKind)]) {
     if targets.is_empty() {
         info!(
@@ -249,7 +670,7 @@ async fn run_warmup(synth: &Arc<Synthesizer>, targets: &[(String, EngineKind)])
     );
     for (voice_id, engine) in targets {
         let started = Instant::now();
-        match synth.warmup_voice(voice_id, "Warmup sample").await {
+        match synth.warmup_voice(voice_id, *engine, "Warmup sample").await {
             Ok(_) => {
                 info!(
                     target = "ishowtts::backend",
@@ -272,12 +693,50 @@ async fn run_warmup(synth: &Arc<Synthesizer>, targets: &[(String, EngineKind)])
     }
 }
 
+/// Joins the Twitch channels and Bilibili rooms marked `auto_start`, so
+/// headless deployments come up already reading chat.
+async fn auto_start_danmaku(service: Arc<DanmakuService>, config: DanmakuConfig) {
+    if let Some(twitch) = config.twitch.filter(|cfg| cfg.enabled && cfg.auto_start) {
+        for channel in twitch.channels {
+            match service
+                .start_twitch(&channel, None, None, &Default::default())
+                .await
+            {
+                Ok(channel) => {
+                    info!(target = "ishowtts::backend", %channel, "auto-started twitch channel")
+                }
+                Err(err) => warn!(
+                    target = "ishowtts::backend",
+                    %channel,
+                    error = %err,
+                    "failed to auto-start twitch channel"
+                ),
+            }
+        }
+    }
+    if let Some(bilibili) = config.bilibili.filter(|cfg| cfg.enabled && cfg.auto_start) {
+        for room in bilibili.room_ids {
+            match service
+                .start_bilibili(&room.to_string(), None, None, &Default::default())
+                .await
+            {
+                Ok(room) => {
+                    info!(target = "ishowtts::backend", %room, "auto-started bilibili room")
+                }
+                Err(err) => warn!(
+                    target = "ishowtts::backend",
+                    %room,
+                    error = %err,
+                    "failed to auto-start bilibili room"
+                ),
+            }
+        }
+    }
+}
+
 fn apply_existing_overrides(synth: &Arc<Synthesizer>, store: &VoiceOverrideStore) -> Result<()> {
     for record in store.all() {
-        let update = VoiceOverrideUpdate {
-            reference_audio: record.reference_audio.clone(),
-            reference_text: record.reference_text.clone(),
-        };
+        let update = record.engine_update();
         if let Err(err) = synth.apply_override(record.engine, &record.voice_id, update) {
             warn!(
                 target = "ishowtts::backend",
@@ -304,6 +763,17 @@ fn build_twitch_auth(cfg: &TwitchConfig) -> Option<TwitchAuth> {
     })
 }
 
+fn build_bilibili_auth(cfg: &BilibiliConfig) -> Option<BilibiliAuth> {
+    let cookie = cfg.cookie.as_deref()?.trim();
+    if cookie.is_empty() {
+        return None;
+    }
+    Some(BilibiliAuth {
+        uid: cfg.uid.unwrap_or_default(),
+        cookie: cookie.to_string(),
+    })
+}
+
 fn normalize_twitch_token(raw: &str) -> Option<String> {
     let trimmed = raw.trim();
     if trimmed.is_empty() {
@@ -338,3 +808,113 @@ fn normalize_twitch_token(raw: &str) -> Option<String> {
         Some(token.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use async_trait::async_trait;
+    use danmaku_gateway::{config::GatewayConfig, MessageQueue};
+    use tokio::{sync::broadcast, task::JoinHandle};
+    use tts_engine::{EngineKind, MockEngine, VoiceDescriptor};
+
+    use crate::danmaku::{BilibiliConnector, ChannelFailure, PresenceTracker, TwitchConnector};
+
+    struct IdleTwitch;
+
+    #[async_trait]
+    impl TwitchConnector for IdleTwitch {
+        async fn spawn(
+            &self,
+            _channel: String,
+            _queue: Arc<MessageQueue>,
+            _presence: PresenceTracker,
+            _auth: Option<TwitchAuth>,
+            _failures: broadcast::Sender<ChannelFailure>,
+        ) -> Result<JoinHandle<()>> {
+            Ok(tokio::spawn(std::future::pending()))
+        }
+
+        async fn probe(&self, channel: &str, _auth: Option<&TwitchAuth>) -> Result<()> {
+            anyhow::ensure!(channel != "missing", "no such channel");
+            Ok(())
+        }
+    }
+
+    struct IdleBilibili;
+
+    #[async_trait]
+    impl BilibiliConnector for IdleBilibili {
+        async fn spawn(
+            &self,
+            _room_id: u64,
+            _queue: Arc<MessageQueue>,
+            _auth: Option<BilibiliAuth>,
+        ) -> Result<JoinHandle<()>> {
+            Ok(tokio::spawn(std::future::pending()))
+        }
+
+        async fn resolve_room(&self, room: u64, _auth: Option<&BilibiliAuth>) -> Result<u64> {
+            Ok(room + 1_000_000)
+        }
+    }
+
+    fn danmaku_service() -> Arc<DanmakuService> {
+        let voice = VoiceDescriptor {
+            id: "walter".to_string(),
+            engine: EngineKind::F5,
+            engine_label: "F5".to_string(),
+            language: None,
+            reference_text: None,
+        };
+        let engine = MockEngine::new(EngineKind::F5, vec![(voice, Vec::new())]);
+        let synthesizer = Synthesizer::new(vec![Arc::new(engine)], 1).unwrap();
+        DanmakuService::new(
+            synthesizer,
+            "walter".to_string(),
+            GatewayConfig::default(),
+            None,
+            Arc::new(IdleTwitch),
+            None,
+            Arc::new(IdleBilibili),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn auto_start_joins_only_enabled_auto_start_sources() {
+        let service = danmaku_service();
+        let config = DanmakuConfig {
+            twitch: Some(TwitchConfig {
+                enabled: true,
+                auto_start: true,
+                channels: vec!["Streamer".to_string(), "missing".to_string()],
+                ..Default::default()
+            }),
+            bilibili: Some(BilibiliConfig {
+                enabled: true,
+                auto_start: false,
+                room_ids: vec![42],
+                ..Default::default()
+            }),
+            youtube: None,
+        };
+        auto_start_danmaku(service.clone(), config).await;
+        assert_eq!(service.status().active_channels, vec!["streamer"]);
+
+        let config = DanmakuConfig {
+            bilibili: Some(BilibiliConfig {
+                enabled: true,
+                auto_start: true,
+                room_ids: vec![42],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        auto_start_danmaku(service.clone(), config).await;
+        assert_eq!(
+            service.status().active_channels,
+            vec!["1000042", "streamer"]
+        );
+    }
+}
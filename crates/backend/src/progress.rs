@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::watch;
+use tts_engine::{ProgressSink, SynthesisProgress};
+
+/// Progress entries are dropped this long after they were created, in case a
+/// request never reaches [`ProgressHub::finish`].
+pub const DEFAULT_PROGRESS_TTL: Duration = Duration::from_secs(300);
+/// How long a finished entry stays around, so a listener that connects just
+/// after the request completed gets the `done` event instead of waiting for
+/// a request that already happened.
+const FINISHED_RETENTION: Duration = Duration::from_secs(30);
+
+/// What `GET /api/tts/progress/:progress_id` streams.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ProgressState {
+    pub completed: u32,
+    /// `0` until the engine reports how many segments it will render.
+    pub total: u32,
+    pub done: bool,
+}
+
+struct Entry {
+    created_at: Instant,
+    finished_at: Option<Instant>,
+    sender: Arc<watch::Sender<ProgressState>>,
+}
+
+impl Entry {
+    fn expired(&self, now: Instant, ttl: Duration) -> bool {
+        match self.finished_at {
+            Some(finished_at) => now.duration_since(finished_at) >= FINISHED_RETENTION,
+            None => now.duration_since(self.created_at) >= ttl,
+        }
+    }
+}
+
+/// Segment progress of manual synthesis requests, keyed by a client-chosen
+/// `progress_id`. Either side may arrive first: the listener and the request
+/// share whichever entry was created earlier.
+pub struct ProgressHub {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl ProgressHub {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn subscribe(&self, id: &str) -> watch::Receiver<ProgressState> {
+        self.entry(id, false).subscribe()
+    }
+
+    /// Sink that publishes engine progress under `id`. A finished entry
+    /// under the same id is replaced, since this is a new request.
+    pub fn sink(&self, id: &str) -> ProgressSink {
+        let sender = self.entry(id, true);
+        Arc::new(move |progress: SynthesisProgress| {
+            sender.send_modify(|state| {
+                state.completed = progress.completed;
+                state.total = progress.total;
+            });
+        })
+    }
+
+    /// Sink for `id` plus a guard that finishes it when dropped, so a
+    /// request cancelled mid-synthesis still ends its listeners' streams.
+    pub fn track(self: &Arc<Self>, id: &str) -> (ProgressSink, ProgressGuard) {
+        let guard = ProgressGuard {
+            hub: self.clone(),
+            id: id.to_string(),
+        };
+        (self.sink(id), guard)
+    }
+
+    /// Marks `id` finished, which ends its streams. The entry is kept
+    /// briefly for listeners that connect late.
+    pub fn finish(&self, id: &str) {
+        let now = Instant::now();
+        let mut entries = self.entries.lock();
+        self.evict_expired(&mut entries, now);
+        if let Some(entry) = entries.get_mut(id) {
+            entry.finished_at.get_or_insert(now);
+            entry.sender.send_modify(|state| state.done = true);
+        }
+    }
+
+    fn entry(&self, id: &str, restart_finished: bool) -> Arc<watch::Sender<ProgressState>> {
+        let now = Instant::now();
+        let mut entries = self.entries.lock();
+        self.evict_expired(&mut entries, now);
+        if restart_finished
+            && entries
+                .get(id)
+                .is_some_and(|entry| entry.finished_at.is_some())
+        {
+            entries.remove(id);
+        }
+        entries
+            .entry(id.to_string())
+            .or_insert_with(|| Entry {
+                created_at: now,
+                finished_at: None,
+                sender: Arc::new(watch::channel(ProgressState::default()).0),
+            })
+            .sender
+            .clone()
+    }
+
+    /// Drops expired entries. A dropped entry whose request is gone also
+    /// drops its sender, which ends the streams still listening to it.
+    fn evict_expired(&self, entries: &mut HashMap<String, Entry>, now: Instant) {
+        entries.retain(|_, entry| !entry.expired(now, self.ttl));
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.entries.lock().len()
+    }
+}
+
+/// Finishes its progress entry when dropped; see [`ProgressHub::track`].
+pub struct ProgressGuard {
+    hub: Arc<ProgressHub>,
+    id: String,
+}
+
+impl Drop for ProgressGuard {
+    fn drop(&mut self) {
+        self.hub.finish(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn late_listeners_see_a_finished_request() {
+        let hub = Arc::new(ProgressHub::new(DEFAULT_PROGRESS_TTL));
+        let (sink, guard) = hub.track("req");
+        sink(SynthesisProgress {
+            completed: 1,
+            total: 2,
+        });
+        drop(guard);
+
+        let late = hub.subscribe("req");
+        let state = *late.borrow();
+        assert!(state.done);
+        assert_eq!((state.completed, state.total), (1, 2));
+
+        // Reusing the id starts over instead of reporting the old request.
+        let _sink = hub.sink("req");
+        assert!(!hub.subscribe("req").borrow().done);
+    }
+
+    #[test]
+    fn unclaimed_entries_expire_after_the_ttl() {
+        let hub = ProgressHub::new(Duration::from_millis(20));
+        let listener = hub.subscribe("never-sent");
+        assert_eq!(hub.len(), 1);
+
+        std::thread::sleep(Duration::from_millis(40));
+        hub.subscribe("other");
+        assert_eq!(hub.len(), 1);
+        // The evicted entry took its sender with it, ending the stream.
+        assert!(listener.has_changed().is_err());
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use parking_lot::{Mutex, RwLock};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// On-disk layout of `data/pronunciations.toml`:
+///
+/// ```toml
+/// [global]
+/// "iShowSpeed" = "eye show speed"
+///
+/// [voices.walter]
+/// "GG" = "good game"
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PronunciationsFile {
+    /// Applied to every voice.
+    #[serde(default)]
+    pub global: BTreeMap<String, String>,
+    /// Per-voice entries, taking precedence over `global`.
+    #[serde(default)]
+    pub voices: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// Written forms of one dictionary compiled into a single pattern, longest
+/// first so "GGWP" wins over "GG".
+struct Dictionary {
+    pattern: Regex,
+    /// Keyed by the lowercased written form.
+    spoken: HashMap<String, String>,
+}
+
+impl Dictionary {
+    fn compile(entries: &BTreeMap<String, String>) -> Result<Option<Self>> {
+        if entries.is_empty() {
+            return Ok(None);
+        }
+        let mut written: Vec<&String> = entries.keys().collect();
+        written.sort_by_key(|term| std::cmp::Reverse(term.chars().count()));
+        let alternatives: Vec<String> = written.iter().map(|term| term_pattern(term)).collect();
+        let pattern = Regex::new(&format!("(?i)(?:{})", alternatives.join("|")))
+            .context("failed to compile pronunciation dictionary")?;
+        let spoken = entries
+            .iter()
+            .map(|(written, spoken)| (written.to_lowercase(), spoken.clone()))
+            .collect();
+        Ok(Some(Self { pattern, spoken }))
+    }
+
+    fn apply(&self, text: &str) -> String {
+        self.pattern
+            .replace_all(text, |caps: &Captures| {
+                let matched = &caps[0];
+                self.spoken
+                    .get(&matched.to_lowercase())
+                    .cloned()
+                    .unwrap_or_else(|| matched.to_string())
+            })
+            .into_owned()
+    }
+}
+
+/// Latin written forms only match whole words, so "GG" leaves "eggs" alone;
+/// edges in CJK text have no word boundary to check.
+fn term_pattern(term: &str) -> String {
+    let is_word = |ch: Option<char>| ch.is_some_and(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+    let mut pattern = String::new();
+    if is_word(term.chars().next()) {
+        pattern.push_str(r"(?-u:\b)");
+    }
+    pattern.push_str(&regex::escape(term));
+    if is_word(term.chars().last()) {
+        pattern.push_str(r"(?-u:\b)");
+    }
+    pattern
+}
+
+struct Compiled {
+    file: PronunciationsFile,
+    global: Option<Dictionary>,
+    /// Global entries merged with each voice's own.
+    voices: HashMap<String, Dictionary>,
+}
+
+impl Compiled {
+    fn new(file: PronunciationsFile) -> Result<Self> {
+        let global = Dictionary::compile(&file.global)?;
+        let mut voices = HashMap::new();
+        for (voice_id, entries) in &file.voices {
+            let mut merged = file.global.clone();
+            merged.retain(|written, _| {
+                !entries
+                    .keys()
+                    .any(|own| own.to_lowercase() == written.to_lowercase())
+            });
+            merged.extend(entries.iter().map(|(k, v)| (k.clone(), v.clone())));
+            if let Some(dictionary) = Dictionary::compile(&merged)? {
+                voices.insert(voice_id.clone(), dictionary);
+            }
+        }
+        Ok(Self {
+            file,
+            global,
+            voices,
+        })
+    }
+}
+
+/// Respellings applied to text before it reaches an engine, for brand names
+/// and gamer tags the models keep getting wrong.
+pub struct PronunciationStore {
+    path: PathBuf,
+    state: RwLock<Compiled>,
+    /// Held across an edit and its save, so concurrent edits are not lost
+    /// while `apply` keeps reading the previous state.
+    writer: Mutex<()>,
+}
+
+impl PronunciationStore {
+    /// Reads `path`, starting empty when it does not exist yet.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = if path.exists() {
+            let raw = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            toml::from_str(&raw).with_context(|| format!("failed to parse {}", path.display()))?
+        } else {
+            PronunciationsFile::default()
+        };
+        let state = Compiled::new(file)?;
+        let voice_entries: usize = state.file.voices.values().map(BTreeMap::len).sum();
+        if !state.file.global.is_empty() || voice_entries > 0 {
+            info!(
+                target = "ishowtts::backend",
+                global = state.file.global.len(),
+                voice_entries,
+                "loaded pronunciation dictionary"
+            );
+        }
+        Ok(Self {
+            path,
+            state: RwLock::new(state),
+            writer: Mutex::new(()),
+        })
+    }
+
+    /// `text` with every known written form replaced by how `voice_id`
+    /// should say it.
+    pub fn apply(&self, voice_id: &str, text: &str) -> String {
+        let state = self.state.read();
+        match state.voices.get(voice_id).or(state.global.as_ref()) {
+            Some(dictionary) => dictionary.apply(text),
+            None => text.to_string(),
+        }
+    }
+
+    pub fn entries(&self) -> PronunciationsFile {
+        self.state.read().file.clone()
+    }
+
+    /// Adds or replaces an entry, globally when `voice_id` is `None`, and
+    /// saves the dictionary. Writes the file, so async callers should run it
+    /// on a blocking thread.
+    pub fn insert(&self, voice_id: Option<&str>, written: &str, spoken: &str) -> Result<()> {
+        let _writer = self.writer.lock();
+        let mut file = self.state.read().file.clone();
+        let entries = match voice_id {
+            Some(voice_id) => file.voices.entry(voice_id.to_string()).or_default(),
+            None => &mut file.global,
+        };
+        // Written forms match case-insensitively; keep one spelling of each.
+        entries.retain(|existing, _| existing.to_lowercase() != written.to_lowercase());
+        entries.insert(written.to_string(), spoken.to_string());
+        let compiled = Compiled::new(file)?;
+        self.persist(&compiled.file)?;
+        *self.state.write() = compiled;
+        Ok(())
+    }
+
+    fn persist(&self, file: &PronunciationsFile) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let raw = toml::to_string_pretty(file)?;
+        fs::write(&self.path, raw)
+            .with_context(|| format!("failed to write {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_whole_words_case_insensitively() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PronunciationStore::load(dir.path().join("pronunciations.toml")).unwrap();
+        store.insert(None, "GG", "good game").unwrap();
+        store.insert(None, "GGWP", "good game well played").unwrap();
+        store.insert(None, "小鬼", "小gui").unwrap();
+
+        assert_eq!(
+            store.apply("walter", "gg eggs GGWP"),
+            "good game eggs good game well played"
+        );
+        assert_eq!(store.apply("walter", "主播GG了"), "主播good game了");
+        assert_eq!(store.apply("walter", "小鬼来了"), "小gui来了");
+    }
+
+    #[test]
+    fn voice_entries_override_global_and_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data").join("pronunciations.toml");
+        let store = PronunciationStore::load(&path).unwrap();
+        store.insert(None, "iShowSpeed", "eye show speed").unwrap();
+        store.insert(Some("walter"), "ishowspeed", "speed").unwrap();
+
+        let reloaded = PronunciationStore::load(&path).unwrap();
+        assert_eq!(reloaded.apply("walter", "hi iShowSpeed"), "hi speed");
+        assert_eq!(
+            reloaded.apply("other", "hi iShowSpeed"),
+            "hi eye show speed"
+        );
+        assert_eq!(reloaded.entries().voices["walter"].len(), 1);
+    }
+
+    #[test]
+    fn concurrent_inserts_are_all_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pronunciations.toml");
+        let store = PronunciationStore::load(&path).unwrap();
+        std::thread::scope(|scope| {
+            for index in 0..8 {
+                let store = &store;
+                scope.spawn(move || {
+                    store
+                        .insert(None, &format!("term{index}"), &format!("spoken {index}"))
+                        .unwrap();
+                });
+            }
+        });
+
+        assert_eq!(store.entries().global.len(), 8);
+        assert_eq!(
+            PronunciationStore::load(&path)
+                .unwrap()
+                .entries()
+                .global
+                .len(),
+            8
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tracing::warn;
+use tts_engine::{EngineKind, TtsRequest};
+
+use crate::synth::Synthesizer;
+
+/// Short enough to cost a fraction of a second on a healthy GPU.
+const PROBE_TEXT: &str = "你好。";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub voice_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub checked_at: DateTime<Utc>,
+    /// Whether this report was served from the cache rather than a new probe.
+    pub cached: bool,
+}
+
+/// Readiness check that renders a short clip on the default voice, unlike
+/// `/api/health` which only counts voices. Results are cached for a few
+/// seconds so frequent probes do not add synthesis load.
+pub struct ReadinessProbe {
+    synthesizer: Arc<Synthesizer>,
+    voice_id: String,
+    /// Engine to render `voice_id` on; `None` uses the engine that serves it
+    /// by default.
+    engine: Option<EngineKind>,
+    timeout: Duration,
+    cache_ttl: Duration,
+    last: Mutex<Option<(Instant, ReadinessReport)>>,
+    /// Held while a probe runs so concurrent checks share its result.
+    running: tokio::sync::Mutex<()>,
+}
+
+impl ReadinessProbe {
+    pub fn new(
+        synthesizer: Arc<Synthesizer>,
+        voice_id: String,
+        engine: Option<EngineKind>,
+        timeout: Duration,
+        cache_ttl: Duration,
+    ) -> Self {
+        Self {
+            synthesizer,
+            voice_id,
+            engine,
+            timeout,
+            cache_ttl,
+            last: Mutex::new(None),
+            running: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub async fn check(&self) -> ReadinessReport {
+        if let Some(report) = self.cached() {
+            return report;
+        }
+        let _running = self.running.lock().await;
+        if let Some(report) = self.cached() {
+            return report;
+        }
+
+        let report = self.probe().await;
+        if !report.ready {
+            warn!(
+                target = "ishowtts::api::ready",
+                voice = %report.voice_id,
+                error = report.error.as_deref().unwrap_or_default(),
+                "readiness probe failed"
+            );
+        }
+        *self.last.lock() = Some((Instant::now(), report.clone()));
+        report
+    }
+
+    fn cached(&self) -> Option<ReadinessReport> {
+        let last = self.last.lock();
+        let (at, report) = last.as_ref()?;
+        (at.elapsed() < self.cache_ttl).then(|| ReadinessReport {
+            cached: true,
+            ..report.clone()
+        })
+    }
+
+    async fn probe(&self) -> ReadinessReport {
+        let started_at = Instant::now();
+        let request = TtsRequest {
+            text: PROBE_TEXT.to_string(),
+            voice_id: self.voice_id.clone(),
+            speed: None,
+            target_rms: None,
+            cross_fade_duration: None,
+            sway_sampling_coef: None,
+            cfg_strength: None,
+            nfe_step: None,
+            fix_duration: None,
+            remove_silence: None,
+            seed: None,
+            normalize_output_rms: None,
+            // A cached clip would pass even with the runtime wedged.
+            no_cache: true,
+            segments: None,
+        };
+        let synthesis = self
+            .synthesizer
+            .synthesize_notify(request, self.engine, || {});
+        let error = match tokio::time::timeout(self.timeout, synthesis).await {
+            Ok(Ok(response)) if response.waveform_len > 0 => None,
+            Ok(Ok(_)) => Some("synthesis returned no audio".to_string()),
+            Ok(Err(err)) => Some(format!("{err:#}")),
+            Err(_) => Some(format!(
+                "synthesis did not finish within {} ms",
+                self.timeout.as_millis()
+            )),
+        };
+        ReadinessReport {
+            ready: error.is_none(),
+            voice_id: self.voice_id.clone(),
+            latency_ms: error
+                .is_none()
+                .then(|| started_at.elapsed().as_millis() as u64),
+            error,
+            checked_at: Utc::now(),
+            cached: false,
+        }
+    }
+}
@@ -1,42 +1,100 @@
-use std::{cmp::max, str::FromStr, sync::Arc, time::Instant};
+use std::{
+    borrow::Cow,
+    cmp::max,
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap, HashSet, VecDeque},
+    hash::{Hash, Hasher},
+    net::SocketAddr,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::{Context, Result};
 use axum::body::Body;
-use axum::http::{HeaderValue, Method, StatusCode};
+use axum::extract::Request;
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use axum::middleware::{self, Next};
 use axum::{
     extract::{
+        rejection::JsonRejection,
         ws::{Message, WebSocket, WebSocketUpgrade},
-        Multipart, Path, Query, State,
+        DefaultBodyLimit, Multipart, Path, Query, State,
+    },
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
     },
-    response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
 use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
 use base64::Engine as _;
 use chrono::{DateTime, Utc};
-use futures::{stream::SplitSink, SinkExt, StreamExt};
+use futures::{future, stream::SplitSink, SinkExt, Stream, StreamExt};
+use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
-use tokio::{fs, sync::broadcast::error::RecvError};
-use tower_http::cors::{Any, CorsLayer};
+use tokio::{
+    fs,
+    sync::broadcast::error::RecvError,
+    time::{interval, MissedTickBehavior},
+};
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream, WatchStream};
+use tower_http::compression::{
+    predicate::{NotForContentType, Predicate},
+    CompressionLayer, DefaultPredicate,
+};
+use tower_http::cors::{AllowHeaders, Any, CorsLayer};
 use tracing::{debug, error, info, instrument, warn};
 use uuid::Uuid;
 
 use crate::{
+    clips::{ClipStore, StoredClip},
+    config::{AppConfig, EstimateConfig, VoiceOverridesConfig},
     danmaku::{
-        DanmakuService, PlaybackItem, StartRequest, StartResponse, StopRequest, StopResponse,
+        gzip_if_smaller, ChannelLimitReached, ChannelNotActive, ChannelPresence, DanmakuService,
+        DanmakuStatus, InvalidChannel, PlaybackItem, SayRequest, SayResponse, SkipControl,
+        StartRequest, StartResponse, StopRequest, StopResponse,
+    },
+    error::ApiError,
+    i18n::Locale,
+    idempotency::{IdempotencyCache, IdempotencyConflict},
+    jobs::{JobQueueFull, JobSnapshot, JobTracker},
+    progress::{ProgressHub, DEFAULT_PROGRESS_TTL},
+    pronunciations::{PronunciationStore, PronunciationsFile},
+    readiness::ReadinessProbe,
+    synth::{
+        EngineGpuStats, EngineLoadState, EngineStatus, EngineSummary, Synthesizer, VoiceStats,
+    },
+    url_guard::{is_public_ip, url_host},
+    voice_overrides::{
+        audio_content_type, audio_extension, infer_audio_extension_from_bytes,
+        limit_reference_text, ClipMode, OverrideAudio, VoiceOverrideStore,
+    },
+    waveform::{
+        self, wav_to_pcm, PcmFormat, WaveformBucket, DEFAULT_WAVEFORM_BUCKETS, MAX_WAVEFORM_BUCKETS,
     },
-    synth::Synthesizer,
-    voice_overrides::{OverrideAudio, VoiceOverrideStore},
 };
-use danmaku::message::{MessageContent, NormalizedMessage, Platform};
+use danmaku::message::{MessageContent, NormalizedMessage, Platform, Priority};
+use danmaku_gateway::DropReason;
 use shimmy::{
     engine::{GenOptions, ModelSpec},
     AppState as ShimmyAppState,
 };
-use tts_engine::{EngineKind, TtsRequest, TtsResponse, VoiceOverrideUpdate};
+use tts_engine::{
+    clip_duration_ms, EngineKind, EngineVoices, ProgressSink, SegmentTiming, TtsRequest,
+    TtsResponse, TtsSegment, VoiceDescriptor, VoiceOverrideUpdate,
+};
 
-const MAX_WORDS_PER_REQUEST: usize = 77;
+const MAX_REFERENCE_AUDIO_BYTES: usize = 10 * 1024 * 1024;
+const MAX_REFERENCE_CLIPS: usize = 8;
+const REFERENCE_AUDIO_FETCH_TIMEOUT: Duration = Duration::from_secs(20);
+const DEFAULT_VOICE_PAGE_SIZE: usize = 20;
+const MAX_VOICE_PAGE_SIZE: usize = 200;
+/// How often the playback websocket pings its client.
+const WS_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(20);
+/// Unanswered pings tolerated before a client is treated as gone.
+const WS_MAX_MISSED_PONGS: u32 = 3;
+const MAX_PROGRESS_ID_LEN: usize = 64;
 
 fn preview_text(value: &str) -> String {
     const LIMIT: usize = 120;
@@ -52,13 +110,91 @@ fn preview_text(value: &str) -> String {
     preview
 }
 
+/// Voices used when a request names none; recomputed by voice reloads.
+#[derive(Clone, Debug)]
+pub struct DefaultVoices {
+    pub voice: String,
+    /// Per-engine defaults for requests that set `engine` but not `voice_id`.
+    pub per_engine: HashMap<EngineKind, String>,
+}
+
 #[derive(Clone)]
 pub struct ApiState {
     pub synthesizer: Arc<Synthesizer>,
-    pub default_voice: String,
+    pub default_voices: Arc<RwLock<DefaultVoices>>,
+    pub default_engine: Option<EngineKind>,
     pub danmaku: Option<Arc<DanmakuService>>,
     pub voice_overrides: Arc<VoiceOverrideStore>,
+    pub voice_overrides_config: Arc<VoiceOverridesConfig>,
+    pub pronunciations: Arc<PronunciationStore>,
     pub shimmy: Arc<ShimmyAppState>,
+    pub idempotency: Arc<IdempotencyCache<SynthesizeResponse>>,
+    pub tts_jobs: Arc<JobTracker<SynthesizeResponse>>,
+    /// Recent `/api/tts` audio; `None` unless `[api] clip_retention_secs` is set.
+    pub clips: Option<Arc<ClipStore>>,
+    pub ws_token: Option<Arc<str>>,
+    pub max_request_bytes: usize,
+    pub ws_compression: bool,
+    pub http_compression: bool,
+    pub estimate: Arc<EstimateConfig>,
+    pub admin_token: Option<Arc<str>>,
+    pub readiness: Arc<ReadinessProbe>,
+    pub progress: Arc<ProgressHub>,
+    /// File the server was started with; re-read by voice reloads.
+    pub config_path: Arc<std::path::PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct WsAuthQuery {
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DanmakuStreamQuery {
+    /// `gzip` asks for compressed clips; see [`PacketEncoding`].
+    #[serde(default)]
+    pub compress: Option<String>,
+    /// How many of the most recent buffered clips to replay on connect;
+    /// omitted replays them all, `0` none.
+    #[serde(default)]
+    pub replay: Option<usize>,
+    /// `f32` or `s16` sends raw little-endian samples instead of WAV.
+    #[serde(default)]
+    pub pcm: Option<String>,
+}
+
+/// Text command a `/danmaku/stream` client may send.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum DanmakuStreamCommand {
+    /// Queue the last `count` buffered clips (all when omitted) for resending.
+    Replay {
+        #[serde(default)]
+        count: Option<usize>,
+    },
+    /// Drop whatever backlog has not been sent yet.
+    Clear,
+}
+
+/// How the audio after a playback header is encoded. Browsers cannot
+/// negotiate permessage-deflate with axum, so compression is opt-in per
+/// connection and flagged in each header instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PacketEncoding {
+    Identity,
+    Gzip,
+}
+
+impl PacketEncoding {
+    /// Gzip only when the client asked for it with `?compress=gzip` and
+    /// `[api] ws_compression` allows it.
+    fn negotiate(requested: Option<&str>, enabled: bool) -> Self {
+        match requested {
+            Some(value) if enabled && value.eq_ignore_ascii_case("gzip") => Self::Gzip,
+            _ => Self::Identity,
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -66,10 +202,52 @@ struct HealthResponse {
     status: &'static str,
     voices: usize,
     default_voice: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_engine: Option<EngineKind>,
+    engines: Vec<EngineStatus>,
+    gpu: Vec<EngineGpuStats>,
+    /// How danmaku lines are framed before synthesis, so clients can preview it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    danmaku_spoken_template: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
+pub struct VoiceListQuery {
+    #[serde(default)]
+    pub engine: Option<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub q: Option<String>,
+    /// 1-based page index.
+    #[serde(default)]
+    pub page: Option<usize>,
+    #[serde(default)]
+    pub page_size: Option<usize>,
+}
+
+impl VoiceListQuery {
+    fn is_empty(&self) -> bool {
+        self.engine.is_none()
+            && self.language.is_none()
+            && self.q.is_none()
+            && self.page.is_none()
+            && self.page_size.is_none()
+    }
+}
+
+#[derive(Serialize)]
+struct VoiceListResponse {
+    voices: Vec<VoiceDescriptor>,
+    total: usize,
+}
+
+/// Serialized only to fingerprint requests for [`IdempotencyCache`]; fields
+/// that may differ between retries of the same request are skipped.
+#[derive(Debug, Deserialize, Serialize)]
 pub struct SynthesizePayload {
+    /// May be omitted when `segments` is given.
+    #[serde(default)]
     pub text: String,
     #[serde(default)]
     pub voice_id: Option<String>,
@@ -95,9 +273,56 @@ pub struct SynthesizePayload {
     pub remove_silence: Option<bool>,
     #[serde(default)]
     pub seed: Option<u64>,
+    #[serde(default)]
+    pub normalize_output_rms: Option<f32>,
+    /// Repeating a key within the TTL returns the first response instead of
+    /// synthesizing again. Omit it to always synthesize.
+    #[serde(default, skip_serializing)]
+    pub idempotency_key: Option<String>,
+    /// Skip synthesis and answer with an [`EstimateResponse`] instead.
+    #[serde(default)]
+    pub estimate_only: bool,
+    /// Bypass the engine's audio cache, e.g. to hear a reference-audio
+    /// change right away.
+    #[serde(default)]
+    pub no_cache: bool,
+    /// IndexTTS lines with their own emotion prompts, rendered into one
+    /// clip. Replaces `text` when present.
+    #[serde(default)]
+    pub segments: Option<Vec<TtsSegment>>,
+    /// Client-chosen id under which segment progress is published; see
+    /// `GET /api/tts/progress/:progress_id`.
+    #[serde(default, skip_serializing)]
+    pub progress_id: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+impl SynthesizePayload {
+    /// Hash of everything that shapes the audio, so a reused idempotency key
+    /// can be told apart from a retry.
+    fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        serde_json::to_vec(self)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Predicted output of a `/api/tts` request, computed without touching an
+/// engine so UIs can show a duration before generating.
+#[derive(Clone, Debug, Serialize)]
+pub struct EstimateResponse {
+    pub voice_id: String,
+    pub engine: String,
+    pub text: String,
+    pub truncated: bool,
+    pub chars: usize,
+    pub chars_per_sec: f32,
+    pub speed: f32,
+    pub estimated_duration_ms: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub struct SynthesizeResponse {
     pub request_id: Uuid,
     pub voice_id: String,
@@ -106,7 +331,39 @@ pub struct SynthesizeResponse {
     pub sample_rate: u32,
     pub audio_base64: String,
     pub waveform_len: usize,
+    pub duration_ms: u32,
     pub format: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference_text_used: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nfe_step_used: Option<u32>,
+    pub peak: f32,
+    pub rms: f32,
+    pub fallback_used: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment_timings: Option<Vec<SegmentTiming>>,
+    /// Text handed to the engine after truncation and pronunciation
+    /// respelling. Only returned to `?debug=true` callers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effective_text: Option<String>,
+}
+
+impl SynthesizeResponse {
+    /// Drops the fields only `?debug=true` callers get. Stored responses keep
+    /// them so a replay or job poll can still be answered either way.
+    fn for_caller(mut self, debug: bool) -> Self {
+        if !debug {
+            self.effective_text = None;
+        }
+        self
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SynthesizeQuery {
+    /// Include [`SynthesizeResponse::effective_text`].
+    #[serde(default)]
+    pub debug: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -115,57 +372,937 @@ struct ShimmyEnvelope {
 }
 
 #[instrument(skip(state))]
-pub async fn health(State(state): State<ApiState>) -> impl IntoResponse {
+pub async fn health(State(state): State<ApiState>, headers: HeaderMap) -> Response {
     let voices_count = state.synthesizer.voices().len();
+    let gpu = match state.synthesizer.cached_gpu_stats() {
+        Some(gpu) => gpu,
+        None => {
+            let synthesizer = state.synthesizer.clone();
+            tokio::task::spawn_blocking(move || synthesizer.gpu_stats())
+                .await
+                .unwrap_or_default()
+        }
+    };
     let response = HealthResponse {
-        status: "ok",
+        status: if voices_count == 0 { "degraded" } else { "ok" },
         voices: voices_count,
-        default_voice: state.default_voice.clone(),
+        default_voice: state.default_voices.read().voice.clone(),
+        default_engine: state.default_engine,
+        engines: state.synthesizer.engine_states(),
+        gpu,
+        danmaku_spoken_template: state
+            .danmaku
+            .as_ref()
+            .map(|service| service.spoken_template().to_string()),
+    };
+    json_with_etag(&headers, &response)
+}
+
+/// Readiness probe: `200` only if a short synthesis on the default voice
+/// succeeded recently, `503` otherwise. `/api/health` stays the cheap
+/// liveness check.
+pub async fn ready(State(state): State<ApiState>) -> Response {
+    let report = state.readiness.check().await;
+    let status = if report.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report)).into_response()
+}
+
+/// Starts loading an engine's models in the background so the first real
+/// request does not pay the cold start. Answers `202` while loading.
+#[instrument(skip(state))]
+async fn warmup_engine(
+    State(state): State<ApiState>,
+    Path(engine): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let engine = EngineKind::from_str(&engine)
+        .ok()
+        .filter(|engine| state.synthesizer.has_engine(*engine))
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("未加载的引擎 '{engine}'")))?;
+
+    let status = EngineStatus {
+        engine,
+        state: state.synthesizer.engine_state(engine),
     };
-    Json(response)
+    if status.state == EngineLoadState::Ready {
+        return Ok((StatusCode::OK, Json(status)));
+    }
+    // The warmup below starts (or is already) loading the engine.
+    let status = EngineStatus {
+        engine,
+        state: EngineLoadState::Loading,
+    };
+
+    let synthesizer = state.synthesizer.clone();
+    tokio::spawn(async move {
+        match synthesizer.warmup_engine(engine).await {
+            Ok(true) => info!(
+                target = "ishowtts::api::engines",
+                %engine,
+                "engine warmup completed"
+            ),
+            Ok(false) => {}
+            Err(err) => warn!(
+                target = "ishowtts::api::engines",
+                %engine,
+                error = %err,
+                "engine warmup failed"
+            ),
+        }
+    });
+    Ok((StatusCode::ACCEPTED, Json(status)))
 }
 
+/// Request counts and latency per voice since startup.
 #[instrument(skip(state))]
-pub async fn list_voices(State(state): State<ApiState>) -> impl IntoResponse {
-    Json(state.synthesizer.voices())
+async fn voice_stats(State(state): State<ApiState>) -> Json<Vec<VoiceStats>> {
+    Json(state.synthesizer.voice_stats())
 }
 
-#[instrument(skip(state, payload))]
-pub async fn synthesize(
+#[derive(Debug, Deserialize)]
+struct PronunciationPayload {
+    /// Form as it appears in chat, matched case-insensitively.
+    written: String,
+    /// Respelling the engine reads instead.
+    spoken: String,
+    /// Limits the entry to one voice; omitted applies it to every voice.
+    #[serde(default)]
+    voice_id: Option<String>,
+}
+
+async fn list_pronunciations(State(state): State<ApiState>) -> Json<PronunciationsFile> {
+    Json(state.pronunciations.entries())
+}
+
+/// Adds or replaces a pronunciation and saves it for later runs.
+#[instrument(skip(state))]
+async fn add_pronunciation(
     State(state): State<ApiState>,
-    Json(payload): Json<SynthesizePayload>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let started_at = Instant::now();
+    Json(payload): Json<PronunciationPayload>,
+) -> Result<Json<PronunciationsFile>, ApiError> {
+    let written = payload.written.trim();
+    let spoken = payload.spoken.trim();
+    if written.is_empty() || spoken.is_empty() {
+        return Err(ApiError::from((
+            StatusCode::BAD_REQUEST,
+            "written 与 spoken 不能为空".to_string(),
+        )));
+    }
     let voice_id = payload
         .voice_id
-        .clone()
-        .unwrap_or_else(|| state.default_voice.clone());
+        .as_deref()
+        .map(str::trim)
+        .filter(|voice_id| !voice_id.is_empty());
+    // Entries are keyed by voice id alone and apply on every engine.
+    if let Some(voice_id) = voice_id {
+        lookup_voice(&state, voice_id, None)?;
+    }
+    let store = state.pronunciations.clone();
+    let (entry_voice, entry_written, entry_spoken) = (
+        voice_id.map(str::to_string),
+        written.to_string(),
+        spoken.to_string(),
+    );
+    tokio::task::spawn_blocking(move || {
+        store.insert(entry_voice.as_deref(), &entry_written, &entry_spoken)
+    })
+    .await
+    .map_err(|err| {
+        ApiError::from((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("保存任务失败: {err}"),
+        ))
+    })?
+    .map_err(|err| {
+        ApiError::from((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("保存发音词典失败: {err:#}"),
+        ))
+    })?;
+    info!(
+        target = "ishowtts::api::pronunciations",
+        %written,
+        %spoken,
+        voice = voice_id.unwrap_or("*"),
+        "pronunciation added"
+    );
+    Ok(Json(state.pronunciations.entries()))
+}
 
-    let voice_meta = state.synthesizer.voice_descriptor(&voice_id).ok_or((
-        StatusCode::BAD_REQUEST,
-        format!("unknown voice_id '{voice_id}'"),
-    ))?;
+#[derive(Deserialize)]
+struct ValidateVoicesPayload {
+    voice_ids: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct VoiceValidation {
+    exists: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    engine: Option<EngineKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    engine_label: Option<String>,
+}
+
+/// Checks a list of voice ids up front, so a script can catch typos before
+/// sending a batch of `/api/tts` calls.
+#[instrument(skip(state, payload))]
+async fn validate_voices(
+    State(state): State<ApiState>,
+    Json(payload): Json<ValidateVoicesPayload>,
+) -> Json<BTreeMap<String, VoiceValidation>> {
+    let results = payload
+        .voice_ids
+        .into_iter()
+        .map(|voice_id| {
+            let descriptor = state.synthesizer.voice_descriptor(&voice_id);
+            let validation = VoiceValidation {
+                exists: descriptor.is_some(),
+                engine: descriptor.as_ref().map(|descriptor| descriptor.engine),
+                engine_label: descriptor.map(|descriptor| descriptor.engine_label),
+            };
+            (voice_id, validation)
+        })
+        .collect();
+    Json(results)
+}
+
+#[derive(Serialize)]
+struct VoiceRef {
+    voice_id: String,
+    engine: EngineKind,
+}
+
+#[derive(Serialize)]
+struct RestartRequired {
+    engine: EngineKind,
+    reason: String,
+}
+
+#[derive(Serialize)]
+struct ReloadVoicesResponse {
+    added: Vec<VoiceRef>,
+    removed: Vec<VoiceRef>,
+    /// Default voice in effect after the reload.
+    default_voice: String,
+    /// Changes the running engines could not pick up.
+    restart_required: Vec<RestartRequired>,
+}
+
+/// Re-reads the config file and swaps in its voices on the running engines,
+/// then re-applies stored overrides and recomputes the default voices.
+/// Changes an engine cannot take live are listed under `restart_required`
+/// instead of failing the request.
+#[instrument(skip(state, headers))]
+async fn reload_voices(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+) -> Result<Json<ReloadVoicesResponse>, ApiError> {
+    authorize_admin(&state, &headers)?;
+    let (config, _) = AppConfig::load(state.config_path.to_path_buf()).map_err(|err| {
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_config",
+            format!("{err:#}"),
+        )
+    })?;
+    if config.f5.voices.is_empty() {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_config",
+            "configuration must declare at least one F5 voice profile",
+        ));
+    }
+
+    let synthesizer = &state.synthesizer;
+    let before: HashSet<(String, EngineKind)> = synthesizer
+        .voices()
+        .into_iter()
+        .map(|voice| (voice.id, voice.engine))
+        .collect();
+
+    let mut updates = vec![(EngineKind::F5, EngineVoices::F5(config.f5.voices.clone()))];
+    let mut restart_required = Vec::new();
+    match (
+        config.index_tts.as_ref(),
+        synthesizer.has_engine(EngineKind::IndexTts),
+    ) {
+        (Some(index_cfg), true) => updates.push((
+            EngineKind::IndexTts,
+            EngineVoices::IndexTts(index_cfg.voices.clone()),
+        )),
+        (Some(_), false) => restart_required.push(RestartRequired {
+            engine: EngineKind::IndexTts,
+            reason: "[index_tts] was added; the engine loads at startup".to_string(),
+        }),
+        (None, true) => restart_required.push(RestartRequired {
+            engine: EngineKind::IndexTts,
+            reason: "[index_tts] was removed; its voices stay until restart".to_string(),
+        }),
+        (None, false) => {}
+    }
+
+    let mut reloaded = HashSet::new();
+    for (engine, voices) in updates {
+        match synthesizer.reload_voices(engine, voices) {
+            Ok(()) => {
+                reloaded.insert(engine);
+            }
+            Err(err) => restart_required.push(RestartRequired {
+                engine,
+                reason: format!("{err:#}"),
+            }),
+        }
+    }
+
+    for record in state.voice_overrides.all() {
+        if !reloaded.contains(&record.engine) {
+            continue;
+        }
+        let update = record.engine_update();
+        if let Err(err) = synthesizer.apply_override(record.engine, &record.voice_id, update) {
+            warn!(
+                target = "ishowtts::api::admin",
+                voice = %record.voice_id,
+                engine = %record.engine,
+                %err,
+                "failed to re-apply voice override after reload"
+            );
+        }
+    }
+
+    let after: HashSet<(String, EngineKind)> = synthesizer
+        .voices()
+        .into_iter()
+        .map(|voice| (voice.id, voice.engine))
+        .collect();
+    let to_refs = |keys: Vec<&(String, EngineKind)>| {
+        let mut refs: Vec<VoiceRef> = keys
+            .into_iter()
+            .map(|(voice_id, engine)| VoiceRef {
+                voice_id: voice_id.clone(),
+                engine: *engine,
+            })
+            .collect();
+        refs.sort_by(|a, b| a.voice_id.cmp(&b.voice_id));
+        refs
+    };
+    let added = to_refs(after.difference(&before).collect());
+    let removed = to_refs(before.difference(&after).collect());
+
+    let voices = synthesizer.voices();
+    let default_voice = crate::resolve_default_voice(&config, &voices);
+    let per_engine = crate::resolve_engine_default_voices(&config, &voices, &default_voice);
+    *state.default_voices.write() = DefaultVoices {
+        voice: default_voice.clone(),
+        per_engine,
+    };
+
+    info!(
+        target = "ishowtts::api::admin",
+        added = added.len(),
+        removed = removed.len(),
+        restart_required = restart_required.len(),
+        %default_voice,
+        "reloaded voice configuration"
+    );
+    Ok(Json(ReloadVoicesResponse {
+        added,
+        removed,
+        default_voice,
+        restart_required,
+    }))
+}
+
+/// Requires `Authorization: Bearer <api.admin_token>`. Without a configured
+/// token the admin endpoints are off.
+fn authorize_admin(state: &ApiState, headers: &HeaderMap) -> Result<(), ApiError> {
+    let Some(expected) = state.admin_token.as_deref() else {
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "admin_disabled",
+            "未配置 api.admin_token，管理接口已禁用",
+        ));
+    };
+    let authorized = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| tokens_match(token.trim(), expected));
+    if !authorized {
+        warn!(
+            target = "ishowtts::api::admin",
+            "rejected admin request without a valid token"
+        );
+        return Err(ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "unauthorized",
+            "缺少或错误的管理 token",
+        ));
+    }
+    Ok(())
+}
+
+#[instrument(skip(state, headers))]
+pub async fn list_voices(
+    State(state): State<ApiState>,
+    Query(query): Query<VoiceListQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    if !state.synthesizer.has_voices() {
+        return Err(ApiError::no_voices());
+    }
+    let voices = state.synthesizer.voices();
+    // The plain call keeps returning the bare array the frontend expects.
+    if query.is_empty() {
+        return Ok(json_with_etag(&headers, &voices));
+    }
+
+    let engine = match query.engine.as_deref().map(str::trim) {
+        Some(value) if !value.is_empty() => Some(
+            EngineKind::from_str(value)
+                .map_err(|_| (StatusCode::BAD_REQUEST, format!("未知的引擎 '{value}'")))?,
+        ),
+        _ => None,
+    };
+    let language = query
+        .language
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+    let needle = query
+        .q
+        .as_deref()
+        .map(|value| value.trim().to_lowercase())
+        .filter(|value| !value.is_empty());
+
+    let matched: Vec<VoiceDescriptor> = voices
+        .into_iter()
+        .filter(|voice| engine.is_none() || engine == Some(voice.engine))
+        .filter(|voice| {
+            language
+                .map(|wanted| {
+                    voice
+                        .language
+                        .as_deref()
+                        .map(|lang| language_matches(lang, wanted))
+                        .unwrap_or(false)
+                })
+                .unwrap_or(true)
+        })
+        .filter(|voice| {
+            needle
+                .as_deref()
+                .map(|needle| voice.id.to_lowercase().contains(needle))
+                .unwrap_or(true)
+        })
+        .collect();
+
+    let total = matched.len();
+    let page_size = query
+        .page_size
+        .unwrap_or(DEFAULT_VOICE_PAGE_SIZE)
+        .clamp(1, MAX_VOICE_PAGE_SIZE);
+    let page = query.page.unwrap_or(1).max(1);
+    let voices = matched
+        .into_iter()
+        .skip((page - 1).saturating_mul(page_size))
+        .take(page_size)
+        .collect();
+
+    Ok(json_with_etag(
+        &headers,
+        &VoiceListResponse { voices, total },
+    ))
+}
+
+/// Serializes `value` as JSON with a weak `ETag` derived from the body, and
+/// answers `304 Not Modified` when the client's `If-None-Match` still matches.
+fn json_with_etag<T: Serialize>(headers: &HeaderMap, value: &T) -> Response {
+    let body = match serde_json::to_vec(value) {
+        Ok(body) => body,
+        Err(err) => {
+            error!(target = "ishowtts::backend", %err, "failed to serialize response");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    let etag = format!("W/\"{:016x}\"", hasher.finish());
+
+    let unchanged = headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|candidate| {
+            candidate == "*" || candidate.trim_start_matches("W/") == etag.trim_start_matches("W/")
+        });
+
+    let mut response = if unchanged {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        (
+            [(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            )],
+            body,
+        )
+            .into_response()
+    };
+    let response_headers = response.headers_mut();
+    response_headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response_headers.insert(header::ETAG, value);
+    }
+    response
+}
+
+#[instrument(skip(state))]
+async fn list_engines(State(state): State<ApiState>) -> Json<Vec<EngineSummary>> {
+    Json(state.synthesizer.engines())
+}
+
+/// `en` matches `en-US`; otherwise tags must match exactly (case-insensitive).
+fn language_matches(voice_language: &str, wanted: &str) -> bool {
+    if voice_language.eq_ignore_ascii_case(wanted) {
+        return true;
+    }
+    voice_language
+        .split(['-', '_'])
+        .next()
+        .map(|primary| primary.eq_ignore_ascii_case(wanted))
+        .unwrap_or(false)
+}
+
+/// How `/api/tts` should encode its reply, picked from the `Accept` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TtsResponseEncoding {
+    /// JSON envelope with base64 audio (the default).
+    Json,
+    /// Raw WAV bytes with metadata in `X-*` headers.
+    Wav,
+}
+
+fn negotiate_tts_encoding(headers: &HeaderMap) -> Result<TtsResponseEncoding, ApiError> {
+    let Some(accept) = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.trim().is_empty())
+    else {
+        return Ok(TtsResponseEncoding::Json);
+    };
+
+    let mut ranges: Vec<(String, f32)> = accept
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';');
+            let media = parts.next()?.trim().to_ascii_lowercase();
+            if media.is_empty() {
+                return None;
+            }
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            Some((media, quality))
+        })
+        .filter(|(_, quality)| *quality > 0.0)
+        .collect();
+    // Stable sort keeps the client's ordering among equal q-values.
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    for (media, _) in &ranges {
+        match media.as_str() {
+            "application/json" | "application/*" | "*/*" => return Ok(TtsResponseEncoding::Json),
+            "audio/wav" | "audio/x-wav" | "audio/wave" | "audio/*" => {
+                return Ok(TtsResponseEncoding::Wav)
+            }
+            _ => {}
+        }
+    }
+
+    Err(ApiError::from((
+        StatusCode::NOT_ACCEPTABLE,
+        format!("不支持的响应格式: {accept}（可用 application/json 或 audio/wav）"),
+    )))
+}
+
+fn encode_tts_response(
+    response: SynthesizeResponse,
+    encoding: TtsResponseEncoding,
+) -> Result<Response, ApiError> {
+    if encoding == TtsResponseEncoding::Json {
+        return Ok(Json(response).into_response());
+    }
+
+    let audio = BASE64_STANDARD
+        .decode(response.audio_base64.as_bytes())
+        .map_err(|err| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("音频解码失败: {err}"),
+            )
+        })?;
+    let mut builder = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, response.format)
+        .header("X-Request-Id", response.request_id.to_string())
+        .header("X-Engine", response.engine.as_str())
+        .header("X-Sample-Rate", response.sample_rate.to_string());
+    if let Ok(value) = HeaderValue::from_str(&response.voice_id) {
+        builder = builder.header("X-Voice-Id", value);
+    }
+    if response.fallback_used {
+        builder = builder.header("X-Fallback-Used", "true");
+    }
+    builder.body(Body::from(audio)).map_err(|err| {
+        ApiError::from((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("构建响应失败: {err}"),
+        ))
+    })
+}
+
+#[instrument(skip(state, headers, payload))]
+pub async fn synthesize(
+    State(state): State<ApiState>,
+    Query(query): Query<SynthesizeQuery>,
+    headers: HeaderMap,
+    payload: Result<Json<SynthesizePayload>, JsonRejection>,
+) -> Result<Response, ApiError> {
+    let Json(payload) = payload.map_err(|rejection| {
+        if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            ApiError::from((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!(
+                    "request body exceeds {} bytes (api.max_request_bytes); \
+                     send at most {} words per request",
+                    state.max_request_bytes,
+                    state.synthesizer.max_request_words()
+                ),
+            ))
+        } else {
+            ApiError::from((rejection.status(), rejection.body_text()))
+        }
+    })?;
+    if !state.synthesizer.has_voices() {
+        return Err(ApiError::no_voices());
+    }
+    if payload.estimate_only {
+        return estimate_tts(&state, &payload).map(|estimate| Json(estimate).into_response());
+    }
+    // Negotiate before synthesizing so an unsupported Accept fails fast.
+    let encoding = negotiate_tts_encoding(&headers)?;
+    let idempotency_key = payload
+        .idempotency_key
+        .as_deref()
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(str::to_string);
+    let Some(key) = idempotency_key else {
+        // Raw WAV callers cannot parse a job envelope, so only JSON callers
+        // are switched to polling when every slot is busy.
+        if encoding == TtsResponseEncoding::Json && state.synthesizer.is_saturated() {
+            return enqueue_tts_job(state, payload, query.debug);
+        }
+        let response = synthesize_uncached(&state, payload, || {}).await?;
+        return encode_tts_response(response.for_caller(query.debug), encoding);
+    };
+
+    let slot = state
+        .idempotency
+        .slot(&key, payload.fingerprint())
+        .map_err(|err| {
+            let IdempotencyConflict(key) = &err;
+            ApiError::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "idempotency_key_conflict",
+                err.to_string(),
+            )
+            .with_arg("key", key)
+        })?;
+    if let Some(existing) = slot.get() {
+        debug!(
+            target = "ishowtts::api::tts",
+            idempotency_key = %key,
+            request_id = %existing.request_id,
+            "replaying cached tts response"
+        );
+        return encode_tts_response(existing.clone().for_caller(query.debug), encoding);
+    }
+    let response = slot
+        .get_or_try_init(|| synthesize_uncached(&state, payload, || {}))
+        .await?;
+    encode_tts_response(response.clone().for_caller(query.debug), encoding)
+}
+
+/// Runs the synthesis in the background and answers `202` with a job the
+/// caller polls via `GET /api/tts/:request_id`. Answers `503` once
+/// `[api] max_pending_jobs` jobs are queued or running.
+fn enqueue_tts_job(
+    state: ApiState,
+    payload: SynthesizePayload,
+    debug: bool,
+) -> Result<Response, ApiError> {
+    let jobs = state.tts_jobs.clone();
+    let job_id = jobs.enqueue().map_err(|err| {
+        let JobQueueFull(limit) = err;
+        ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "job_queue_full",
+            err.to_string(),
+        )
+        .with_arg("limit", limit)
+    })?;
+    let snapshot = jobs.snapshot(job_id);
+    debug!(
+        target = "ishowtts::api::tts",
+        request_id = %job_id,
+        position = snapshot.as_ref().and_then(|job| job.position),
+        "synthesis slots busy; queued tts job"
+    );
+    tokio::spawn(async move {
+        let started = jobs.clone();
+        let outcome = synthesize_uncached(&state, payload, move || started.start(job_id))
+            .await
+            .map(|response| response.for_caller(debug));
+        jobs.finish(job_id, outcome);
+    });
+    Ok((
+        StatusCode::ACCEPTED,
+        [(header::LOCATION, format!("/api/tts/{job_id}"))],
+        Json(snapshot),
+    )
+        .into_response())
+}
+
+#[instrument(skip(state))]
+async fn get_tts_job(
+    State(state): State<ApiState>,
+    Path(request_id): Path<Uuid>,
+) -> Result<Json<JobSnapshot<SynthesizeResponse>>, ApiError> {
+    state
+        .tts_jobs
+        .snapshot(request_id)
+        .map(Json)
+        .ok_or_else(|| {
+            ApiError::from((
+                StatusCode::NOT_FOUND,
+                format!("no tts job '{request_id}' (finished jobs expire after a few minutes)"),
+            ))
+        })
+}
+
+/// Audio of an earlier `/api/tts` response, for a stable download URL or a
+/// replay without keeping the base64 around.
+#[instrument(skip(state))]
+async fn get_tts_audio(
+    State(state): State<ApiState>,
+    Path(request_id): Path<Uuid>,
+) -> Result<Response, ApiError> {
+    let Some(clips) = state.clips.as_ref() else {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "clip_retention_disabled",
+            "未开启音频保留（[api] clip_retention_secs）",
+        ));
+    };
+    let clip = clips.get(request_id).ok_or_else(|| {
+        let retention_secs = clips.ttl().as_secs();
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            "clip_not_found",
+            format!("没有请求 '{request_id}' 的音频（音频保留 {retention_secs} 秒）"),
+        )
+        .with_arg("request_id", request_id)
+        .with_arg("retention_secs", retention_secs)
+    })?;
+    let extension = audio_extension(clip.format);
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, clip.format)
+        .header("X-Request-Id", request_id.to_string())
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("inline; filename=\"tts-{request_id}.{extension}\""),
+        )
+        .body(Body::from(clip.audio))
+        .map_err(|err| {
+            ApiError::from((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("构建响应失败: {err}"),
+            ))
+        })
+}
+
+/// Progress ids are chosen by the client and used as map keys, so they are
+/// kept short and URL-safe.
+fn validate_progress_id(progress_id: &str) -> Result<(), ApiError> {
+    let valid = !progress_id.is_empty()
+        && progress_id.len() <= MAX_PROGRESS_ID_LEN
+        && progress_id
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_'));
+    if valid {
+        Ok(())
+    } else {
+        Err(ApiError::from((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "progress_id 只能包含字母、数字、- 和 _，且不超过 {MAX_PROGRESS_ID_LEN} 个字符"
+            ),
+        )))
+    }
+}
+
+/// Segment progress of the `POST /api/tts` carrying the same `progress_id`.
+/// The stream may be opened before the request is sent and ends with a
+/// `done` event once it finishes. Engines that render in one step send no
+/// progress, so listeners only see the final event.
+async fn stream_tts_progress(
+    State(state): State<ApiState>,
+    Path(progress_id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, ApiError> {
+    validate_progress_id(&progress_id)?;
+    let events = WatchStream::new(state.progress.subscribe(&progress_id))
+        .scan(false, |finished, progress| {
+            // Let the `done` update through, then end the stream.
+            let next = (!*finished).then_some(progress);
+            *finished |= progress.done;
+            future::ready(next)
+        })
+        .map(|progress| Event::default().event("progress").json_data(progress))
+        .take_until(tokio::time::sleep(DEFAULT_PROGRESS_TTL));
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+fn estimate_tts(
+    state: &ApiState,
+    payload: &SynthesizePayload,
+) -> Result<EstimateResponse, ApiError> {
+    let (voice_id, voice_meta, requested_engine) = resolve_payload_voice(state, payload)?;
+    let source_text = payload_text(payload)?;
+    let (text, truncated) = truncate_text(&source_text, state.synthesizer.max_request_words());
+    if text.is_empty() {
+        return Err(ApiError::text_empty());
+    }
+
+    let chars = text.chars().filter(|ch| !ch.is_whitespace()).count();
+    let chars_per_sec = state
+        .estimate
+        .chars_per_sec_for(&voice_id, voice_meta.language.as_deref());
+    let speed = payload.speed.filter(|speed| *speed > 0.0).unwrap_or(1.0);
+    let estimated_duration_ms = (chars as f32 / (chars_per_sec * speed) * 1000.0).round() as u64;
+    let engine = match requested_engine.as_deref() {
+        Some("shimmy") => "shimmy".to_string(),
+        _ => voice_meta.engine.as_str().to_string(),
+    };
+    Ok(EstimateResponse {
+        voice_id,
+        engine,
+        text,
+        truncated,
+        chars,
+        chars_per_sec,
+        speed,
+        estimated_duration_ms,
+    })
+}
+
+/// Picks the voice a payload targets: its `voice_id`, else the default for
+/// its `engine` (or the configured default engine), else the global default.
+/// Also returns the lowercased engine name the payload asked for.
+fn resolve_payload_voice(
+    state: &ApiState,
+    payload: &SynthesizePayload,
+) -> Result<(String, VoiceDescriptor, Option<String>), ApiError> {
     let requested_engine = payload
         .engine
         .as_ref()
         .map(|value| value.to_ascii_lowercase());
-    let is_shimmy = matches!(requested_engine.as_deref(), Some("shimmy"));
+    let voice_id = payload.voice_id.clone().unwrap_or_else(|| {
+        let defaults = state.default_voices.read();
+        requested_engine
+            .as_deref()
+            .map(|name| EngineKind::from_str(name).ok())
+            .unwrap_or(state.default_engine)
+            .and_then(|engine| defaults.per_engine.get(&engine).cloned())
+            .unwrap_or_else(|| defaults.voice.clone())
+    });
 
-    if let Some(engine_name) = requested_engine.as_deref() {
-        if engine_name != "shimmy" && engine_name != voice_meta.engine.as_str() {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                format!(
-                    "voice '{voice_id}' belongs to engine '{}', not '{engine_name}'",
-                    voice_meta.engine.as_str()
-                ),
-            ));
-        }
+    // Without an engine, a voice the default engine also has is rendered there.
+    let voice_meta = state
+        .default_engine
+        .and_then(|engine| state.synthesizer.resolve_voice(&voice_id, Some(engine)))
+        .or_else(|| state.synthesizer.voice_descriptor(&voice_id))
+        .ok_or_else(|| ApiError::voice_not_found(StatusCode::BAD_REQUEST, &voice_id))?;
+
+    // A voice id may be registered on several engines; an explicit engine
+    // picks that rendering instead of the default one.
+    let voice_meta = match requested_engine.as_deref() {
+        Some(engine_name) if engine_name != "shimmy" => EngineKind::from_str(engine_name)
+            .ok()
+            .and_then(|engine| state.synthesizer.resolve_voice(&voice_id, Some(engine)))
+            .ok_or_else(|| {
+                ApiError::engine_mismatch(&voice_id, voice_meta.engine.as_str(), engine_name)
+            })?,
+        _ => voice_meta,
+    };
+    Ok((voice_id, voice_meta, requested_engine))
+}
+
+async fn synthesize_uncached<F: FnOnce() + Send>(
+    state: &ApiState,
+    payload: SynthesizePayload,
+    on_start: F,
+) -> Result<SynthesizeResponse, ApiError> {
+    let Some(progress_id) = payload.progress_id.clone() else {
+        return synthesize_reporting(state, payload, on_start, None).await;
+    };
+    validate_progress_id(&progress_id)?;
+    let (sink, _finish) = state.progress.track(&progress_id);
+    synthesize_reporting(state, payload, on_start, Some(sink)).await
+}
+
+async fn synthesize_reporting<F: FnOnce() + Send>(
+    state: &ApiState,
+    payload: SynthesizePayload,
+    on_start: F,
+    progress: Option<ProgressSink>,
+) -> Result<SynthesizeResponse, ApiError> {
+    let started_at = Instant::now();
+    let (voice_id, voice_meta, requested_engine) = resolve_payload_voice(state, &payload)?;
+    let is_shimmy = matches!(requested_engine.as_deref(), Some("shimmy"));
+    if payload.segments.is_some() && (is_shimmy || voice_meta.engine != EngineKind::IndexTts) {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "segments_unsupported",
+            format!("音色 '{voice_id}' 所在的引擎不支持分段合成（仅 IndexTTS）"),
+        )
+        .with_arg("voice_id", &voice_id));
     }
 
-    let (truncated_text, _) = truncate_text(&payload.text, MAX_WORDS_PER_REQUEST);
+    let source_text = payload_text(&payload)?;
+    let (truncated_text, truncated) =
+        truncate_text(&source_text, state.synthesizer.max_request_words());
     if truncated_text.is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "text must not be empty".into()));
+        return Err(ApiError::text_empty());
+    }
+    if truncated && payload.segments.is_some() {
+        // Cutting words would leave the segments out of step with `text`.
+        return Err(ApiError::from((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "segments exceed {} words in total",
+                state.synthesizer.max_request_words()
+            ),
+        )));
     }
 
     let mut request = build_request(truncated_text.clone(), &payload, &voice_id);
@@ -177,12 +1314,15 @@ pub async fn synthesize(
         requested_engine = requested_engine.as_deref(),
         shimmy_model = payload.shimmy_model.as_deref(),
         text_len = text_for_request.len(),
-        original_len = payload.text.len(),
-        truncated = payload.text.len() != text_for_request.len(),
+        original_len = source_text.len(),
+        truncated,
+        segments = payload.segments.as_ref().map(Vec::len),
         text_preview = %text_preview_debug,
         "tts request accepted"
     );
     let raw_response: TtsResponse = if is_shimmy {
+        // Shimmy queues inside its own engine; report it as running now.
+        on_start();
         let model_id = payload
             .shimmy_model
             .clone()
@@ -219,18 +1359,28 @@ pub async fn synthesize(
                 format!("解析 Shimmy 响应失败: {err}"),
             )
         })?;
-        envelope.response
+        let mut response = envelope.response;
+        response.duration_ms = clip_duration_ms(response.waveform_len, response.sample_rate);
+        response
     } else {
         state
             .synthesizer
-            .synthesize(request)
+            .synthesize_with_progress(request, Some(voice_meta.engine), on_start, progress)
             .await
             .map_err(|err| (StatusCode::BAD_GATEWAY, err.to_string()))?
     };
-    let response = map_response(raw_response);
+    let mut response = map_response(raw_response);
+    response.effective_text = Some(if is_shimmy {
+        text_for_request.clone()
+    } else {
+        state
+            .synthesizer
+            .effective_text(&voice_meta.id, &text_for_request)
+    });
 
     let elapsed_ms = started_at.elapsed().as_millis();
-    let (audio_bytes, audio_kb) = match BASE64_STANDARD.decode(response.audio_base64.as_bytes()) {
+    let decoded = BASE64_STANDARD.decode(response.audio_base64.as_bytes());
+    let (audio_bytes, audio_kb) = match &decoded {
         Ok(buf) => {
             let len = buf.len();
             let kb = ((len as f64) / 1024.0 * 10.0).round() / 10.0;
@@ -245,8 +1395,18 @@ pub async fn synthesize(
             (0, 0.0)
         }
     };
+    if let (Some(clips), Ok(audio)) = (state.clips.as_ref(), decoded) {
+        clips.insert(
+            response.request_id,
+            StoredClip {
+                format: response.format,
+                audio: audio.into(),
+            },
+        );
+    }
 
     let text_preview_info = preview_text(&text_for_request);
+    let reference_text_preview = response.reference_text_used.as_deref().map(preview_text);
     info!(
         target = "ishowtts::api::tts",
         voice_id = %response.voice_id,
@@ -259,10 +1419,11 @@ pub async fn synthesize(
         audio_kb,
         text_len = text_for_request.len(),
         text_preview = %text_preview_info,
+        reference_text_preview = reference_text_preview.as_deref(),
         "tts synthesis complete"
     );
 
-    Ok(Json(response))
+    Ok(response)
 }
 
 fn map_response(resp: TtsResponse) -> SynthesizeResponse {
@@ -274,10 +1435,37 @@ fn map_response(resp: TtsResponse) -> SynthesizeResponse {
         sample_rate: resp.sample_rate,
         audio_base64: resp.audio_base64,
         waveform_len: resp.waveform_len,
+        duration_ms: resp.duration_ms,
         format: "audio/wav",
+        reference_text_used: resp.reference_text_used,
+        nfe_step_used: resp.nfe_step_used,
+        peak: resp.peak,
+        rms: resp.rms,
+        fallback_used: resp.fallback_used,
+        segment_timings: resp.segment_timings,
+        effective_text: None,
     }
 }
 
+/// The text a payload asks to speak: its segments joined, else `text`.
+fn payload_text(payload: &SynthesizePayload) -> Result<String, ApiError> {
+    let Some(segments) = payload.segments.as_deref() else {
+        return Ok(payload.text.clone());
+    };
+    if segments.is_empty()
+        || segments
+            .iter()
+            .any(|segment| segment.text.trim().is_empty())
+    {
+        return Err(ApiError::text_empty());
+    }
+    Ok(segments
+        .iter()
+        .map(|segment| segment.text.trim())
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
 fn shimmy_default_voice(spec: &ModelSpec) -> Option<String> {
     spec.template.as_ref().and_then(|template| {
         template
@@ -300,6 +1488,9 @@ fn build_request(text: String, payload: &SynthesizePayload, voice_id: &str) -> T
         fix_duration: payload.fix_duration,
         remove_silence: payload.remove_silence,
         seed: payload.seed,
+        normalize_output_rms: payload.normalize_output_rms,
+        no_cache: payload.no_cache,
+        segments: payload.segments.clone(),
     }
 }
 
@@ -321,15 +1512,23 @@ fn truncate_text(text: &str, max_words: usize) -> (String, bool) {
     (truncated, true)
 }
 
-pub fn build_api_router(state: ApiState) -> Router {
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
-        .allow_headers(Any);
+pub fn build_api_router(state: ApiState, allowed_origins: Option<&[String]>) -> Result<Router> {
+    let cors = api_cors_layer(allowed_origins)?;
 
     let api_routes = Router::new()
         .route("/health", get(health))
+        .route("/ready", get(ready))
         .route("/voices", get(list_voices))
+        .route("/engines", get(list_engines))
+        .route("/engines/:engine/warmup", post(warmup_engine))
+        .route("/admin/reload-voices", post(reload_voices))
+        .route("/voices/stats", get(voice_stats))
+        .route("/voices/validate", post(validate_voices))
+        .route(
+            "/pronunciations",
+            get(list_pronunciations).post(add_pronunciation),
+        )
+        .route("/voices/:voice_id", get(get_voice))
         .route(
             "/voices/:voice_id/reference",
             get(get_voice_reference)
@@ -340,17 +1539,107 @@ pub fn build_api_router(state: ApiState) -> Router {
             "/voices/:voice_id/reference/audio",
             get(get_voice_reference_audio),
         )
-        .route("/tts", post(synthesize))
+        .route(
+            "/voices/:voice_id/reference/waveform",
+            get(get_voice_reference_waveform),
+        )
+        .route(
+            "/tts",
+            post(synthesize).layer(DefaultBodyLimit::max(state.max_request_bytes)),
+        )
+        .route("/tts/:request_id", get(get_tts_job))
+        .route("/tts/:request_id/audio", get(get_tts_audio))
+        .route("/tts/progress/:progress_id", get(stream_tts_progress))
         .route("/danmaku/start", post(start_danmaku))
         .route("/danmaku/stop", post(stop_danmaku))
         .route("/danmaku/enqueue", post(enqueue_danmaku))
+        .route("/danmaku/say", post(say_danmaku))
+        .route("/danmaku/skip/:channel", post(skip_danmaku))
+        .route("/danmaku/filter-test", post(filter_test_danmaku))
+        .route(
+            "/danmaku/session/:channel/export",
+            get(export_danmaku_session),
+        )
+        // Served with CORS so overlays on other origins can use EventSource.
+        .route("/danmaku/events", get(stream_danmaku_events))
         .with_state(state.clone())
+        // Inside compression so the localized body is the one compressed.
+        .layer(middleware::from_fn(localize_errors))
+        .layer(api_compression_layer(state.http_compression))
         .layer(cors);
 
-    Router::new()
-        .merge(api_routes)
-        .route("/danmaku/stream", get(stream_danmaku_ws))
-        .with_state(state)
+    let router = Router::new()
+        .route("/danmaku/stream", get(stream_danmaku_ws))
+        .route("/danmaku/status", get(danmaku_status))
+        .route("/danmaku/presence", get(danmaku_presence))
+        .route("/danmaku/presence/stream", get(stream_presence_ws))
+        .with_state(state)
+        .layer(middleware::from_fn(localize_errors))
+        .merge(api_routes);
+    Ok(router)
+}
+
+/// Re-renders [`ApiError`] bodies in the language asked for by
+/// `Accept-Language`; handlers always produce the Chinese default.
+async fn localize_errors(request: Request, next: Next) -> Response {
+    let locale = Locale::from_accept_language(
+        request
+            .headers()
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok()),
+    );
+    let response = next.run(request).await;
+    if locale == Locale::default() {
+        return response;
+    }
+    let Some(error) = response.extensions().get::<ApiError>().cloned() else {
+        return response;
+    };
+    let (mut parts, _) = response.into_parts();
+    let (_, body) = error.localized_response(locale).into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, body)
+}
+
+fn api_cors_layer(allowed_origins: Option<&[String]>) -> Result<CorsLayer> {
+    let methods = [Method::GET, Method::POST, Method::OPTIONS];
+    let Some(origins) = allowed_origins else {
+        return Ok(CorsLayer::new()
+            .allow_origin(Any)
+            .allow_methods(methods)
+            .allow_headers(Any));
+    };
+
+    let origins = origins
+        .iter()
+        .map(|origin| origin.trim().trim_end_matches('/'))
+        .filter(|origin| !origin.is_empty())
+        .map(|origin| {
+            HeaderValue::from_str(origin)
+                .with_context(|| format!("invalid entry in api.allowed_origins: {origin}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    info!(
+        target = "ishowtts::backend",
+        origins = origins.len(),
+        "restricting CORS to configured origins"
+    );
+    // Credentials rule out wildcard headers, so echo back what the browser asks for.
+    Ok(CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods(methods)
+        .allow_headers(AllowHeaders::mirror_request())
+        .allow_credentials(true))
+}
+
+/// Compresses JSON for clients that accept it. WAV, PCM and reference clips
+/// are excluded by content type, and `text/event-stream` by the default
+/// predicate; the danmaku websockets live outside this router altogether.
+fn api_compression_layer(enabled: bool) -> CompressionLayer<impl Predicate> {
+    CompressionLayer::new()
+        .gzip(enabled)
+        .br(enabled)
+        .compress_when(DefaultPredicate::new().and(NotForContentType::const_new("audio/")))
 }
 
 pub fn build_shimmy_router(state: Arc<ShimmyAppState>) -> Router {
@@ -402,21 +1691,83 @@ struct VoiceReferenceResponse {
     override_reference_text: Option<String>,
     baseline_audio_available: bool,
     override_audio_available: bool,
+    /// Clips currently fed to the engine, in prompt order.
+    reference_clips: Vec<ReferenceClip>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    override_updated_at: Option<DateTime<Utc>>,
+    /// Set when the uploaded reference text was cut to
+    /// `max_reference_text_chars`.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    reference_text_truncated: bool,
+}
+
+/// A single voice with its override state, for callers that need one entry
+/// rather than the whole list.
+#[derive(Debug, Serialize)]
+struct VoiceDetailResponse {
+    #[serde(flatten)]
+    descriptor: VoiceDescriptor,
+    has_override: bool,
+    override_audio: bool,
+    override_text: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     override_updated_at: Option<DateTime<Utc>>,
 }
 
+#[derive(Debug, Serialize)]
+struct ReferenceClip {
+    /// `baseline` or `override`; pass it with `index` to `/reference/audio`.
+    source: &'static str,
+    index: usize,
+    file_name: Option<String>,
+    available: bool,
+}
+
 #[derive(Debug, Deserialize)]
 struct VoiceReferenceAudioQuery {
     source: String,
+    #[serde(default)]
+    index: usize,
+    #[serde(default)]
+    engine: Option<EngineKind>,
+}
+
+/// `?engine=` picks which rendering of a voice id registered on several
+/// engines a voice route acts on; without it the default one is used.
+#[derive(Debug, Default, Deserialize)]
+struct VoiceEngineQuery {
+    #[serde(default)]
+    engine: Option<EngineKind>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VoiceReferenceWaveformQuery {
+    source: String,
+    #[serde(default)]
+    index: usize,
+    #[serde(default)]
+    buckets: Option<usize>,
+    #[serde(default)]
+    engine: Option<EngineKind>,
+}
+
+#[derive(Serialize)]
+struct VoiceReferenceWaveformResponse {
+    voice_id: String,
+    source: &'static str,
+    index: usize,
+    sample_rate: u32,
+    duration_ms: u64,
+    buckets: Vec<WaveformBucket>,
 }
 
 #[instrument(skip(state))]
 async fn get_voice_reference(
     State(state): State<ApiState>,
     Path(voice_id): Path<String>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let payload = build_voice_reference_response(&state, &voice_id)?;
+    Query(query): Query<VoiceEngineQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let payload = build_voice_reference_response(&state, &voice_id, query.engine)?;
     let text_override = payload
         .override_reference_text
         .as_ref()
@@ -453,28 +1804,43 @@ async fn get_voice_reference(
     Ok(Json(payload))
 }
 
+#[instrument(skip(state, headers))]
+async fn get_voice(
+    State(state): State<ApiState>,
+    Path(voice_id): Path<String>,
+    Query(query): Query<VoiceEngineQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let descriptor = lookup_voice(&state, &voice_id, query.engine)?;
+    let record = state.voice_overrides.get(&voice_id, descriptor.engine);
+    let response = VoiceDetailResponse {
+        has_override: record.is_some(),
+        override_audio: record
+            .as_ref()
+            .is_some_and(|record| !record.reference_audio.is_empty()),
+        override_text: record
+            .as_ref()
+            .is_some_and(|record| record.reference_text.is_some()),
+        override_updated_at: record.and_then(|record| record.updated_at),
+        descriptor,
+    };
+    Ok(json_with_etag(&headers, &response))
+}
+
 #[instrument(skip(state, multipart))]
 async fn set_voice_reference(
     State(state): State<ApiState>,
     Path(voice_id): Path<String>,
+    Query(query): Query<VoiceEngineQuery>,
     mut multipart: Multipart,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let descriptor = state
-        .synthesizer
-        .voice_descriptor(&voice_id)
-        .or_else(|| {
-            state
-                .synthesizer
-                .voices()
-                .into_iter()
-                .find(|voice| voice.id == voice_id)
-        })
-        .ok_or((StatusCode::NOT_FOUND, format!("未知音色 '{voice_id}'")))?;
-    let engine = descriptor.engine;
+) -> Result<impl IntoResponse, ApiError> {
+    let engine = lookup_voice(&state, &voice_id, query.engine)?.engine;
 
     let mut text_override: Option<String> = None;
     let mut text_supplied = false;
     let mut temp_audio: Option<OverrideAudio> = None;
+    let mut audio_url: Option<String> = None;
+    let mut clip_mode = ClipMode::Replace;
 
     while let Some(field) = multipart
         .next_field()
@@ -491,6 +1857,32 @@ async fn set_voice_reference(
                     .map_err(|err| (StatusCode::BAD_REQUEST, format!("读取文本失败: {err}")))?;
                 text_override = Some(value.trim().to_string());
             }
+            Some("mode") => {
+                let value = field
+                    .text()
+                    .await
+                    .map_err(|err| (StatusCode::BAD_REQUEST, format!("读取 mode 失败: {err}")))?;
+                clip_mode = match value.trim().to_ascii_lowercase().as_str() {
+                    "" | "replace" => ClipMode::Replace,
+                    "append" => ClipMode::Append,
+                    other => {
+                        return Err(ApiError::from((
+                            StatusCode::BAD_REQUEST,
+                            format!("未知的 mode '{other}'，可选 replace 或 append"),
+                        )));
+                    }
+                };
+            }
+            Some("audio_url") => {
+                let value = field
+                    .text()
+                    .await
+                    .map_err(|err| (StatusCode::BAD_REQUEST, format!("读取音频链接失败: {err}")))?;
+                let value = value.trim();
+                if !value.is_empty() {
+                    audio_url = Some(value.to_string());
+                }
+            }
             Some("audio") => {
                 let filename_ext = field
                     .file_name()
@@ -520,8 +1912,8 @@ async fn set_voice_reference(
                 if data.is_empty() {
                     continue;
                 }
-                if data.len() > 10 * 1024 * 1024 {
-                    return Err((StatusCode::BAD_REQUEST, "音频文件超过 10MB 限制".into()));
+                if data.len() > MAX_REFERENCE_AUDIO_BYTES {
+                    return Err((StatusCode::BAD_REQUEST, "音频文件超过 10MB 限制".into()).into());
                 }
 
                 temp_audio = Some(OverrideAudio {
@@ -533,11 +1925,53 @@ async fn set_voice_reference(
         }
     }
 
+    if let Some(url) = audio_url {
+        if temp_audio.is_some() {
+            return Err(ApiError::from((
+                StatusCode::BAD_REQUEST,
+                "audio 与 audio_url 只能提供其中一个".into(),
+            )));
+        }
+        temp_audio = Some(fetch_reference_audio(&url).await?);
+    }
+
     if temp_audio.is_none() && !text_supplied {
-        return Err((
+        return Err(ApiError::from((
             StatusCode::BAD_REQUEST,
             "请上传参考音频或提供参考文本".into(),
-        ));
+        )));
+    }
+    if let Some(audio) = &temp_audio {
+        verify_reference_audio(audio).await?;
+    }
+
+    let mut text_truncated = false;
+    if let Some(text) = text_override.as_mut() {
+        let limits = &state.voice_overrides_config;
+        let max_chars = limits.max_reference_text_chars.get(&engine).copied();
+        let (limited, truncated) =
+            limit_reference_text(text, max_chars, limits.reference_text_overflow).map_err(
+                |err| {
+                    ApiError::new(
+                        StatusCode::BAD_REQUEST,
+                        "reference_text_too_long",
+                        err.to_string(),
+                    )
+                    .with_arg("chars", err.chars)
+                    .with_arg("max_chars", err.max_chars)
+                },
+            )?;
+        if truncated {
+            warn!(
+                target = "ishowtts::api::voices",
+                voice = %voice_id,
+                %engine,
+                max_chars,
+                "reference text truncated"
+            );
+        }
+        *text = limited;
+        text_truncated = truncated;
     }
 
     let incoming_text_len = text_override.as_ref().map(|text| text.len());
@@ -563,9 +1997,52 @@ async fn set_voice_reference(
         None
     };
 
+    let baseline = state.synthesizer.baseline(&voice_id, engine);
+    // An empty text field clears the override text, falling back to the
+    // baseline transcript.
+    let resulting_text = match text_for_store.as_deref().map(str::trim) {
+        Some(text) if !text.is_empty() => Some(text.to_string()),
+        Some(_) => None,
+        None => state
+            .voice_overrides
+            .get(&voice_id, engine)
+            .and_then(|record| record.reference_text),
+    }
+    .or_else(|| {
+        baseline
+            .as_ref()
+            .and_then(|baseline| baseline.reference_text.clone())
+    });
+    ensure_reference_text(engine, resulting_text.as_deref())?;
+
+    let baseline_audio = baseline
+        .map(|baseline| baseline.reference_audio)
+        .unwrap_or_default();
+    if temp_audio.is_some() && clip_mode == ClipMode::Append {
+        let active = state
+            .voice_overrides
+            .get(&voice_id, engine)
+            .map(|record| record.reference_audio.len())
+            .filter(|count| *count > 0)
+            .unwrap_or(baseline_audio.len());
+        if active >= MAX_REFERENCE_CLIPS {
+            return Err(ApiError::from((
+                StatusCode::BAD_REQUEST,
+                format!("每个音色最多 {MAX_REFERENCE_CLIPS} 段参考音频"),
+            )));
+        }
+    }
+
     let record = state
         .voice_overrides
-        .set(&voice_id, engine, temp_audio.clone(), text_for_store)
+        .set(
+            &voice_id,
+            engine,
+            temp_audio.clone(),
+            clip_mode,
+            &baseline_audio,
+            text_for_store,
+        )
         .map_err(|err| {
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -573,10 +2050,7 @@ async fn set_voice_reference(
             )
         })?;
 
-    let update = VoiceOverrideUpdate {
-        reference_audio: record.reference_audio.clone(),
-        reference_text: record.reference_text.clone(),
-    };
+    let update = record.engine_update();
 
     state
         .synthesizer
@@ -588,7 +2062,8 @@ async fn set_voice_reference(
             )
         })?;
 
-    let payload = build_voice_reference_response(&state, &voice_id)?;
+    let mut payload = build_voice_reference_response(&state, &voice_id, Some(engine))?;
+    payload.reference_text_truncated = text_truncated;
     let text_override = payload
         .override_reference_text
         .as_ref()
@@ -612,13 +2087,15 @@ async fn set_voice_reference(
         .map(|text| preview_text(text));
     let override_audio_path = record
         .reference_audio
-        .as_ref()
+        .last()
         .map(|path| path.to_string_lossy().to_string());
     info!(
         target = "ishowtts::api::voices",
         voice = %payload.voice_id,
         engine = %payload.engine,
         audio_override = payload.override_audio_available,
+        clips = payload.reference_clips.len(),
+        ?clip_mode,
         text_override,
         incoming_text_len,
         incoming_text_preview = incoming_text_preview.as_deref(),
@@ -633,23 +2110,130 @@ async fn set_voice_reference(
     Ok(Json(payload))
 }
 
+/// Rejects an override change that would leave a voice without the reference
+/// transcript its engine needs.
+fn ensure_reference_text(engine: EngineKind, active_text: Option<&str>) -> Result<(), ApiError> {
+    let missing = active_text
+        .map(|text| text.trim().is_empty())
+        .unwrap_or(true);
+    if engine.requires_reference_text() && missing {
+        return Err(ApiError::reference_text_required());
+    }
+    Ok(())
+}
+
+/// Downloads a reference clip for `set_voice_reference`, applying the same
+/// size and format limits as a direct upload. Only public http(s) targets are
+/// allowed, and the resolved address is pinned so DNS cannot swap it for an
+/// internal one between the check and the request.
+async fn fetch_reference_audio(raw_url: &str) -> Result<OverrideAudio, ApiError> {
+    let url = reqwest::Url::parse(raw_url)
+        .map_err(|err| (StatusCode::BAD_REQUEST, format!("音频链接无效: {err}")))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(ApiError::from((
+            StatusCode::BAD_REQUEST,
+            "音频链接仅支持 http/https".into(),
+        )));
+    }
+    let host = url_host(&url).ok_or((StatusCode::BAD_REQUEST, "音频链接缺少主机名".into()))?;
+    let port = url
+        .port_or_known_default()
+        .ok_or((StatusCode::BAD_REQUEST, "音频链接缺少端口".into()))?;
+
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), port))
+        .await
+        .map_err(|err| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("无法解析音频链接主机: {err}"),
+            )
+        })?
+        .collect();
+    let target = addrs
+        .first()
+        .copied()
+        .ok_or((StatusCode::BAD_REQUEST, "无法解析音频链接主机".into()))?;
+    if addrs.iter().any(|addr| !is_public_ip(addr.ip())) {
+        warn!(
+            target = "ishowtts::api::voices",
+            %host,
+            "rejected reference audio url pointing at a private address"
+        );
+        return Err(ApiError::from((
+            StatusCode::BAD_REQUEST,
+            "音频链接不能指向内网地址".into(),
+        )));
+    }
+
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(REFERENCE_AUDIO_FETCH_TIMEOUT)
+        .resolve(&host, target)
+        .build()
+        .map_err(|err| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("创建下载客户端失败: {err}"),
+            )
+        })?;
+    let mut response = client
+        .get(url.clone())
+        .send()
+        .await
+        .map_err(|err| (StatusCode::BAD_GATEWAY, format!("下载参考音频失败: {err}")))?;
+    if !response.status().is_success() {
+        return Err(ApiError::from((
+            StatusCode::BAD_GATEWAY,
+            format!("下载参考音频失败: HTTP {}", response.status()),
+        )));
+    }
+    if response
+        .content_length()
+        .map(|len| len > MAX_REFERENCE_AUDIO_BYTES as u64)
+        .unwrap_or(false)
+    {
+        return Err((StatusCode::BAD_REQUEST, "音频文件超过 10MB 限制".into()).into());
+    }
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|err| (StatusCode::BAD_GATEWAY, format!("下载参考音频失败: {err}")))?
+    {
+        if bytes.len() + chunk.len() > MAX_REFERENCE_AUDIO_BYTES {
+            return Err((StatusCode::BAD_REQUEST, "音频文件超过 10MB 限制".into()).into());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    if bytes.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "下载的参考音频为空".into()).into());
+    }
+
+    let extension = infer_audio_extension_from_bytes(&bytes).ok_or((
+        StatusCode::BAD_REQUEST,
+        "无法识别的音频格式，仅支持 wav/mp3/flac/ogg/m4a/opus".into(),
+    ))?;
+    debug!(
+        target = "ishowtts::api::voices",
+        %host,
+        bytes = bytes.len(),
+        format = %extension,
+        "reference audio downloaded"
+    );
+    Ok(OverrideAudio {
+        bytes,
+        extension: Some(extension),
+    })
+}
+
 #[instrument(skip(state))]
 async fn delete_voice_reference(
     State(state): State<ApiState>,
     Path(voice_id): Path<String>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let descriptor = state
-        .synthesizer
-        .voice_descriptor(&voice_id)
-        .or_else(|| {
-            state
-                .synthesizer
-                .voices()
-                .into_iter()
-                .find(|voice| voice.id == voice_id)
-        })
-        .ok_or((StatusCode::NOT_FOUND, format!("未知音色 '{voice_id}'")))?;
-    let engine = descriptor.engine;
+    Query(query): Query<VoiceEngineQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let engine = lookup_voice(&state, &voice_id, query.engine)?.engine;
 
     debug!(
         target = "ishowtts::api::voices",
@@ -658,6 +2242,14 @@ async fn delete_voice_reference(
         "voice reference reset requested"
     );
 
+    let baseline = state.synthesizer.baseline(&voice_id, engine);
+    ensure_reference_text(
+        engine,
+        baseline
+            .as_ref()
+            .and_then(|baseline| baseline.reference_text.as_deref()),
+    )?;
+
     state
         .voice_overrides
         .remove(&voice_id, engine)
@@ -668,7 +2260,7 @@ async fn delete_voice_reference(
             )
         })?;
 
-    if let Some(baseline) = state.synthesizer.baseline(&voice_id) {
+    if let Some(baseline) = baseline {
         let update = VoiceOverrideUpdate {
             reference_audio: Some(baseline.reference_audio.clone()),
             reference_text: baseline.reference_text.clone(),
@@ -690,7 +2282,7 @@ async fn delete_voice_reference(
         );
     }
 
-    let payload = build_voice_reference_response(&state, &voice_id)?;
+    let payload = build_voice_reference_response(&state, &voice_id, Some(engine))?;
     let active_text_len = payload
         .active_reference_text
         .as_ref()
@@ -725,100 +2317,272 @@ async fn get_voice_reference_audio(
     State(state): State<ApiState>,
     Path(voice_id): Path<String>,
     Query(query): Query<VoiceReferenceAudioQuery>,
-) -> Result<Response, (StatusCode, String)> {
+) -> Result<Response, ApiError> {
     debug!(
         target = "ishowtts::api::voices",
         voice = %voice_id,
         source = %query.source,
+        index = query.index,
         "voice reference audio requested"
     );
-    let descriptor = state
-        .synthesizer
-        .voice_descriptor(&voice_id)
-        .ok_or((StatusCode::NOT_FOUND, format!("未知音色 '{voice_id}'")))?;
-    let engine = descriptor.engine;
+    let (source_label, audio_path) =
+        resolve_reference_clip(&state, &voice_id, query.engine, &query.source, query.index)?;
+
+    let data = fs::read(&audio_path)
+        .await
+        .map_err(|err| (StatusCode::NOT_FOUND, format!("读取音频失败: {err}")))?;
+
+    // Overrides keep whatever container the user uploaded, so label the
+    // response by its actual bytes rather than assuming WAV.
+    let detected_ext = detect_audio_extension(&data, &audio_path);
+    let content_type = detected_ext
+        .as_deref()
+        .map(audio_content_type)
+        .unwrap_or("application/octet-stream");
+    debug!(
+        target = "ishowtts::api::voices",
+        voice = %voice_id,
+        source = source_label,
+        format = detected_ext.as_deref(),
+        content_type,
+        "voice reference audio format detected"
+    );
+
+    let mut builder = Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", content_type)
+        .header("Cache-Control", "no-store")
+        .header("X-Voice-Reference-Source", source_label);
+
+    if let Some(filename) = audio_path.file_name().and_then(|s| s.to_str()) {
+        if let Ok(value) = HeaderValue::from_str(&format!("inline; filename=\"{}\"", filename)) {
+            builder = builder.header("Content-Disposition", value);
+        }
+    }
+
+    builder.body(Body::from(data)).map_err(|err| {
+        ApiError::from((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("构建响应失败: {err}"),
+        ))
+    })
+}
+
+/// Rejects uploads the engines would choke on later: video containers,
+/// unrecognised formats and audio that does not decode. Opus is only
+/// sniffed, since the decoder here cannot read it.
+async fn verify_reference_audio(audio: &OverrideAudio) -> Result<(), ApiError> {
+    let invalid =
+        |message: String| ApiError::new(StatusCode::BAD_REQUEST, "invalid_audio", message);
+    if waveform::contains_video(&audio.bytes) {
+        return Err(invalid(
+            "上传的文件包含视频轨道，请只上传音频（wav/mp3/flac/ogg/opus/m4a）".into(),
+        ));
+    }
+    let Some(extension) = infer_audio_extension_from_bytes(&audio.bytes) else {
+        return Err(invalid(
+            "无法识别的音频格式，支持 wav/mp3/flac/ogg/opus/m4a".into(),
+        ));
+    };
+    if extension == "opus" {
+        return Ok(());
+    }
+
+    let data = audio.bytes.clone();
+    let decoded =
+        tokio::task::spawn_blocking(move || waveform::decode_mono(data, Some(extension.as_str())))
+            .await
+            .map_err(|err| {
+                ApiError::from((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("解码任务失败: {err}"),
+                ))
+            })?;
+    match decoded {
+        Ok((samples, _)) if !samples.is_empty() => Ok(()),
+        Ok(_) => Err(invalid("参考音频不含任何采样".into())),
+        Err(err) => Err(invalid(format!("无法解码参考音频: {err}"))),
+    }
+}
+
+#[instrument(skip(state))]
+async fn get_voice_reference_waveform(
+    State(state): State<ApiState>,
+    Path(voice_id): Path<String>,
+    Query(query): Query<VoiceReferenceWaveformQuery>,
+) -> Result<Json<VoiceReferenceWaveformResponse>, ApiError> {
+    let (source_label, audio_path) =
+        resolve_reference_clip(&state, &voice_id, query.engine, &query.source, query.index)?;
+
+    let data = fs::read(&audio_path)
+        .await
+        .map_err(|err| (StatusCode::NOT_FOUND, format!("读取音频失败: {err}")))?;
+    let extension = detect_audio_extension(&data, &audio_path);
+    let (samples, sample_rate) =
+        tokio::task::spawn_blocking(move || waveform::decode_mono(data, extension.as_deref()))
+            .await
+            .map_err(|err| {
+                ApiError::from((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("解码任务失败: {err}"),
+                ))
+            })?
+            .map_err(|err| {
+                ApiError::new(
+                    StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                    "unsupported_audio",
+                    format!("无法解码参考音频: {err}"),
+                )
+            })?;
+
+    let bucket_count = query
+        .buckets
+        .unwrap_or(DEFAULT_WAVEFORM_BUCKETS)
+        .clamp(1, MAX_WAVEFORM_BUCKETS);
+    let buckets = waveform::summarize(&samples, bucket_count);
+    debug!(
+        target = "ishowtts::api::voices",
+        voice = %voice_id,
+        source = source_label,
+        index = query.index,
+        samples = samples.len(),
+        buckets = buckets.len(),
+        "voice reference waveform computed"
+    );
 
-    let (source_label, audio_path) = match query.source.to_ascii_lowercase().as_str() {
+    Ok(Json(VoiceReferenceWaveformResponse {
+        voice_id,
+        source: source_label,
+        index: query.index,
+        sample_rate,
+        duration_ms: samples.len() as u64 * 1000 / sample_rate as u64,
+        buckets,
+    }))
+}
+
+/// Finds clip `index` of the voice's baseline or override reference audio.
+fn resolve_reference_clip(
+    state: &ApiState,
+    voice_id: &str,
+    engine: Option<EngineKind>,
+    source: &str,
+    index: usize,
+) -> Result<(&'static str, std::path::PathBuf), ApiError> {
+    let engine = lookup_voice(state, voice_id, engine)?.engine;
+
+    let (source_label, clips) = match source.to_ascii_lowercase().as_str() {
         "baseline" => {
             let baseline = state
                 .synthesizer
-                .baseline(&voice_id)
+                .baseline(voice_id, engine)
                 .ok_or((StatusCode::NOT_FOUND, "该音色没有默认参考音频".into()))?;
             ("baseline", baseline.reference_audio)
         }
         "override" => {
             let record = state
                 .voice_overrides
-                .get(&voice_id, engine)
+                .get(voice_id, engine)
                 .ok_or((StatusCode::NOT_FOUND, "尚未上传参考音频覆盖".into()))?;
-            let path = record
-                .reference_audio
-                .ok_or((StatusCode::NOT_FOUND, "覆盖记录缺少音频文件".into()))?;
-            ("override", path)
+            if record.reference_audio.is_empty() {
+                return Err(ApiError::from((
+                    StatusCode::NOT_FOUND,
+                    "覆盖记录缺少音频文件".into(),
+                )));
+            }
+            ("override", record.reference_audio)
         }
         other => {
-            return Err((
+            return Err(ApiError::from((
                 StatusCode::BAD_REQUEST,
                 format!("未知的 source 参数 '{other}'"),
-            ));
+            )));
         }
     };
 
-    let data = fs::read(&audio_path)
-        .await
-        .map_err(|err| (StatusCode::NOT_FOUND, format!("读取音频失败: {err}")))?;
+    let audio_path = clips.into_iter().nth(index).ok_or_else(|| {
+        ApiError::from((
+            StatusCode::NOT_FOUND,
+            format!("参考音频片段 {index} 不存在"),
+        ))
+    })?;
+    Ok((source_label, audio_path))
+}
 
-    let mut builder = Response::builder()
-        .status(StatusCode::OK)
-        .header("Content-Type", "audio/wav")
-        .header("Cache-Control", "no-store")
-        .header("X-Voice-Reference-Source", source_label);
+/// Container of a stored clip, judged by its bytes first and its file
+/// extension second.
+fn detect_audio_extension(data: &[u8], path: &std::path::Path) -> Option<String> {
+    infer_audio_extension_from_bytes(data).or_else(|| {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase())
+    })
+}
 
-    if let Some(filename) = audio_path.file_name().and_then(|s| s.to_str()) {
-        if let Ok(value) = HeaderValue::from_str(&format!("inline; filename=\"{}\"", filename)) {
-            builder = builder.header("Content-Disposition", value);
-        }
+/// `voice_id` on `engine`; without one, on the default engine when it has
+/// the voice, as `/api/tts` resolves it.
+fn lookup_voice(
+    state: &ApiState,
+    voice_id: &str,
+    engine: Option<EngineKind>,
+) -> Result<VoiceDescriptor, ApiError> {
+    let synthesizer = &state.synthesizer;
+    match engine {
+        Some(engine) => synthesizer.resolve_voice(voice_id, Some(engine)),
+        None => state
+            .default_engine
+            .and_then(|engine| synthesizer.resolve_voice(voice_id, Some(engine)))
+            .or_else(|| synthesizer.voice_descriptor(voice_id)),
     }
-
-    builder.body(Body::from(data)).map_err(|err| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("构建响应失败: {err}"),
-        )
-    })
+    .ok_or_else(|| ApiError::voice_not_found(StatusCode::NOT_FOUND, voice_id))
 }
 
 fn build_voice_reference_response(
     state: &ApiState,
     voice_id: &str,
-) -> Result<VoiceReferenceResponse, (StatusCode, String)> {
-    let descriptor = state
-        .synthesizer
-        .voice_descriptor(voice_id)
-        .or_else(|| {
-            state
-                .synthesizer
-                .voices()
-                .into_iter()
-                .find(|voice| voice.id == voice_id)
-        })
-        .ok_or((StatusCode::NOT_FOUND, format!("未知音色 '{voice_id}'")))?;
+    engine: Option<EngineKind>,
+) -> Result<VoiceReferenceResponse, ApiError> {
+    let descriptor = lookup_voice(state, voice_id, engine)?;
 
     let engine = descriptor.engine;
-    let baseline = state.synthesizer.baseline(voice_id);
+    let baseline = state.synthesizer.baseline(voice_id, engine);
     let override_record = state.voice_overrides.get(voice_id, engine);
 
     let baseline_audio_available = baseline
         .as_ref()
-        .map(|record| record.reference_audio.exists())
+        .map(|record| clips_available(&record.reference_audio))
         .unwrap_or(false);
 
     let override_audio_available = override_record
         .as_ref()
-        .and_then(|record| record.reference_audio.as_ref())
-        .map(|path| path.exists())
+        .map(|record| clips_available(&record.reference_audio))
         .unwrap_or(false);
 
+    let (active_source, active_clips) = match override_record.as_ref() {
+        Some(record) if !record.reference_audio.is_empty() => {
+            ("override", record.reference_audio.as_slice())
+        }
+        _ => (
+            "baseline",
+            baseline
+                .as_ref()
+                .map(|record| record.reference_audio.as_slice())
+                .unwrap_or_default(),
+        ),
+    };
+    let reference_clips = active_clips
+        .iter()
+        .enumerate()
+        .map(|(index, path)| ReferenceClip {
+            source: active_source,
+            index,
+            file_name: path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(str::to_string),
+            available: path.exists(),
+        })
+        .collect();
+
     Ok(VoiceReferenceResponse {
         voice_id: voice_id.to_string(),
         engine: engine.as_str().to_string(),
@@ -833,18 +2597,22 @@ fn build_voice_reference_response(
             .and_then(|record| record.reference_text.clone()),
         baseline_audio_available,
         override_audio_available,
+        reference_clips,
         override_updated_at: override_record.and_then(|record| record.updated_at),
+        reference_text_truncated: false,
     })
 }
 
+fn clips_available(clips: &[std::path::PathBuf]) -> bool {
+    !clips.is_empty() && clips.iter().all(|path| path.exists())
+}
+
 #[instrument(skip(state, payload))]
 async fn start_danmaku(
     State(state): State<ApiState>,
     Json(payload): Json<StartRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let service = state
-        .danmaku
-        .ok_or((StatusCode::NOT_IMPLEMENTED, "弹幕播报未启用".into()))?;
+) -> Result<impl IntoResponse, ApiError> {
+    let service = state.danmaku.ok_or_else(ApiError::danmaku_disabled)?;
     debug!(
         target = "ishowtts::api::danmaku",
         platform = %payload.platform,
@@ -853,57 +2621,92 @@ async fn start_danmaku(
         engine = payload.engine.as_deref(),
         "danmaku start requested"
     );
-    match payload.platform.to_lowercase().as_str() {
-        "twitch" => {
-            let engine = match payload.engine.as_deref() {
-                Some(value) => match EngineKind::from_str(value) {
-                    Ok(kind) => Some(kind),
-                    Err(_) => {
-                        return Err((StatusCode::BAD_REQUEST, format!("不支持的模型 '{value}'")))
-                    }
-                },
-                None => None,
-            };
+    let engine = match payload.engine.as_deref() {
+        Some(value) => match EngineKind::from_str(value) {
+            Ok(kind) => Some(kind),
+            Err(_) => {
+                return Err(ApiError::new(
+                    StatusCode::BAD_REQUEST,
+                    "unknown_engine",
+                    format!("不支持的模型 '{value}'"),
+                )
+                .with_arg("engine", value))
+            }
+        },
+        None => None,
+    };
 
-            let channel = service
-                .start_twitch(&payload.channel, payload.voice_id.clone(), engine)
+    let started = match payload.platform.to_lowercase().as_str() {
+        "twitch" => {
+            service
+                .start_twitch(
+                    &payload.channel,
+                    payload.voice_id.clone(),
+                    engine,
+                    &payload.stingers,
+                )
                 .await
-                .map_err(|err| (StatusCode::BAD_GATEWAY, err.to_string()))?;
-            info!(
-                target = "ishowtts::api::danmaku",
-                platform = %payload.platform,
-                channel = %channel,
-                voice_id = payload.voice_id.as_deref(),
-                engine = payload.engine.as_deref(),
-                "danmaku start accepted"
-            );
-            Ok((
-                StatusCode::ACCEPTED,
-                Json(StartResponse {
-                    status: "started".into(),
-                    channel,
-                }),
-            ))
         }
-        "youtube" => Err((
-            StatusCode::NOT_IMPLEMENTED,
-            "YouTube 弹幕播报即将支持".into(),
-        )),
-        other => Err((
-            StatusCode::BAD_REQUEST,
-            format!("unsupported platform '{other}'"),
-        )),
-    }
+        "bilibili" => {
+            service
+                .start_bilibili(
+                    &payload.channel,
+                    payload.voice_id.clone(),
+                    engine,
+                    &payload.stingers,
+                )
+                .await
+        }
+        "youtube" => {
+            return Err(ApiError::from((
+                StatusCode::NOT_IMPLEMENTED,
+                "YouTube 弹幕播报即将支持".into(),
+            )))
+        }
+        other => {
+            return Err(ApiError::from((
+                StatusCode::BAD_REQUEST,
+                format!("unsupported platform '{other}'"),
+            )))
+        }
+    };
+    let channel = started.map_err(|err| {
+        if let Some(ChannelLimitReached(limit)) = err.downcast_ref::<ChannelLimitReached>() {
+            ApiError::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                "channel_limit_reached",
+                err.to_string(),
+            )
+            .with_arg("limit", limit)
+        } else if err.downcast_ref::<InvalidChannel>().is_some() {
+            ApiError::new(StatusCode::BAD_REQUEST, "invalid_channel", err.to_string())
+        } else {
+            ApiError::from((StatusCode::BAD_GATEWAY, err.to_string()))
+        }
+    })?;
+    info!(
+        target = "ishowtts::api::danmaku",
+        platform = %payload.platform,
+        channel = %channel,
+        voice_id = payload.voice_id.as_deref(),
+        engine = payload.engine.as_deref(),
+        "danmaku start accepted"
+    );
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(StartResponse {
+            status: "started".into(),
+            channel,
+        }),
+    ))
 }
 
 #[instrument(skip(state, payload))]
 async fn stop_danmaku(
     State(state): State<ApiState>,
     Json(payload): Json<StopRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let service = state
-        .danmaku
-        .ok_or((StatusCode::NOT_IMPLEMENTED, "弹幕播报未启用".into()))?;
+) -> Result<impl IntoResponse, ApiError> {
+    let service = state.danmaku.ok_or_else(ApiError::danmaku_disabled)?;
 
     debug!(
         target = "ishowtts::api::danmaku",
@@ -912,48 +2715,54 @@ async fn stop_danmaku(
         "danmaku stop requested"
     );
 
-    match payload.platform.to_lowercase().as_str() {
-        "twitch" => match service.stop_twitch(&payload.channel) {
-            Ok(Some(channel)) => {
-                info!(
-                    target = "ishowtts::api::danmaku",
-                    platform = %payload.platform,
-                    channel = %channel,
-                    "danmaku stop accepted"
-                );
-                Ok((
-                    StatusCode::ACCEPTED,
-                    Json(StopResponse {
-                        status: "stopped".into(),
-                        channel: Some(channel),
-                    }),
-                ))
-            }
-            Ok(None) => {
-                info!(
-                    target = "ishowtts::api::danmaku",
-                    platform = %payload.platform,
-                    channel = %payload.channel,
-                    "danmaku already idle"
-                );
-                Ok((
-                    StatusCode::OK,
-                    Json(StopResponse {
-                        status: "idle".into(),
-                        channel: None,
-                    }),
-                ))
-            }
-            Err(err) => Err((StatusCode::BAD_REQUEST, err.to_string())),
-        },
-        "youtube" => Err((
-            StatusCode::NOT_IMPLEMENTED,
-            "YouTube 弹幕播报即将支持".into(),
-        )),
-        other => Err((
-            StatusCode::BAD_REQUEST,
-            format!("unsupported platform '{other}'"),
-        )),
+    let stopped = match payload.platform.to_lowercase().as_str() {
+        "twitch" => service.stop_twitch(&payload.channel),
+        "bilibili" => service.stop_bilibili(&payload.channel),
+        "youtube" => {
+            return Err(ApiError::from((
+                StatusCode::NOT_IMPLEMENTED,
+                "YouTube 弹幕播报即将支持".into(),
+            )))
+        }
+        other => {
+            return Err(ApiError::from((
+                StatusCode::BAD_REQUEST,
+                format!("unsupported platform '{other}'"),
+            )))
+        }
+    };
+    match stopped {
+        Ok(Some(channel)) => {
+            info!(
+                target = "ishowtts::api::danmaku",
+                platform = %payload.platform,
+                channel = %channel,
+                "danmaku stop accepted"
+            );
+            Ok((
+                StatusCode::ACCEPTED,
+                Json(StopResponse {
+                    status: "stopped".into(),
+                    channel: Some(channel),
+                }),
+            ))
+        }
+        Ok(None) => {
+            info!(
+                target = "ishowtts::api::danmaku",
+                platform = %payload.platform,
+                channel = %payload.channel,
+                "danmaku already idle"
+            );
+            Ok((
+                StatusCode::OK,
+                Json(StopResponse {
+                    status: "idle".into(),
+                    channel: None,
+                }),
+            ))
+        }
+        Err(err) => Err((StatusCode::BAD_REQUEST, err.to_string()).into()),
     }
 }
 
@@ -961,10 +2770,8 @@ async fn stop_danmaku(
 async fn enqueue_danmaku(
     State(state): State<ApiState>,
     Json(payload): Json<NormalizedMessage>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let service = state
-        .danmaku
-        .ok_or((StatusCode::NOT_IMPLEMENTED, "弹幕播报未启用".into()))?;
+) -> Result<impl IntoResponse, ApiError> {
+    let service = state.danmaku.ok_or_else(ApiError::danmaku_disabled)?;
     let message_preview = match &payload.content {
         MessageContent::Text(text) | MessageContent::System(text) => preview_text(text),
     };
@@ -1009,57 +2816,562 @@ async fn enqueue_danmaku(
     }
 }
 
+/// Reads a streamer's own line into an active channel's playback stream,
+/// skipping the chat filter and queue.
+async fn say_danmaku(
+    State(state): State<ApiState>,
+    Json(payload): Json<SayRequest>,
+) -> Result<Json<SayResponse>, ApiError> {
+    let service = state
+        .danmaku
+        .clone()
+        .ok_or_else(ApiError::danmaku_disabled)?;
+    if payload.text.trim().is_empty() {
+        return Err(ApiError::text_empty());
+    }
+    if let Some(voice_id) = payload.voice_id.as_deref() {
+        lookup_voice(&state, voice_id, payload.engine)?;
+    }
+    let packet_id = service
+        .say(
+            &payload.channel,
+            &payload.text,
+            payload.voice_id.as_deref(),
+            payload.engine,
+        )
+        .await
+        .map_err(|err| match err.downcast_ref::<ChannelNotActive>() {
+            Some(ChannelNotActive(channel)) => {
+                ApiError::new(StatusCode::NOT_FOUND, "channel_not_active", err.to_string())
+                    .with_arg("channel", channel)
+            }
+            None => ApiError::from((StatusCode::BAD_GATEWAY, format!("{err:#}"))),
+        })?;
+    info!(
+        target = "ishowtts::api::danmaku",
+        channel = %payload.channel,
+        %packet_id,
+        text = %preview_text(&payload.text),
+        "announcement queued"
+    );
+    Ok(Json(SayResponse {
+        channel: payload.channel.trim().to_ascii_lowercase(),
+        packet_id,
+    }))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SkipQuery {
+    /// Clip the caller is playing; without it clients stop their current clip.
+    #[serde(default)]
+    packet_id: Option<Uuid>,
+}
+
+/// Kill switch for a clip that slipped through: drops it from the replay
+/// buffer and sends a `skip` text frame so stream clients stop playing it.
+async fn skip_danmaku(
+    State(state): State<ApiState>,
+    Path(channel): Path<String>,
+    Query(query): Query<SkipQuery>,
+) -> Result<Json<SkipControl>, ApiError> {
+    let service = state.danmaku.ok_or_else(ApiError::danmaku_disabled)?;
+    let control = service
+        .skip(&channel, query.packet_id)
+        .map_err(|err| match err.downcast_ref::<ChannelNotActive>() {
+            Some(ChannelNotActive(channel)) => {
+                ApiError::new(StatusCode::NOT_FOUND, "channel_not_active", err.to_string())
+                    .with_arg("channel", channel)
+            }
+            None => ApiError::from((StatusCode::INTERNAL_SERVER_ERROR, format!("{err:#}"))),
+        })?;
+    Ok(Json(control))
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FilterTestPayload {
+    Message(NormalizedMessage),
+    Raw {
+        text: String,
+        #[serde(default)]
+        username: Option<String>,
+        #[serde(default)]
+        channel: Option<String>,
+        #[serde(default)]
+        metadata: Option<serde_json::Value>,
+    },
+}
+
+impl FilterTestPayload {
+    fn into_message(self) -> NormalizedMessage {
+        match self {
+            FilterTestPayload::Message(message) => message,
+            FilterTestPayload::Raw {
+                text,
+                username,
+                channel,
+                metadata,
+            } => NormalizedMessage::new_text(
+                Platform::Twitch,
+                channel.unwrap_or_default(),
+                None,
+                username.unwrap_or_default(),
+                Priority::Normal,
+                text,
+                metadata.unwrap_or(serde_json::Value::Null),
+            ),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct FilterTestResponse {
+    accepted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<DropReason>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    display_text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spoken_text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority: Option<Priority>,
+}
+
+#[instrument(skip(state, payload))]
+async fn filter_test_danmaku(
+    State(state): State<ApiState>,
+    Json(payload): Json<FilterTestPayload>,
+) -> Result<Json<FilterTestResponse>, ApiError> {
+    let service = state.danmaku.ok_or_else(ApiError::danmaku_disabled)?;
+    let message = payload.into_message();
+    let response = match service.filter_test(&message) {
+        Ok(filtered) => FilterTestResponse {
+            accepted: true,
+            reason: None,
+            display_text: Some(filtered.display_text),
+            spoken_text: Some(filtered.spoken_text),
+            priority: Some(filtered.priority),
+        },
+        Err(reason) => FilterTestResponse {
+            accepted: false,
+            reason: Some(reason),
+            display_text: None,
+            spoken_text: None,
+            priority: None,
+        },
+    };
+    debug!(
+        target = "ishowtts::api::danmaku",
+        user = %message.username,
+        accepted = response.accepted,
+        "danmaku filter test"
+    );
+    Ok(Json(response))
+}
+
 #[instrument(skip(state))]
 async fn stream_danmaku_ws(
     State(state): State<ApiState>,
+    Query(auth): Query<WsAuthQuery>,
+    Query(options): Query<DanmakuStreamQuery>,
+    headers: HeaderMap,
     ws: WebSocketUpgrade,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ApiError> {
+    let ws = authorize_ws(&state, &auth, &headers, ws)?;
     let service = state
         .danmaku
         .as_ref()
-        .ok_or((StatusCode::NOT_IMPLEMENTED, "弹幕播报未启用".into()))?
+        .ok_or_else(ApiError::danmaku_disabled)?
         .clone();
+    let encoding = PacketEncoding::negotiate(options.compress.as_deref(), state.ws_compression);
+
+    let pcm = match options.pcm.as_deref() {
+        Some(value) => Some(PcmFormat::parse(value).ok_or_else(|| {
+            ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "invalid_pcm_format",
+                format!("不支持的 PCM 格式 '{value}'，可选 f32 或 s16"),
+            )
+            .with_arg("format", value)
+        })?),
+        None => None,
+    };
 
+    let replay = options.replay;
     Ok(ws.on_upgrade(move |socket| async move {
-        if let Err(err) = handle_danmaku_ws(socket, service).await {
+        if let Err(err) = handle_danmaku_ws(socket, service, encoding, pcm, replay).await {
             error!(%err, "danmaku websocket channel terminated with error");
         }
     }))
 }
 
-async fn handle_danmaku_ws(socket: WebSocket, service: Arc<DanmakuService>) -> Result<()> {
+/// Text-only feed of the playback headers for overlays that show chat but
+/// never play audio. Subscribes to the same broadcast as the websocket.
+#[instrument(skip(state))]
+async fn stream_danmaku_events(
+    State(state): State<ApiState>,
+    Query(auth): Query<WsAuthQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, ApiError> {
+    if let Some(expected) = state.ws_token.as_deref() {
+        // EventSource cannot set headers, so only `?token=` is accepted.
+        let authorized = auth
+            .token
+            .as_deref()
+            .is_some_and(|token| tokens_match(token, expected));
+        if !authorized {
+            warn!(
+                target = "ishowtts::api::danmaku",
+                "rejected event stream without a valid token"
+            );
+            return Err(ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "unauthorized",
+                "缺少或错误的 token",
+            ));
+        }
+    }
+    let service = state
+        .danmaku
+        .as_ref()
+        .ok_or_else(ApiError::danmaku_disabled)?;
+
+    let mut closing = service.subscribe_closing();
+    let events = BroadcastStream::new(service.subscribe_playback())
+        .filter_map(|item| {
+            future::ready(match item {
+                Ok(item) => Some(item),
+                Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                    warn!(skipped, "event stream listener lagged; dropping events");
+                    None
+                }
+            })
+        })
+        .map(|item| {
+            Event::default()
+                .event("danmaku")
+                .json_data(playback_header(&item))
+        })
+        .take_until(async move {
+            closing.wait_for(|closing| *closing).await.ok();
+        });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// Checks the configured websocket token, accepted either as `?token=` or as
+/// one of the offered subprotocols. A matching subprotocol is echoed back, as
+/// browsers drop the connection otherwise.
+fn authorize_ws(
+    state: &ApiState,
+    auth: &WsAuthQuery,
+    headers: &HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Result<WebSocketUpgrade, ApiError> {
+    let Some(expected) = state.ws_token.as_deref() else {
+        return Ok(ws);
+    };
+
+    if auth
+        .token
+        .as_deref()
+        .is_some_and(|token| tokens_match(token, expected))
+    {
+        return Ok(ws);
+    }
+
+    let offered = headers
+        .get_all(header::SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|protocol| tokens_match(protocol, expected));
+    if offered {
+        return Ok(ws.protocols([expected.to_string()]));
+    }
+
+    warn!(
+        target = "ishowtts::api::danmaku",
+        "rejected websocket upgrade without a valid token"
+    );
+    Err(ApiError::new(
+        StatusCode::UNAUTHORIZED,
+        "unauthorized",
+        "缺少或错误的 websocket token",
+    ))
+}
+
+/// Compares without exiting early on the first differing byte.
+fn tokens_match(candidate: &str, expected: &str) -> bool {
+    candidate.len() == expected.len()
+        && candidate
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[instrument(skip(state))]
+async fn danmaku_status(State(state): State<ApiState>) -> Result<Json<DanmakuStatus>, ApiError> {
+    let service = state
+        .danmaku
+        .as_ref()
+        .ok_or_else(ApiError::danmaku_disabled)?;
+    Ok(Json(service.status()))
+}
+
+/// Everything read on `channel` since it started, as one WAV with short
+/// gaps between clips. Needs `[recording] record_session = true`.
+#[instrument(skip(state))]
+async fn export_danmaku_session(
+    State(state): State<ApiState>,
+    Path(channel): Path<String>,
+) -> Result<Response, ApiError> {
+    let service = state
+        .danmaku
+        .clone()
+        .ok_or_else(ApiError::danmaku_disabled)?;
+    if !service.recorder().is_enabled() {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "session_recording_disabled",
+            "未开启弹幕会话录制（[recording] record_session）",
+        ));
+    }
+    let channel = channel.trim().trim_start_matches('#').to_ascii_lowercase();
+    let export_channel = channel.clone();
+    let stitched = tokio::task::spawn_blocking(move || service.recorder().export(&export_channel))
+        .await
+        .map_err(|err| {
+            ApiError::from((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("导出任务失败: {err}"),
+            ))
+        })?
+        .map_err(|err| {
+            ApiError::from((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("拼接会话音频失败: {err}"),
+            ))
+        })?
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NOT_FOUND,
+                "session_empty",
+                format!("频道 '{channel}' 没有录制的音频"),
+            )
+            .with_arg("channel", &channel)
+        })?;
+
+    info!(
+        target = "ishowtts::api::danmaku",
+        %channel,
+        frames = stitched.frames,
+        bytes = stitched.wav.len(),
+        "exported danmaku session"
+    );
+    let filename = format!(
+        "danmaku-{}-{}.wav",
+        channel,
+        Utc::now().format("%Y%m%d-%H%M%S")
+    );
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "audio/wav")
+        .header("Cache-Control", "no-store")
+        .header(
+            "Content-Disposition",
+            format!("attachment; filename=\"{filename}\""),
+        )
+        .body(Body::from(stitched.wav))
+        .map_err(|err| {
+            ApiError::from((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("构建响应失败: {err}"),
+            ))
+        })
+}
+
+#[instrument(skip(state))]
+async fn danmaku_presence(
+    State(state): State<ApiState>,
+) -> Result<Json<Vec<ChannelPresence>>, ApiError> {
+    let service = state
+        .danmaku
+        .as_ref()
+        .ok_or_else(ApiError::danmaku_disabled)?;
+    Ok(Json(service.presence().snapshot()))
+}
+
+#[instrument(skip(state))]
+async fn stream_presence_ws(
+    State(state): State<ApiState>,
+    Query(auth): Query<WsAuthQuery>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Result<impl IntoResponse, ApiError> {
+    let ws = authorize_ws(&state, &auth, &headers, ws)?;
+    let service = state
+        .danmaku
+        .as_ref()
+        .ok_or_else(ApiError::danmaku_disabled)?
+        .clone();
+
+    Ok(ws.on_upgrade(move |socket| async move {
+        if let Err(err) = handle_presence_ws(socket, service).await {
+            error!(%err, "presence websocket channel terminated with error");
+        }
+    }))
+}
+
+async fn handle_presence_ws(socket: WebSocket, service: Arc<DanmakuService>) -> Result<()> {
     let (mut sink, mut stream) = socket.split();
+    let mut receiver = service.presence().subscribe();
+    let mut closing = service.subscribe_closing();
+
+    for presence in service.presence().snapshot() {
+        let text = serde_json::to_string(&presence).context("failed to encode presence")?;
+        sink.send(Message::Text(text)).await?;
+    }
 
-    for item in service.pending_playback() {
-        if let Err(err) = send_packet(&mut sink, &item).await {
-            return Err(err);
+    loop {
+        tokio::select! {
+            _ = async { closing.wait_for(|closing| *closing).await.ok(); } => {
+                sink.send(Message::Close(None)).await.ok();
+                break;
+            }
+            update = receiver.recv() => {
+                match update {
+                    Ok(presence) => {
+                        let text = serde_json::to_string(&presence)
+                            .context("failed to encode presence")?;
+                        sink.send(Message::Text(text)).await?;
+                    }
+                    // Every update carries the full channel state, so
+                    // skipped ones are harmless.
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+            ws_msg = stream.next() => {
+                match ws_msg {
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => {}
+                    Some(Err(err)) => return Err(anyhow::Error::new(err)),
+                }
+            }
         }
     }
 
+    Ok(())
+}
+
+async fn handle_danmaku_ws(
+    socket: WebSocket,
+    service: Arc<DanmakuService>,
+    encoding: PacketEncoding,
+    pcm: Option<PcmFormat>,
+    replay: Option<usize>,
+) -> Result<()> {
+    let (mut sink, mut stream) = socket.split();
+
+    // Sent one clip per loop turn so a `clear` can cut a long backlog short.
+    let mut backlog = recent_playback(&service, replay);
+
     let mut receiver = service.subscribe_playback();
+    let mut failures = service.subscribe_failures();
+    let mut skips = service.subscribe_skips();
+    let mut closing = service.subscribe_closing();
+    // Half-open connections never send a FIN, so without our own pings a
+    // vanished client would keep its receiver (and lag warnings) alive.
+    let mut heartbeat = interval(WS_HEARTBEAT_INTERVAL);
+    heartbeat.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    heartbeat.tick().await;
+    let mut missed_pongs = 0u32;
 
     loop {
         tokio::select! {
+            _ = heartbeat.tick() => {
+                if missed_pongs >= WS_MAX_MISSED_PONGS {
+                    warn!(
+                        target = "ishowtts::playback",
+                        missed_pongs,
+                        "websocket client stopped answering pings; closing"
+                    );
+                    sink.send(Message::Close(None)).await.ok();
+                    break;
+                }
+                missed_pongs += 1;
+                sink.send(Message::Ping(Vec::new()))
+                    .await
+                    .context("failed to send websocket ping")?;
+            }
+            _ = async { closing.wait_for(|closing| *closing).await.ok(); } => {
+                while let Ok(item) = receiver.try_recv() {
+                    send_packet(&mut sink, &item, encoding, pcm).await?;
+                }
+                sink.send(Message::Close(None)).await.ok();
+                break;
+            }
             msg = receiver.recv() => {
                 match msg {
-                    Ok(item) => {
-                        if let Err(err) = send_packet(&mut sink, &item).await {
-                            return Err(err);
-                        }
-                    }
+                    Ok(item) => send_packet(&mut sink, &item, encoding, pcm).await?,
                     Err(RecvError::Lagged(skipped)) => {
                         warn!(skipped, "websocket listener lagged; dropping playback events");
                     }
                     Err(RecvError::Closed) => break,
                 }
             }
+            failure = failures.recv() => {
+                // Status events travel as text frames; clips stay binary.
+                if let Ok(failure) = failure {
+                    let text = serde_json::to_string(&failure)
+                        .context("failed to encode channel failure")?;
+                    sink.send(Message::Text(text)).await?;
+                }
+            }
+            skip = skips.recv() => {
+                if let Ok(skip) = skip {
+                    // A skipped clip still waiting in this client's replay
+                    // backlog should not be sent after all.
+                    if let Some(packet_id) = skip.packet_id {
+                        backlog.retain(|item| item.packet_id != packet_id);
+                    }
+                    let text = serde_json::to_string(&skip)
+                        .context("failed to encode skip control")?;
+                    sink.send(Message::Text(text)).await?;
+                }
+            }
+            _ = future::ready(()), if !backlog.is_empty() => {
+                if let Some(item) = backlog.pop_front() {
+                    send_packet(&mut sink, &item, encoding, pcm).await?;
+                }
+            }
             ws_msg = stream.next() => {
                 match ws_msg {
                     Some(Ok(Message::Close(_))) | None => break,
                     Some(Ok(Message::Ping(payload))) => {
                         sink.send(Message::Pong(payload)).await.ok();
                     }
-                    Some(Ok(Message::Text(_))) | Some(Ok(Message::Binary(_))) | Some(Ok(Message::Pong(_))) => {
+                    Some(Ok(Message::Pong(_))) => {
+                        missed_pongs = 0;
+                    }
+                    Some(Ok(Message::Text(text))) => {
+                        match serde_json::from_str::<DanmakuStreamCommand>(&text) {
+                            Ok(DanmakuStreamCommand::Replay { count }) => {
+                                backlog = recent_playback(&service, count);
+                            }
+                            Ok(DanmakuStreamCommand::Clear) => backlog.clear(),
+                            Err(err) => {
+                                debug!(
+                                    target = "ishowtts::playback",
+                                    %err,
+                                    "ignoring unknown websocket command"
+                                );
+                            }
+                        }
+                    }
+                    Some(Ok(Message::Binary(_))) => {
                         // ignore client data
                     }
                     Some(Err(err)) => {
@@ -1073,31 +3385,98 @@ async fn handle_danmaku_ws(socket: WebSocket, service: Arc<DanmakuService>) -> R
     Ok(())
 }
 
-async fn send_packet(sink: &mut SplitSink<WebSocket, Message>, item: &PlaybackItem) -> Result<()> {
-    use serde_json::json;
+/// The last `count` buffered clips, oldest first; all of them for `None`.
+fn recent_playback(service: &DanmakuService, count: Option<usize>) -> VecDeque<PlaybackItem> {
+    let mut pending: VecDeque<PlaybackItem> = service.pending_playback().into();
+    if let Some(count) = count {
+        let skip = pending.len().saturating_sub(count);
+        pending.drain(..skip);
+    }
+    pending
+}
 
-    let platform = match item.platform {
+fn platform_name(platform: &Platform) -> &'static str {
+    match platform {
         Platform::Twitch => "Twitch",
         Platform::YouTube => "YouTube",
-    };
+        Platform::Bilibili => "Bilibili",
+    }
+}
 
-    let header = json!({
-        "platform": platform,
+/// Metadata sent ahead of each clip; also the payload of the SSE feed.
+fn playback_header(item: &PlaybackItem) -> serde_json::Value {
+    serde_json::json!({
+        "packet_id": item.packet_id,
+        "platform": platform_name(&item.platform),
         "channel": item.channel,
         "username": item.username,
         "display_text": item.display_text,
         "format": item.format,
+        "sample_rate": item.sample_rate,
         "color": item.color,
+        "peak": item.peak,
+        "rms": item.rms,
+        "announcement": item.announcement,
+    })
+}
+
+async fn send_packet(
+    sink: &mut SplitSink<WebSocket, Message>,
+    item: &PlaybackItem,
+    encoding: PacketEncoding,
+    pcm: Option<PcmFormat>,
+) -> Result<()> {
+    let platform = platform_name(&item.platform);
+    let mut header = playback_header(item);
+
+    let raw = pcm.and_then(|format| match wav_to_pcm(&item.audio, format) {
+        Ok((samples, channels)) => {
+            header["format"] = serde_json::Value::from("audio/pcm");
+            header["sample_format"] = serde_json::Value::from(format.as_str());
+            header["channels"] = serde_json::Value::from(channels);
+            Some(samples)
+        }
+        Err(err) => {
+            warn!(
+                target = "ishowtts::playback",
+                packet_id = %item.packet_id,
+                %err,
+                "failed to unwrap clip to PCM; sending WAV"
+            );
+            None
+        }
     });
+    // Only flag the clip as gzip when that actually saved bytes. WAV clips
+    // are compressed once for every listener; PCM is per connection.
+    let (raw, compressed) = match (encoding, raw) {
+        (PacketEncoding::Identity, raw) => (raw, None),
+        (PacketEncoding::Gzip, None) => (None, item.gzipped_audio().await.map(Cow::Borrowed)),
+        (PacketEncoding::Gzip, Some(samples)) => {
+            let (samples, compressed) = tokio::task::spawn_blocking(move || {
+                let compressed = gzip_if_smaller(&samples);
+                (samples, compressed)
+            })
+            .await
+            .context("PCM compression task failed")?;
+            (Some(samples), compressed.map(Cow::Owned))
+        }
+    };
+    if compressed.is_some() {
+        header["encoding"] = serde_json::Value::from("gzip");
+    }
+    let audio = compressed
+        .as_deref()
+        .or(raw.as_deref())
+        .unwrap_or(item.audio.as_slice());
 
     let header_bytes = serde_json::to_vec(&header).context("failed to encode playback header")?;
     let header_len =
         u32::try_from(header_bytes.len()).context("playback header too large to encode")?;
 
-    let mut payload = Vec::with_capacity(4 + header_bytes.len() + item.audio.len());
+    let mut payload = Vec::with_capacity(4 + header_bytes.len() + audio.len());
     payload.extend_from_slice(&header_len.to_le_bytes());
     payload.extend_from_slice(&header_bytes);
-    payload.extend_from_slice(&item.audio);
+    payload.extend_from_slice(audio);
 
     sink.send(Message::Binary(payload))
         .await
@@ -1105,17 +3484,67 @@ async fn send_packet(sink: &mut SplitSink<WebSocket, Message>, item: &PlaybackIt
 
     let audio_bytes = item.audio.len();
     let audio_kb = ((audio_bytes as f64) / 1024.0 * 10.0).round() / 10.0;
+    let sent_bytes = audio.len();
 
     info!(
         target = "ishowtts::playback",
+        packet_id = %item.packet_id,
         platform = %platform,
         channel = %item.channel,
         user = %item.username,
         sample_rate = item.sample_rate,
         audio_bytes,
         audio_kb,
+        sent_bytes,
         "playback packet sent"
     );
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gzip_is_negotiated_only_when_requested_and_enabled() {
+        assert_eq!(
+            PacketEncoding::negotiate(Some("gzip"), true),
+            PacketEncoding::Gzip
+        );
+        assert_eq!(
+            PacketEncoding::negotiate(Some("GZIP"), true),
+            PacketEncoding::Gzip
+        );
+        assert_eq!(
+            PacketEncoding::negotiate(Some("gzip"), false),
+            PacketEncoding::Identity
+        );
+        assert_eq!(
+            PacketEncoding::negotiate(Some("br"), true),
+            PacketEncoding::Identity
+        );
+        assert_eq!(
+            PacketEncoding::negotiate(None, true),
+            PacketEncoding::Identity
+        );
+    }
+
+    #[test]
+    fn fingerprint_ignores_the_key_and_progress_id() {
+        let payload = |value: serde_json::Value| -> SynthesizePayload {
+            serde_json::from_value(value).unwrap()
+        };
+        let first = payload(serde_json::json!({
+            "text": "hello", "voice_id": "walter", "idempotency_key": "a", "progress_id": "p1",
+        }));
+        let retry = payload(serde_json::json!({
+            "text": "hello", "voice_id": "walter", "idempotency_key": "b", "progress_id": "p2",
+        }));
+        let edited = payload(serde_json::json!({
+            "text": "hello!", "voice_id": "walter", "idempotency_key": "a",
+        }));
+        assert_eq!(first.fingerprint(), retry.fingerprint());
+        assert_ne!(first.fingerprint(), edited.fingerprint());
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use danmaku_gateway::RecordingConfig;
+use parking_lot::Mutex;
+use tracing::info;
+use tts_engine::{stitch_wav, StitchedAudio};
+
+#[derive(Default)]
+struct ChannelRecording {
+    clips: Vec<Arc<Vec<u8>>>,
+    duration: Duration,
+    /// Set once a clip has been turned away for exceeding the cap.
+    full: bool,
+}
+
+/// Clips read on each channel, kept for a whole-session export. Clips share
+/// their buffers with the playback queue, so recording costs no copies until
+/// an export is requested.
+pub struct SessionRecorder {
+    enabled: bool,
+    max_duration: Duration,
+    gap_ms: u32,
+    channels: Mutex<HashMap<String, ChannelRecording>>,
+}
+
+impl SessionRecorder {
+    pub fn new(config: &RecordingConfig) -> Self {
+        Self {
+            enabled: config.record_session,
+            max_duration: Duration::from_secs(config.max_session_secs),
+            gap_ms: config.gap_ms,
+            channels: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Appends a clip to `channel`'s session unless that would pass the cap.
+    pub fn record(&self, channel: &str, audio: Arc<Vec<u8>>, duration: Duration) {
+        if !self.enabled {
+            return;
+        }
+        let mut channels = self.channels.lock();
+        let recording = channels.entry(channel.to_string()).or_default();
+        if recording.duration + duration > self.max_duration {
+            if !recording.full {
+                recording.full = true;
+                info!(
+                    target = "ishowtts::danmaku",
+                    %channel,
+                    max_secs = self.max_duration.as_secs(),
+                    "session recording full; later clips are not recorded"
+                );
+            }
+            return;
+        }
+        recording.clips.push(audio);
+        recording.duration += duration;
+    }
+
+    /// The recorded session joined into one WAV, or `None` when nothing was
+    /// recorded for `channel`.
+    pub fn export(&self, channel: &str) -> Result<Option<StitchedAudio>> {
+        let clips: Vec<Vec<u8>> = match self.channels.lock().get(channel) {
+            Some(recording) if !recording.clips.is_empty() => recording
+                .clips
+                .iter()
+                .map(|clip| clip.as_ref().clone())
+                .collect(),
+            _ => return Ok(None),
+        };
+        stitch_wav(&clips, self.gap_ms, 0).map(Some)
+    }
+
+    pub fn clear(&self, channel: &str) {
+        self.channels.lock().remove(channel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorder(max_session_secs: u64) -> SessionRecorder {
+        SessionRecorder::new(&RecordingConfig {
+            record_session: true,
+            max_session_secs,
+            gap_ms: 0,
+        })
+    }
+
+    fn wav(channels: u16, sample_rate: u32, frames: u32) -> Arc<Vec<u8>> {
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+        for _ in 0..frames * u32::from(channels) {
+            writer.write_sample(1_000i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        Arc::new(cursor.into_inner())
+    }
+
+    #[test]
+    fn stops_recording_at_the_duration_cap() {
+        let recorder = recorder(2);
+        for _ in 0..3 {
+            recorder.record("chan", wav(1, 1_000, 1_000), Duration::from_secs(1));
+        }
+        let exported = recorder.export("chan").unwrap().unwrap();
+        assert_eq!(exported.frames, 2_000);
+        assert!(recorder.export("other").unwrap().is_none());
+    }
+
+    #[test]
+    fn exports_clips_of_mixed_formats() {
+        let recorder = recorder(60);
+        recorder.record("chan", wav(1, 24_000, 2_400), Duration::from_millis(100));
+        // A stereo clip at another engine's output rate.
+        recorder.record("chan", wav(2, 48_000, 4_800), Duration::from_millis(100));
+        let exported = recorder.export("chan").unwrap().unwrap();
+        assert_eq!((exported.sample_rate, exported.frames), (24_000, 4_800));
+    }
+
+    #[test]
+    fn clear_forgets_the_channel() {
+        let recorder = recorder(60);
+        recorder.record("chan", wav(1, 1_000, 100), Duration::from_millis(100));
+        recorder.record("other", wav(1, 1_000, 100), Duration::from_millis(100));
+        recorder.clear("chan");
+        assert!(recorder.export("chan").unwrap().is_none());
+        assert!(recorder.export("other").unwrap().is_some());
+    }
+}
@@ -49,6 +49,8 @@ struct ShimmyTtsPayload {
     remove_silence: Option<bool>,
     #[serde(default)]
     seed: Option<u64>,
+    #[serde(default)]
+    normalize_output_rms: Option<f32>,
 }
 
 #[derive(Debug, Serialize)]
@@ -100,6 +102,7 @@ impl LoadedModel for F5LoadedModel {
                 fix_duration: None,
                 remove_silence: None,
                 seed: None,
+                normalize_output_rms: None,
             }
         };
 
@@ -120,6 +123,9 @@ impl LoadedModel for F5LoadedModel {
             fix_duration: payload.fix_duration,
             remove_silence: payload.remove_silence,
             seed: payload.seed,
+            normalize_output_rms: payload.normalize_output_rms,
+            no_cache: false,
+            segments: None,
         };
 
         let mut response = self.synthesizer.synthesize(request).await?;
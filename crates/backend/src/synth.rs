@@ -1,27 +1,130 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use base64::Engine as _;
 use tokio::sync::Semaphore;
-use tracing::instrument;
+use tracing::{instrument, warn};
 
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 
+use serde::Serialize;
+
+use crate::chunking::{split_into_chunks, MAX_CHUNK_WORDS};
+use crate::config::{ChunkingConfig, FallbackConfig};
+use crate::pronunciations::PronunciationStore;
+use crate::voice_overrides::infer_audio_extension_from_bytes;
+use crate::waveform::decode_mono;
 use tts_engine::{
-    EngineKind, TtsEngine, TtsRequest, TtsResponse, VoiceDescriptor, VoiceOverrideUpdate,
+    clip_duration_ms, stitch_wav, EngineKind, EngineVoices, GpuStats, ParameterSpec, ProgressSink,
+    SynthesisProgress, TtsEngine, TtsRequest, TtsResponse, VoiceDescriptor, VoiceOverrideUpdate,
 };
 
+/// Voices are registered per engine, so the same id may exist on several.
+type VoiceKey = (String, EngineKind);
+
+/// When the cached GPU figures were read, and the figures.
+type GpuStatsReading = (Instant, Vec<EngineGpuStats>);
+
+/// How long [`Synthesizer::gpu_stats`] reuses its last reading, so frequent
+/// health polls do not contend for the GIL.
+const GPU_STATS_TTL: Duration = Duration::from_secs(5);
+
 pub struct Synthesizer {
     engines: HashMap<EngineKind, Arc<dyn TtsEngine>>,
-    voice_map: RwLock<HashMap<String, VoiceDescriptor>>,
-    baseline_map: HashMap<String, VoiceBaseline>,
+    /// Registration order; decides which engine serves a voice id that was
+    /// requested without an engine.
+    engine_order: Vec<EngineKind>,
+    /// Shared between clones so a voice reload reaches every holder.
+    voice_map: Arc<RwLock<HashMap<VoiceKey, VoiceDescriptor>>>,
+    baseline_map: Arc<RwLock<HashMap<VoiceKey, VoiceBaseline>>>,
     limiter: Arc<Semaphore>,
+    fallback: FallbackConfig,
+    chunking: ChunkingConfig,
+    pronunciations: Option<Arc<PronunciationStore>>,
+    /// Engines that have completed at least one synthesis.
+    ready: Arc<RwLock<HashSet<EngineKind>>>,
+    warming: Arc<Mutex<HashSet<EngineKind>>>,
+    stats: Arc<Mutex<HashMap<VoiceKey, StatsCounter>>>,
+    gpu_stats: Arc<Mutex<Option<GpuStatsReading>>>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct StatsCounter {
+    requests: u64,
+    failures: u64,
+    cache_hits: u64,
+    total_elapsed_ms: u64,
+}
+
+/// Usage of one voice since startup, counting every caller of
+/// [`Synthesizer::synthesize_notify`]. Latency includes the wait for a slot.
+#[derive(Clone, Debug, Serialize)]
+pub struct VoiceStats {
+    pub voice_id: String,
+    pub engine: EngineKind,
+    pub requests: u64,
+    pub failures: u64,
+    pub total_elapsed_ms: u64,
+    /// Mean over successful requests.
+    pub avg_elapsed_ms: f64,
+    pub cache_hit_rate: f64,
+}
+
+/// Whether an engine still has its cold-start model load ahead of it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineLoadState {
+    /// Up, but nothing has been synthesized yet, so the first request still
+    /// pays the cold start.
+    Idle,
+    /// A warmup is running.
+    Loading,
+    Ready,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct EngineStatus {
+    pub engine: EngineKind,
+    pub state: EngineLoadState,
+}
+
+/// A voice whose active reference audio failed [`Synthesizer::self_check`].
+#[derive(Clone, Debug, Serialize)]
+pub struct VoiceProblem {
+    pub voice_id: String,
+    pub engine: EngineKind,
+    pub problems: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SelfCheckReport {
+    pub total: usize,
+    pub healthy: usize,
+    pub unhealthy: Vec<VoiceProblem>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct EngineGpuStats {
+    pub engine: EngineKind,
+    #[serde(flatten)]
+    pub stats: GpuStats,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct EngineSummary {
+    pub engine: EngineKind,
+    pub label: &'static str,
+    pub voices: usize,
+    pub parameters: Vec<ParameterSpec>,
 }
 
 #[derive(Clone)]
 pub struct VoiceBaseline {
-    pub reference_audio: PathBuf,
+    pub reference_audio: Vec<PathBuf>,
     pub reference_text: Option<String>,
 }
 
@@ -30,8 +133,9 @@ impl Synthesizer {
         let limiter = Arc::new(Semaphore::new(max_parallel.max(1)));
 
         let mut engine_map: HashMap<EngineKind, Arc<dyn TtsEngine>> = HashMap::new();
-        let mut voice_map: HashMap<String, VoiceDescriptor> = HashMap::new();
-        let mut baseline_map: HashMap<String, VoiceBaseline> = HashMap::new();
+        let mut engine_order = Vec::new();
+        let mut voice_map: HashMap<VoiceKey, VoiceDescriptor> = HashMap::new();
+        let mut baseline_map: HashMap<VoiceKey, VoiceBaseline> = HashMap::new();
 
         for engine in engines {
             let kind = engine.kind();
@@ -40,21 +144,22 @@ impl Synthesizer {
             }
             let mut duplicates = Vec::new();
             for descriptor in engine.voice_descriptors() {
-                if voice_map.contains_key(&descriptor.id) {
+                let key = (descriptor.id.clone(), kind);
+                if voice_map.contains_key(&key) {
                     duplicates.push(descriptor.id.clone());
                     continue;
                 }
                 if let Some((audio_path, reference_text)) = engine.resolve_reference(&descriptor.id)
                 {
                     baseline_map.insert(
-                        descriptor.id.clone(),
+                        key.clone(),
                         VoiceBaseline {
                             reference_audio: audio_path,
                             reference_text,
                         },
                     );
                 }
-                voice_map.insert(descriptor.id.clone(), descriptor);
+                voice_map.insert(key, descriptor);
             }
             if !duplicates.is_empty() {
                 anyhow::bail!(
@@ -64,54 +169,593 @@ impl Synthesizer {
                 );
             }
             engine_map.insert(kind, engine);
+            engine_order.push(kind);
         }
 
         Ok(Self {
             engines: engine_map,
-            voice_map: RwLock::new(voice_map),
-            baseline_map,
+            engine_order,
+            voice_map: Arc::new(RwLock::new(voice_map)),
+            baseline_map: Arc::new(RwLock::new(baseline_map)),
             limiter,
+            fallback: FallbackConfig::default(),
+            chunking: ChunkingConfig::default(),
+            pronunciations: None,
+            ready: Arc::new(RwLock::new(HashSet::new())),
+            warming: Arc::new(Mutex::new(HashSet::new())),
+            stats: Arc::new(Mutex::new(HashMap::new())),
+            gpu_stats: Arc::new(Mutex::new(None)),
         })
     }
 
-    #[instrument(skip(self, request))]
+    /// Enables retrying failed syntheses on a fallback voice. Pairs naming
+    /// unknown voices are dropped with a warning.
+    pub fn with_fallback(mut self, mut fallback: FallbackConfig) -> Self {
+        {
+            fallback.voices.retain(|voice, target| {
+                let known = self.voice_descriptor(voice).is_some()
+                    && self.voice_descriptor(target).is_some();
+                if !known {
+                    warn!(
+                        target = "ishowtts::synth",
+                        %voice,
+                        fallback = %target,
+                        "ignoring fallback pair with an unknown voice"
+                    );
+                }
+                known && voice != target
+            });
+        }
+        if let Some(engine) = fallback.engine {
+            if !self.engines.contains_key(&engine) {
+                warn!(
+                    target = "ishowtts::synth",
+                    %engine,
+                    "fallback engine is not loaded; only explicit pairs apply"
+                );
+                fallback.engine = None;
+            }
+        }
+        self.fallback = fallback;
+        self
+    }
+
+    /// Sets how text longer than [`MAX_CHUNK_WORDS`] is split and joined.
+    pub fn with_chunking(mut self, chunking: ChunkingConfig) -> Self {
+        self.chunking = chunking;
+        self
+    }
+
+    /// Respells text with `pronunciations` before it reaches an engine.
+    pub fn with_pronunciations(mut self, pronunciations: Arc<PronunciationStore>) -> Self {
+        self.pronunciations = Some(pronunciations);
+        self
+    }
+
+    /// Most words a request may carry before the rest is truncated.
+    pub fn max_request_words(&self) -> usize {
+        if self.chunking.enabled {
+            MAX_CHUNK_WORDS * self.chunking.max_chunks.max(1)
+        } else {
+            MAX_CHUNK_WORDS
+        }
+    }
+
+    /// Synthesizes on the engine that serves `request.voice_id` by default;
+    /// see [`Self::resolve_voice`].
     pub async fn synthesize(&self, request: TtsRequest) -> Result<TtsResponse> {
+        self.synthesize_notify(request, None, || {}).await
+    }
+
+    /// Like [`Self::synthesize`], rendering the voice on `engine` when given
+    /// and calling `on_start` once a synthesis slot has been acquired.
+    pub async fn synthesize_notify<F: FnOnce()>(
+        &self,
+        request: TtsRequest,
+        engine: Option<EngineKind>,
+        on_start: F,
+    ) -> Result<TtsResponse> {
+        self.synthesize_with_progress(request, engine, on_start, None)
+            .await
+    }
+
+    /// Like [`Self::synthesize_notify`], reporting finished chunks, or the
+    /// engine's own segments for a single-chunk request, to `progress`.
+    #[instrument(skip(self, request, on_start, progress))]
+    pub async fn synthesize_with_progress<F: FnOnce()>(
+        &self,
+        request: TtsRequest,
+        engine: Option<EngineKind>,
+        on_start: F,
+        progress: Option<ProgressSink>,
+    ) -> Result<TtsResponse> {
+        let started_at = Instant::now();
         let _permit = self
             .limiter
             .acquire()
             .await
             .expect("semaphore closed unexpectedly");
+        on_start();
         let voice_id = request.voice_id.clone();
-        let descriptor = {
-            let voices = self.voice_map.read();
-            voices
-                .get(&voice_id)
-                .cloned()
-                .ok_or_else(|| anyhow::anyhow!("voice '{}' is not registered", voice_id))?
+        let descriptor = self
+            .resolve_voice(&voice_id, engine)
+            .ok_or_else(|| match engine {
+                Some(engine) => {
+                    anyhow::anyhow!("voice '{voice_id}' is not registered on '{engine}'")
+                }
+                None => anyhow::anyhow!("voice '{}' is not registered", voice_id),
+            })?;
+
+        let request = self.respell(request, &descriptor);
+        let result = self
+            .synthesize_chunks(request, &descriptor, progress.as_ref())
+            .await;
+        self.record_stats(&descriptor, started_at, &result);
+        result
+    }
+
+    /// Applies the pronunciation dictionary of the voice that will render
+    /// `request` to its text and segments.
+    fn respell(&self, mut request: TtsRequest, descriptor: &VoiceDescriptor) -> TtsRequest {
+        if self.pronunciations.is_none() {
+            return request;
+        }
+        request.text = self.effective_text(&descriptor.id, &request.text);
+        for segment in request.segments.iter_mut().flatten() {
+            segment.text = self.effective_text(&descriptor.id, &segment.text);
+        }
+        request
+    }
+
+    /// `text` as `voice_id` will receive it, after pronunciation respelling.
+    pub fn effective_text(&self, voice_id: &str, text: &str) -> String {
+        match &self.pronunciations {
+            Some(pronunciations) => pronunciations.apply(voice_id, text),
+            None => text.to_string(),
+        }
+    }
+
+    fn record_stats(
+        &self,
+        descriptor: &VoiceDescriptor,
+        started_at: Instant,
+        result: &Result<TtsResponse>,
+    ) {
+        let mut stats = self.stats.lock();
+        let counter = stats
+            .entry((descriptor.id.clone(), descriptor.engine))
+            .or_default();
+        counter.requests += 1;
+        match result {
+            Ok(response) => {
+                counter.total_elapsed_ms += started_at.elapsed().as_millis() as u64;
+                counter.cache_hits += u64::from(response.cache_hit);
+            }
+            Err(_) => counter.failures += 1,
+        }
+    }
+
+    /// Per-voice usage since startup, most requested first.
+    pub fn voice_stats(&self) -> Vec<VoiceStats> {
+        let mut stats: Vec<VoiceStats> = self
+            .stats
+            .lock()
+            .iter()
+            .map(|((voice_id, engine), counter)| {
+                let succeeded = counter.requests - counter.failures;
+                let ratio = |value: u64| {
+                    if succeeded == 0 {
+                        0.0
+                    } else {
+                        value as f64 / succeeded as f64
+                    }
+                };
+                VoiceStats {
+                    voice_id: voice_id.clone(),
+                    engine: *engine,
+                    requests: counter.requests,
+                    failures: counter.failures,
+                    total_elapsed_ms: counter.total_elapsed_ms,
+                    avg_elapsed_ms: ratio(counter.total_elapsed_ms),
+                    cache_hit_rate: ratio(counter.cache_hits),
+                }
+            })
+            .collect();
+        stats.sort_by(|a, b| {
+            b.requests
+                .cmp(&a.requests)
+                .then_with(|| a.voice_id.cmp(&b.voice_id))
+        });
+        stats
+    }
+
+    /// Renders `request` in one engine call, or chunk by chunk when the text
+    /// is too long for one.
+    async fn synthesize_chunks(
+        &self,
+        request: TtsRequest,
+        descriptor: &VoiceDescriptor,
+        progress: Option<&ProgressSink>,
+    ) -> Result<TtsResponse> {
+        // Segments already split the text where the caller wants it.
+        let chunks = if request.segments.is_some() {
+            Vec::new()
+        } else {
+            self.split_request(&request.text, descriptor)
+        };
+        if chunks.len() <= 1 {
+            return self
+                .synthesize_with_fallback(request, descriptor, progress)
+                .await;
+        }
+        let total = chunks.len() as u32;
+        let mut voice = descriptor.clone();
+        let mut responses: Vec<TtsResponse> = Vec::with_capacity(chunks.len());
+        for (index, text) in chunks.iter().enumerate() {
+            let chunk = chunk_request(&request, text, descriptor, &voice);
+            let mut response = self.synthesize_with_fallback(chunk, &voice, None).await?;
+            let switched = voice.id != descriptor.id || voice.engine != descriptor.engine;
+            if response.fallback_used && !switched {
+                // One clip, one voice: the fallback renders the remaining
+                // chunks and redoes the ones the primary voice already did.
+                if let Some(fallback) = self.fallback_voice(descriptor) {
+                    warn!(
+                        target = "ishowtts::synth",
+                        voice = %descriptor.id,
+                        fallback = %fallback.id,
+                        chunk = index + 1,
+                        chunks = total,
+                        "chunk fell back; rendering every chunk with the fallback voice"
+                    );
+                    voice = fallback;
+                    for (redo, text) in chunks[..index].iter().enumerate() {
+                        let chunk = chunk_request(&request, text, descriptor, &voice);
+                        responses[redo] =
+                            self.synthesize_with_fallback(chunk, &voice, None).await?;
+                        responses[redo].fallback_used = true;
+                    }
+                }
+            }
+            response.fallback_used |= switched;
+            responses.push(response);
+            if let Some(progress) = progress {
+                progress(SynthesisProgress {
+                    completed: index as u32 + 1,
+                    total,
+                });
+            }
+        }
+        self.stitch_responses(responses)
+    }
+
+    /// Sentence chunks for `text`, capped at `chunking.max_chunks`. Empty or
+    /// single-element when the text fits one engine call.
+    fn split_request(&self, text: &str, descriptor: &VoiceDescriptor) -> Vec<String> {
+        if !self.chunking.enabled {
+            return Vec::new();
+        }
+        let mut chunks = split_into_chunks(text, MAX_CHUNK_WORDS);
+        let max_chunks = self.chunking.max_chunks.max(1);
+        if chunks.len() > max_chunks {
+            warn!(
+                target = "ishowtts::synth",
+                voice = %descriptor.id,
+                chunks = chunks.len(),
+                max_chunks,
+                "text exceeds chunking.max_chunks; dropping the remainder"
+            );
+            chunks.truncate(max_chunks);
+        }
+        chunks
+    }
+
+    /// Joins per-chunk responses into one clip, keeping the first chunk's
+    /// metadata.
+    fn stitch_responses(&self, responses: Vec<TtsResponse>) -> Result<TtsResponse> {
+        let clips = responses
+            .iter()
+            .map(|response| {
+                BASE64_STANDARD
+                    .decode(response.audio_base64.as_bytes())
+                    .context("chunk audio is not valid base64")
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let stitched = stitch_wav(&clips, self.chunking.gap_ms, self.chunking.crossfade_ms)?;
+        let fallback_used = responses.iter().any(|response| response.fallback_used);
+        let cache_hit = responses.iter().all(|response| response.cache_hit);
+        let mut response = responses
+            .into_iter()
+            .next()
+            .expect("stitching needs at least one chunk");
+        response.audio_base64 = BASE64_STANDARD.encode(&stitched.wav);
+        response.sample_rate = stitched.sample_rate;
+        response.waveform_len = stitched.frames;
+        response.duration_ms = clip_duration_ms(stitched.frames, stitched.sample_rate);
+        response.peak = stitched.peak;
+        response.rms = stitched.rms;
+        response.fallback_used = fallback_used;
+        response.cache_hit = cache_hit;
+        Ok(response)
+    }
+
+    /// One engine call for `descriptor`, retried on the fallback voice when
+    /// it fails. Only the first attempt reports `progress`.
+    async fn synthesize_with_fallback(
+        &self,
+        request: TtsRequest,
+        descriptor: &VoiceDescriptor,
+        progress: Option<&ProgressSink>,
+    ) -> Result<TtsResponse> {
+        let voice_id = request.voice_id.clone();
+        let engine = self.engine_for(descriptor)?;
+        let fallback = self.fallback_voice(descriptor);
+        let retry = fallback.as_ref().map(|_| request.clone());
+
+        let attempt = match progress {
+            Some(progress) => {
+                engine
+                    .synthesize_with_progress(request, progress.clone())
+                    .await
+            }
+            None => engine.synthesize(request).await,
+        };
+        let err = match attempt {
+            Ok(response) => {
+                self.mark_ready(descriptor.engine);
+                return Ok(response);
+            }
+            Err(err) => err,
+        };
+        let (Some(fallback), Some(mut retry)) = (fallback, retry) else {
+            return Err(err);
+        };
+
+        warn!(
+            target = "ishowtts::synth",
+            voice = %voice_id,
+            engine = %descriptor.engine,
+            fallback = %fallback.id,
+            fallback_engine = %fallback.engine,
+            error = %err,
+            "synthesis failed; retrying with fallback voice"
+        );
+        retry.voice_id = fallback.id.clone();
+        if fallback.engine != descriptor.engine {
+            // Engine-specific defaults such as the NFE step do not carry over.
+            retry.nfe_step = None;
+        }
+        let mut response = self
+            .engine_for(&fallback)?
+            .synthesize(retry)
+            .await
+            .with_context(|| {
+                format!(
+                    "fallback voice '{}' failed after '{}' errored: {err:#}",
+                    fallback.id, voice_id
+                )
+            })?;
+        self.mark_ready(fallback.engine);
+        response.fallback_used = true;
+        Ok(response)
+    }
+
+    fn mark_ready(&self, engine: EngineKind) {
+        if self.ready.read().contains(&engine) {
+            return;
+        }
+        self.ready.write().insert(engine);
+    }
+
+    pub fn has_engine(&self, engine: EngineKind) -> bool {
+        self.engines.contains_key(&engine)
+    }
+
+    pub fn engine_state(&self, engine: EngineKind) -> EngineLoadState {
+        if self.ready.read().contains(&engine) {
+            EngineLoadState::Ready
+        } else if self.warming.lock().contains(&engine) {
+            EngineLoadState::Loading
+        } else {
+            EngineLoadState::Idle
+        }
+    }
+
+    /// Load state of every registered engine, in name order.
+    pub fn engine_states(&self) -> Vec<EngineStatus> {
+        let mut states: Vec<EngineStatus> = self
+            .engines
+            .keys()
+            .map(|engine| EngineStatus {
+                engine: *engine,
+                state: self.engine_state(*engine),
+            })
+            .collect();
+        states.sort_by_key(|status| status.engine.as_str());
+        states
+    }
+
+    /// [`Self::gpu_stats`] if it was read within the last few seconds.
+    pub fn cached_gpu_stats(&self) -> Option<Vec<EngineGpuStats>> {
+        let cached = self.gpu_stats.lock();
+        let (at, stats) = cached.as_ref()?;
+        (at.elapsed() < GPU_STATS_TTL).then(|| stats.clone())
+    }
+
+    /// Device placement and VRAM of every engine backed by torch, in name
+    /// order. Blocks on the GIL unless a recent reading is cached, so call it
+    /// off the async runtime.
+    pub fn gpu_stats(&self) -> Vec<EngineGpuStats> {
+        if let Some(stats) = self.cached_gpu_stats() {
+            return stats;
+        }
+        let mut stats: Vec<EngineGpuStats> = self
+            .engines
+            .iter()
+            .filter_map(|(engine, backend)| {
+                backend.gpu_stats().map(|stats| EngineGpuStats {
+                    engine: *engine,
+                    stats,
+                })
+            })
+            .collect();
+        stats.sort_by_key(|entry| entry.engine.as_str());
+        *self.gpu_stats.lock() = Some((Instant::now(), stats.clone()));
+        stats
+    }
+
+    /// Runs a short synthesis on the engine's first voice so its models are
+    /// loaded before a real request arrives. Returns `false` without doing
+    /// anything when the engine is already ready or another warmup is running.
+    pub async fn warmup_engine(&self, engine: EngineKind) -> Result<bool> {
+        if self.engine_state(engine) == EngineLoadState::Ready
+            || !self.warming.lock().insert(engine)
+        {
+            return Ok(false);
+        }
+        let voice = self
+            .voices()
+            .into_iter()
+            .find(|voice| voice.engine == engine);
+        let result = match voice {
+            Some(voice) => self.warmup_voice(&voice.id, engine, "Warmup sample").await,
+            None => Err(anyhow::anyhow!(
+                "engine '{engine}' has no voices to warm up"
+            )),
         };
-        let engine = self.engines.get(&descriptor.engine).ok_or_else(|| {
+        self.warming.lock().remove(&engine);
+        result.map(|()| true)
+    }
+
+    fn engine_for(&self, descriptor: &VoiceDescriptor) -> Result<&Arc<dyn TtsEngine>> {
+        self.engines.get(&descriptor.engine).ok_or_else(|| {
             anyhow::anyhow!(
                 "engine '{}' not initialised for voice '{}'",
                 descriptor.engine,
-                voice_id
+                descriptor.id
             )
-        })?;
-        engine.synthesize(request).await
+        })
+    }
+
+    /// Explicit pair first; otherwise a voice on the fallback engine, preferring
+    /// one whose primary language subtag matches.
+    fn fallback_voice(&self, descriptor: &VoiceDescriptor) -> Option<VoiceDescriptor> {
+        if let Some(target) = self.fallback.voices.get(&descriptor.id) {
+            return self.voice_descriptor(target);
+        }
+        let voices = self.voice_map.read();
+
+        let engine = self
+            .fallback
+            .engine
+            .filter(|engine| *engine != descriptor.engine)?;
+        let primary = |language: Option<&str>| {
+            language
+                .and_then(|tag| tag.split(['-', '_']).next())
+                .map(str::to_ascii_lowercase)
+        };
+        let wanted = primary(descriptor.language.as_deref());
+        let mut candidates: Vec<&VoiceDescriptor> = voices
+            .values()
+            .filter(|voice| voice.engine == engine)
+            .collect();
+        candidates.sort_by(|a, b| a.id.cmp(&b.id));
+        candidates
+            .iter()
+            .find(|voice| wanted.is_some() && primary(voice.language.as_deref()) == wanted)
+            .or_else(|| candidates.first())
+            .map(|voice| (*voice).clone())
+    }
+
+    /// True when every synthesis slot is taken, so a new request would wait.
+    pub fn is_saturated(&self) -> bool {
+        self.limiter.available_permits() == 0
+    }
+
+    /// Checks that every voice's active reference clips exist, are non-empty
+    /// and decode. Reads and decodes each file, so call it off the async
+    /// runtime.
+    pub fn self_check(&self) -> SelfCheckReport {
+        let voices = self.voices();
+        let mut unhealthy = Vec::new();
+        for voice in &voices {
+            let Some(engine) = self.engines.get(&voice.engine) else {
+                continue;
+            };
+            let Some((clips, _)) = engine.resolve_reference(&voice.id) else {
+                continue;
+            };
+            let mut problems: Vec<String> = clips
+                .iter()
+                .filter_map(|clip| check_reference_clip(clip).err())
+                .collect();
+            if clips.is_empty() {
+                problems.push("no reference audio configured".to_string());
+            }
+            if !problems.is_empty() {
+                unhealthy.push(VoiceProblem {
+                    voice_id: voice.id.clone(),
+                    engine: voice.engine,
+                    problems,
+                });
+            }
+        }
+        SelfCheckReport {
+            total: voices.len(),
+            healthy: voices.len() - unhealthy.len(),
+            unhealthy,
+        }
+    }
+
+    /// Whether any engine still has a voice to synthesize with.
+    pub fn has_voices(&self) -> bool {
+        !self.voice_map.read().is_empty()
     }
 
     pub fn voices(&self) -> Vec<VoiceDescriptor> {
         let voices_guard = self.voice_map.read();
         let mut voices: Vec<VoiceDescriptor> = voices_guard.values().cloned().collect();
-        voices.sort_by(|a, b| a.id.cmp(&b.id));
+        voices.sort_by(|a, b| {
+            a.id.cmp(&b.id)
+                .then_with(|| a.engine.as_str().cmp(b.engine.as_str()))
+        });
         voices
     }
 
+    /// The voice `voice_id` on whichever engine serves it by default.
     pub fn voice_descriptor(&self, voice_id: &str) -> Option<VoiceDescriptor> {
-        self.voice_map.read().get(voice_id).cloned()
+        self.resolve_voice(voice_id, None)
     }
 
-    pub async fn warmup_voice(&self, voice_id: &str, text: &str) -> Result<()> {
-        let request = TtsRequest {
+    /// Looks up `voice_id` on `engine`, or, without an engine, on the first
+    /// registered engine that has a voice with that id.
+    pub fn resolve_voice(
+        &self,
+        voice_id: &str,
+        engine: Option<EngineKind>,
+    ) -> Option<VoiceDescriptor> {
+        let voices = self.voice_map.read();
+        match engine {
+            Some(engine) => voices.get(&(voice_id.to_string(), engine)).cloned(),
+            None => self
+                .engine_order
+                .iter()
+                .find_map(|engine| voices.get(&(voice_id.to_string(), *engine)).cloned()),
+        }
+    }
+
+    pub async fn warmup_voice(&self, voice_id: &str, engine: EngineKind, text: &str) -> Result<()> {
+        let request = Self::default_request(voice_id, text);
+        let _ = self.synthesize_notify(request, Some(engine), || {}).await?;
+        Ok(())
+    }
+
+    /// Synthesizes `text` with the engine defaults for every tuning knob.
+    pub async fn synthesize_text(&self, voice_id: &str, text: &str) -> Result<TtsResponse> {
+        self.synthesize(Self::default_request(voice_id, text)).await
+    }
+
+    fn default_request(voice_id: &str, text: &str) -> TtsRequest {
+        TtsRequest {
             text: text.to_string(),
             voice_id: voice_id.to_string(),
             speed: None,
@@ -123,10 +767,10 @@ impl Synthesizer {
             fix_duration: None,
             remove_silence: None,
             seed: None,
-        };
-
-        let _ = self.synthesize(request).await?;
-        Ok(())
+            normalize_output_rms: None,
+            no_cache: false,
+            segments: None,
+        }
     }
 
     pub fn apply_override(
@@ -137,10 +781,11 @@ impl Synthesizer {
     ) -> Result<()> {
         if let Some(engine_impl) = self.engines.get(&engine) {
             engine_impl.apply_override(voice_id, update.clone())?;
-            if let Some(descriptor) = self.voice_map.write().get_mut(voice_id) {
+            let key = (voice_id.to_string(), engine);
+            if let Some(descriptor) = self.voice_map.write().get_mut(&key) {
                 if let Some(text) = update.reference_text {
                     descriptor.reference_text = Some(text);
-                } else if let Some(baseline) = self.baseline_map.get(voice_id) {
+                } else if let Some(baseline) = self.baseline_map.read().get(&key) {
                     descriptor.reference_text = baseline.reference_text.clone();
                 }
             }
@@ -154,8 +799,70 @@ impl Synthesizer {
         }
     }
 
-    pub fn baseline(&self, voice_id: &str) -> Option<VoiceBaseline> {
-        self.baseline_map.get(voice_id).cloned()
+    /// Swaps `engine`'s voices for `voices` on the live engine and refreshes
+    /// the registry and baselines from it. Overrides are not re-applied.
+    pub fn reload_voices(&self, engine: EngineKind, voices: EngineVoices) -> Result<()> {
+        let engine_impl = self
+            .engines
+            .get(&engine)
+            .ok_or_else(|| anyhow::anyhow!("engine '{engine}' is not loaded"))?;
+        engine_impl.reload_voices(voices)?;
+
+        let descriptors = engine_impl.voice_descriptors();
+        let mut voice_map = self.voice_map.write();
+        let mut baseline_map = self.baseline_map.write();
+        voice_map.retain(|(_, kind), _| *kind != engine);
+        baseline_map.retain(|(_, kind), _| *kind != engine);
+        for descriptor in descriptors {
+            let key = (descriptor.id.clone(), engine);
+            if let Some((reference_audio, reference_text)) =
+                engine_impl.resolve_reference(&descriptor.id)
+            {
+                baseline_map.insert(
+                    key.clone(),
+                    VoiceBaseline {
+                        reference_audio,
+                        reference_text,
+                    },
+                );
+            }
+            voice_map.insert(key, descriptor);
+        }
+        Ok(())
+    }
+
+    /// Loaded engines with their voice counts and tunable parameters, in
+    /// name order.
+    pub fn engines(&self) -> Vec<EngineSummary> {
+        let voices = self.voice_map.read();
+        let mut engines: Vec<EngineSummary> = self
+            .engines
+            .iter()
+            .map(|(kind, engine)| EngineSummary {
+                engine: *kind,
+                label: kind.label(),
+                voices: voices
+                    .values()
+                    .filter(|voice| voice.engine == *kind)
+                    .count(),
+                parameters: engine.parameters(),
+            })
+            .collect();
+        engines.sort_by_key(|summary| summary.engine.as_str());
+        engines
+    }
+
+    pub fn default_nfe_step(&self, engine: EngineKind) -> Option<u32> {
+        self.engines
+            .get(&engine)
+            .and_then(|engine| engine.default_nfe_step())
+    }
+
+    pub fn baseline(&self, voice_id: &str, engine: EngineKind) -> Option<VoiceBaseline> {
+        self.baseline_map
+            .read()
+            .get(&(voice_id.to_string(), engine))
+            .cloned()
     }
 }
 
@@ -163,9 +870,238 @@ impl Clone for Synthesizer {
     fn clone(&self) -> Self {
         Self {
             engines: self.engines.clone(),
-            voice_map: RwLock::new(self.voice_map.read().clone()),
+            engine_order: self.engine_order.clone(),
+            voice_map: self.voice_map.clone(),
             baseline_map: self.baseline_map.clone(),
             limiter: self.limiter.clone(),
+            fallback: self.fallback.clone(),
+            chunking: self.chunking.clone(),
+            pronunciations: self.pronunciations.clone(),
+            ready: self.ready.clone(),
+            warming: self.warming.clone(),
+            stats: self.stats.clone(),
+            gpu_stats: self.gpu_stats.clone(),
+        }
+    }
+}
+
+/// Why a reference clip cannot be used, if it cannot.
+pub(crate) fn check_reference_clip(path: &Path) -> std::result::Result<(), String> {
+    let display = path.display();
+    let bytes = std::fs::read(path).map_err(|err| format!("{display}: cannot read ({err})"))?;
+    if bytes.is_empty() {
+        return Err(format!("{display}: file is empty"));
+    }
+    let extension = path.extension().and_then(|ext| ext.to_str());
+    // The decoder cannot read Opus; like uploads, such clips are only sniffed.
+    if infer_audio_extension_from_bytes(&bytes).as_deref() == Some("opus") {
+        return Ok(());
+    }
+    if extension.is_some_and(|ext| ext.eq_ignore_ascii_case("opus")) {
+        return Err(format!("{display}: is not an Ogg Opus file"));
+    }
+    let (samples, _) = decode_mono(bytes, extension)
+        .map_err(|err| format!("{display}: does not decode ({err})"))?;
+    if samples.is_empty() {
+        return Err(format!("{display}: contains no audio"));
+    }
+    Ok(())
+}
+
+/// `request` narrowed to one chunk of its text, rendered on `voice` instead
+/// of the `requested` one once a chunk has fallen back.
+fn chunk_request(
+    request: &TtsRequest,
+    text: &str,
+    requested: &VoiceDescriptor,
+    voice: &VoiceDescriptor,
+) -> TtsRequest {
+    let mut chunk = request.clone();
+    chunk.text = text.to_string();
+    chunk.voice_id = voice.id.clone();
+    if voice.engine != requested.engine {
+        // Engine-specific defaults such as the NFE step do not carry over.
+        chunk.nfe_step = None;
+    }
+    chunk
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reference_check_sniffs_opus_without_decoding() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut opus = b"OggS".to_vec();
+        opus.resize(28, 0);
+        opus.extend_from_slice(b"OpusHead");
+        opus.resize(64, 0);
+        let path = dir.path().join("ref.opus");
+        std::fs::write(&path, &opus).unwrap();
+        assert_eq!(check_reference_clip(&path), Ok(()));
+
+        let bogus = dir.path().join("bogus.opus");
+        std::fs::write(&bogus, b"not audio at all").unwrap();
+        assert!(check_reference_clip(&bogus)
+            .unwrap_err()
+            .contains("not an Ogg Opus file"));
+
+        let empty = dir.path().join("empty.wav");
+        std::fs::write(&empty, b"").unwrap();
+        assert!(check_reference_clip(&empty)
+            .unwrap_err()
+            .contains("file is empty"));
+    }
+
+    fn mock_voice(id: &str, engine: EngineKind) -> VoiceDescriptor {
+        VoiceDescriptor {
+            id: id.to_string(),
+            engine,
+            engine_label: engine.to_string(),
+            language: None,
+            reference_text: None,
+        }
+    }
+
+    fn mock_synthesizer() -> Synthesizer {
+        let voice = mock_voice("walter", EngineKind::F5);
+        let engine = tts_engine::MockEngine::new(EngineKind::F5, vec![(voice, Vec::new())]);
+        Synthesizer::new(vec![Arc::new(engine)], 1).unwrap()
+    }
+
+    /// A [`tts_engine::MockEngine`] that records the text of every call and,
+    /// when `flaky`, fails any text containing `"BROKEN"`.
+    struct FlakyEngine {
+        inner: tts_engine::MockEngine,
+        flaky: bool,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl TtsEngine for FlakyEngine {
+        fn kind(&self) -> EngineKind {
+            self.inner.kind()
+        }
+
+        fn voice_descriptors(&self) -> Vec<VoiceDescriptor> {
+            self.inner.voice_descriptors()
         }
+
+        async fn synthesize(&self, request: TtsRequest) -> Result<TtsResponse> {
+            self.calls.lock().push(request.text.clone());
+            if self.flaky && request.text.contains("BROKEN") {
+                anyhow::bail!("engine choked on the chunk");
+            }
+            self.inner.synthesize(request).await
+        }
+
+        fn apply_override(&self, voice_id: &str, update: VoiceOverrideUpdate) -> Result<()> {
+            self.inner.apply_override(voice_id, update)
+        }
+
+        fn resolve_reference(&self, voice_id: &str) -> Option<(Vec<PathBuf>, Option<String>)> {
+            self.inner.resolve_reference(voice_id)
+        }
+    }
+
+    /// `sentences` sentences of 50 words each, so every one is its own chunk.
+    fn long_text(sentences: &[&str]) -> String {
+        sentences
+            .iter()
+            .map(|word| format!("{}.", vec![*word; 50].join(" ")))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[tokio::test]
+    async fn long_text_is_chunked_truncated_and_stitched_in_one_voice() {
+        let primary_calls = Arc::new(Mutex::new(Vec::new()));
+        let primary = FlakyEngine {
+            inner: tts_engine::MockEngine::new(
+                EngineKind::F5,
+                vec![(mock_voice("walter", EngineKind::F5), Vec::new())],
+            ),
+            flaky: true,
+            calls: primary_calls.clone(),
+        };
+        let fallback_calls = Arc::new(Mutex::new(Vec::new()));
+        let fallback = FlakyEngine {
+            inner: tts_engine::MockEngine::new(
+                EngineKind::IndexTts,
+                vec![(mock_voice("index-walter", EngineKind::IndexTts), Vec::new())],
+            ),
+            flaky: false,
+            calls: fallback_calls.clone(),
+        };
+        let synthesizer = Synthesizer::new(vec![Arc::new(primary), Arc::new(fallback)], 1)
+            .unwrap()
+            .with_fallback(FallbackConfig {
+                voices: HashMap::from([("walter".to_string(), "index-walter".to_string())]),
+                engine: None,
+            })
+            .with_chunking(ChunkingConfig {
+                enabled: true,
+                max_chunks: 3,
+                gap_ms: 100,
+                crossfade_ms: 0,
+            });
+        assert_eq!(synthesizer.max_request_words(), MAX_CHUNK_WORDS * 3);
+
+        // Four chunks: the fourth is dropped, so its marker never reaches an
+        // engine.
+        let response = synthesizer
+            .synthesize_text("walter", &long_text(&["la", "da", "na", "BROKEN"]))
+            .await
+            .unwrap();
+        assert_eq!(response.voice_id, "walter");
+        assert!(!response.fallback_used);
+        assert_eq!(primary_calls.lock().len(), 3);
+        assert!(fallback_calls.lock().is_empty());
+        let single = synthesizer
+            .synthesize_text("walter", &long_text(&["la"]))
+            .await
+            .unwrap();
+        assert!(response.duration_ms >= 3 * single.duration_ms + 200);
+
+        // The second chunk fails on the primary voice: the first is redone
+        // on the fallback so the clip never switches voice halfway.
+        primary_calls.lock().clear();
+        let response = synthesizer
+            .synthesize_text("walter", &long_text(&["pa", "BROKEN", "ta"]))
+            .await
+            .unwrap();
+        assert_eq!(response.voice_id, "index-walter");
+        assert!(response.fallback_used);
+        assert_eq!(primary_calls.lock().len(), 2);
+        let redone = fallback_calls.lock().clone();
+        assert_eq!(redone.len(), 3);
+        assert!(redone[0].contains("BROKEN"));
+        assert!(redone[1].starts_with("pa"));
+        assert!(redone[2].starts_with("ta"));
+    }
+
+    #[tokio::test]
+    async fn engines_are_idle_until_warmed_up() {
+        let synthesizer = mock_synthesizer();
+        assert_eq!(
+            synthesizer.engine_state(EngineKind::F5),
+            EngineLoadState::Idle
+        );
+
+        synthesizer.warming.lock().insert(EngineKind::F5);
+        assert_eq!(
+            synthesizer.engine_state(EngineKind::F5),
+            EngineLoadState::Loading
+        );
+        // Another warmup is already running.
+        assert!(!synthesizer.warmup_engine(EngineKind::F5).await.unwrap());
+        synthesizer.warming.lock().remove(&EngineKind::F5);
+
+        assert!(synthesizer.warmup_engine(EngineKind::F5).await.unwrap());
+        assert_eq!(
+            synthesizer.engine_state(EngineKind::F5),
+            EngineLoadState::Ready
+        );
     }
 }
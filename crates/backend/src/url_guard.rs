@@ -0,0 +1,157 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use reqwest::Url;
+
+/// The host of `url` in the form `lookup_host` and `resolve` expect: IPv6
+/// literals lose the brackets `Url::host_str` keeps around them.
+pub fn url_host(url: &Url) -> Option<String> {
+    let host = url.host_str()?;
+    let host = host
+        .strip_prefix('[')
+        .and_then(|inner| inner.strip_suffix(']'))
+        .unwrap_or(host);
+    Some(host.to_string())
+}
+
+/// Whether `ip` is a globally routable address that reference audio may be
+/// downloaded from. IPv6 forms that carry an IPv4 address are judged by that
+/// address.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_public_v4(v4),
+        IpAddr::V6(v6) => match embedded_v4(v6) {
+            Some(v4) => is_public_v4(v4),
+            None => is_public_v6(v6),
+        },
+    }
+}
+
+fn is_public_v4(v4: Ipv4Addr) -> bool {
+    let octets = v4.octets();
+    !(v4.is_private()
+        || v4.is_loopback()
+        || v4.is_link_local()
+        || v4.is_unspecified()
+        || v4.is_broadcast()
+        || v4.is_multicast()
+        || v4.is_documentation()
+        // 0.0.0.0/8 "this network"
+        || octets[0] == 0
+        // 100.64.0.0/10 carrier-grade NAT
+        || (octets[0] == 100 && (octets[1] & 0xC0) == 64)
+        // 192.0.0.0/24 IETF protocol assignments
+        || (octets[0] == 192 && octets[1] == 0 && octets[2] == 0)
+        // 198.18.0.0/15 benchmarking
+        || (octets[0] == 198 && (octets[1] & 0xFE) == 18)
+        // 240.0.0.0/4 reserved
+        || octets[0] >= 240)
+}
+
+fn is_public_v6(v6: Ipv6Addr) -> bool {
+    let segments = v6.segments();
+    !(v6.is_loopback()
+        || v6.is_unspecified()
+        || v6.is_multicast()
+        // fc00::/7 unique local, fe80::/10 link local
+        || (segments[0] & 0xFE00) == 0xFC00
+        || (segments[0] & 0xFFC0) == 0xFE80
+        // 64:ff9b:1::/48 local-use NAT64
+        || segments[..3] == [0x64, 0xFF9B, 1]
+        // 2001:db8::/32 documentation
+        || segments[..2] == [0x2001, 0xDB8])
+}
+
+/// The IPv4 address inside an IPv4-mapped (`::ffff:a.b.c.d`),
+/// IPv4-compatible (`::a.b.c.d`), NAT64 (`64:ff9b::/96`) or 6to4
+/// (`2002::/16`) address.
+fn embedded_v4(v6: Ipv6Addr) -> Option<Ipv4Addr> {
+    if let Some(mapped) = v6.to_ipv4_mapped() {
+        return Some(mapped);
+    }
+    let segments = v6.segments();
+    let octets = v6.octets();
+    let low = Ipv4Addr::new(octets[12], octets[13], octets[14], octets[15]);
+    if segments[..6] == [0; 6] && !v6.is_loopback() && !v6.is_unspecified() {
+        return Some(low);
+    }
+    if segments[..6] == [0x64, 0xFF9B, 0, 0, 0, 0] {
+        return Some(low);
+    }
+    if segments[0] == 0x2002 {
+        return Some(Ipv4Addr::new(octets[2], octets[3], octets[4], octets[5]));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn public(addr: &str) -> bool {
+        is_public_ip(addr.parse().unwrap())
+    }
+
+    #[test]
+    fn rejects_special_ipv4_ranges() {
+        for addr in [
+            "0.0.0.0",
+            "0.1.2.3",
+            "10.0.0.1",
+            "100.64.0.1",
+            "127.0.0.1",
+            "169.254.169.254",
+            "172.16.0.1",
+            "192.0.0.8",
+            "192.168.1.1",
+            "198.18.0.1",
+            "198.19.255.255",
+            "240.0.0.1",
+            "255.255.255.255",
+        ] {
+            assert!(!public(addr), "{addr} should be rejected");
+        }
+        for addr in ["1.1.1.1", "8.8.8.8", "192.0.1.1", "198.20.0.1"] {
+            assert!(public(addr), "{addr} should be allowed");
+        }
+    }
+
+    #[test]
+    fn judges_ipv6_by_embedded_ipv4() {
+        for addr in [
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::10.0.0.1",
+            "64:ff9b::a9fe:a9fe",
+            "64:ff9b:1::1",
+            "2002:7f00:1::",
+            "2001:db8::1",
+        ] {
+            assert!(!public(addr), "{addr} should be rejected");
+        }
+        for addr in [
+            "2606:4700:4700::1111",
+            "::ffff:8.8.8.8",
+            "64:ff9b::808:808",
+            "2002:808:808::",
+        ] {
+            assert!(public(addr), "{addr} should be allowed");
+        }
+    }
+
+    #[test]
+    fn unbrackets_ipv6_hosts() {
+        let host = |raw: &str| url_host(&Url::parse(raw).unwrap());
+        assert_eq!(host("http://[::1]:8080/a.wav").as_deref(), Some("::1"));
+        assert_eq!(
+            host("https://127.0.0.1/a.wav").as_deref(),
+            Some("127.0.0.1")
+        );
+        assert_eq!(
+            host("https://Example.com/a.wav").as_deref(),
+            Some("example.com")
+        );
+    }
+}
@@ -1,14 +1,17 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
-use tracing::debug;
-use tts_engine::EngineKind;
+use tracing::{debug, info, warn};
+use tts_engine::{EngineKind, VoiceOverrideUpdate};
+
+use crate::config::ReferenceTextOverflow;
 
 #[derive(Clone, Debug)]
 pub struct OverrideAudio {
@@ -16,15 +19,66 @@ pub struct OverrideAudio {
     pub extension: Option<String>,
 }
 
+/// How an uploaded clip combines with the voice's current reference clips.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClipMode {
+    #[default]
+    Replace,
+    Append,
+}
+
+/// Reference text over the engine's `max_reference_text_chars`.
+#[derive(Debug, thiserror::Error)]
+#[error("参考文本共 {chars} 字，超过上限 {max_chars} 字")]
+pub struct ReferenceTextTooLong {
+    pub chars: usize,
+    pub max_chars: usize,
+}
+
+/// Applies a reference text limit, returning the text to store and whether
+/// it was cut short. `None` or `0` means no limit.
+pub fn limit_reference_text(
+    text: &str,
+    max_chars: Option<usize>,
+    overflow: ReferenceTextOverflow,
+) -> Result<(String, bool), ReferenceTextTooLong> {
+    let Some(max_chars) = max_chars.filter(|max| *max > 0) else {
+        return Ok((text.to_string(), false));
+    };
+    let chars = text.chars().count();
+    if chars <= max_chars {
+        return Ok((text.to_string(), false));
+    }
+    match overflow {
+        ReferenceTextOverflow::Reject => Err(ReferenceTextTooLong { chars, max_chars }),
+        ReferenceTextOverflow::Truncate => {
+            let truncated: String = text.chars().take(max_chars).collect();
+            Ok((truncated.trim_end().to_string(), true))
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct VoiceOverrideRecord {
     pub voice_id: String,
     pub engine: EngineKind,
-    pub reference_audio: Option<PathBuf>,
+    /// Active override clips in prompt order; empty when only the text is
+    /// overridden.
+    pub reference_audio: Vec<PathBuf>,
     pub reference_text: Option<String>,
     pub updated_at: Option<DateTime<Utc>>,
 }
 
+impl VoiceOverrideRecord {
+    pub fn engine_update(&self) -> VoiceOverrideUpdate {
+        VoiceOverrideUpdate {
+            reference_audio: (!self.reference_audio.is_empty())
+                .then(|| self.reference_audio.clone()),
+            reference_text: self.reference_text.clone(),
+        }
+    }
+}
+
 #[derive(Default, Serialize, Deserialize)]
 struct OverridesFile {
     entries: HashMap<String, StoredOverride>,
@@ -33,11 +87,31 @@ struct OverridesFile {
 #[derive(Clone, Serialize, Deserialize)]
 struct StoredOverride {
     engine: EngineKind,
-    reference_audio: Option<String>,
+    /// Older files store a single path (or null) here.
+    #[serde(default, deserialize_with = "deserialize_stored_clips")]
+    reference_audio: Vec<String>,
     reference_text: Option<String>,
     updated_at: Option<DateTime<Utc>>,
 }
 
+fn deserialize_stored_clips<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StoredClips {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match Option::<StoredClips>::deserialize(deserializer)? {
+        None => Vec::new(),
+        Some(StoredClips::One(path)) => vec![path],
+        Some(StoredClips::Many(paths)) => paths,
+    })
+}
+
 fn make_key(voice_id: &str, engine: EngineKind) -> String {
     format!("{}::{}", voice_id, engine.as_str())
 }
@@ -105,18 +179,23 @@ impl VoiceOverrideStore {
             .collect()
     }
 
+    /// Stores an override. With [`ClipMode::Append`] the uploaded clip is
+    /// added after the existing override clips, or after `baseline_audio` when
+    /// the voice has no audio override yet.
     pub fn set(
         &self,
         voice_id: &str,
         engine: EngineKind,
         temp_audio: Option<OverrideAudio>,
+        mode: ClipMode,
+        baseline_audio: &[PathBuf],
         reference_text: Option<String>,
     ) -> Result<VoiceOverrideRecord> {
         let mut state = self.state.lock();
         let key = make_key(voice_id, engine);
         let mut entry = state.entries.get(&key).cloned().unwrap_or(StoredOverride {
             engine,
-            reference_audio: None,
+            reference_audio: Vec::new(),
             reference_text: None,
             updated_at: None,
         });
@@ -142,7 +221,16 @@ impl VoiceOverrideStore {
                     )
                 })
                 .unwrap_or_else(|| "wav".to_string());
-            let file_name = format!("{}_{}.{}", voice_id, engine.as_str(), final_ext);
+            let file_name = match mode {
+                ClipMode::Replace => format!("{}_{}.{}", voice_id, engine.as_str(), final_ext),
+                ClipMode::Append => format!(
+                    "{}_{}_{}.{}",
+                    voice_id,
+                    engine.as_str(),
+                    Utc::now().timestamp_millis(),
+                    final_ext
+                ),
+            };
             let target_path = self.audio_dir.join(file_name);
             fs::write(&target_path, &audio.bytes).with_context(|| {
                 format!(
@@ -169,7 +257,23 @@ impl VoiceOverrideStore {
                 .strip_prefix(&self.base_dir)
                 .map(|p| p.to_string_lossy().to_string())
                 .unwrap_or_else(|_| target_path.to_string_lossy().to_string());
-            entry.reference_audio = Some(rel);
+            match mode {
+                ClipMode::Replace => {
+                    for old in std::mem::take(&mut entry.reference_audio) {
+                        if old != rel {
+                            self.remove_owned_clip(&old);
+                        }
+                    }
+                }
+                ClipMode::Append if entry.reference_audio.is_empty() => {
+                    entry.reference_audio = baseline_audio
+                        .iter()
+                        .map(|path| path.to_string_lossy().to_string())
+                        .collect();
+                }
+                ClipMode::Append => {}
+            }
+            entry.reference_audio.push(rel);
         }
 
         if let Some(text) = reference_text.clone() {
@@ -197,9 +301,8 @@ impl VoiceOverrideStore {
         let key = make_key(voice_id, engine);
         let removed = state.entries.remove(&key);
         if let Some(entry) = removed.as_ref() {
-            if let Some(rel) = &entry.reference_audio {
-                let path = self.base_dir.join(rel);
-                let _ = fs::remove_file(path);
+            for rel in &entry.reference_audio {
+                self.remove_owned_clip(rel);
             }
         }
         self.persist(&state)?;
@@ -216,11 +319,76 @@ impl VoiceOverrideStore {
         })
     }
 
+    /// Deletes files in the audio directory that no override references and
+    /// that were last modified more than `retention` ago. Returns how many
+    /// files were removed.
+    pub fn collect_garbage(&self, retention: Duration) -> Result<usize> {
+        let state = self.state.lock();
+        let referenced: HashSet<PathBuf> = state
+            .entries
+            .values()
+            .flat_map(|entry| entry.reference_audio.iter())
+            .map(|rel| self.base_dir.join(rel))
+            .collect();
+
+        let now = SystemTime::now();
+        let mut removed = 0;
+        let dir = fs::read_dir(&self.audio_dir).with_context(|| {
+            format!(
+                "failed to list overrides audio directory {}",
+                self.audio_dir.display()
+            )
+        })?;
+        for dir_entry in dir {
+            let dir_entry = dir_entry?;
+            let path = dir_entry.path();
+            let metadata = dir_entry.metadata()?;
+            if !metadata.is_file() || referenced.contains(&path) {
+                continue;
+            }
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .unwrap_or_default();
+            if age < retention {
+                continue;
+            }
+            match fs::remove_file(&path) {
+                Ok(()) => removed += 1,
+                Err(err) => warn!(
+                    target = "ishowtts::voice_overrides",
+                    path = %path.display(),
+                    %err,
+                    "failed to remove orphaned override audio"
+                ),
+            }
+        }
+
+        if removed > 0 {
+            info!(
+                target = "ishowtts::voice_overrides",
+                removed, "removed orphaned override audio files"
+            );
+        }
+        Ok(removed)
+    }
+
+    /// Deletes a stored clip, leaving baseline clips that were seeded by an
+    /// append untouched.
+    fn remove_owned_clip(&self, rel: &str) {
+        let path = self.base_dir.join(rel);
+        if path.starts_with(&self.audio_dir) {
+            let _ = fs::remove_file(path);
+        }
+    }
+
     fn record_from_entry(&self, voice_id: &str, entry: StoredOverride) -> VoiceOverrideRecord {
         let audio_path = entry
             .reference_audio
-            .as_ref()
-            .map(|rel| self.base_dir.join(rel));
+            .iter()
+            .map(|rel| self.base_dir.join(rel))
+            .collect();
         VoiceOverrideRecord {
             voice_id: voice_id.to_string(),
             engine: entry.engine,
@@ -235,6 +403,200 @@ fn split_key(key: &str) -> Option<&str> {
     key.split_once("::").map(|(voice_id, _)| voice_id)
 }
 
-fn infer_audio_extension_from_bytes(_bytes: &[u8]) -> Option<String> {
-    None
+/// `ftyp` major brands of MP4 files that hold audio. Others (3GP, HEIF
+/// images, QuickTime) are not reference audio.
+const MP4_AUDIO_BRANDS: [&[u8; 4]; 7] = [
+    b"M4A ", b"M4B ", b"F4A ", b"isom", b"iso2", b"mp41", b"mp42",
+];
+
+/// An MPEG audio frame header: 11 sync bits, then a version other than the
+/// reserved `01` and a layer other than `00`. ADTS AAC shares the sync word
+/// but always has layer `00`.
+fn is_mpeg_audio_frame(bytes: &[u8]) -> bool {
+    let [first, second, ..] = bytes else {
+        return false;
+    };
+    let version = (second >> 3) & 0b11;
+    let layer = (second >> 1) & 0b11;
+    *first == 0xFF && (second & 0xE0) == 0xE0 && version != 0b01 && layer != 0b00
+}
+
+pub fn infer_audio_extension_from_bytes(bytes: &[u8]) -> Option<String> {
+    let ext = if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WAVE" {
+        "wav"
+    } else if bytes.starts_with(b"fLaC") {
+        "flac"
+    } else if bytes.starts_with(b"OggS") {
+        if bytes.len() >= 36 && &bytes[28..36] == b"OpusHead" {
+            "opus"
+        } else {
+            "ogg"
+        }
+    } else if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" {
+        if !MP4_AUDIO_BRANDS.iter().any(|brand| &bytes[8..12] == *brand) {
+            return None;
+        }
+        "m4a"
+    } else if bytes.starts_with(b"ID3") || is_mpeg_audio_frame(bytes) {
+        "mp3"
+    } else {
+        return None;
+    };
+    Some(ext.to_string())
+}
+
+pub fn audio_content_type(extension: &str) -> &'static str {
+    match extension {
+        "wav" => "audio/wav",
+        "mp3" => "audio/mpeg",
+        "flac" => "audio/flac",
+        "ogg" => "audio/ogg",
+        "opus" => "audio/ogg; codecs=opus",
+        "m4a" => "audio/mp4",
+        _ => "application/octet-stream",
+    }
+}
+
+/// File extension for a `Content-Type` produced by [`audio_content_type`] or
+/// a synthesis response `format`; parameters such as `codecs=` are honoured.
+pub fn audio_extension(content_type: &str) -> &'static str {
+    let (essence, params) = content_type.split_once(';').unwrap_or((content_type, ""));
+    match essence.trim().to_ascii_lowercase().as_str() {
+        "audio/wav" | "audio/x-wav" | "audio/wave" => "wav",
+        "audio/mpeg" | "audio/mp3" => "mp3",
+        "audio/flac" => "flac",
+        "audio/ogg" if params.contains("opus") => "opus",
+        "audio/ogg" => "ogg",
+        "audio/opus" => "opus",
+        "audio/mp4" | "audio/aac" => "m4a",
+        _ => "bin",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn audio_files(store: &VoiceOverrideStore) -> Vec<PathBuf> {
+        fs::read_dir(&store.audio_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect()
+    }
+
+    fn upload(extension: &str) -> Option<OverrideAudio> {
+        Some(OverrideAudio {
+            bytes: vec![0; 16],
+            extension: Some(extension.to_string()),
+        })
+    }
+
+    #[test]
+    fn sniffs_audio_containers() {
+        let sniff = |bytes: &[u8]| infer_audio_extension_from_bytes(bytes);
+        assert_eq!(sniff(b"RIFF\0\0\0\0WAVEfmt ").as_deref(), Some("wav"));
+        assert_eq!(sniff(b"fLaC\0\0\0\x22").as_deref(), Some("flac"));
+        assert_eq!(sniff(b"ID3\x04\0\0\0\0").as_deref(), Some("mp3"));
+        // MPEG-1 Layer III frame header.
+        assert_eq!(sniff(&[0xFF, 0xFB, 0x90, 0x64]).as_deref(), Some("mp3"));
+        // MPEG-4 ADTS AAC shares the sync word but has layer 00.
+        assert_eq!(sniff(&[0xFF, 0xF1, 0x50, 0x80]), None);
+        assert_eq!(sniff(&[0xFF, 0xF9, 0x50, 0x80]), None);
+
+        let mut opus = b"OggS".to_vec();
+        opus.resize(28, 0);
+        opus.extend_from_slice(b"OpusHead");
+        assert_eq!(sniff(&opus).as_deref(), Some("opus"));
+        assert_eq!(sniff(b"OggS\0\x02").as_deref(), Some("ogg"));
+    }
+
+    #[test]
+    fn sniffs_mp4_by_major_brand() {
+        let ftyp = |brand: &[u8; 4]| {
+            let mut bytes = vec![0, 0, 0, 0x18];
+            bytes.extend_from_slice(b"ftyp");
+            bytes.extend_from_slice(brand);
+            bytes
+        };
+        let sniff = |bytes: Vec<u8>| infer_audio_extension_from_bytes(&bytes);
+        assert_eq!(sniff(ftyp(b"M4A ")).as_deref(), Some("m4a"));
+        assert_eq!(sniff(ftyp(b"isom")).as_deref(), Some("m4a"));
+        assert_eq!(sniff(ftyp(b"3gp4")), None);
+        assert_eq!(sniff(ftyp(b"heic")), None);
+        assert_eq!(sniff(ftyp(b"qt  ")), None);
+        assert_eq!(sniff(b"\0\0\0\x18ftyp".to_vec()), None);
+    }
+
+    #[test]
+    fn replacing_override_twice_leaves_one_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = VoiceOverrideStore::load(dir.path()).unwrap();
+        for ext in ["wav", "mp3", "flac"] {
+            store
+                .set(
+                    "walter",
+                    EngineKind::F5,
+                    upload(ext),
+                    ClipMode::Replace,
+                    &[],
+                    None,
+                )
+                .unwrap();
+        }
+
+        let files = audio_files(&store);
+        assert_eq!(files.len(), 1);
+        let record = store.get("walter", EngineKind::F5).unwrap();
+        assert_eq!(record.reference_audio, files);
+    }
+
+    #[test]
+    fn collect_garbage_removes_unreferenced_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = VoiceOverrideStore::load(dir.path()).unwrap();
+        store
+            .set(
+                "walter",
+                EngineKind::F5,
+                upload("wav"),
+                ClipMode::Replace,
+                &[],
+                None,
+            )
+            .unwrap();
+        fs::write(store.audio_dir.join("stale_f5.wav"), b"orphan").unwrap();
+
+        assert_eq!(store.collect_garbage(Duration::from_secs(3600)).unwrap(), 0);
+        assert_eq!(store.collect_garbage(Duration::ZERO).unwrap(), 1);
+        let record = store.get("walter", EngineKind::F5).unwrap();
+        assert_eq!(audio_files(&store), record.reference_audio);
+    }
+
+    #[test]
+    fn reference_text_limit_counts_chars_at_the_boundary() {
+        let text = "你好世界";
+        assert_eq!(
+            limit_reference_text(text, Some(4), ReferenceTextOverflow::Reject).unwrap(),
+            (text.to_string(), false)
+        );
+        let err = limit_reference_text(text, Some(3), ReferenceTextOverflow::Reject).unwrap_err();
+        assert_eq!((err.chars, err.max_chars), (4, 3));
+        assert_eq!(
+            limit_reference_text(text, Some(3), ReferenceTextOverflow::Truncate).unwrap(),
+            ("你好世".to_string(), true)
+        );
+        assert_eq!(
+            limit_reference_text(text, None, ReferenceTextOverflow::Reject).unwrap(),
+            (text.to_string(), false)
+        );
+    }
+
+    #[test]
+    fn audio_extension_round_trips_content_types() {
+        for extension in ["wav", "mp3", "flac", "ogg", "opus", "m4a"] {
+            assert_eq!(audio_extension(audio_content_type(extension)), extension);
+        }
+        assert_eq!(audio_extension("audio/x-wav"), "wav");
+        assert_eq!(audio_extension("text/plain"), "bin");
+    }
 }
@@ -0,0 +1,163 @@
+use std::io::{Cursor, ErrorKind};
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use symphonia::core::{
+    audio::SampleBuffer, codecs::DecoderOptions, errors::Error as SymphoniaError,
+    formats::FormatOptions, io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+};
+
+/// Bucket count used when the client does not ask for one.
+pub const DEFAULT_WAVEFORM_BUCKETS: usize = 200;
+/// Upper bound on `?buckets=`; thumbnails never need more.
+pub const MAX_WAVEFORM_BUCKETS: usize = 2000;
+
+/// Level summary of one slice of a clip.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct WaveformBucket {
+    pub peak: f32,
+    pub rms: f32,
+}
+
+/// Decodes any container the override uploads accept (except Opus) and
+/// downmixes it to mono. Returns the samples and their sample rate.
+pub fn decode_mono(bytes: Vec<u8>, extension: Option<&str>) -> Result<(Vec<f32>, u32)> {
+    let mut hint = Hint::new();
+    if let Some(ext) = extension {
+        hint.with_extension(ext);
+    }
+    let source = MediaSourceStream::new(Box::new(Cursor::new(bytes)), Default::default());
+    let probed = symphonia::default::get_probe().format(
+        &hint,
+        source,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    )?;
+    let mut format = probed.format;
+    let track = format
+        .default_track()
+        .ok_or_else(|| anyhow!("no audio track"))?;
+    let track_id = track.id;
+    let mut sample_rate = track.codec_params.sample_rate.unwrap_or(0);
+    let mut decoder =
+        symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+
+    let mut mono = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.into()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt frame should not hide the rest of the clip.
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(err) => return Err(err.into()),
+        };
+        let spec = *decoded.spec();
+        sample_rate = spec.rate;
+        let channels = spec.channels.count().max(1);
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        mono.extend(
+            buffer
+                .samples()
+                .chunks(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+        );
+    }
+
+    if sample_rate == 0 {
+        return Err(anyhow!("unknown sample rate"));
+    }
+    Ok((mono, sample_rate))
+}
+
+/// Whether `bytes` look like a container that carries video: Matroska/WebM,
+/// AVI, Ogg Theora, or an MP4/MOV with a `vide` track handler.
+pub fn contains_video(bytes: &[u8]) -> bool {
+    const OGG_THEORA: &[u8] = b"\x80theora";
+    if bytes.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        return true;
+    }
+    if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"AVI " {
+        return true;
+    }
+    if bytes.starts_with(b"OggS") {
+        return bytes.windows(OGG_THEORA.len()).any(|w| w == OGG_THEORA);
+    }
+    // `hdlr` box: tag, version/flags, pre_defined, then the handler type.
+    bytes.len() >= 8
+        && &bytes[4..8] == b"ftyp"
+        && bytes
+            .windows(16)
+            .any(|w| &w[0..4] == b"hdlr" && &w[12..16] == b"vide")
+}
+
+/// Raw sample layout a websocket client may ask for instead of WAV.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PcmFormat {
+    F32Le,
+    S16Le,
+}
+
+impl PcmFormat {
+    /// Accepts `f32`/`f32le` and `s16`/`s16le`, case-insensitively.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "f32" | "f32le" => Some(Self::F32Le),
+            "s16" | "s16le" => Some(Self::S16Le),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::F32Le => "f32le",
+            Self::S16Le => "s16le",
+        }
+    }
+}
+
+/// Strips the WAV container from `wav`, returning its interleaved samples
+/// as little-endian `format` bytes and the channel count.
+pub fn wav_to_pcm(wav: &[u8], format: PcmFormat) -> Result<(Vec<u8>, u16)> {
+    let reader = hound::WavReader::new(Cursor::new(wav))?;
+    let spec = reader.spec();
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.into_samples::<f32>().collect::<Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = (1_i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .into_samples::<i32>()
+                .map(|sample| sample.map(|value| value as f32 / scale))
+                .collect::<Result<_, _>>()?
+        }
+    };
+    let bytes = match format {
+        PcmFormat::F32Le => samples.iter().flat_map(|s| s.to_le_bytes()).collect(),
+        PcmFormat::S16Le => samples
+            .iter()
+            .flat_map(|s| ((s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes())
+            .collect(),
+    };
+    Ok((bytes, spec.channels))
+}
+
+/// Splits `samples` into at most `buckets` contiguous slices of near-equal
+/// length and reports the peak and RMS of each.
+pub fn summarize(samples: &[f32], buckets: usize) -> Vec<WaveformBucket> {
+    let count = buckets.min(samples.len());
+    (0..count)
+        .map(|i| {
+            let slice = &samples[i * samples.len() / count..(i + 1) * samples.len() / count];
+            let peak = slice.iter().fold(0.0f32, |acc, s| acc.max(s.abs()));
+            let rms = (slice.iter().map(|s| s * s).sum::<f32>() / slice.len() as f32).sqrt();
+            WaveformBucket { peak, rms }
+        })
+        .collect()
+}
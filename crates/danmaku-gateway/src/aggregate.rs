@@ -0,0 +1,263 @@
+//! Folds bursts of the same short chat message ("W", "gg", "PogChamp") into
+//! one readout, so hype moments do not queue a clip per chatter.
+
+use std::collections::HashMap;
+use std::future::Future;
+
+use tokio::sync::mpsc;
+use tokio::time::{timeout_at, Duration, Instant};
+
+use crate::config::QueueConfig;
+use crate::filter::FilteredMessage;
+
+/// Longest spoken text, in characters, that is considered for grouping.
+/// Anything longer is read on its own.
+pub const MAX_AGGREGATE_CHARS: usize = 20;
+/// Senders named before the rest are counted.
+const NAMED_SENDERS: usize = 2;
+
+/// Groups messages collected over [`window`](Self::window) by channel and
+/// text, merging groups with at least `min_count` distinct senders.
+#[derive(Debug, Clone)]
+pub struct ChatAggregator {
+    window: Duration,
+    min_count: usize,
+}
+
+impl ChatAggregator {
+    /// `None` when `aggregate_window_ms` is 0, i.e. aggregation is off.
+    pub fn from_config(config: &QueueConfig) -> Option<Self> {
+        (config.aggregate_window_ms > 0).then(|| Self {
+            window: Duration::from_millis(config.aggregate_window_ms),
+            min_count: config.aggregate_min_count,
+        })
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Whether `message` is short enough to wait for others like it.
+    pub fn is_candidate(&self, message: &FilteredMessage) -> bool {
+        message.spoken_text.chars().count() <= MAX_AGGREGATE_CHARS
+    }
+
+    /// Waits out the window `first` opens, collecting the short messages
+    /// that arrive from `rx` meanwhile, and returns them [grouped](Self::group).
+    /// Messages too long to group are not held back: each is handed to
+    /// `flush` as it arrives, so it may be read before the burst it
+    /// interrupted.
+    pub async fn collect_window<F, Fut>(
+        &self,
+        first: FilteredMessage,
+        rx: &mut mpsc::Receiver<FilteredMessage>,
+        mut flush: F,
+    ) -> Vec<FilteredMessage>
+    where
+        F: FnMut(FilteredMessage) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let deadline = Instant::now() + self.window;
+        let mut batch = vec![first];
+        while let Ok(Some(next)) = timeout_at(deadline, rx.recv()).await {
+            if self.is_candidate(&next) {
+                batch.push(next);
+            } else {
+                flush(next).await;
+            }
+        }
+        self.group(batch)
+    }
+
+    /// Merges each group of matching short messages into a single message
+    /// spoken by "Alice, Bob and 3 others", at the position of its first
+    /// member. Everything else passes through in order.
+    pub fn group(&self, batch: Vec<FilteredMessage>) -> Vec<FilteredMessage> {
+        let keys: Vec<Option<(String, String)>> = batch
+            .iter()
+            .map(|message| {
+                self.is_candidate(message).then(|| {
+                    (
+                        message.source.channel.clone(),
+                        message.spoken_text.to_lowercase(),
+                    )
+                })
+            })
+            .collect();
+        let mut senders: HashMap<&(String, String), Vec<&str>> = HashMap::new();
+        for (key, message) in keys.iter().zip(&batch) {
+            if let Some(key) = key {
+                let names = senders.entry(key).or_default();
+                if !names.contains(&message.source.username.as_str()) {
+                    names.push(&message.source.username);
+                }
+            }
+        }
+        let merged: HashMap<&(String, String), String> = senders
+            .into_iter()
+            .filter(|(_, names)| names.len() >= self.min_count)
+            .map(|(key, names)| (key, speakers_label(&names)))
+            .collect();
+
+        let mut out: Vec<FilteredMessage> = Vec::with_capacity(batch.len());
+        let mut slots: HashMap<&(String, String), usize> = HashMap::new();
+        for (key, message) in keys.iter().zip(batch.iter()) {
+            let Some((key, label)) = key
+                .as_ref()
+                .and_then(|key| merged.get_key_value(key).map(|(key, label)| (*key, label)))
+            else {
+                out.push(message.clone());
+                continue;
+            };
+            match slots.get(key) {
+                Some(&slot) => {
+                    let group = &mut out[slot];
+                    group.priority = group.priority.higher(message.priority);
+                }
+                None => {
+                    slots.insert(key, out.len());
+                    let mut group = message.clone();
+                    group.source.username = label.clone();
+                    group.source.user_id = None;
+                    out.push(group);
+                }
+            }
+        }
+        out
+    }
+}
+
+/// "Alice", "Alice and Bob", or "Alice, Bob and 3 others".
+pub fn speakers_label(names: &[&str]) -> String {
+    match names {
+        [] => String::new(),
+        [only] => only.to_string(),
+        [first, second] => format!("{first} and {second}"),
+        _ => {
+            let rest = names.len() - NAMED_SENDERS;
+            let named = names[..NAMED_SENDERS].join(", ");
+            if rest == 1 {
+                format!("{named} and 1 other")
+            } else {
+                format!("{named} and {rest} others")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use danmaku::message::{NormalizedMessage, Platform, Priority};
+
+    fn filtered(channel: &str, user: &str, text: &str) -> FilteredMessage {
+        FilteredMessage {
+            source: NormalizedMessage::new_text(
+                Platform::Twitch,
+                channel,
+                Some(user.to_string()),
+                user,
+                Priority::Normal,
+                text,
+                serde_json::Value::Null,
+            ),
+            display_text: text.to_string(),
+            spoken_text: text.to_string(),
+            priority: Priority::Normal,
+            accepted_at: chrono::Utc::now(),
+        }
+    }
+
+    fn aggregator(min_count: usize) -> ChatAggregator {
+        ChatAggregator::from_config(&QueueConfig {
+            aggregate_window_ms: 1_500,
+            aggregate_min_count: min_count,
+            ..QueueConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn disabled_without_window() {
+        assert!(ChatAggregator::from_config(&QueueConfig::default()).is_none());
+    }
+
+    #[test]
+    fn merges_repeated_short_messages() {
+        let batch = vec![
+            filtered("chan", "alice", "W"),
+            filtered("chan", "bob", "w"),
+            filtered("chan", "carol", "what a play, that was unbelievable"),
+            filtered("chan", "dave", "W"),
+            filtered("chan", "erin", "W"),
+            filtered("chan", "frank", "W"),
+        ];
+        let out = aggregator(3).group(batch);
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0].source.username, "alice, bob and 3 others");
+        assert_eq!(out[0].spoken_text, "W");
+        assert_eq!(out[1].source.username, "carol");
+    }
+
+    #[test]
+    fn small_groups_and_other_channels_stay_separate() {
+        let mut batch = vec![
+            filtered("chan", "alice", "gg"),
+            filtered("chan", "alice", "gg"),
+            filtered("other", "bob", "gg"),
+        ];
+        batch[2].priority = Priority::Moderator;
+        let out = aggregator(2).group(batch.clone());
+        let users: Vec<_> = out.iter().map(|m| m.source.username.as_str()).collect();
+        assert_eq!(users, ["alice", "alice", "bob"]);
+
+        batch.push(filtered("other", "carol", "GG"));
+        let out = aggregator(2).group(batch);
+        assert_eq!(out.len(), 3);
+        assert_eq!(out[2].source.username, "bob and carol");
+        assert_eq!(out[2].priority, Priority::Moderator);
+    }
+
+    #[tokio::test]
+    async fn long_messages_skip_the_window() {
+        let aggregator = ChatAggregator::from_config(&QueueConfig {
+            aggregate_window_ms: 300,
+            aggregate_min_count: 2,
+            ..QueueConfig::default()
+        })
+        .unwrap();
+        let (tx, mut rx) = mpsc::channel(8);
+        tx.send(filtered(
+            "chan",
+            "carol",
+            "what a play, that was unbelievable",
+        ))
+        .await
+        .unwrap();
+        tx.send(filtered("chan", "bob", "W")).await.unwrap();
+
+        let started = Instant::now();
+        let mut flushed = Vec::new();
+        let out = aggregator
+            .collect_window(filtered("chan", "alice", "W"), &mut rx, |message| {
+                flushed.push((message.source.username, started.elapsed()));
+                async {}
+            })
+            .await;
+
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0].0, "carol");
+        assert!(flushed[0].1 < Duration::from_millis(150));
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].source.username, "alice and bob");
+    }
+
+    #[test]
+    fn labels_name_two_senders() {
+        assert_eq!(speakers_label(&["a"]), "a");
+        assert_eq!(speakers_label(&["a", "b"]), "a and b");
+        assert_eq!(speakers_label(&["a", "b", "c"]), "a, b and 1 other");
+        assert_eq!(speakers_label(&["a", "b", "c", "d"]), "a, b and 2 others");
+    }
+}
@@ -1,6 +1,10 @@
-use std::{fs, path::Path};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize, Clone)]
@@ -11,6 +15,22 @@ pub struct GatewayConfig {
     pub filter: FilterConfig,
     #[serde(default)]
     pub tts: TtsConfig,
+    #[serde(default)]
+    pub recording: RecordingConfig,
+}
+
+/// Keeps every clip read on a channel so the session can be exported as one
+/// file afterwards.
+#[derive(Debug, Deserialize, Clone)]
+pub struct RecordingConfig {
+    #[serde(default)]
+    pub record_session: bool,
+    /// Audio kept per channel; clips past this are not recorded.
+    #[serde(default = "default_max_session_secs")]
+    pub max_session_secs: u64,
+    /// Silence between clips in the exported file.
+    #[serde(default = "default_session_gap_ms")]
+    pub gap_ms: u32,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -19,6 +39,26 @@ pub struct QueueConfig {
     pub capacity: usize,
     #[serde(default = "default_rate_limit_per_sec")]
     pub rate_limit_per_sec: f32,
+    /// Synthesized clips kept for replay; the oldest is dropped when full.
+    #[serde(default = "default_playback_capacity")]
+    pub playback_capacity: usize,
+    /// Silence kept between consecutive readouts. Clips are held back until
+    /// the previous one should have finished, so this adds latency when
+    /// chat is busy.
+    #[serde(default)]
+    pub min_playback_gap_ms: u64,
+    /// Channels that may be watched at once across all platforms; every
+    /// watcher feeds the same queue and GPU. `0` means no limit.
+    #[serde(default)]
+    pub max_channels: usize,
+    /// How long the worker waits after a short message for others saying
+    /// the same thing, so a burst of "W" is read once as "Alice, Bob and 3
+    /// others say W". `0` turns aggregation off.
+    #[serde(default)]
+    pub aggregate_window_ms: u64,
+    /// Distinct senders needed before matching messages are merged.
+    #[serde(default = "default_aggregate_min_count")]
+    pub aggregate_min_count: usize,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -29,8 +69,56 @@ pub struct FilterConfig {
     pub max_chars: usize,
     #[serde(default)]
     pub banned_keywords: Vec<String>,
+    /// What happens to a message containing a banned keyword.
+    #[serde(default)]
+    pub banned_keyword_action: BannedKeywordAction,
+    /// Replacement for each banned keyword in `mask` mode.
+    #[serde(default = "default_banned_keyword_mask")]
+    pub banned_keyword_mask: String,
     #[serde(default)]
     pub allow_links: bool,
+    /// Drop messages whose platform timestamp (Twitch `tmi-sent-ts`) is older
+    /// than this on arrival, e.g. a backlog replayed after a reconnect.
+    /// `0` disables the check; messages without a timestamp always pass.
+    #[serde(default)]
+    pub max_message_age_ms: u64,
+    /// Drop a user's message when they had one read within this window, so
+    /// one busy chatter cannot take over the readout. `0` disables.
+    #[serde(default)]
+    pub per_user_cooldown_ms: u64,
+    /// Per-channel replacements for `per_user_cooldown_ms`, keyed by the
+    /// channel name (Twitch login or Bilibili room id).
+    #[serde(default)]
+    pub channel_user_cooldown_ms: HashMap<String, u64>,
+    /// What happens to emoji before the text is synthesized.
+    #[serde(default)]
+    pub emoji_action: EmojiAction,
+    /// Spoken names for `speak` mode, added to or replacing the built-in
+    /// table. An empty name silences that emoji.
+    #[serde(default)]
+    pub emoji_names: HashMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BannedKeywordAction {
+    /// Skip the message.
+    #[default]
+    Drop,
+    /// Read the message with each keyword replaced by `banned_keyword_mask`.
+    Mask,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmojiAction {
+    /// Pass emoji through to the engine unchanged.
+    #[default]
+    Keep,
+    /// Remove emoji from the text.
+    Strip,
+    /// Replace emoji with a short spoken name; ones without a name are removed.
+    Speak,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -41,14 +129,61 @@ pub struct TtsConfig {
     pub voice_id: Option<String>,
     #[serde(default)]
     pub timeout_secs: Option<u64>,
+    /// How a chat line is read out. `{user}` and `{message}` are replaced;
+    /// leave out `{user}` to skip reading the name.
+    #[serde(default)]
+    pub spoken_template: Option<String>,
+    /// Pick a voice whose `language` matches the detected language of each
+    /// message, falling back to the channel voice when unsure.
+    #[serde(default)]
+    pub auto_language_voice: bool,
+    /// Normalize every danmaku clip to this RMS level so readouts sound
+    /// equally loud whatever the reference clip.
+    #[serde(default)]
+    pub normalize_output_rms: Option<f32>,
+    /// When a channel is started with an engine its voice does not belong
+    /// to, use the voice's own engine (with a warning) instead of refusing.
+    #[serde(default = "default_correct_engine_mismatch")]
+    pub correct_engine_mismatch: bool,
+    /// Speech rate for danmaku readouts only (1.0 = normal), so busy chat
+    /// can be read a little faster. Requests from the TTS panel keep their
+    /// own `speed`. Engines without a speed control ignore it.
+    #[serde(default)]
+    pub danmaku_speed: Option<f32>,
+    /// WAV played right before each chat readout, e.g. a short chime.
+    /// Channels may override it when started.
+    #[serde(default)]
+    pub danmaku_prefix_audio: Option<PathBuf>,
+    /// WAV played right after each chat readout.
+    #[serde(default)]
+    pub danmaku_suffix_audio: Option<PathBuf>,
+    /// Directory whose WAV files channels may pick as their stingers by file
+    /// name. Without it, channels can only keep or mute the configured ones.
+    #[serde(default)]
+    pub danmaku_stinger_dir: Option<PathBuf>,
 }
 
+pub const DEFAULT_SPOKEN_TEMPLATE: &str = "{user} says: {message}";
+const MIN_DANMAKU_SPEED: f32 = 0.5;
+const MAX_DANMAKU_SPEED: f32 = 2.0;
+
 impl Default for GatewayConfig {
     fn default() -> Self {
         Self {
             queue: QueueConfig::default(),
             filter: FilterConfig::default(),
             tts: TtsConfig::default(),
+            recording: RecordingConfig::default(),
+        }
+    }
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            record_session: false,
+            max_session_secs: default_max_session_secs(),
+            gap_ms: default_session_gap_ms(),
         }
     }
 }
@@ -58,6 +193,11 @@ impl Default for QueueConfig {
         Self {
             capacity: default_queue_capacity(),
             rate_limit_per_sec: default_rate_limit_per_sec(),
+            playback_capacity: default_playback_capacity(),
+            min_playback_gap_ms: 0,
+            max_channels: 0,
+            aggregate_window_ms: 0,
+            aggregate_min_count: default_aggregate_min_count(),
         }
     }
 }
@@ -68,7 +208,14 @@ impl Default for FilterConfig {
             max_words: default_max_words(),
             max_chars: default_max_chars(),
             banned_keywords: Vec::new(),
+            banned_keyword_action: BannedKeywordAction::Drop,
+            banned_keyword_mask: default_banned_keyword_mask(),
             allow_links: false,
+            max_message_age_ms: 0,
+            per_user_cooldown_ms: 0,
+            channel_user_cooldown_ms: HashMap::new(),
+            emoji_action: EmojiAction::Keep,
+            emoji_names: HashMap::new(),
         }
     }
 }
@@ -79,6 +226,14 @@ impl Default for TtsConfig {
             endpoint: default_tts_endpoint(),
             voice_id: None,
             timeout_secs: Some(15),
+            spoken_template: None,
+            auto_language_voice: false,
+            normalize_output_rms: None,
+            correct_engine_mismatch: default_correct_engine_mismatch(),
+            danmaku_speed: None,
+            danmaku_prefix_audio: None,
+            danmaku_suffix_audio: None,
+            danmaku_stinger_dir: None,
         }
     }
 }
@@ -91,6 +246,22 @@ fn default_rate_limit_per_sec() -> f32 {
     1.5
 }
 
+fn default_playback_capacity() -> usize {
+    128
+}
+
+fn default_aggregate_min_count() -> usize {
+    3
+}
+
+fn default_max_session_secs() -> u64 {
+    60 * 60
+}
+
+fn default_session_gap_ms() -> u32 {
+    300
+}
+
 fn default_max_words() -> usize {
     77
 }
@@ -99,6 +270,14 @@ fn default_max_chars() -> usize {
     280
 }
 
+fn default_correct_engine_mismatch() -> bool {
+    true
+}
+
+fn default_banned_keyword_mask() -> String {
+    "哔".to_string()
+}
+
 fn default_tts_endpoint() -> String {
     "http://127.0.0.1:27121/api/tts".to_string()
 }
@@ -110,8 +289,81 @@ impl GatewayConfig {
         })?;
         let config: GatewayConfig =
             toml::from_str(&content).with_context(|| "failed to parse gateway config")?;
+        config.validate()?;
         Ok(config)
     }
+
+    pub fn validate(&self) -> Result<()> {
+        if let Some(template) = &self.tts.spoken_template {
+            if !template.contains("{message}") {
+                bail!("tts.spoken_template must contain the {{message}} placeholder");
+            }
+        }
+        if let Some(speed) = self.tts.danmaku_speed {
+            if !(MIN_DANMAKU_SPEED..=MAX_DANMAKU_SPEED).contains(&speed) {
+                bail!(
+                    "tts.danmaku_speed must be between {MIN_DANMAKU_SPEED} and {MAX_DANMAKU_SPEED}, got {speed}"
+                );
+            }
+        }
+        if self.queue.aggregate_window_ms > 0 && self.queue.aggregate_min_count < 2 {
+            bail!(
+                "queue.aggregate_min_count must be at least 2, got {}",
+                self.queue.aggregate_min_count
+            );
+        }
+        Ok(())
+    }
+}
+
+impl FilterConfig {
+    /// Cooldown window for users of `channel`.
+    pub fn user_cooldown_ms(&self, channel: &str) -> u64 {
+        self.channel_user_cooldown_ms
+            .get(channel)
+            .copied()
+            .unwrap_or(self.per_user_cooldown_ms)
+    }
+}
+
+impl TtsConfig {
+    pub fn spoken_template(&self) -> &str {
+        self.spoken_template
+            .as_deref()
+            .unwrap_or(DEFAULT_SPOKEN_TEMPLATE)
+    }
+
+    /// Builds the text handed to TTS for a chat line. With no speaker name
+    /// only the message is read, regardless of the template.
+    pub fn render_spoken_text(&self, user: &str, message: &str) -> String {
+        let user = user.trim();
+        if user.is_empty() {
+            return message.to_string();
+        }
+        render_template(self.spoken_template(), user, message)
+    }
+}
+
+// Single pass so a username containing "{message}" is not expanded again.
+fn render_template(template: &str, user: &str, message: &str) -> String {
+    let mut out = String::with_capacity(template.len() + user.len() + message.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start..];
+        if let Some(after) = tail.strip_prefix("{user}") {
+            out.push_str(user);
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix("{message}") {
+            out.push_str(message);
+            rest = after;
+        } else {
+            out.push('{');
+            rest = &tail[1..];
+        }
+    }
+    out.push_str(rest);
+    out
 }
 
 #[cfg(test)]
@@ -124,6 +376,8 @@ mod tests {
 [queue]
 capacity = 100
 rate_limit_per_sec = 2.0
+min_playback_gap_ms = 250
+max_channels = 4
 
 [filter]
 max_words = 50
@@ -135,7 +389,55 @@ voice_id = "walter"
 "#;
         let cfg: GatewayConfig = toml::from_str(toml).unwrap();
         assert_eq!(cfg.queue.capacity, 100);
+        assert_eq!(cfg.queue.min_playback_gap_ms, 250);
+        assert_eq!(cfg.queue.max_channels, 4);
         assert_eq!(cfg.filter.max_words, 50);
         assert_eq!(cfg.tts.voice_id.as_deref(), Some("walter"));
     }
+
+    #[test]
+    fn render_spoken_text_templates() {
+        let mut tts = TtsConfig::default();
+        assert_eq!(tts.render_spoken_text("alice", "hi"), "alice says: hi");
+        assert_eq!(tts.render_spoken_text("  ", "hi"), "hi");
+
+        tts.spoken_template = Some("{user}说：{message}".into());
+        assert_eq!(tts.render_spoken_text("小明", "你好"), "小明说：你好");
+
+        tts.spoken_template = Some("{message}".into());
+        assert_eq!(tts.render_spoken_text("alice", "hi"), "hi");
+
+        tts.spoken_template = Some("{user}: {message}".into());
+        assert_eq!(tts.render_spoken_text("{message}", "hi"), "{message}: hi");
+    }
+
+    #[test]
+    fn validate_requires_message_placeholder() {
+        let mut cfg = GatewayConfig::default();
+        assert!(cfg.validate().is_ok());
+        cfg.tts.spoken_template = Some("{user} is here".into());
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn validate_bounds_danmaku_speed() {
+        let mut cfg = GatewayConfig::default();
+        cfg.tts.danmaku_speed = Some(1.25);
+        assert!(cfg.validate().is_ok());
+        cfg.tts.danmaku_speed = Some(0.0);
+        assert!(cfg.validate().is_err());
+        cfg.tts.danmaku_speed = Some(f32::NAN);
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn validate_requires_two_senders_to_aggregate() {
+        let mut cfg = GatewayConfig::default();
+        cfg.queue.aggregate_min_count = 1;
+        assert!(cfg.validate().is_ok());
+        cfg.queue.aggregate_window_ms = 1_500;
+        assert!(cfg.validate().is_err());
+        cfg.queue.aggregate_min_count = 2;
+        assert!(cfg.validate().is_ok());
+    }
 }
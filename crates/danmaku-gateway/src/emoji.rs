@@ -0,0 +1,294 @@
+//! Emoji handling for chat text before it reaches the TTS engines, which
+//! otherwise read emoji as noise or silently skip them.
+
+use std::collections::HashMap;
+
+use crate::config::EmojiAction;
+
+const ZWJ: char = '\u{200D}';
+const VS15: char = '\u{FE0E}';
+const VS16: char = '\u{FE0F}';
+const KEYCAP: char = '\u{20E3}';
+
+/// Spoken names for the emoji that show up most in chat. Keys are
+/// normalised with [`normalize`].
+const DEFAULT_NAMES: &[(&str, &str)] = &[
+    ("😀", "嘿嘿"),
+    ("😁", "嘻嘻"),
+    ("😂", "笑哭"),
+    ("🤣", "笑得打滚"),
+    ("😄", "大笑"),
+    ("😅", "苦笑"),
+    ("😆", "眯眼笑"),
+    ("😇", "天使"),
+    ("😊", "微笑"),
+    ("😋", "好吃"),
+    ("😍", "花痴"),
+    ("🥰", "爱心笑脸"),
+    ("😘", "飞吻"),
+    ("😎", "墨镜"),
+    ("😏", "得意"),
+    ("😒", "不高兴"),
+    ("😢", "哭"),
+    ("😭", "大哭"),
+    ("😤", "生气"),
+    ("😡", "怒火中烧"),
+    ("😱", "吓死了"),
+    ("😳", "脸红"),
+    ("😴", "睡着了"),
+    ("🙄", "翻白眼"),
+    ("🤔", "想一想"),
+    ("🤗", "抱抱"),
+    ("🤡", "小丑"),
+    ("🤯", "炸裂"),
+    ("🥺", "可怜"),
+    ("💀", "骷髅"),
+    ("👀", "围观"),
+    ("👍", "点赞"),
+    ("👎", "踩"),
+    ("👏", "鼓掌"),
+    ("👋", "挥手"),
+    ("🙏", "拜托"),
+    ("💪", "加油"),
+    ("🤝", "握手"),
+    ("❤", "爱心"),
+    ("💔", "心碎"),
+    ("🔥", "火"),
+    ("🎉", "庆祝"),
+    ("🎂", "蛋糕"),
+    ("🌹", "玫瑰"),
+    ("🍉", "吃瓜"),
+    ("💯", "一百分"),
+    ("🐶", "狗"),
+    ("🐱", "猫"),
+    ("🐷", "猪"),
+];
+
+/// Rewrites emoji in chat text according to an [`EmojiAction`].
+#[derive(Debug, Clone)]
+pub struct EmojiProcessor {
+    action: EmojiAction,
+    names: HashMap<String, String>,
+}
+
+impl EmojiProcessor {
+    /// `overrides` maps emoji to spoken names on top of the built-in table;
+    /// an empty name silences that emoji.
+    pub fn new(action: EmojiAction, overrides: &HashMap<String, String>) -> Self {
+        let mut names: HashMap<String, String> = DEFAULT_NAMES
+            .iter()
+            .map(|(emoji, name)| (normalize(emoji), name.to_string()))
+            .collect();
+        for (emoji, name) in overrides {
+            names.insert(normalize(emoji), name.trim().to_string());
+        }
+        Self { action, names }
+    }
+
+    pub fn process(&self, text: &str) -> String {
+        if self.action == EmojiAction::Keep {
+            return text.to_string();
+        }
+        let chars: Vec<char> = text.chars().collect();
+        let mut out = String::with_capacity(text.len());
+        let mut found = false;
+        let mut index = 0;
+        while index < chars.len() {
+            let Some(end) = emoji_end(&chars, index) else {
+                out.push(chars[index]);
+                index += 1;
+                continue;
+            };
+            found = true;
+            if self.action == EmojiAction::Speak {
+                if let Some(name) = self.name_for(&chars[index..end]) {
+                    // Keep spoken names from fusing with neighbouring Latin words.
+                    if out
+                        .chars()
+                        .last()
+                        .is_some_and(|c| c.is_ascii_alphanumeric())
+                    {
+                        out.push(' ');
+                    }
+                    out.push_str(name);
+                    if chars.get(end).is_some_and(|c| c.is_ascii_alphanumeric()) {
+                        out.push(' ');
+                    }
+                }
+            }
+            index = end;
+        }
+        if found {
+            out.split_whitespace().collect::<Vec<_>>().join(" ")
+        } else {
+            out
+        }
+    }
+
+    /// Name for a whole sequence, falling back to its first component so an
+    /// unlisted ZWJ combination still reads as its base emoji.
+    fn name_for(&self, sequence: &[char]) -> Option<&str> {
+        let whole: String = sequence.iter().collect();
+        if let Some(name) = self.names.get(&normalize(&whole)) {
+            return Some(name)
+                .filter(|name| !name.is_empty())
+                .map(String::as_str);
+        }
+        let first: String = sequence.iter().take_while(|c| **c != ZWJ).collect();
+        self.names
+            .get(&normalize(&first))
+            .filter(|name| !name.is_empty())
+            .map(String::as_str)
+    }
+}
+
+/// Drops variation selectors and skin tones so `👍🏽` and `❤️` share the
+/// entries of `👍` and `❤`.
+fn normalize(emoji: &str) -> String {
+    emoji
+        .chars()
+        .filter(|c| !matches!(*c, VS15 | VS16) && !is_skin_tone(*c))
+        .collect()
+}
+
+/// End (exclusive) of the emoji sequence starting at `start`, if any.
+fn emoji_end(chars: &[char], start: usize) -> Option<usize> {
+    let c = chars[start];
+    let next = chars.get(start + 1).copied();
+
+    if is_regional_indicator(c) {
+        // A pair is a flag; a lone indicator is still not speakable text.
+        return Some(if next.is_some_and(is_regional_indicator) {
+            start + 2
+        } else {
+            start + 1
+        });
+    }
+
+    if c.is_ascii_digit() || c == '#' || c == '*' {
+        return match (next, chars.get(start + 2).copied()) {
+            (Some(KEYCAP), _) => Some(start + 2),
+            (Some(VS16), Some(KEYCAP)) => Some(start + 3),
+            _ => None,
+        };
+    }
+
+    let is_base = is_pictographic(c) || (is_text_default(c) && next == Some(VS16));
+    if !is_base {
+        return None;
+    }
+
+    let mut end = start + 1;
+    while let Some(&c) = chars.get(end) {
+        if matches!(c, VS15 | VS16) || is_skin_tone(c) || is_tag(c) {
+            end += 1;
+        } else if c == ZWJ && chars.get(end + 1).copied().is_some_and(is_pictographic) {
+            end += 2;
+        } else {
+            break;
+        }
+    }
+    Some(end)
+}
+
+/// Code points that render as emoji on their own.
+fn is_pictographic(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1F0FF
+            | 0x1F170..=0x1F1E5
+            | 0x1F200..=0x1F3FA
+            | 0x1F400..=0x1FAFF
+            | 0x2600..=0x27BF
+            | 0x231A..=0x231B
+            | 0x23E9..=0x23F3
+            | 0x23F8..=0x23FA
+            | 0x2B05..=0x2B07
+            | 0x2B1B..=0x2B1C
+            | 0x2B50
+            | 0x2B55
+            | 0x3030
+            | 0x303D
+            | 0x3297
+            | 0x3299
+    )
+}
+
+/// Symbols that are plain text unless followed by VS16 (`©️`, `™️`, `↔️`).
+fn is_text_default(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x00A9 | 0x00AE | 0x203C | 0x2049 | 0x2122 | 0x2139 | 0x2194..=0x21AA | 0x24C2
+            | 0x25AA..=0x25FE | 0x2934..=0x2935
+    )
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+fn is_skin_tone(c: char) -> bool {
+    ('\u{1F3FB}'..='\u{1F3FF}').contains(&c)
+}
+
+fn is_tag(c: char) -> bool {
+    ('\u{E0020}'..='\u{E007F}').contains(&c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn processor(action: EmojiAction) -> EmojiProcessor {
+        EmojiProcessor::new(action, &HashMap::new())
+    }
+
+    #[test]
+    fn strip_removes_multi_codepoint_sequences() {
+        let strip = processor(EmojiAction::Strip);
+        assert_eq!(strip.process("哈哈😂😂"), "哈哈");
+        // ZWJ family, skin tone, flag, keycap and a tagged subdivision flag.
+        assert_eq!(strip.process("家人👨‍👩‍👧‍👦来了"), "家人来了");
+        assert_eq!(strip.process("nice 👍🏽 play"), "nice play");
+        assert_eq!(strip.process("🇨🇳加油"), "加油");
+        assert_eq!(strip.process("第1️⃣名"), "第名");
+        assert_eq!(
+            strip.process("go 🏴\u{E0067}\u{E0062}\u{E0065}\u{E006E}\u{E0067}\u{E007F}!"),
+            "go !"
+        );
+        assert_eq!(strip.process("❤️"), "");
+    }
+
+    #[test]
+    fn strip_leaves_plain_text_symbols() {
+        let strip = processor(EmojiAction::Strip);
+        assert_eq!(strip.process("A© 2024 → 1#2 ↔ ok"), "A© 2024 → 1#2 ↔ ok");
+    }
+
+    #[test]
+    fn speak_names_emoji_and_falls_back_to_base() {
+        let speak = processor(EmojiAction::Speak);
+        assert_eq!(speak.process("主播太强了👍🏽"), "主播太强了点赞");
+        assert_eq!(speak.process("gg❤️gg"), "gg 爱心 gg");
+        // Unlisted ZWJ sequence reads as its first component.
+        assert_eq!(speak.process("😢‍🔥"), "哭");
+        // Nothing to say for unknown emoji.
+        assert_eq!(speak.process("🦩好看"), "好看");
+    }
+
+    #[test]
+    fn speak_uses_overrides() {
+        let overrides = HashMap::from([
+            ("👍".to_string(), "thumbs up".to_string()),
+            ("🔥".to_string(), String::new()),
+        ]);
+        let speak = EmojiProcessor::new(EmojiAction::Speak, &overrides);
+        assert_eq!(speak.process("wow👍🏻🔥"), "wow thumbs up");
+    }
+
+    #[test]
+    fn keep_is_identity() {
+        let keep = processor(EmojiAction::Keep);
+        assert_eq!(keep.process("哈哈😂  👨‍👩‍👧"), "哈哈😂  👨‍👩‍👧");
+    }
+}
@@ -1,23 +1,78 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
 use anyhow::Result;
 use regex::Regex;
 use serde::Serialize;
 use tokio::time::{Duration, Instant};
 
-use danmaku::message::{MessageContent, NormalizedMessage};
+use danmaku::message::{MessageContent, NormalizedMessage, Priority};
 
-use crate::config::FilterConfig;
+use crate::config::{BannedKeywordAction, FilterConfig};
+use crate::emoji::EmojiProcessor;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct FilteredMessage {
     pub source: NormalizedMessage,
-    pub sanitized_text: String,
+    /// What viewers see: the message on one line with banned keywords
+    /// masked, keeping its emoji, links and punctuation.
+    pub display_text: String,
+    /// What the engine reads: emoji handled per `emoji_action`, links
+    /// removed and the length capped.
+    pub spoken_text: String,
+    pub priority: Priority,
     pub accepted_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Why [`MessageFilter::filter`] rejected a message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "kind", content = "detail")]
+pub enum DropReason {
+    NotText,
+    Empty,
+    ContainsLink,
+    BannedKeyword(String),
+    /// Age in milliseconds when the message arrived.
+    TooOld(u64),
+    /// Milliseconds left of the sender's cooldown.
+    Cooldown(u64),
+}
+
+impl std::fmt::Display for DropReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DropReason::NotText => f.write_str("not a text message"),
+            DropReason::Empty => f.write_str("message is empty"),
+            DropReason::ContainsLink => f.write_str("message contains a link"),
+            DropReason::BannedKeyword(keyword) => write!(f, "banned keyword '{keyword}'"),
+            DropReason::TooOld(age_ms) => write!(f, "message is {age_ms} ms old"),
+            DropReason::Cooldown(remaining_ms) => {
+                write!(f, "sender is cooling down for another {remaining_ms} ms")
+            }
+        }
+    }
+}
+
+/// Counters of messages the filter turned away.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct FilterStats {
+    pub cooldown_dropped: u64,
+}
+
+/// Senders above this many trigger pruning of expired cooldowns.
+const COOLDOWN_PRUNE_THRESHOLD: usize = 4096;
+
 pub struct MessageFilter {
     config: FilterConfig,
     banned_regex: Option<Regex>,
     link_regex: Regex,
+    /// Whole links, removed from spoken text when `allow_links` lets them in.
+    url_regex: Regex,
+    emoji: EmojiProcessor,
+    /// When each `(channel, sender)` last had a message accepted.
+    last_accepted: Mutex<HashMap<(String, String), Instant>>,
+    cooldown_dropped: AtomicU64,
 }
 
 impl MessageFilter {
@@ -34,30 +89,107 @@ impl MessageFilter {
             Some(Regex::new(&format!("(?i)({})", pattern))?)
         };
         let link_regex = Regex::new(r"https?://|www\.").expect("invalid default link regex");
+        let url_regex =
+            Regex::new(r"(?i)(?:https?://|www\.)\S+").expect("invalid default url regex");
+        let emoji = EmojiProcessor::new(config.emoji_action, &config.emoji_names);
         Ok(Self {
             config,
             banned_regex,
             link_regex,
+            url_regex,
+            emoji,
+            last_accepted: Mutex::new(HashMap::new()),
+            cooldown_dropped: AtomicU64::new(0),
         })
     }
 
+    pub fn stats(&self) -> FilterStats {
+        FilterStats {
+            cooldown_dropped: self.cooldown_dropped.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Enforces the per-user cooldown for a message that passed
+    /// [`filter`](Self::filter), starting a new window when it is admitted.
+    /// Kept apart from `filter` so dry runs do not use up a user's turn.
+    pub fn admit(&self, message: &NormalizedMessage) -> Result<(), DropReason> {
+        let window_ms = self.config.user_cooldown_ms(&message.channel);
+        if window_ms == 0 {
+            return Ok(());
+        }
+        let window = Duration::from_millis(window_ms);
+        let sender = message
+            .user_id
+            .clone()
+            .unwrap_or_else(|| message.username.to_lowercase());
+        let now = Instant::now();
+        let mut last_accepted = self.last_accepted.lock().expect("cooldown map poisoned");
+        let key = (message.channel.clone(), sender);
+        if let Some(last) = last_accepted.get(&key) {
+            let elapsed = now.duration_since(*last);
+            if elapsed < window {
+                self.cooldown_dropped.fetch_add(1, Ordering::Relaxed);
+                return Err(DropReason::Cooldown((window - elapsed).as_millis() as u64));
+            }
+        }
+        if last_accepted.len() >= COOLDOWN_PRUNE_THRESHOLD {
+            last_accepted.retain(|(channel, _), last| {
+                now.duration_since(*last)
+                    < Duration::from_millis(self.config.user_cooldown_ms(channel))
+            });
+        }
+        last_accepted.insert(key, now);
+        Ok(())
+    }
+
     pub fn sanitize(&self, message: &NormalizedMessage) -> Option<FilteredMessage> {
+        self.filter(message).ok()
+    }
+
+    /// Like [`sanitize`](Self::sanitize) but reports why a message was dropped.
+    pub fn filter(&self, message: &NormalizedMessage) -> Result<FilteredMessage, DropReason> {
         let text = match &message.content {
             MessageContent::Text(t) => t,
-            MessageContent::System(_) => return None,
+            MessageContent::System(_) => return Err(DropReason::NotText),
         };
-        let mut sanitized = text.replace(['\r', '\n'], " ").trim().to_string();
-        if sanitized.is_empty() {
-            return None;
+        if self.config.max_message_age_ms > 0 {
+            if let Some(age_ms) = message_age_ms(message) {
+                if age_ms > self.config.max_message_age_ms {
+                    return Err(DropReason::TooOld(age_ms));
+                }
+            }
+        }
+        let mut display = text.replace(['\r', '\n'], " ").trim().to_string();
+        if display.is_empty() {
+            return Err(DropReason::Empty);
+        }
+
+        if !self.config.allow_links && self.link_regex.is_match(&display) {
+            return Err(DropReason::ContainsLink);
         }
 
-        if !self.config.allow_links && self.link_regex.is_match(&sanitized) {
-            return None;
+        let mut sanitized = self.emoji.process(&display);
+        if self.config.allow_links {
+            sanitized = self.url_regex.replace_all(&sanitized, " ").into_owned();
+        }
+        sanitized = sanitized.split_whitespace().collect::<Vec<_>>().join(" ");
+        if sanitized.is_empty() {
+            return Err(DropReason::Empty);
         }
 
         if let Some(regex) = &self.banned_regex {
-            if regex.is_match(&sanitized) {
-                return None;
+            match self.config.banned_keyword_action {
+                BannedKeywordAction::Drop => {
+                    // Spoken emoji names can spell a keyword the original lacks.
+                    if let Some(found) = regex.find(&sanitized).or_else(|| regex.find(&display)) {
+                        return Err(DropReason::BannedKeyword(found.as_str().to_string()));
+                    }
+                }
+                BannedKeywordAction::Mask => {
+                    let mask = regex::NoExpand(&self.config.banned_keyword_mask);
+                    sanitized = regex.replace_all(&sanitized, mask.clone()).into_owned();
+                    display = regex.replace_all(&display, mask).into_owned();
+                }
             }
         }
 
@@ -70,14 +202,53 @@ impl MessageFilter {
             sanitized.truncate(self.config.max_chars);
         }
 
-        Some(FilteredMessage {
+        Ok(FilteredMessage {
             source: message.clone(),
-            sanitized_text: sanitized,
+            display_text: display,
+            spoken_text: sanitized,
+            priority: resolve_priority(message),
             accepted_at: chrono::Utc::now(),
         })
     }
 }
 
+/// Time since the platform says the message was sent, from the
+/// `timestamp_ms` metadata. Clock skew that puts it in the future counts as 0.
+fn message_age_ms(message: &NormalizedMessage) -> Option<u64> {
+    let sent_ms = message.metadata.get("timestamp_ms")?.as_i64()?;
+    let age_ms = chrono::Utc::now()
+        .timestamp_millis()
+        .saturating_sub(sent_ms);
+    Some(u64::try_from(age_ms).unwrap_or(0))
+}
+
+/// Combines the platform-assigned priority with whatever the chat badges
+/// imply, keeping the more urgent of the two.
+fn resolve_priority(message: &NormalizedMessage) -> Priority {
+    let badges = message
+        .metadata
+        .get("badges")
+        .and_then(|value| value.as_array())
+        .map(|badges| {
+            badges
+                .iter()
+                .filter_map(|badge| badge.as_str())
+                .filter_map(badge_priority)
+                .fold(Priority::Normal, Priority::higher)
+        })
+        .unwrap_or_default();
+    message.priority.higher(badges)
+}
+
+fn badge_priority(badge: &str) -> Option<Priority> {
+    let name = badge.split('/').next().unwrap_or(badge);
+    match name {
+        "broadcaster" | "moderator" | "staff" | "admin" => Some(Priority::Moderator),
+        "vip" | "subscriber" | "founder" => Some(Priority::Subscriber),
+        _ => None,
+    }
+}
+
 #[derive(Debug)]
 pub struct RateLimiter {
     last_emit: Option<Instant>,
@@ -111,6 +282,7 @@ impl RateLimiter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::EmojiAction;
     use danmaku::message::{NormalizedMessage, Platform, Priority};
 
     fn make_message(text: &str) -> NormalizedMessage {
@@ -131,7 +303,14 @@ mod tests {
             max_words: 10,
             max_chars: 50,
             banned_keywords: vec!["spoiler".into()],
+            banned_keyword_action: BannedKeywordAction::Drop,
+            banned_keyword_mask: "哔".into(),
             allow_links: false,
+            max_message_age_ms: 0,
+            per_user_cooldown_ms: 0,
+            channel_user_cooldown_ms: Default::default(),
+            emoji_action: EmojiAction::Keep,
+            emoji_names: Default::default(),
         })
         .unwrap();
         assert!(filter
@@ -143,18 +322,201 @@ mod tests {
         assert!(filter.sanitize(&make_message("nice message")).is_some());
     }
 
+    #[test]
+    fn filter_reports_drop_reasons() {
+        let filter = MessageFilter::new(FilterConfig {
+            max_words: 10,
+            max_chars: 50,
+            banned_keywords: vec!["spoiler".into()],
+            banned_keyword_action: BannedKeywordAction::Drop,
+            banned_keyword_mask: "哔".into(),
+            allow_links: false,
+            max_message_age_ms: 0,
+            per_user_cooldown_ms: 0,
+            channel_user_cooldown_ms: Default::default(),
+            emoji_action: EmojiAction::Keep,
+            emoji_names: Default::default(),
+        })
+        .unwrap();
+        assert_eq!(
+            filter.filter(&make_message("   ")).unwrap_err(),
+            DropReason::Empty
+        );
+        assert_eq!(
+            filter
+                .filter(&make_message("see www.example.com"))
+                .unwrap_err(),
+            DropReason::ContainsLink
+        );
+        assert_eq!(
+            filter
+                .filter(&make_message("big SPOILER ahead"))
+                .unwrap_err(),
+            DropReason::BannedKeyword("SPOILER".into())
+        );
+        assert_eq!(
+            filter.filter(&make_message("fine")).unwrap().spoken_text,
+            "fine"
+        );
+    }
+
+    #[test]
+    fn filter_masks_keywords() {
+        let filter = MessageFilter::new(FilterConfig {
+            banned_keywords: vec!["spoiler".into()],
+            banned_keyword_action: BannedKeywordAction::Mask,
+            ..FilterConfig::default()
+        })
+        .unwrap();
+        let filtered = filter
+            .filter(&make_message("big SPOILER ahead, spoiler!"))
+            .unwrap();
+        assert_eq!(filtered.spoken_text, "big 哔 ahead, 哔!");
+        assert_eq!(filtered.display_text, "big 哔 ahead, 哔!");
+    }
+
     #[test]
     fn filter_truncates_words() {
         let filter = MessageFilter::new(FilterConfig {
             max_words: 3,
             max_chars: 100,
             banned_keywords: vec![],
+            banned_keyword_action: BannedKeywordAction::Drop,
+            banned_keyword_mask: "哔".into(),
             allow_links: true,
+            max_message_age_ms: 0,
+            per_user_cooldown_ms: 0,
+            channel_user_cooldown_ms: Default::default(),
+            emoji_action: EmojiAction::Keep,
+            emoji_names: Default::default(),
         })
         .unwrap();
         let msg = filter
             .sanitize(&make_message("one two three four five"))
             .unwrap();
-        assert_eq!(msg.sanitized_text.split_whitespace().count(), 3);
+        assert_eq!(msg.spoken_text.split_whitespace().count(), 3);
+        assert_eq!(msg.display_text, "one two three four five");
+    }
+
+    #[test]
+    fn filter_drops_stale_messages() {
+        let filter = MessageFilter::new(FilterConfig {
+            max_message_age_ms: 30_000,
+            ..FilterConfig::default()
+        })
+        .unwrap();
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let mut message = make_message("hello");
+
+        message.metadata = serde_json::json!({ "timestamp_ms": now_ms - 60_000 });
+        assert!(matches!(
+            filter.filter(&message).unwrap_err(),
+            DropReason::TooOld(age_ms) if age_ms >= 60_000
+        ));
+
+        message.metadata = serde_json::json!({ "timestamp_ms": now_ms - 1_000 });
+        assert!(filter.filter(&message).is_ok());
+
+        message.metadata = serde_json::Value::Null;
+        assert!(filter.filter(&message).is_ok());
+    }
+
+    #[test]
+    fn filter_assigns_priority_from_badges() {
+        let filter = MessageFilter::new(FilterConfig::default()).unwrap();
+        let mut message = make_message("hello there");
+        assert_eq!(
+            filter.sanitize(&message).unwrap().priority,
+            Priority::Normal
+        );
+
+        message.metadata = serde_json::json!({ "badges": ["subscriber/12", "premium/1"] });
+        assert_eq!(
+            filter.sanitize(&message).unwrap().priority,
+            Priority::Subscriber
+        );
+
+        message.metadata = serde_json::json!({ "badges": ["vip/1", "moderator/1"] });
+        assert_eq!(
+            filter.sanitize(&message).unwrap().priority,
+            Priority::Moderator
+        );
+
+        message.priority = Priority::Paid;
+        assert_eq!(filter.sanitize(&message).unwrap().priority, Priority::Paid);
+    }
+
+    #[test]
+    fn admit_enforces_per_user_cooldown() {
+        let mut config = FilterConfig {
+            per_user_cooldown_ms: 60_000,
+            ..FilterConfig::default()
+        };
+        config.channel_user_cooldown_ms.insert("quiet".into(), 0);
+        let filter = MessageFilter::new(config).unwrap();
+
+        let message = make_message("hello there");
+        assert!(filter.admit(&message).is_ok());
+        assert!(matches!(
+            filter.admit(&message),
+            Err(DropReason::Cooldown(_))
+        ));
+
+        let mut other_user = make_message("hello there");
+        other_user.user_id = Some("u2".into());
+        assert!(filter.admit(&other_user).is_ok());
+
+        let mut other_channel = make_message("hello there");
+        other_channel.channel = "quiet".into();
+        assert!(filter.admit(&other_channel).is_ok());
+        assert!(filter.admit(&other_channel).is_ok());
+
+        assert_eq!(filter.stats().cooldown_dropped, 1);
+    }
+
+    #[test]
+    fn filter_drops_emoji_only_messages_when_stripping() {
+        let filter = MessageFilter::new(FilterConfig {
+            emoji_action: EmojiAction::Strip,
+            ..FilterConfig::default()
+        })
+        .unwrap();
+        assert_eq!(
+            filter.filter(&make_message("😂👍🏽 🇨🇳")).unwrap_err(),
+            DropReason::Empty
+        );
+        assert_eq!(
+            filter
+                .sanitize(&make_message("好活😂😂"))
+                .unwrap()
+                .spoken_text,
+            "好活"
+        );
+    }
+
+    #[test]
+    fn filter_keeps_original_text_for_display() {
+        let filter = MessageFilter::new(FilterConfig {
+            allow_links: true,
+            emoji_action: EmojiAction::Strip,
+            ..FilterConfig::default()
+        })
+        .unwrap();
+        let filtered = filter
+            .filter(&make_message(
+                "好活😂\n看 https://clips.twitch.tv/Abc 这个!",
+            ))
+            .unwrap();
+        assert_eq!(
+            filtered.display_text,
+            "好活😂 看 https://clips.twitch.tv/Abc 这个!"
+        );
+        assert_eq!(filtered.spoken_text, "好活 看 这个!");
+        assert_eq!(
+            filter
+                .filter(&make_message("www.example.com 😂"))
+                .unwrap_err(),
+            DropReason::Empty
+        );
     }
 }
@@ -1,9 +1,18 @@
+pub mod aggregate;
 pub mod config;
+pub mod emoji;
 pub mod filter;
+pub mod priority;
 pub mod queue;
 pub mod tts;
 
-pub use config::{FilterConfig, GatewayConfig, QueueConfig, TtsConfig};
-pub use filter::{FilteredMessage, MessageFilter};
+pub use aggregate::ChatAggregator;
+pub use config::{
+    BannedKeywordAction, EmojiAction, FilterConfig, GatewayConfig, QueueConfig, RecordingConfig,
+    TtsConfig,
+};
+pub use emoji::EmojiProcessor;
+pub use filter::{DropReason, FilterStats, FilteredMessage, MessageFilter};
+pub use priority::PriorityQueue;
 pub use queue::MessageQueue;
 pub use tts::{TtsClient, TtsRequestPayload, TtsResponsePayload};
@@ -0,0 +1,175 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use danmaku::message::Priority;
+
+/// Buffer that hands items out by [`Priority`], oldest first within a level.
+#[derive(Debug, Clone)]
+pub struct PriorityQueue<T> {
+    /// Each item carries its insertion sequence so the oldest one overall
+    /// can be found for eviction.
+    lanes: BTreeMap<u8, VecDeque<(u64, T)>>,
+    len: usize,
+    next_seq: u64,
+}
+
+impl<T> Default for PriorityQueue<T> {
+    fn default() -> Self {
+        Self {
+            lanes: BTreeMap::new(),
+            len: 0,
+            next_seq: 0,
+        }
+    }
+}
+
+impl<T> PriorityQueue<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, priority: Priority, item: T) {
+        self.lanes
+            .entry(priority.rank())
+            .or_default()
+            .push_back((self.next_seq, item));
+        self.next_seq += 1;
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let rank = *self.lanes.keys().next()?;
+        self.pop_lane(rank)
+    }
+
+    /// Removes the least recently pushed item regardless of priority.
+    pub fn pop_oldest(&mut self) -> Option<T> {
+        let rank = self
+            .lanes
+            .iter()
+            .filter_map(|(&rank, lane)| lane.front().map(|(seq, _)| (*seq, rank)))
+            .min()?
+            .1;
+        self.pop_lane(rank)
+    }
+
+    fn pop_lane(&mut self, rank: u8) -> Option<T> {
+        let lane = self.lanes.get_mut(&rank)?;
+        let item = lane.pop_front().map(|(_, item)| item);
+        if lane.is_empty() {
+            self.lanes.remove(&rank);
+        }
+        if item.is_some() {
+            self.len -= 1;
+        }
+        item
+    }
+
+    /// Removes the first item, in pop order, that `matches` accepts.
+    pub fn remove_first<F>(&mut self, mut matches: F) -> Option<T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let (rank, index) = self.lanes.iter().find_map(|(&rank, lane)| {
+            lane.iter()
+                .position(|(_, item)| matches(item))
+                .map(|index| (rank, index))
+        })?;
+        let lane = self.lanes.get_mut(&rank)?;
+        let item = lane.remove(index).map(|(_, item)| item);
+        if lane.is_empty() {
+            self.lanes.remove(&rank);
+        }
+        if item.is_some() {
+            self.len -= 1;
+        }
+        item
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterates in the order items would be popped.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.lanes
+            .values()
+            .flat_map(|lane| lane.iter().map(|(_, item)| item))
+    }
+
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        for lane in self.lanes.values_mut() {
+            lane.retain(|(_, item)| keep(item));
+        }
+        self.lanes.retain(|_, lane| !lane.is_empty());
+        self.len = self.lanes.values().map(VecDeque::len).sum();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pops_by_priority_then_fifo() {
+        let mut queue = PriorityQueue::new();
+        queue.push(Priority::Normal, "n1");
+        queue.push(Priority::Moderator, "m1");
+        queue.push(Priority::Normal, "n2");
+        queue.push(Priority::Gift, "g1");
+        queue.push(Priority::Moderator, "m2");
+
+        assert_eq!(
+            queue.iter().copied().collect::<Vec<_>>(),
+            vec!["g1", "m1", "m2", "n1", "n2"]
+        );
+        assert_eq!(queue.pop(), Some("g1"));
+        assert_eq!(queue.pop(), Some("m1"));
+        assert_eq!(queue.len(), 3);
+    }
+
+    #[test]
+    fn pop_oldest_ignores_priority() {
+        let mut queue = PriorityQueue::new();
+        queue.push(Priority::Normal, "n1");
+        queue.push(Priority::Gift, "g1");
+        queue.push(Priority::Normal, "n2");
+        assert_eq!(queue.pop_oldest(), Some("n1"));
+        assert_eq!(queue.pop_oldest(), Some("g1"));
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.pop(), Some("n2"));
+        assert_eq!(queue.pop_oldest(), None);
+    }
+
+    #[test]
+    fn remove_first_takes_match_in_pop_order() {
+        let mut queue = PriorityQueue::new();
+        queue.push(Priority::Normal, ("a", 1));
+        queue.push(Priority::Normal, ("b", 2));
+        queue.push(Priority::Gift, ("b", 3));
+        assert_eq!(queue.remove_first(|(key, _)| *key == "b"), Some(("b", 3)));
+        assert_eq!(queue.remove_first(|(key, _)| *key == "b"), Some(("b", 2)));
+        assert_eq!(queue.remove_first(|(key, _)| *key == "b"), None);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.pop(), Some(("a", 1)));
+    }
+
+    #[test]
+    fn retain_updates_len() {
+        let mut queue = PriorityQueue::new();
+        queue.push(Priority::Normal, 1);
+        queue.push(Priority::Paid, 2);
+        queue.push(Priority::Normal, 3);
+        queue.retain(|value| *value != 2);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(3));
+        assert!(queue.is_empty());
+    }
+}
@@ -31,28 +31,45 @@ impl MessageQueue {
         )
     }
 
+    pub fn filter(&self) -> &MessageFilter {
+        &self.filter
+    }
+
+    /// Number of accepted messages still waiting for the consumer.
+    pub fn pending(&self) -> usize {
+        self.tx.max_capacity() - self.tx.capacity()
+    }
+
     pub async fn enqueue(&self, message: &NormalizedMessage) -> Result<bool> {
-        if let Some(filtered) = self.filter.sanitize(message) {
-            let mut limiter = self.limiter.lock().await;
-            limiter.throttle().await;
-            drop(limiter);
-            if self.tx.send(filtered.clone()).await.is_ok() {
+        match self
+            .filter
+            .filter(message)
+            .and_then(|filtered| self.filter.admit(message).map(|_| filtered))
+        {
+            Ok(filtered) => {
+                let mut limiter = self.limiter.lock().await;
+                limiter.throttle().await;
+                drop(limiter);
+                if self.tx.send(filtered.clone()).await.is_ok() {
+                    tracing::trace!(
+                        target = "ishowtts::danmaku",
+                        channel = %filtered.source.channel,
+                        user = %filtered.source.username,
+                        text = %filtered.spoken_text,
+                        "enqueued filtered message"
+                    );
+                    return Ok(true);
+                }
+            }
+            Err(reason) => {
                 tracing::trace!(
                     target = "ishowtts::danmaku",
-                    channel = %filtered.source.channel,
-                    user = %filtered.source.username,
-                    text = %filtered.sanitized_text,
-                    "enqueued filtered message"
+                    channel = %message.channel,
+                    user = %message.username,
+                    %reason,
+                    "message dropped by filter"
                 );
-                return Ok(true);
             }
-        } else {
-            tracing::trace!(
-                target = "ishowtts::danmaku",
-                channel = %message.channel,
-                user = %message.username,
-                "message dropped by filter"
-            );
         }
         Ok(false)
     }
@@ -80,8 +97,10 @@ mod tests {
     async fn enqueue_and_receive() {
         let filter = MessageFilter::new(FilterConfig::default()).unwrap();
         let (queue, mut rx) = MessageQueue::new(filter, QueueConfig::default());
+        assert_eq!(queue.pending(), 0);
         assert!(queue.enqueue(&make_message("hello world")).await.unwrap());
+        assert_eq!(queue.pending(), 1);
         let msg = rx.recv().await.unwrap();
-        assert_eq!(msg.sanitized_text, "hello world");
+        assert_eq!(msg.spoken_text, "hello world");
     }
 }
@@ -10,6 +10,8 @@ pub struct TtsRequestPayload {
     pub text: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub voice_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normalize_output_rms: Option<f32>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -42,6 +44,7 @@ impl TtsClient {
         let payload = TtsRequestPayload {
             text: text.to_string(),
             voice_id: self.config.voice_id.clone(),
+            normalize_output_rms: self.config.normalize_output_rms,
         };
         let request = self.http.post(&self.config.endpoint).json(&payload);
         let response = request
@@ -72,9 +75,11 @@ mod tests {
         let payload = TtsRequestPayload {
             text: "hello".into(),
             voice_id: Some("walter".into()),
+            normalize_output_rms: None,
         };
         let json = serde_json::to_string(&payload).unwrap();
         assert!(json.contains("\"voice_id\":"));
+        assert!(!json.contains("normalize_output_rms"));
     }
 
     #[tokio::test]
@@ -97,6 +102,14 @@ mod tests {
             endpoint: format!("{}/api/tts", server.base_url()),
             voice_id: Some("walter".into()),
             timeout_secs: Some(5),
+            spoken_template: None,
+            auto_language_voice: false,
+            normalize_output_rms: None,
+            correct_engine_mismatch: true,
+            danmaku_speed: None,
+            danmaku_prefix_audio: None,
+            danmaku_suffix_audio: None,
+            danmaku_stinger_dir: None,
         })
         .unwrap();
 
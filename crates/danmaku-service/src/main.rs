@@ -18,14 +18,14 @@ use tokio::{
     task::JoinHandle,
     time::{sleep, Duration},
 };
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use tracing_subscriber::EnvFilter;
 
 use danmaku::config::DanmakuConfig;
 use danmaku::message::{NormalizedMessage, Platform, Priority};
-use danmaku::twitch::{parse_ping, parse_privmsg};
+use danmaku::twitch::{parse_channel_input, parse_ping, parse_privmsg};
 use danmaku_gateway::{
-    config::GatewayConfig, FilteredMessage, MessageFilter, MessageQueue, TtsClient,
+    config::GatewayConfig, ChatAggregator, FilteredMessage, MessageFilter, MessageQueue, TtsClient,
 };
 
 #[derive(Clone)]
@@ -81,12 +81,21 @@ async fn main() -> Result<()> {
             .await?;
 
     if let Some(twitch) = danmaku_config.twitch {
-        if twitch.enabled {
+        if twitch.enabled && twitch.auto_start {
+            for channel in twitch.channels {
+                match start_twitch(state.clone(), channel.clone()).await {
+                    Ok(_) => info!(%channel, "auto-started Twitch collector"),
+                    Err((_, err)) => warn!(%channel, %err, "failed to auto-start Twitch collector"),
+                }
+            }
+        } else if twitch.enabled {
             info!(channels = ?twitch.channels, "ready to start Twitch collectors via UI");
         }
     }
     if let Some(youtube) = danmaku_config.youtube {
-        if youtube.enabled {
+        if youtube.enabled && youtube.auto_start {
+            warn!(channel = ?youtube.channel_id, "YouTube auto_start ignored: support coming soon");
+        } else if youtube.enabled {
             info!(channel = ?youtube.channel_id, "YouTube support coming soon");
         }
     }
@@ -154,7 +163,7 @@ async fn start_twitch(
     state: Arc<AppState>,
     user_input: String,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let channel = parse_twitch_channel(&user_input).ok_or_else(|| {
+    let channel = parse_channel_input(&user_input).ok_or_else(|| {
         (
             StatusCode::BAD_REQUEST,
             "请输入正确的 Twitch 用户名或频道链接".into(),
@@ -223,6 +232,7 @@ async fn build_app_state_with_connector(
     twitch_connector: Arc<dyn TwitchConnector>,
 ) -> Result<(Arc<AppState>, JoinHandle<Result<()>>)> {
     let filter = MessageFilter::new(config.filter.clone())?;
+    let aggregator = ChatAggregator::from_config(&config.queue);
     let (queue_inner, mut rx) = MessageQueue::new(filter, config.queue.clone());
     let queue = Arc::new(queue_inner);
     let playback = Arc::new(Mutex::new(VecDeque::new()));
@@ -237,9 +247,25 @@ async fn build_app_state_with_connector(
 
     let worker_state = state.clone();
     let handle = tokio::spawn(async move {
+        let process = |filtered: FilteredMessage| {
+            let state = &worker_state;
+            async move {
+                if let Err(err) = process_message(state, filtered).await {
+                    error!(%err, "failed to process message");
+                }
+            }
+        };
         while let Some(filtered) = rx.recv().await {
-            if let Err(err) = process_message(&worker_state, filtered).await {
-                error!(%err, "failed to process message");
+            // Same aggregation as the backend's danmaku worker: short
+            // messages wait out the window, longer ones are read at once.
+            let batch = match &aggregator {
+                Some(aggregator) if aggregator.is_candidate(&filtered) => {
+                    aggregator.collect_window(filtered, &mut rx, &process).await
+                }
+                _ => vec![filtered],
+            };
+            for filtered in batch {
+                process(filtered).await;
             }
         }
         Ok(())
@@ -249,7 +275,7 @@ async fn build_app_state_with_connector(
 }
 
 async fn process_message(state: &Arc<AppState>, filtered: FilteredMessage) -> Result<()> {
-    let tts_response = state.tts.synthesize(&filtered.sanitized_text).await?;
+    let tts_response = state.tts.synthesize(&filtered.spoken_text).await?;
     let item = PlaybackItem {
         message: filtered.source,
         audio_base64: tts_response.audio_base64,
@@ -261,29 +287,6 @@ async fn process_message(state: &Arc<AppState>, filtered: FilteredMessage) -> Re
     Ok(())
 }
 
-fn parse_twitch_channel(input: &str) -> Option<String> {
-    let trimmed = input.trim();
-    if trimmed.is_empty() {
-        return None;
-    }
-    let lower = trimmed.to_lowercase();
-    let channel = if let Some(idx) = lower.find("twitch.tv/") {
-        let after = &trimmed[idx + "twitch.tv/".len()..];
-        after
-            .split(|c: char| c == '/' || c == '?' || c == '&')
-            .next()
-            .unwrap_or("")
-    } else {
-        trimmed
-    };
-    let channel = channel.trim_matches('/');
-    if channel.is_empty() {
-        None
-    } else {
-        Some(channel.to_lowercase())
-    }
-}
-
 #[async_trait]
 impl TwitchConnector for RealTwitchConnector {
     async fn spawn(&self, channel: String, queue: Arc<MessageQueue>) -> Result<JoinHandle<()>> {
@@ -499,18 +502,39 @@ mod tests {
             queue: danmaku_gateway::QueueConfig {
                 capacity: 16,
                 rate_limit_per_sec: 100.0,
+                playback_capacity: 16,
+                min_playback_gap_ms: 0,
+                max_channels: 0,
+                aggregate_window_ms: 0,
+                aggregate_min_count: 3,
             },
             filter: danmaku_gateway::FilterConfig {
                 max_words: 10,
                 max_chars: 200,
                 banned_keywords: vec![],
+                banned_keyword_action: danmaku_gateway::BannedKeywordAction::Drop,
+                banned_keyword_mask: "哔".into(),
                 allow_links: true,
+                max_message_age_ms: 0,
+                per_user_cooldown_ms: 0,
+                channel_user_cooldown_ms: Default::default(),
+                emoji_action: Default::default(),
+                emoji_names: Default::default(),
             },
             tts: danmaku_gateway::TtsConfig {
                 endpoint: format!("{}/api/tts", server.base_url()),
                 voice_id: Some("walter".into()),
                 timeout_secs: Some(5),
+                spoken_template: None,
+                auto_language_voice: false,
+                normalize_output_rms: None,
+                correct_engine_mismatch: true,
+                danmaku_speed: None,
+                danmaku_prefix_audio: None,
+                danmaku_suffix_audio: None,
+                danmaku_stinger_dir: None,
             },
+            recording: Default::default(),
         };
         let (state, worker) =
             build_app_state_with_connector(gateway_config, Arc::new(MockTwitchConnector))
@@ -573,7 +597,16 @@ mod tests {
                 endpoint: format!("{}/api/tts", server.base_url()),
                 voice_id: Some("walter".into()),
                 timeout_secs: Some(5),
+                spoken_template: None,
+                auto_language_voice: false,
+                normalize_output_rms: None,
+                correct_engine_mismatch: true,
+                danmaku_speed: None,
+                danmaku_prefix_audio: None,
+                danmaku_suffix_audio: None,
+                danmaku_stinger_dir: None,
             },
+            recording: Default::default(),
         };
         let (state, worker) =
             build_app_state_with_connector(gateway_config, Arc::new(MockTwitchConnector))
@@ -0,0 +1,369 @@
+use std::io::Read;
+
+use anyhow::{anyhow, bail, Context, Result};
+use flate2::read::ZlibDecoder;
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+
+use crate::message::{NormalizedMessage, Platform, Priority};
+
+/// Every frame starts with this big-endian header.
+pub const HEADER_LEN: usize = 16;
+
+/// Body encodings carried in the header's protocol version field.
+const PROTOVER_JSON: u16 = 0;
+const PROTOVER_INT: u16 = 1;
+const PROTOVER_ZLIB: u16 = 2;
+const PROTOVER_BROTLI: u16 = 3;
+
+/// Frame operations used by the live danmaku websocket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Heartbeat,
+    HeartbeatReply,
+    Notification,
+    Auth,
+    AuthReply,
+    Other(u32),
+}
+
+impl Operation {
+    const fn code(self) -> u32 {
+        match self {
+            Operation::Heartbeat => 2,
+            Operation::HeartbeatReply => 3,
+            Operation::Notification => 5,
+            Operation::Auth => 7,
+            Operation::AuthReply => 8,
+            Operation::Other(code) => code,
+        }
+    }
+
+    const fn from_code(code: u32) -> Self {
+        match code {
+            2 => Operation::Heartbeat,
+            3 => Operation::HeartbeatReply,
+            5 => Operation::Notification,
+            7 => Operation::Auth,
+            8 => Operation::AuthReply,
+            other => Operation::Other(other),
+        }
+    }
+}
+
+/// One decoded frame. Compressed frames are expanded into the frames they
+/// wrap, so callers only ever see plain bodies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet {
+    pub operation: Operation,
+    pub body: Vec<u8>,
+}
+
+impl Packet {
+    /// Online viewer count carried by heartbeat replies.
+    pub fn popularity(&self) -> Option<u32> {
+        if self.operation != Operation::HeartbeatReply {
+            return None;
+        }
+        let bytes: [u8; 4] = self.body.get(..4)?.try_into().ok()?;
+        Some(u32::from_be_bytes(bytes))
+    }
+
+    pub fn json(&self) -> Result<JsonValue> {
+        serde_json::from_slice(&self.body).context("invalid bilibili packet body")
+    }
+}
+
+pub fn encode_packet(operation: Operation, body: &[u8]) -> Vec<u8> {
+    let total = (HEADER_LEN + body.len()) as u32;
+    let mut frame = Vec::with_capacity(HEADER_LEN + body.len());
+    frame.extend_from_slice(&total.to_be_bytes());
+    frame.extend_from_slice(&(HEADER_LEN as u16).to_be_bytes());
+    frame.extend_from_slice(&PROTOVER_INT.to_be_bytes());
+    frame.extend_from_slice(&operation.code().to_be_bytes());
+    frame.extend_from_slice(&1u32.to_be_bytes());
+    frame.extend_from_slice(body);
+    frame
+}
+
+/// Join request for `room_id`. Protocol version 2 asks for zlib rather than
+/// brotli compression. `uid = 0` with no key joins anonymously, in which
+/// case Bilibili masks usernames.
+pub fn auth_packet(room_id: u64, uid: u64, key: Option<&str>, buvid: Option<&str>) -> Vec<u8> {
+    let mut body = json!({
+        "uid": uid,
+        "roomid": room_id,
+        "protover": PROTOVER_ZLIB,
+        "platform": "web",
+        "type": 2,
+    });
+    if let Some(key) = key {
+        body["key"] = json!(key);
+    }
+    if let Some(buvid) = buvid {
+        body["buvid"] = json!(buvid);
+    }
+    encode_packet(Operation::Auth, body.to_string().as_bytes())
+}
+
+pub fn heartbeat_packet() -> Vec<u8> {
+    encode_packet(Operation::Heartbeat, b"[object Object]")
+}
+
+/// Splits a websocket message into frames, inflating compressed batches.
+pub fn decode_packets(data: &[u8]) -> Result<Vec<Packet>> {
+    let mut packets = Vec::new();
+    decode_into(data, &mut packets)?;
+    Ok(packets)
+}
+
+fn decode_into(mut data: &[u8], packets: &mut Vec<Packet>) -> Result<()> {
+    while !data.is_empty() {
+        if data.len() < HEADER_LEN {
+            bail!("truncated bilibili frame header ({} bytes)", data.len());
+        }
+        let total = u32::from_be_bytes(data[0..4].try_into()?) as usize;
+        let header_len = u16::from_be_bytes(data[4..6].try_into()?) as usize;
+        let protover = u16::from_be_bytes(data[6..8].try_into()?);
+        let operation = Operation::from_code(u32::from_be_bytes(data[8..12].try_into()?));
+        if total < header_len || header_len < HEADER_LEN || total > data.len() {
+            bail!("malformed bilibili frame (length {total}, header {header_len})");
+        }
+        let body = &data[header_len..total];
+        match protover {
+            PROTOVER_ZLIB => {
+                let mut inflated = Vec::new();
+                ZlibDecoder::new(body)
+                    .read_to_end(&mut inflated)
+                    .context("failed to inflate bilibili frame")?;
+                decode_into(&inflated, packets)?;
+            }
+            PROTOVER_BROTLI => bail!("brotli-compressed bilibili frames are not supported"),
+            PROTOVER_JSON | PROTOVER_INT => packets.push(Packet {
+                operation,
+                body: body.to_vec(),
+            }),
+            other => return Err(anyhow!("unknown bilibili protocol version {other}")),
+        }
+        data = &data[total..];
+    }
+    Ok(())
+}
+
+/// A chat line or super chat from a Bilibili live room.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BilibiliDanmaku {
+    pub room_id: u64,
+    pub uid: Option<u64>,
+    pub username: String,
+    pub message: String,
+    /// Super chat price in CNY.
+    pub price: Option<u32>,
+    /// 1 = 总督, 2 = 提督, 3 = 舰长; 0 for everyone else.
+    pub guard_level: u8,
+    pub is_admin: bool,
+    pub color: Option<String>,
+}
+
+impl BilibiliDanmaku {
+    pub fn to_normalized(&self) -> NormalizedMessage {
+        let mut priority = Priority::Normal;
+        if self.guard_level > 0 {
+            priority = Priority::Subscriber;
+        }
+        if self.is_admin {
+            priority = Priority::Moderator;
+        }
+        if self.price.unwrap_or_default() > 0 {
+            priority = Priority::Paid;
+        }
+        let mut metadata = json!({
+            "guard_level": self.guard_level,
+            "room_id": self.room_id,
+        });
+        if let Some(color) = &self.color {
+            metadata["color"] = json!(color);
+        }
+        if let Some(price) = self.price {
+            metadata["super_chat"] = json!({ "price": price });
+        }
+        NormalizedMessage::new_text(
+            Platform::Bilibili,
+            self.room_id.to_string(),
+            self.uid.map(|uid| uid.to_string()),
+            self.username.clone(),
+            priority,
+            self.message.clone(),
+            metadata,
+        )
+    }
+}
+
+/// Extracts chat from a notification body. Other commands (gifts, entry
+/// effects, rank updates, ...) yield `None`.
+pub fn parse_notification(room_id: u64, body: &JsonValue) -> Option<BilibiliDanmaku> {
+    let cmd = body.get("cmd")?.as_str()?;
+    // Newer servers append a version suffix, e.g. "DANMU_MSG:4:0:2:2:2:0".
+    match cmd.split(':').next()? {
+        "DANMU_MSG" => parse_danmu_msg(room_id, body.get("info")?),
+        "SUPER_CHAT_MESSAGE" => parse_super_chat(room_id, body.get("data")?),
+        _ => None,
+    }
+}
+
+fn parse_danmu_msg(room_id: u64, info: &JsonValue) -> Option<BilibiliDanmaku> {
+    let message = info.get(1)?.as_str()?.to_string();
+    let user = info.get(2)?;
+    let color = info
+        .get(0)
+        .and_then(|meta| meta.get(3))
+        .and_then(JsonValue::as_u64)
+        .filter(|color| *color != 0xFFFFFF)
+        .map(|color| format!("#{color:06X}"));
+    Some(BilibiliDanmaku {
+        room_id,
+        uid: user
+            .get(0)
+            .and_then(JsonValue::as_u64)
+            .filter(|uid| *uid > 0),
+        username: user.get(1)?.as_str()?.to_string(),
+        message,
+        price: None,
+        guard_level: info.get(7).and_then(JsonValue::as_u64).unwrap_or(0) as u8,
+        is_admin: user.get(2).and_then(JsonValue::as_u64) == Some(1),
+        color,
+    })
+}
+
+fn parse_super_chat(room_id: u64, data: &JsonValue) -> Option<BilibiliDanmaku> {
+    Some(BilibiliDanmaku {
+        room_id,
+        uid: data.get("uid").and_then(JsonValue::as_u64),
+        username: data.get("user_info")?.get("uname")?.as_str()?.to_string(),
+        message: data.get("message")?.as_str()?.to_string(),
+        price: data
+            .get("price")
+            .and_then(JsonValue::as_u64)
+            .map(|price| price as u32),
+        guard_level: data
+            .get("user_info")
+            .and_then(|user| user.get("guard_level"))
+            .and_then(JsonValue::as_u64)
+            .unwrap_or(0) as u8,
+        is_admin: false,
+        color: data
+            .get("background_bottom_color")
+            .and_then(JsonValue::as_str)
+            .map(str::to_string),
+    })
+}
+
+/// Accepts a room number or a `live.bilibili.com/<room>` link. The result
+/// may be a short room id that still needs resolving to the real one.
+pub fn parse_room_input(input: &str) -> Option<u64> {
+    let trimmed = input.trim();
+    let after = match trimmed.find("live.bilibili.com/") {
+        Some(idx) => &trimmed[idx + "live.bilibili.com/".len()..],
+        None => trimmed,
+    };
+    after
+        .split(['/', '?', '#'])
+        .find(|segment| !segment.is_empty() && *segment != "h5")?
+        .parse()
+        .ok()
+        .filter(|room| *room > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::ZlibEncoder, Compression};
+
+    use super::*;
+
+    fn frame(protover: u16, operation: u32, body: &[u8]) -> Vec<u8> {
+        let mut frame = encode_packet(Operation::from_code(operation), body);
+        frame[6..8].copy_from_slice(&protover.to_be_bytes());
+        frame
+    }
+
+    #[test]
+    fn decode_plain_and_compressed_frames() {
+        let chat = br#"{"cmd":"DANMU_MSG"}"#;
+        let mut batch = frame(PROTOVER_JSON, 5, chat);
+        batch.extend(frame(PROTOVER_JSON, 5, br#"{"cmd":"SEND_GIFT"}"#));
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&batch).unwrap();
+        let mut data = frame(PROTOVER_ZLIB, 5, &encoder.finish().unwrap());
+        data.extend(frame(PROTOVER_INT, 3, &1234u32.to_be_bytes()));
+
+        let packets = decode_packets(&data).unwrap();
+        assert_eq!(packets.len(), 3);
+        assert_eq!(packets[0].operation, Operation::Notification);
+        assert_eq!(packets[0].body, chat);
+        assert_eq!(packets[2].popularity(), Some(1234));
+        assert!(decode_packets(&data[..10]).is_err());
+    }
+
+    #[test]
+    fn parse_danmu_msg_notification() {
+        let body = json!({
+            "cmd": "DANMU_MSG:4:0:2:2:2:0",
+            "info": [
+                [0, 1, 25, 16738408, 1700000000000u64],
+                "主播晚上好",
+                [12345, "观众A", 1, 0, 0, 10000, 1, ""],
+                [],
+                [],
+                {},
+                0,
+                3
+            ]
+        });
+        let danmaku = parse_notification(100, &body).unwrap();
+        assert_eq!(danmaku.username, "观众A");
+        assert_eq!(danmaku.message, "主播晚上好");
+        assert_eq!(danmaku.uid, Some(12345));
+        assert_eq!(danmaku.guard_level, 3);
+        assert_eq!(danmaku.color.as_deref(), Some("#FF6868"));
+        let normalized = danmaku.to_normalized();
+        assert_eq!(normalized.platform, Platform::Bilibili);
+        assert_eq!(normalized.channel, "100");
+        assert_eq!(normalized.priority, Priority::Moderator);
+
+        let gift = json!({ "cmd": "SEND_GIFT", "data": {} });
+        assert!(parse_notification(100, &gift).is_none());
+    }
+
+    #[test]
+    fn parse_super_chat_is_paid() {
+        let body = json!({
+            "cmd": "SUPER_CHAT_MESSAGE",
+            "data": {
+                "uid": 7,
+                "price": 30,
+                "message": "加油",
+                "user_info": { "uname": "老板", "guard_level": 0 }
+            }
+        });
+        let normalized = parse_notification(1, &body).unwrap().to_normalized();
+        assert_eq!(normalized.priority, Priority::Paid);
+        assert_eq!(normalized.content.as_text(), Some("加油"));
+    }
+
+    #[test]
+    fn parse_room_links() {
+        assert_eq!(parse_room_input("21452505"), Some(21452505));
+        assert_eq!(
+            parse_room_input("https://live.bilibili.com/h5/6?spm_id=1"),
+            Some(6)
+        );
+        assert_eq!(
+            parse_room_input("live.bilibili.com/22637261?live_from=1"),
+            Some(22637261)
+        );
+        assert_eq!(parse_room_input("someone"), None);
+        assert_eq!(parse_room_input("0"), None);
+    }
+}
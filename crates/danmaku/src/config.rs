@@ -9,6 +9,8 @@ pub struct DanmakuConfig {
     pub twitch: Option<TwitchConfig>,
     #[serde(default)]
     pub youtube: Option<YouTubeConfig>,
+    #[serde(default)]
+    pub bilibili: Option<BilibiliConfig>,
 }
 
 #[derive(Debug, Deserialize, Clone, Default)]
@@ -25,6 +27,17 @@ pub struct TwitchConfig {
     pub oauth_token: Option<String>,
     #[serde(default)]
     pub channels: Vec<String>,
+    /// Seconds of IRC silence before the connection is probed with a PING;
+    /// unanswered probes trigger a reconnect. Defaults to 120.
+    #[serde(default)]
+    pub read_timeout_secs: Option<u64>,
+    /// Consecutive failed reconnects before a channel is given up on;
+    /// 0 retries forever. Defaults to 10.
+    #[serde(default)]
+    pub max_reconnect_attempts: Option<u32>,
+    /// Join `channels` on boot instead of waiting for a start from the UI.
+    #[serde(default)]
+    pub auto_start: bool,
 }
 
 #[derive(Debug, Deserialize, Clone, Default)]
@@ -41,6 +54,27 @@ pub struct YouTubeConfig {
     pub refresh_token: Option<String>,
     #[serde(default)]
     pub channel_id: Option<String>,
+    /// Start watching `channel_id` on boot once a YouTube connector exists.
+    #[serde(default)]
+    pub auto_start: bool,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct BilibiliConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Account uid paired with `cookie`; leave both unset to join
+    /// anonymously (Bilibili then masks usernames).
+    #[serde(default)]
+    pub uid: Option<u64>,
+    /// Browser cookie string (`SESSDATA=...; buvid3=...`) for logged-in access.
+    #[serde(default)]
+    pub cookie: Option<String>,
+    #[serde(default)]
+    pub room_ids: Vec<u64>,
+    /// Join `room_ids` on boot instead of waiting for a start from the UI.
+    #[serde(default)]
+    pub auto_start: bool,
 }
 
 impl DanmakuConfig {
@@ -64,13 +98,25 @@ mod tests {
 enabled = true
 client_id = "abc"
 channels = ["foo", "bar"]
+read_timeout_secs = 90
+auto_start = true
 
 [youtube]
 enabled = true
 refresh_token = "refresh"
+
+[bilibili]
+enabled = true
+room_ids = [21452505]
 "#;
         let cfg: DanmakuConfig = toml::from_str(toml).unwrap();
-        assert!(cfg.twitch.unwrap().enabled);
+        let twitch = cfg.twitch.unwrap();
+        assert!(twitch.enabled);
+        assert_eq!(twitch.read_timeout_secs, Some(90));
+        assert!(twitch.auto_start);
         assert_eq!(cfg.youtube.unwrap().refresh_token.unwrap(), "refresh");
+        let bilibili = cfg.bilibili.unwrap();
+        assert_eq!(bilibili.room_ids, vec![21452505]);
+        assert!(!bilibili.auto_start);
     }
 }
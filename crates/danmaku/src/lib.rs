@@ -1,7 +1,8 @@
+pub mod bilibili;
 pub mod config;
 pub mod message;
 pub mod twitch;
 pub mod youtube;
 
-pub use config::{DanmakuConfig, TwitchConfig, YouTubeConfig};
+pub use config::{BilibiliConfig, DanmakuConfig, TwitchConfig, YouTubeConfig};
 pub use message::{MessageContent, NormalizedMessage, Platform, Priority};
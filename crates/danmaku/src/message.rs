@@ -7,13 +7,15 @@ use uuid::Uuid;
 pub enum Platform {
     Twitch,
     YouTube,
+    Bilibili,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum Priority {
     Gift,
     Paid,
     Moderator,
+    Subscriber,
     Mention,
     Normal,
 }
@@ -24,6 +26,29 @@ impl Default for Priority {
     }
 }
 
+impl Priority {
+    /// Lower rank is read out first.
+    pub const fn rank(&self) -> u8 {
+        match self {
+            Priority::Gift => 0,
+            Priority::Paid => 1,
+            Priority::Moderator => 2,
+            Priority::Subscriber => 3,
+            Priority::Mention => 4,
+            Priority::Normal => 5,
+        }
+    }
+
+    /// Returns whichever of the two priorities should be read out first.
+    pub fn higher(self, other: Priority) -> Priority {
+        if other.rank() < self.rank() {
+            other
+        } else {
+            self
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum MessageContent {
     Text(String),
@@ -109,4 +134,15 @@ mod tests {
         );
         assert!(format!("{msg}").contains("hello"));
     }
+
+    #[test]
+    fn priority_rank_ordering() {
+        assert!(Priority::Gift.rank() < Priority::Moderator.rank());
+        assert!(Priority::Subscriber.rank() < Priority::Normal.rank());
+        assert_eq!(Priority::Normal.higher(Priority::Paid), Priority::Paid);
+        assert_eq!(
+            Priority::Moderator.higher(Priority::Normal),
+            Priority::Moderator
+        );
+    }
 }
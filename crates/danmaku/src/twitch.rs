@@ -336,7 +336,162 @@ pub fn parse_clearmsg(line: &str) -> Result<Option<(String, String)>> {
     Ok(Some((channel, target)))
 }
 
+/// Channel modes announced by `ROOMSTATE`. Twitch only sends the tags that
+/// changed after the initial snapshot, so unknown fields stay `None`.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct RoomState {
+    pub emote_only: Option<bool>,
+    /// Minimum follow age in minutes; `-1` means followers-only is off.
+    pub followers_only: Option<i32>,
+    pub subs_only: Option<bool>,
+    pub slow_secs: Option<u32>,
+}
+
+impl RoomState {
+    /// Applies the fields present in `update` on top of `self`.
+    pub fn merge(&mut self, update: &RoomState) {
+        if update.emote_only.is_some() {
+            self.emote_only = update.emote_only;
+        }
+        if update.followers_only.is_some() {
+            self.followers_only = update.followers_only;
+        }
+        if update.subs_only.is_some() {
+            self.subs_only = update.subs_only;
+        }
+        if update.slow_secs.is_some() {
+            self.slow_secs = update.slow_secs;
+        }
+    }
+}
+
+/// Non-chat channel events used for presence tracking.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TwitchChannelEvent {
+    Join { channel: String, user: String },
+    Part { channel: String, user: String },
+    RoomState { channel: String, state: RoomState },
+}
+
+pub fn parse_channel_event(line: &str) -> Result<Option<TwitchChannelEvent>> {
+    let msg = parse_irc_message(line)?;
+    let channel = || {
+        msg.params
+            .first()
+            .map(|param| param.trim_start_matches('#').to_string())
+            .ok_or_else(|| anyhow!("{} missing channel", msg.command))
+    };
+    let user = || {
+        msg.prefix
+            .as_deref()
+            .map(|prefix| prefix.split('!').next().unwrap_or(prefix).to_string())
+            .ok_or_else(|| anyhow!("{} missing prefix", msg.command))
+    };
+
+    let event = match msg.command.as_str() {
+        "JOIN" => TwitchChannelEvent::Join {
+            channel: channel()?,
+            user: user()?,
+        },
+        "PART" => TwitchChannelEvent::Part {
+            channel: channel()?,
+            user: user()?,
+        },
+        "ROOMSTATE" => {
+            let flag = |key: &str| msg.tags.get(key).map(|value| value == "1");
+            TwitchChannelEvent::RoomState {
+                channel: channel()?,
+                state: RoomState {
+                    emote_only: flag("emote-only"),
+                    followers_only: msg
+                        .tags
+                        .get("followers-only")
+                        .and_then(|value| value.parse().ok()),
+                    subs_only: flag("subs-only"),
+                    slow_secs: msg.tags.get("slow").and_then(|value| value.parse().ok()),
+                },
+            }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(event))
+}
+
+/// Server `NOTICE`, e.g. a failed login or a suspended channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TwitchNotice {
+    /// `None` for server-wide notices (`NOTICE *`).
+    pub channel: Option<String>,
+    pub msg_id: Option<String>,
+    pub message: String,
+}
+
+/// `msg-id`s for channels that cannot be joined however often we retry.
+const FATAL_NOTICE_IDS: &[&str] = &[
+    "msg_banned",
+    "msg_channel_blocked",
+    "msg_channel_suspended",
+    "tos_ban",
+];
+
+impl TwitchNotice {
+    /// Whether the notice rules out reconnecting: a failed login or a
+    /// banned, suspended or deleted channel.
+    pub fn is_fatal(&self) -> bool {
+        match self.msg_id.as_deref() {
+            Some(id) => FATAL_NOTICE_IDS.contains(&id),
+            None => {
+                self.message.starts_with("Login authentication failed")
+                    || self.message.starts_with("Improperly formatted auth")
+            }
+        }
+    }
+}
+
+pub fn parse_notice(line: &str) -> Result<Option<TwitchNotice>> {
+    let msg = parse_irc_message(line)?;
+    if msg.command != "NOTICE" {
+        return Ok(None);
+    }
+    if msg.params.len() < 2 {
+        return Err(anyhow!("NOTICE missing params"));
+    }
+    let channel = msg.params[0]
+        .strip_prefix('#')
+        .map(|channel| channel.to_string());
+    Ok(Some(TwitchNotice {
+        channel,
+        msg_id: msg.tags.get("msg-id").cloned(),
+        message: msg.params[1].clone(),
+    }))
+}
+
+/// Accepts a Twitch login or a `twitch.tv/<login>` link and returns the
+/// lowercased login, or `None` unless it follows Twitch's username rules
+/// (1-25 ASCII letters, digits or underscores). The check keeps arbitrary
+/// input out of the IRC `JOIN` line.
+pub fn parse_channel_input(input: &str) -> Option<String> {
+    let trimmed = input.trim();
+    if trimmed.len() > MAX_CHANNEL_INPUT_LEN {
+        return None;
+    }
+    let after = match trimmed.to_ascii_lowercase().find("twitch.tv/") {
+        Some(idx) => trimmed[idx + "twitch.tv/".len()..]
+            .split(['/', '?', '&', '#'])
+            .next()
+            .unwrap_or(""),
+        None => trimmed.trim_start_matches('#'),
+    };
+    let channel = after.trim_matches('/').to_ascii_lowercase();
+    CHANNEL_RE.is_match(&channel).then_some(channel)
+}
+
+/// Longest input worth parsing: a full channel URL with some query string.
+const MAX_CHANNEL_INPUT_LEN: usize = 256;
+
 lazy_static::lazy_static! {
+    static ref CHANNEL_RE: Regex = Regex::new(r"^[a-z0-9_]{1,25}$").unwrap();
     static ref PING_RE: Regex = Regex::new(r"^PING :?(?P<token>.+)").unwrap();
 }
 
@@ -367,6 +522,126 @@ mod tests {
             .and_then(|value| value.as_str())
             .unwrap();
         assert_eq!(color, "#00FF7F");
+        assert_eq!(
+            normalized
+                .metadata
+                .get("timestamp_ms")
+                .and_then(|value| value.as_i64()),
+            Some(1_660_000_000_000)
+        );
+    }
+
+    #[test]
+    fn parse_membership_events() {
+        let join = parse_channel_event(":viewer!viewer@viewer.tmi.twitch.tv JOIN #channel")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            join,
+            TwitchChannelEvent::Join {
+                channel: "channel".into(),
+                user: "viewer".into(),
+            }
+        );
+        let part = parse_channel_event(":viewer!viewer@viewer.tmi.twitch.tv PART #channel")
+            .unwrap()
+            .unwrap();
+        assert!(matches!(part, TwitchChannelEvent::Part { .. }));
+        assert!(parse_channel_event("PING :tmi.twitch.tv")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn parse_roomstate_partial_update() {
+        let line = "@emote-only=0;followers-only=10;r9k=0;room-id=123;slow=0;subs-only=0 :tmi.twitch.tv ROOMSTATE #channel";
+        let Some(TwitchChannelEvent::RoomState { channel, state }) =
+            parse_channel_event(line).unwrap()
+        else {
+            panic!("expected ROOMSTATE");
+        };
+        assert_eq!(channel, "channel");
+        assert_eq!(state.followers_only, Some(10));
+        assert_eq!(state.subs_only, Some(false));
+
+        let mut merged = state.clone();
+        let update = "@followers-only=-1;room-id=123 :tmi.twitch.tv ROOMSTATE #channel";
+        if let Some(TwitchChannelEvent::RoomState { state, .. }) =
+            parse_channel_event(update).unwrap()
+        {
+            assert_eq!(state.slow_secs, None);
+            merged.merge(&state);
+        }
+        assert_eq!(merged.followers_only, Some(-1));
+        assert_eq!(merged.slow_secs, Some(0));
+    }
+
+    #[test]
+    fn parse_notice_scopes() {
+        let login = parse_notice(":tmi.twitch.tv NOTICE * :Login authentication failed")
+            .unwrap()
+            .unwrap();
+        assert_eq!(login.channel, None);
+        assert_eq!(login.message, "Login authentication failed");
+
+        let suspended = parse_notice(
+            "@msg-id=msg_channel_suspended :tmi.twitch.tv NOTICE #gone :This channel does not exist or has been suspended.",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(suspended.channel.as_deref(), Some("gone"));
+        assert_eq!(suspended.msg_id.as_deref(), Some("msg_channel_suspended"));
+    }
+
+    #[test]
+    fn notice_fatality() {
+        let login = parse_notice(":tmi.twitch.tv NOTICE * :Login authentication failed")
+            .unwrap()
+            .unwrap();
+        assert!(login.is_fatal());
+
+        let suspended = parse_notice(
+            "@msg-id=msg_channel_suspended :tmi.twitch.tv NOTICE #gone :This channel does not exist or has been suspended.",
+        )
+        .unwrap()
+        .unwrap();
+        assert!(suspended.is_fatal());
+
+        let slow = parse_notice(
+            "@msg-id=slow_on :tmi.twitch.tv NOTICE #foo :This room is now in slow mode.",
+        )
+        .unwrap()
+        .unwrap();
+        assert!(!slow.is_fatal());
+    }
+
+    #[test]
+    fn parse_channel_inputs() {
+        assert_eq!(parse_channel_input("  Shroud "), Some("shroud".into()));
+        assert_eq!(parse_channel_input("#xqc"), Some("xqc".into()));
+        assert_eq!(
+            parse_channel_input("https://www.twitch.tv/Some_User"),
+            Some("some_user".into())
+        );
+        assert_eq!(
+            parse_channel_input("twitch.tv/foo/videos?filter=archives"),
+            Some("foo".into())
+        );
+        assert_eq!(
+            parse_channel_input("https://twitch.tv/bar?referrer=raid&x=1"),
+            Some("bar".into())
+        );
+    }
+
+    #[test]
+    fn parse_channel_rejects_invalid_logins() {
+        assert_eq!(parse_channel_input(""), None);
+        assert_eq!(parse_channel_input("https://twitch.tv/"), None);
+        assert_eq!(parse_channel_input("foo bar"), None);
+        assert_eq!(parse_channel_input("foo\r\nPRIVMSG #x :hi"), None);
+        assert_eq!(parse_channel_input("主播"), None);
+        assert_eq!(parse_channel_input(&"a".repeat(26)), None);
+        assert_eq!(parse_channel_input(&"a".repeat(10_000)), None);
     }
 
     #[test]
@@ -1,5 +1,5 @@
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
-use gloo_net::http::Request;
+use gloo_net::http::{Request, Response};
 use gloo_timers::future::TimeoutFuture;
 use js_sys::{Array, Date, Uint8Array};
 use serde::{Deserialize, Serialize};
@@ -11,8 +11,9 @@ use wasm_bindgen::JsCast;
 use wasm_bindgen::JsValue;
 use wasm_bindgen_futures::{spawn_local, JsFuture};
 use web_sys::{
-    BinaryType, Blob, BlobPropertyBag, CloseEvent, Event as DomEvent, File, FormData,
-    HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement, MessageEvent, Url, WebSocket,
+    BinaryType, Blob, BlobPropertyBag, CloseEvent, Event as DomEvent, EventSource, File, FormData,
+    HtmlAnchorElement, HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement, MessageEvent, Url,
+    WebSocket,
 };
 use yew::events::{Event, InputEvent, MouseEvent};
 use yew::prelude::*;
@@ -22,8 +23,21 @@ const BACKEND_URL: &str = env_backend_url();
 const HISTORY_CAPACITY: usize = 100;
 const PAGE_SIZE: usize = 10;
 const HISTORY_STORAGE_KEY: &str = "ishowtts_history_v1";
+/// Set to "1" when the user turned off saving history to localStorage.
+const NO_HISTORY_PREF_KEY: &str = "ishowtts_pref_no_history";
 const DANMAKU_LOG_CAPACITY: usize = 50;
+/// The gateway's default `tts.spoken_template`, used until `/api/health`
+/// reports the configured one.
+const DANMAKU_SPOKEN_TEMPLATE: &str = "{user} says: {message}";
+/// Stand-in name when "模拟弹幕格式" is on but no username was typed.
+const DANMAKU_PREVIEW_USER: &str = "观众";
 const HEALTH_POLL_INTERVAL_MS: u32 = 30_000;
+/// Upper bound for the health poll delay while the backend keeps failing.
+const HEALTH_POLL_MAX_INTERVAL_MS: u32 = 5 * 60_000;
+
+/// Shared secret for the backend websockets (`api.ws_token`), baked in at
+/// build time like the backend URL.
+const WS_TOKEN: Option<&str> = option_env!("ISHOWTTS_WS_TOKEN");
 
 const fn env_backend_url() -> &'static str {
     match option_env!("ISHOWTTS_BACKEND_URL") {
@@ -34,12 +48,20 @@ const fn env_backend_url() -> &'static str {
 
 fn backend_ws_url(path: &str) -> String {
     let trimmed = BACKEND_URL.trim_end_matches('/');
-    if let Some(rest) = trimmed.strip_prefix("https://") {
+    let url = if let Some(rest) = trimmed.strip_prefix("https://") {
         format!("wss://{}{}", rest, path)
     } else if let Some(rest) = trimmed.strip_prefix("http://") {
         format!("ws://{}{}", rest, path)
     } else {
         format!("ws://{}{}", trimmed, path)
+    };
+    match WS_TOKEN.filter(|token| !token.is_empty()) {
+        Some(token) => {
+            let separator = if url.contains('?') { '&' } else { '?' };
+            let encoded = js_sys::encode_uri_component(token);
+            format!("{url}{separator}token={}", String::from(encoded))
+        }
+        None => url,
     }
 }
 
@@ -70,7 +92,20 @@ struct VoiceReferenceDetail {
     baseline_audio_available: bool,
     override_audio_available: bool,
     #[serde(default)]
+    reference_clips: Vec<ReferenceClipInfo>,
+    #[serde(default)]
     override_updated_at: Option<String>,
+    #[serde(default)]
+    reference_text_truncated: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct ReferenceClipInfo {
+    source: String,
+    index: usize,
+    #[serde(default)]
+    file_name: Option<String>,
+    available: bool,
 }
 
 #[derive(Clone, Debug, PartialEq, Deserialize)]
@@ -78,6 +113,40 @@ struct HealthResponse {
     status: String,
     voices: usize,
     default_voice: String,
+    /// Engine the operator wants preselected, e.g. `index_tts`.
+    #[serde(default)]
+    default_engine: Option<String>,
+    #[serde(default)]
+    engines: Vec<EngineHealth>,
+    /// `tts.spoken_template` of the backend's danmaku gateway, when enabled.
+    #[serde(default)]
+    danmaku_spoken_template: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct EngineHealth {
+    engine: String,
+    /// `idle` until the engine has synthesized once, `loading` while a
+    /// warmup runs, then `ready`.
+    state: String,
+}
+
+impl HealthResponse {
+    fn engine_state(&self, engine: &str) -> Option<&str> {
+        self.engines
+            .iter()
+            .find(|status| status.engine == engine)
+            .map(|status| status.state.as_str())
+    }
+
+    fn engine_loading(&self, engine: &str) -> bool {
+        self.engine_state(engine) == Some("loading")
+    }
+
+    /// Not ready yet, whether or not a warmup is running.
+    fn engine_cold(&self, engine: &str) -> bool {
+        matches!(self.engine_state(engine), Some("idle" | "loading"))
+    }
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -92,7 +161,23 @@ struct TtsResponse {
     sample_rate: u32,
     audio_base64: String,
     waveform_len: usize,
+    #[serde(default)]
+    duration_ms: Option<u32>,
     format: String,
+    #[serde(default)]
+    nfe_step_used: Option<u32>,
+    #[serde(default)]
+    peak: Option<f32>,
+    #[serde(default)]
+    rms: Option<f32>,
+    #[serde(default)]
+    fallback_used: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiErrorBody {
+    code: String,
+    message: String,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -109,14 +194,53 @@ struct DanmakuStopResponse {
     channel: Option<String>,
 }
 
+/// Text frame on the danmaku stream, e.g. a channel the backend gave up on.
+#[derive(Debug, Deserialize)]
+struct StreamStatusEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    channel: String,
+    #[serde(default)]
+    reason: Option<String>,
+    /// Clip a `skip` targets; `None` means whatever is playing.
+    #[serde(default)]
+    packet_id: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct PacketHeader {
+    #[serde(default)]
+    packet_id: Option<String>,
     platform: String,
     channel: String,
     username: String,
     display_text: String,
     format: String,
+    /// Older backends omit this; their clips were always 24 kHz.
+    #[serde(default)]
+    sample_rate: Option<u32>,
     color: Option<String>,
+    #[serde(default)]
+    peak: Option<f32>,
+    #[serde(default)]
+    rms: Option<f32>,
+    /// `gzip` when the audio after the header is compressed.
+    #[serde(default)]
+    encoding: Option<String>,
+    /// Streamer line sent through `/api/danmaku/say` rather than chat.
+    #[serde(default)]
+    announcement: bool,
+}
+
+/// `/api/voices/:voice_id/reference`, pinned to the engine of the loaded
+/// `detail` so a voice id shared by several engines keeps editing the one
+/// shown in the modal.
+fn voice_reference_url(voice_id: &str, detail: Option<&VoiceReferenceDetail>) -> String {
+    let url = format!("{BACKEND_URL}/api/voices/{voice_id}/reference");
+    match detail.filter(|detail| detail.voice_id == voice_id) {
+        Some(detail) => format!("{url}?engine={}", detail.engine),
+        None => url,
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -130,8 +254,19 @@ struct ClipHistoryItem {
     created_at: String,
     sample_rate: u32,
     waveform_len: usize,
+    #[serde(default)]
+    duration_ms: Option<u32>,
     format: String,
     audio_src: String,
+    #[serde(default)]
+    nfe_step: Option<u32>,
+    #[serde(default)]
+    peak: Option<f32>,
+    #[serde(default)]
+    rms: Option<f32>,
+    /// Pinned clips are skipped when trimming history to capacity.
+    #[serde(default)]
+    pinned: bool,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -139,6 +274,8 @@ struct DanmakuLogEntry {
     timestamp: String,
     message: String,
     color: Option<String>,
+    packet_id: Option<String>,
+    announcement: bool,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -161,6 +298,20 @@ struct ShimmyModelListResponse {
     models: Vec<ShimmyModelInfo>,
 }
 
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct EngineInfo {
+    engine: String,
+    #[serde(default)]
+    parameters: Vec<EngineParameter>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct EngineParameter {
+    name: String,
+    #[serde(default)]
+    default: Option<f64>,
+}
+
 #[derive(Clone, Debug, PartialEq, Deserialize)]
 struct ShimmyModelInfo {
     name: String,
@@ -200,6 +351,57 @@ fn parse_engine_choice(value: &str) -> Option<EngineModelChoice> {
     None
 }
 
+/// Engine select value and voice id to show after the voice list is
+/// (re)loaded. The current choices are kept while they still exist, so a
+/// refetch after a backend blip does not reset the panel; otherwise the
+/// default engine, then the first listed one, is picked.
+fn reconcile_selection(
+    voices: &[VoiceSummary],
+    default_engine: Option<&str>,
+    current_engine: Option<&str>,
+    current_voice: Option<&str>,
+) -> (Option<String>, Option<String>) {
+    let engine = match current_engine.and_then(parse_engine_choice) {
+        Some(EngineModelChoice::Tts { engine_label })
+            if voices
+                .iter()
+                .any(|voice| voice.engine_label == engine_label) =>
+        {
+            current_engine.map(str::to_string)
+        }
+        // Shimmy models come from their own list, which a voice reload
+        // does not touch.
+        Some(EngineModelChoice::Shimmy { .. }) => current_engine.map(str::to_string),
+        _ => default_engine
+            .and_then(|engine| voices.iter().find(|voice| voice.engine == engine))
+            .or_else(|| voices.first())
+            .map(|voice| format!("tts:{}", voice.engine_label)),
+    };
+    let engine_label = match engine.as_deref().and_then(parse_engine_choice) {
+        Some(EngineModelChoice::Tts { engine_label }) => Some(engine_label),
+        _ => None,
+    };
+    let on_engine = |voice: &VoiceSummary| {
+        engine_label
+            .as_ref()
+            .map_or(true, |label| &voice.engine_label == label)
+    };
+    let voice = current_voice
+        .filter(|id| {
+            voices
+                .iter()
+                .any(|voice| voice.id == *id && on_engine(voice))
+        })
+        .map(str::to_string)
+        .or_else(|| {
+            voices
+                .iter()
+                .find(|voice| on_engine(voice))
+                .map(|voice| voice.id.clone())
+        });
+    (engine, voice)
+}
+
 #[derive(Clone, Debug, PartialEq, Default)]
 struct HistoryState {
     entries: VecDeque<ClipHistoryItem>,
@@ -209,6 +411,24 @@ enum HistoryAction {
     Push(ClipHistoryItem),
     Clear,
     Hydrate(Vec<ClipHistoryItem>),
+    /// Adds imported clips; entries already present (by id) are kept as-is.
+    Merge(Vec<ClipHistoryItem>),
+    TogglePin(usize),
+}
+
+/// Drops the oldest unpinned clips until the list fits; pinned clips only go
+/// once nothing else is left to evict.
+fn trim_history(entries: &mut VecDeque<ClipHistoryItem>) {
+    while entries.len() > HISTORY_CAPACITY {
+        match entries.iter().rposition(|clip| !clip.pinned) {
+            Some(index) => {
+                entries.remove(index);
+            }
+            None => {
+                entries.pop_back();
+            }
+        }
+    }
 }
 
 impl Reducible for HistoryState {
@@ -219,17 +439,26 @@ impl Reducible for HistoryState {
         match action {
             HistoryAction::Push(clip) => {
                 entries.push_front(clip);
-                while entries.len() > HISTORY_CAPACITY {
-                    entries.pop_back();
-                }
+                trim_history(&mut entries);
             }
             HistoryAction::Clear => {
                 entries.clear();
             }
             HistoryAction::Hydrate(items) => {
-                entries.clear();
-                for clip in items.into_iter().take(HISTORY_CAPACITY) {
-                    entries.push_back(clip);
+                entries = items.into_iter().collect();
+                trim_history(&mut entries);
+            }
+            HistoryAction::Merge(items) => {
+                let mut known: HashSet<usize> = entries.iter().map(|clip| clip.id).collect();
+                entries.extend(items.into_iter().filter(|clip| known.insert(clip.id)));
+                entries
+                    .make_contiguous()
+                    .sort_by_key(|clip| std::cmp::Reverse(clip.id));
+                trim_history(&mut entries);
+            }
+            HistoryAction::TogglePin(id) => {
+                if let Some(clip) = entries.iter_mut().find(|clip| clip.id == id) {
+                    clip.pinned = !clip.pinned;
                 }
             }
         }
@@ -270,6 +499,10 @@ impl Default for AdvancedTtsOptions {
 enum SynthesisStatus {
     Idle,
     Loading,
+    /// Waiting for a synthesis slot; holds the 1-based queue position.
+    Queued(usize),
+    /// Segments rendered so far and in total, for engines that report them.
+    Progress(u32, u32),
     Ready(String),
     Error(String),
 }
@@ -285,6 +518,10 @@ impl SynthesisStatus {
         match self {
             Self::Idle => "等待输入，准备开始语音合成".to_string(),
             Self::Loading => "正在合成语音，请稍候...".to_string(),
+            Self::Queued(position) => format!("排队中，第 {position} 位..."),
+            Self::Progress(completed, total) => {
+                format!("正在合成语音，{completed}/{total} 段已合成...")
+            }
             Self::Ready(msg) => msg.clone(),
             Self::Error(msg) => format!("⚠️ {msg}"),
         }
@@ -293,7 +530,7 @@ impl SynthesisStatus {
     fn css_class(&self) -> &'static str {
         match self {
             Self::Idle => "idle",
-            Self::Loading => "loading",
+            Self::Loading | Self::Queued(_) | Self::Progress(..) => "loading",
             Self::Ready(_) => "ready",
             Self::Error(_) => "error",
         }
@@ -306,11 +543,176 @@ fn now_string() -> String {
         .into()
 }
 
+/// Prefers a localized message for known error codes, falling back to the
+/// server's message or the raw body.
+#[derive(Debug, Deserialize)]
+struct TtsJob {
+    request_id: String,
+    status: String,
+    #[serde(default)]
+    position: Option<usize>,
+    #[serde(default)]
+    result: Option<TtsResponse>,
+    #[serde(default)]
+    error: Option<ApiErrorBody>,
+}
+
+const TTS_JOB_POLL_INTERVAL_MS: u32 = 500;
+
+#[derive(Debug, Deserialize)]
+struct TtsProgressEvent {
+    completed: u32,
+    total: u32,
+    #[serde(default)]
+    done: bool,
+}
+
+/// Listens to `/api/tts/progress/:id` and shows segment progress in the
+/// status line. Closes the stream when dropped.
+struct TtsProgressListener {
+    source: EventSource,
+    _on_progress: Closure<dyn FnMut(MessageEvent)>,
+}
+
+impl TtsProgressListener {
+    /// `None` when the stream cannot be opened; the panel then keeps the
+    /// plain loading state.
+    fn open(progress_id: &str, status_state: UseStateHandle<SynthesisStatus>) -> Option<Self> {
+        let source =
+            EventSource::new(&format!("{BACKEND_URL}/api/tts/progress/{progress_id}")).ok()?;
+        let on_progress = Closure::wrap(Box::new(move |event: MessageEvent| {
+            let Some(data) = event.data().as_string() else {
+                return;
+            };
+            if let Ok(progress) = serde_json::from_str::<TtsProgressEvent>(&data) {
+                if !progress.done && progress.total > 0 {
+                    status_state.set(SynthesisStatus::Progress(
+                        progress.completed,
+                        progress.total,
+                    ));
+                }
+            }
+        }) as Box<dyn FnMut(MessageEvent)>);
+        source
+            .add_event_listener_with_callback("progress", on_progress.as_ref().unchecked_ref())
+            .ok()?;
+        Some(Self {
+            source,
+            _on_progress: on_progress,
+        })
+    }
+}
+
+impl Drop for TtsProgressListener {
+    fn drop(&mut self) {
+        self.source.close();
+    }
+}
+
+/// Delay before the next `/api/health` poll: the base interval doubled per
+/// consecutive failure, stretched to the server's `Retry-After` if longer,
+/// capped, and jittered by up to 10% so many open tabs do not poll in step.
+fn health_poll_delay_ms(failures: u32, retry_after_ms: Option<u32>) -> u32 {
+    let backoff = HEALTH_POLL_INTERVAL_MS
+        .saturating_mul(1 << failures.min(16))
+        .max(retry_after_ms.unwrap_or(0))
+        .min(HEALTH_POLL_MAX_INTERVAL_MS);
+    if failures == 0 {
+        return backoff;
+    }
+    let jitter = js_sys::Math::random() * f64::from(backoff) * 0.1;
+    backoff - jitter as u32
+}
+
+/// `Retry-After` in delta-seconds form; HTTP dates are ignored.
+fn retry_after_ms(resp: &Response) -> Option<u32> {
+    resp.headers()
+        .get("Retry-After")?
+        .trim()
+        .parse::<u32>()
+        .ok()
+        .map(|secs| secs.saturating_mul(1_000))
+}
+
+fn new_progress_id() -> String {
+    format!(
+        "{:x}-{:x}",
+        Date::now() as u64,
+        (js_sys::Math::random() * f64::from(u32::MAX)) as u32
+    )
+}
+
+/// Polls a queued `/api/tts` job until it finishes, reporting the queue
+/// position through `status_state` meanwhile.
+async fn await_tts_job(
+    job: TtsJob,
+    status_state: &UseStateHandle<SynthesisStatus>,
+) -> Result<TtsResponse, String> {
+    let url = format!("{BACKEND_URL}/api/tts/{}", job.request_id);
+    let mut job = job;
+    let mut running = false;
+    loop {
+        match job.status.as_str() {
+            "done" => return job.result.ok_or_else(|| "任务结果缺失".to_string()),
+            "failed" => {
+                return Err(job
+                    .error
+                    .map(|err| err.message)
+                    .unwrap_or_else(|| "合成失败".into()))
+            }
+            // Set once, so segment progress from the event stream is kept.
+            "running" if !running => {
+                running = true;
+                status_state.set(SynthesisStatus::Loading);
+            }
+            "running" => {}
+            _ => status_state.set(SynthesisStatus::Queued(job.position.unwrap_or(1))),
+        }
+        TimeoutFuture::new(TTS_JOB_POLL_INTERVAL_MS).await;
+        let resp = Request::get(&url)
+            .send()
+            .await
+            .map_err(|err| format!("查询任务失败: {err}"))?;
+        if !resp.ok() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(describe_api_error(status, &body));
+        }
+        job = resp
+            .json::<TtsJob>()
+            .await
+            .map_err(|err| format!("解析任务状态失败: {err}"))?;
+    }
+}
+
+fn describe_api_error(status: u16, body: &str) -> String {
+    match serde_json::from_str::<ApiErrorBody>(body) {
+        Ok(err) => match err.code.as_str() {
+            "voice_not_found" => "音色不存在，请刷新音色列表".into(),
+            "engine_mismatch" => "所选音色与模型不匹配".into(),
+            "text_empty" => "请输入要合成的文本".into(),
+            "danmaku_disabled" => "后端未启用弹幕播报".into(),
+            _ => err.message,
+        },
+        Err(_) => format!("{status} {body}"),
+    }
+}
+
+/// Frames `message` the way the backend reads a danmaku aloud.
+fn render_danmaku_text(template: Option<&str>, user: &str, message: &str) -> String {
+    template
+        .unwrap_or(DANMAKU_SPOKEN_TEMPLATE)
+        .replace("{user}", user)
+        .replace("{message}", message)
+}
+
 fn log_entry(message: impl Into<String>, color: Option<String>) -> DanmakuLogEntry {
     DanmakuLogEntry {
         timestamp: now_string(),
         message: message.into(),
         color,
+        packet_id: None,
+        announcement: false,
     }
 }
 
@@ -322,6 +724,14 @@ fn push_log(mut logs: Vec<DanmakuLogEntry>, entry: DanmakuLogEntry) -> Vec<Danma
     logs
 }
 
+fn gunzip(bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    use std::io::Read as _;
+
+    let mut inflated = Vec::with_capacity(bytes.len() * 2);
+    flate2::read::GzDecoder::new(bytes).read_to_end(&mut inflated)?;
+    Ok(inflated)
+}
+
 fn make_object_url(format: &str, audio: &[u8]) -> Option<String> {
     let array = Uint8Array::new_with_length(audio.len() as u32);
     array.copy_from(audio);
@@ -333,6 +743,22 @@ fn make_object_url(format: &str, audio: &[u8]) -> Option<String> {
     Url::create_object_url_with_blob(&blob).ok()
 }
 
+fn trigger_download(url: &str, file_name: &str) -> Option<()> {
+    let document = web_sys::window()?.document()?;
+    let anchor: HtmlAnchorElement = document.create_element("a").ok()?.dyn_into().ok()?;
+    anchor.set_href(url);
+    anchor.set_download(file_name);
+    anchor.click();
+    Some(())
+}
+
+fn platform_label(platform: &str) -> &'static str {
+    match platform {
+        "bilibili" => "Bilibili",
+        _ => "Twitch",
+    }
+}
+
 fn float_value(input: &str) -> Option<serde_json::Value> {
     let trimmed = input.trim();
     if trimmed.is_empty() {
@@ -351,11 +777,105 @@ fn u32_value(input: &str) -> Option<serde_json::Value> {
     Some(serde_json::Value::Number(value.into()))
 }
 
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct WaveformBucket {
+    peak: f32,
+    rms: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct ReferenceWaveform {
+    duration_ms: u64,
+    buckets: Vec<WaveformBucket>,
+}
+
+#[derive(Properties, PartialEq)]
+struct ReferenceWaveformProps {
+    voice_id: String,
+    source: &'static str,
+    label: &'static str,
+    /// Changes whenever the clip may have changed, to trigger a refetch.
+    revision: Option<String>,
+}
+
+/// Peak/RMS thumbnail of a voice's reference clip, as reported by
+/// `/api/voices/:voice_id/reference/waveform`.
+#[function_component(ReferenceWaveformView)]
+fn reference_waveform_view(props: &ReferenceWaveformProps) -> Html {
+    let waveform_state = use_state(|| Option::<Result<ReferenceWaveform, String>>::None);
+
+    {
+        let waveform_state = waveform_state.clone();
+        use_effect_with(
+            (props.voice_id.clone(), props.source, props.revision.clone()),
+            move |(voice_id, source, _): &(String, &'static str, Option<String>)| {
+                waveform_state.set(None);
+                let url = format!(
+                    "{BACKEND_URL}/api/voices/{voice_id}/reference/waveform?source={source}"
+                );
+                spawn_local(async move {
+                    let outcome = match Request::get(&url).send().await {
+                        Ok(resp) if resp.ok() => resp
+                            .json::<ReferenceWaveform>()
+                            .await
+                            .map_err(|err| format!("解析波形失败: {err}")),
+                        Ok(resp) => Err(format!("波形不可用 ({})", resp.status())),
+                        Err(err) => Err(format!("请求波形失败: {err}")),
+                    };
+                    waveform_state.set(Some(outcome));
+                });
+                || ()
+            },
+        );
+    }
+
+    let body = match &*waveform_state {
+        None => html! { <p class="muted small">{"正在生成波形..."}</p> },
+        Some(Err(message)) => html! { <p class="muted small">{message.clone()}</p> },
+        Some(Ok(waveform)) => {
+            let width = waveform.buckets.len().max(1);
+            let bars = waveform
+                .buckets
+                .iter()
+                .enumerate()
+                .map(|(i, bucket)| {
+                    let peak = bucket.peak.clamp(0.0, 1.0) * 20.0;
+                    let rms = bucket.rms.clamp(0.0, 1.0) * 20.0;
+                    html! {
+                        <>
+                            <rect class="waveform-peak" x={i.to_string()} y={format!("{:.2}", 20.0 - peak)} width="0.8" height={format!("{:.2}", peak * 2.0)} />
+                            <rect class="waveform-rms" x={i.to_string()} y={format!("{:.2}", 20.0 - rms)} width="0.8" height={format!("{:.2}", rms * 2.0)} />
+                        </>
+                    }
+                })
+                .collect::<Html>();
+            html! {
+                <>
+                    <svg class="waveform-svg" viewBox={format!("0 0 {width} 40")} preserveAspectRatio="none">
+                        {bars}
+                    </svg>
+                    <span class="muted small">{format!("{:.1} 秒", waveform.duration_ms as f64 / 1000.0)}</span>
+                </>
+            }
+        }
+    };
+
+    html! {
+        <div class="waveform-thumb">
+            <span class="metric-label">{props.label}</span>
+            {body}
+        </div>
+    }
+}
+
 #[function_component(App)]
 fn app() -> Html {
     let text_state = use_state(|| String::new());
+    let simulate_danmaku_state = use_state(|| false);
+    let danmaku_user_state = use_state(String::new);
     let voices_state = use_state(Vec::<VoiceSummary>::new);
     let shimmy_models_state = use_state(Vec::<ShimmyModelInfo>::new);
+    let engines_state = use_state(Vec::<EngineInfo>::new);
     let selected_voice_state = use_state(|| Option::<String>::None);
     let selected_engine_state = use_state(|| Option::<String>::None);
     let voice_manager_open_state = use_state(|| false);
@@ -383,6 +903,8 @@ fn app() -> Html {
     });
     let backend_health_state = use_state(|| Option::<HealthResponse>::None);
     let health_error_state = use_state(|| Option::<String>::None);
+    // Bumped when the health poll recovers, so the voice list is refetched.
+    let voices_reload_state = use_state(|| 0u32);
     let status_state = use_state(SynthesisStatus::default);
     let advanced_visible = use_state(|| false);
     let advanced_state = use_state(AdvancedTtsOptions::default);
@@ -391,13 +913,21 @@ fn app() -> Html {
     let current_page = use_state(|| 0usize);
     let detail_clip_state = use_state(|| Option::<ClipHistoryItem>::None);
     let history_hydrated = use_state(|| false);
+    let no_history_state = use_state(|| {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .and_then(|storage| storage.get_item(NO_HISTORY_PREF_KEY).ok().flatten())
+            .is_some_and(|value| value == "1")
+    });
     let danmaku_channel_state = use_state(|| String::new());
+    let danmaku_platform_state = use_state(|| String::from("twitch"));
     let danmaku_status_state = use_state(|| String::from("等待启动"));
     let danmaku_active_state = use_state(|| false);
     let danmaku_stream_ready_state = use_state(|| false);
     let danmaku_active_channel_state = use_state(|| Option::<String>::None);
     let danmaku_log_state = use_state(Vec::<DanmakuLogEntry>::new);
     let danmaku_audio_state = use_state(|| Option::<String>::None);
+    let danmaku_playing_state = use_state(|| Option::<String>::None);
     let danmaku_websocket = use_mut_ref(|| None::<WebSocket>);
     let danmaku_ws_message = use_mut_ref(|| None::<Closure<dyn FnMut(MessageEvent)>>);
     let danmaku_ws_error = use_mut_ref(|| None::<Closure<dyn FnMut(DomEvent)>>);
@@ -423,13 +953,17 @@ fn app() -> Html {
         let history_state = history_state.clone();
         let history_hydrated = history_hydrated.clone();
         let current_page = current_page.clone();
+        let clip_counter = clip_counter.clone();
+        let no_history = *no_history_state;
         use_effect_with((), move |_| {
             if !*history_hydrated {
-                if let Some(window) = web_sys::window() {
+                if let Some(window) = web_sys::window().filter(|_| !no_history) {
                     if let Ok(Some(storage)) = window.local_storage() {
                         if let Ok(Some(raw)) = storage.get_item(HISTORY_STORAGE_KEY) {
                             if let Ok(items) = serde_json::from_str::<Vec<ClipHistoryItem>>(&raw) {
                                 if !items.is_empty() {
+                                    let max_id = items.iter().map(|clip| clip.id).max();
+                                    clip_counter.set(max_id.unwrap_or_default());
                                     history_state.dispatch(HistoryAction::Hydrate(items));
                                     current_page.set(0);
                                 }
@@ -446,11 +980,14 @@ fn app() -> Html {
     {
         let history_hydrated = history_hydrated.clone();
         let entries = history_state.entries.clone();
-        use_effect_with((entries, *history_hydrated), move |(entries, hydrated)| {
+        let deps = (entries, *history_hydrated, *no_history_state);
+        use_effect_with(deps, move |(entries, hydrated, no_history)| {
             if *hydrated {
                 if let Some(window) = web_sys::window() {
                     if let Ok(Some(storage)) = window.local_storage() {
-                        if entries.is_empty() {
+                        // With saving off, history lives only in memory and
+                        // anything stored earlier is wiped.
+                        if *no_history || entries.is_empty() {
                             let _ = storage.remove_item(HISTORY_STORAGE_KEY);
                         } else if let Ok(json) =
                             serde_json::to_string(&entries.iter().cloned().collect::<Vec<_>>())
@@ -470,6 +1007,7 @@ fn app() -> Html {
         let error_ref = danmaku_ws_error.clone();
         let close_ref = danmaku_ws_close.clone();
         let audio_state = danmaku_audio_state.clone();
+        let playing_state = danmaku_playing_state.clone();
         let log_state = danmaku_log_state.clone();
         let status_state = danmaku_status_state.clone();
         let active_state = danmaku_active_state.clone();
@@ -483,13 +1021,14 @@ fn app() -> Html {
         let voices_state_ws = voices_state.clone();
 
         use_effect_with((), move |_| {
-            let ws_url = backend_ws_url("/api/danmaku/stream");
+            let ws_url = backend_ws_url("/api/danmaku/stream?compress=gzip");
             match WebSocket::new(&ws_url) {
                 Ok(ws) => {
                     ws.set_binary_type(BinaryType::Arraybuffer);
 
                     let message_handler = {
                         let audio_state = audio_state.clone();
+                        let playing_state = playing_state.clone();
                         let log_state = log_state.clone();
                         let status_state = status_state.clone();
                         let active_state = active_state.clone();
@@ -523,6 +1062,17 @@ fn app() -> Html {
 
                                 match serde_json::from_slice::<PacketHeader>(header_bytes) {
                                     Ok(header) => {
+                                        let audio_bytes = match header.encoding.as_deref() {
+                                            Some("gzip") => match gunzip(&audio_bytes) {
+                                                Ok(inflated) => inflated,
+                                                Err(err) => {
+                                                    status_state
+                                                        .set(format!("解压弹幕音频失败: {err}"));
+                                                    return;
+                                                }
+                                            },
+                                            _ => audio_bytes,
+                                        };
                                         if let Some(current) = (*audio_state).clone() {
                                             let _ = Url::revoke_object_url(&current);
                                         }
@@ -532,7 +1082,7 @@ fn app() -> Html {
                                             audio_state.set(Some(url));
                                         }
 
-                                        let entry = log_entry(
+                                        let mut entry = log_entry(
                                             format!(
                                                 "{} ({})：{}",
                                                 header.username,
@@ -541,6 +1091,9 @@ fn app() -> Html {
                                             ),
                                             header.color.clone(),
                                         );
+                                        entry.packet_id = header.packet_id.clone();
+                                        entry.announcement = header.announcement;
+                                        playing_state.set(header.packet_id.clone());
                                         let history = push_log((*log_state).clone(), entry);
                                         log_state.set(history);
 
@@ -596,10 +1149,15 @@ fn app() -> Html {
                                             voice_id: voice_label,
                                             text: clip_text,
                                             created_at: now_string(),
-                                            sample_rate: 24_000,
+                                            sample_rate: header.sample_rate.unwrap_or(24_000),
                                             waveform_len: audio_bytes.len(),
+                                            duration_ms: None,
                                             format: header.format.clone(),
                                             audio_src,
+                                            nfe_step: None,
+                                            peak: header.peak,
+                                            rms: header.rms,
+                                            pinned: false,
                                         };
 
                                         history_state.dispatch(HistoryAction::Push(clip));
@@ -609,10 +1167,40 @@ fn app() -> Html {
                                     }
                                 }
                             } else if let Some(text) = event.data().as_string() {
-                                status_state.set(format!(
-                                    "收到未知的弹幕消息格式: {}",
-                                    text.chars().take(128).collect::<String>()
-                                ));
+                                match serde_json::from_str::<StreamStatusEvent>(&text) {
+                                    Ok(status) if status.kind == "channel_failed" => {
+                                        status_state.set(format!(
+                                            "频道 {} 连接失败，已停止: {}",
+                                            status.channel,
+                                            status.reason.unwrap_or_default()
+                                        ));
+                                        if (*active_channel_state).as_deref()
+                                            == Some(status.channel.as_str())
+                                        {
+                                            active_channel_state.set(None);
+                                            active_state.set(false);
+                                        }
+                                    }
+                                    Ok(status) if status.kind == "skip" => {
+                                        let targets_current = status.packet_id.is_none()
+                                            || *playing_state == status.packet_id;
+                                        if targets_current
+                                            && (*active_channel_state).as_deref()
+                                                == Some(status.channel.as_str())
+                                        {
+                                            if let Some(current) = (*audio_state).clone() {
+                                                let _ = Url::revoke_object_url(&current);
+                                            }
+                                            audio_state.set(None);
+                                            playing_state.set(None);
+                                            status_state.set("已跳过当前弹幕".into());
+                                        }
+                                    }
+                                    _ => status_state.set(format!(
+                                        "收到未知的弹幕消息格式: {}",
+                                        text.chars().take(128).collect::<String>()
+                                    )),
+                                }
                             }
                         }) as Box<dyn FnMut(MessageEvent)>)
                     };
@@ -673,55 +1261,35 @@ fn app() -> Html {
         let voices_state = voices_state.clone();
         let selected_engine_state = selected_engine_state.clone();
         let status_state = status_state.clone();
-        use_effect_with((), move |_| {
+        use_effect_with(*voices_reload_state, move |_| {
             let voices_state = voices_state.clone();
             let selected_voice_state = selected_voice_state.clone();
             let selected_engine_state = selected_engine_state.clone();
             let status_state = status_state.clone();
             spawn_local(async move {
+                let default_engine = match Request::get(&format!("{BACKEND_URL}/api/health"))
+                    .send()
+                    .await
+                {
+                    Ok(resp) => resp
+                        .json::<HealthResponse>()
+                        .await
+                        .ok()
+                        .and_then(|health| health.default_engine),
+                    Err(_) => None,
+                };
                 match Request::get(&format!("{BACKEND_URL}/api/voices"))
                     .send()
                     .await
                 {
                     Ok(resp) => match resp.json::<Vec<VoiceSummary>>().await {
                         Ok(voices) if !voices.is_empty() => {
-                            let mut engine_order = Vec::new();
-                            for voice in &voices {
-                                if !engine_order.contains(&voice.engine_label) {
-                                    engine_order.push(voice.engine_label.clone());
-                                }
-                            }
-
-                            let mut engine_to_use = (*selected_engine_state).clone();
-                            if engine_to_use
-                                .as_ref()
-                                .map(|engine| engine_order.contains(engine))
-                                != Some(true)
-                            {
-                                engine_to_use = engine_order.first().cloned();
-                            }
-
-                            let voice_to_use = {
-                                let current_voice = (*selected_voice_state).clone();
-                                let engine_ref = engine_to_use.clone();
-                                current_voice.and_then(|voice_id| {
-                                    voices
-                                        .iter()
-                                        .find(|v| {
-                                            v.id == voice_id
-                                                && Some(v.engine_label.clone()) == engine_ref
-                                        })
-                                        .map(|v| v.id.clone())
-                                })
-                            }
-                            .or_else(|| {
-                                engine_to_use.as_ref().and_then(|engine| {
-                                    voices
-                                        .iter()
-                                        .find(|v| &v.engine_label == engine)
-                                        .map(|v| v.id.clone())
-                                })
-                            });
+                            let (engine_to_use, voice_to_use) = reconcile_selection(
+                                &voices,
+                                default_engine.as_deref(),
+                                (*selected_engine_state).as_deref(),
+                                (*selected_voice_state).as_deref(),
+                            );
 
                             voices_state.set(voices);
                             selected_engine_state.set(engine_to_use);
@@ -742,6 +1310,25 @@ fn app() -> Html {
         });
     }
 
+    {
+        let engines_state = engines_state.clone();
+        use_effect_with((), move |_| {
+            let engines_state = engines_state.clone();
+            spawn_local(async move {
+                // Older backends lack this endpoint; every control then stays visible.
+                if let Ok(resp) = Request::get(&format!("{BACKEND_URL}/api/engines"))
+                    .send()
+                    .await
+                {
+                    if let Ok(engines) = resp.json::<Vec<EngineInfo>>().await {
+                        engines_state.set(engines);
+                    }
+                }
+            });
+            || ()
+        });
+    }
+
     {
         let shimmy_models_state = shimmy_models_state.clone();
         let status_state = status_state.clone();
@@ -846,29 +1433,49 @@ fn app() -> Html {
     {
         let health_state = backend_health_state.clone();
         let health_error_state = health_error_state.clone();
+        let voices_reload_state = voices_reload_state.clone();
         use_effect_with((), move |_| {
             let health_state = health_state.clone();
             let health_error_state = health_error_state.clone();
+            let voices_reload_state = voices_reload_state.clone();
             spawn_local(async move {
+                let mut failures = 0u32;
+                let mut reloads = 0u32;
                 loop {
-                    match Request::get(&format!("{BACKEND_URL}/api/health"))
+                    let mut retry_after = None;
+                    let ok = match Request::get(&format!("{BACKEND_URL}/api/health"))
                         .send()
                         .await
                     {
+                        Ok(resp) if !resp.ok() => {
+                            retry_after = retry_after_ms(&resp);
+                            health_error_state
+                                .set(Some(format!("健康检查失败 ({})", resp.status())));
+                            false
+                        }
                         Ok(resp) => match resp.json::<HealthResponse>().await {
                             Ok(health) => {
                                 health_state.set(Some(health));
                                 health_error_state.set(None);
+                                true
                             }
                             Err(err) => {
-                                health_error_state.set(Some(format!("解析健康信息失败: {err}")))
+                                health_error_state.set(Some(format!("解析健康信息失败: {err}")));
+                                false
                             }
                         },
                         Err(err) => {
-                            health_error_state.set(Some(format!("请求健康信息失败: {err}")))
+                            health_error_state.set(Some(format!("请求健康信息失败: {err}")));
+                            false
                         }
+                    };
+                    if ok && failures > 0 {
+                        // The backend may have restarted with other voices.
+                        reloads += 1;
+                        voices_reload_state.set(reloads);
                     }
-                    TimeoutFuture::new(HEALTH_POLL_INTERVAL_MS).await;
+                    failures = if ok { 0 } else { failures.saturating_add(1) };
+                    TimeoutFuture::new(health_poll_delay_ms(failures, retry_after)).await;
                 }
             });
             || ()
@@ -884,11 +1491,30 @@ fn app() -> Html {
         })
     };
 
+    let on_simulate_danmaku_toggle = {
+        let simulate_danmaku_state = simulate_danmaku_state.clone();
+        Callback::from(move |event: Event| {
+            if let Some(input) = event.target_dyn_into::<HtmlInputElement>() {
+                simulate_danmaku_state.set(input.checked());
+            }
+        })
+    };
+
+    let on_danmaku_user_input = {
+        let danmaku_user_state = danmaku_user_state.clone();
+        Callback::from(move |event: InputEvent| {
+            if let Some(input) = event.target_dyn_into::<HtmlInputElement>() {
+                danmaku_user_state.set(input.value());
+            }
+        })
+    };
+
     let voices_state_for_model = voices_state.clone();
     let on_model_change = {
         let selected_engine_state = selected_engine_state.clone();
         let selected_voice_state = selected_voice_state.clone();
         let voices_state = voices_state_for_model.clone();
+        let health_state = backend_health_state.clone();
         Callback::from(move |event: Event| {
             if let Some(select) = event.target_dyn_into::<HtmlSelectElement>() {
                 let value = select.value();
@@ -899,6 +1525,28 @@ fn app() -> Html {
                     let voices = (*voices_state).clone();
                     let current_voice = (*selected_voice_state).clone();
                     let choice = parse_engine_choice(&value);
+                    if let Some(EngineModelChoice::Tts { ref engine_label }) = choice {
+                        // Start loading a cold engine now rather than on the
+                        // first submit.
+                        let engine = voices
+                            .iter()
+                            .find(|v| &v.engine_label == engine_label)
+                            .map(|v| v.engine.clone());
+                        let cold = engine.as_deref().is_some_and(|engine| {
+                            (*health_state)
+                                .as_ref()
+                                .is_some_and(|health| health.engine_cold(engine))
+                        });
+                        if let (true, Some(engine)) = (cold, engine) {
+                            spawn_local(async move {
+                                // Best effort: the health poll picks up the
+                                // new state, and a failed warmup just leaves
+                                // the load to the first request.
+                                let url = format!("{BACKEND_URL}/api/engines/{engine}/warmup");
+                                let _ = Request::post(&url).send().await;
+                            });
+                        }
+                    }
                     let next_voice = match choice {
                         Some(EngineModelChoice::Tts { ref engine_label }) => voices
                             .iter()
@@ -974,6 +1622,25 @@ fn app() -> Html {
     let fix_duration_input = make_input_handler(|opts| &mut opts.fix_duration);
     let seed_input = make_input_handler(|opts| &mut opts.seed);
 
+    let no_history_toggle = {
+        let no_history_state = no_history_state.clone();
+        Callback::from(move |event: Event| {
+            if let Some(input) = event.target_dyn_into::<HtmlInputElement>() {
+                let enabled = input.checked();
+                if let Some(storage) =
+                    web_sys::window().and_then(|window| window.local_storage().ok().flatten())
+                {
+                    let _ = if enabled {
+                        storage.set_item(NO_HISTORY_PREF_KEY, "1")
+                    } else {
+                        storage.remove_item(NO_HISTORY_PREF_KEY)
+                    };
+                }
+                no_history_state.set(enabled);
+            }
+        })
+    };
+
     let remove_silence_toggle = {
         let advanced_state = advanced_state.clone();
         Callback::from(move |event: Event| {
@@ -1064,6 +1731,7 @@ fn app() -> Html {
                 return;
             }
 
+            let url = voice_reference_url(&voice_id, (*voice_reference_state).as_ref());
             voice_reference_loading_state.set(true);
             voice_reference_error_state.set(None);
             voice_reference_notice_state.set(None);
@@ -1105,8 +1773,7 @@ fn app() -> Html {
                     }
                 }
 
-                let builder =
-                    Request::post(&format!("{BACKEND_URL}/api/voices/{}/reference", voice_id));
+                let builder = Request::post(&url);
 
                 let response = match builder.body(form) {
                     Ok(request) => request.send().await,
@@ -1125,11 +1792,20 @@ fn app() -> Html {
                                 .clone()
                                 .or(detail.active_reference_text.clone())
                                 .unwrap_or_default();
+                            let text_truncated = detail.reference_text_truncated;
                             voice_reference_state.set(Some(detail));
                             voice_reference_text_state.set(next_text);
                             voice_reference_file_state.set(None);
-                            voice_reference_notice_state.set(Some("参考覆盖已保存".into()));
-                            toast_success.set(Some(ToastMessage::success("参考音色已保存")));
+                            if text_truncated {
+                                voice_reference_notice_state
+                                    .set(Some("参考文本超过长度上限，已截断后保存".into()));
+                                toast_success.set(Some(ToastMessage::info(
+                                    "参考音色已保存（参考文本过长，已截断）",
+                                )));
+                            } else {
+                                voice_reference_notice_state.set(Some("参考覆盖已保存".into()));
+                                toast_success.set(Some(ToastMessage::success("参考音色已保存")));
+                            }
                             modal_state.set(false);
                             voice_reference_loading_state.set(false);
                             if let Some(input) = reference_file_input.cast::<HtmlInputElement>() {
@@ -1171,6 +1847,7 @@ fn app() -> Html {
                 return;
             };
 
+            let url = voice_reference_url(&voice_id, (*voice_reference_state).as_ref());
             voice_reference_loading_state.set(true);
             voice_reference_error_state.set(None);
             voice_reference_notice_state.set(None);
@@ -1185,10 +1862,7 @@ fn app() -> Html {
             let toast_info = toast_info.clone();
             let modal_state = modal_state.clone();
             spawn_local(async move {
-                match Request::delete(&format!("{BACKEND_URL}/api/voices/{}/reference", voice_id))
-                    .send()
-                    .await
-                {
+                match Request::delete(&url).send().await {
                     Ok(resp) => match resp.json::<VoiceReferenceDetail>().await {
                         Ok(detail) => {
                             let next_text = detail
@@ -1233,6 +1907,8 @@ fn app() -> Html {
 
     let on_submit = {
         let text_state = text_state_submit;
+        let simulate_danmaku_state = simulate_danmaku_state.clone();
+        let danmaku_user_state = danmaku_user_state.clone();
         let selected_voice_state = selected_voice_state_submit;
         let selected_engine_state = selected_engine_state_submit;
         let advanced_state = advanced_state_submit;
@@ -1240,12 +1916,27 @@ fn app() -> Html {
         let history_state = history_state_submit;
         let clip_counter = clip_counter_submit;
         let voices_state = voices_state_submit;
+        let health_state = backend_health_state.clone();
         Callback::from(move |_| {
             let text = (*text_state).trim().to_string();
             if text.is_empty() {
                 status_state.set(SynthesisStatus::Error("请输入要合成的文本".into()));
                 return;
             }
+            let text = if *simulate_danmaku_state {
+                let user = danmaku_user_state.trim();
+                let user = if user.is_empty() {
+                    DANMAKU_PREVIEW_USER
+                } else {
+                    user
+                };
+                let template = (*health_state)
+                    .as_ref()
+                    .and_then(|health| health.danmaku_spoken_template.as_deref());
+                render_danmaku_text(template, user, &text)
+            } else {
+                text
+            };
 
             let voice_id = match (*selected_voice_state).clone() {
                 Some(value) => value,
@@ -1319,6 +2010,11 @@ fn app() -> Html {
             if let Some(value) = u32_value(&options.seed) {
                 payload.insert("seed".into(), value);
             }
+            let progress_id = new_progress_id();
+            payload.insert(
+                "progress_id".into(),
+                serde_json::Value::String(progress_id.clone()),
+            );
 
             let payload_base = payload.clone();
             let history_state = history_state.clone();
@@ -1356,6 +2052,9 @@ fn app() -> Html {
                     }
                 };
 
+                // Opened before the request so no early segment is missed.
+                let progress_listener =
+                    TtsProgressListener::open(&progress_id, status_state.clone());
                 let request = Request::post(&format!("{BACKEND_URL}/api/tts"))
                     .header("Content-Type", "application/json")
                     .body(request_body);
@@ -1386,11 +2085,21 @@ fn app() -> Html {
                         created_at: now_string(),
                         sample_rate: data.sample_rate,
                         waveform_len: data.waveform_len,
+                        duration_ms: data.duration_ms,
                         format: data.format.clone(),
                         audio_src,
+                        nfe_step: data.nfe_step_used,
+                        peak: data.peak,
+                        rms: data.rms,
+                        pinned: false,
                     };
                     history_state.dispatch(HistoryAction::Push(clip));
-                    status_state.set(SynthesisStatus::Ready("生成完成 ✅".into()));
+                    let message = if data.fallback_used {
+                        format!("生成完成（原音色失败，已改用备用音色 {}）✅", data.voice_id)
+                    } else {
+                        "生成完成 ✅".to_string()
+                    };
+                    status_state.set(SynthesisStatus::Ready(message));
                 };
 
                 let response = match request {
@@ -1402,13 +2111,39 @@ fn app() -> Html {
                 };
 
                 match response {
-                    Ok(resp) => match resp.json::<TtsResponse>().await {
-                        Ok(data) => handle_success(data),
+                    Ok(resp) if resp.status() == 202 => match resp.json::<TtsJob>().await {
+                        Ok(job) => {
+                            let outcome = await_tts_job(job, &status_state).await;
+                            drop(progress_listener);
+                            match outcome {
+                                Ok(data) => handle_success(data),
+                                Err(message) => status_state
+                                    .set(SynthesisStatus::Error(format!("生成失败: {message}"))),
+                            }
+                        }
                         Err(err) => {
                             status_state.set(SynthesisStatus::Error(format!("解析响应失败: {err}")))
                         }
                     },
+                    Ok(resp) if resp.ok() => {
+                        drop(progress_listener);
+                        match resp.json::<TtsResponse>().await {
+                            Ok(data) => handle_success(data),
+                            Err(err) => status_state
+                                .set(SynthesisStatus::Error(format!("解析响应失败: {err}"))),
+                        }
+                    }
+                    Ok(resp) => {
+                        drop(progress_listener);
+                        let status = resp.status();
+                        let body = resp.text().await.unwrap_or_default();
+                        status_state.set(SynthesisStatus::Error(format!(
+                            "生成失败: {}",
+                            describe_api_error(status, &body)
+                        )))
+                    }
                     Err(err) => {
+                        drop(progress_listener);
                         status_state.set(SynthesisStatus::Error(format!("请求失败: {err}")))
                     }
                 }
@@ -1425,8 +2160,96 @@ fn app() -> Html {
         })
     };
 
+    let history_import_input = use_node_ref();
+
+    let on_export_history = {
+        let history_state = history_state.clone();
+        let toast_state = toast_state.clone();
+        Callback::from(move |_| {
+            let items: Vec<ClipHistoryItem> = history_state.entries.iter().cloned().collect();
+            let Ok(json) = serde_json::to_string(&items) else {
+                return;
+            };
+            let file_name = format!("ishowtts_history_{}.json", Date::now() as u64);
+            let exported = make_object_url("application/json", json.as_bytes())
+                .and_then(|url| {
+                    let result = trigger_download(&url, &file_name);
+                    // Give the browser a moment to start the download before
+                    // releasing the blob.
+                    spawn_local(async move {
+                        TimeoutFuture::new(1_000).await;
+                        let _ = Url::revoke_object_url(&url);
+                    });
+                    result
+                })
+                .is_some();
+            if !exported {
+                toast_state.set(Some(ToastMessage::info("导出失败")));
+            }
+        })
+    };
+
+    let on_import_history_click = {
+        let history_import_input = history_import_input.clone();
+        Callback::from(move |_| {
+            if let Some(input) = history_import_input.cast::<HtmlInputElement>() {
+                input.click();
+            }
+        })
+    };
+
+    let on_import_history = {
+        let history_state = history_state.clone();
+        let clip_counter = clip_counter.clone();
+        let current_page = current_page.clone();
+        let toast_state = toast_state.clone();
+        Callback::from(move |event: Event| {
+            let Some(input) = event.target_dyn_into::<HtmlInputElement>() else {
+                return;
+            };
+            let file = input.files().and_then(|files| files.item(0));
+            // Reset so picking the same file again still fires `change`.
+            input.set_value("");
+            let Some(file) = file else {
+                return;
+            };
+            let history_state = history_state.clone();
+            let clip_counter = clip_counter.clone();
+            let current_page = current_page.clone();
+            let toast_state = toast_state.clone();
+            spawn_local(async move {
+                let text = JsFuture::from(file.text())
+                    .await
+                    .ok()
+                    .and_then(|value| value.as_string());
+                let Some(text) = text else {
+                    toast_state.set(Some(ToastMessage::info("读取文件失败")));
+                    return;
+                };
+                match serde_json::from_str::<Vec<ClipHistoryItem>>(&text) {
+                    Ok(items) => {
+                        let count = items.len();
+                        if let Some(max_id) = items.iter().map(|clip| clip.id).max() {
+                            clip_counter.set((*clip_counter).max(max_id));
+                        }
+                        history_state.dispatch(HistoryAction::Merge(items));
+                        current_page.set(0);
+                        toast_state.set(Some(ToastMessage::success(format!(
+                            "已导入 {} 条记录",
+                            count
+                        ))));
+                    }
+                    Err(err) => {
+                        toast_state.set(Some(ToastMessage::info(format!("导入失败: {}", err))));
+                    }
+                }
+            });
+        })
+    };
+
     let on_start_danmaku = {
         let channel_state = danmaku_channel_state.clone();
+        let platform_state = danmaku_platform_state.clone();
         let status_state = danmaku_status_state.clone();
         let active_state = danmaku_active_state.clone();
         let active_channel_state = danmaku_active_channel_state.clone();
@@ -1472,7 +2295,8 @@ fn app() -> Html {
 
             active_state.set(true);
             stream_ready_state.set(false);
-            status_state.set("正在连接 Twitch 频道...".into());
+            let platform = (*platform_state).clone();
+            status_state.set(format!("正在连接 {} 频道...", platform_label(&platform)));
             active_channel_state.set(None);
             let status_state = status_state.clone();
             let active_state = active_state.clone();
@@ -1483,10 +2307,7 @@ fn app() -> Html {
 
             spawn_local(async move {
                 let mut payload = serde_json::Map::<String, serde_json::Value>::new();
-                payload.insert(
-                    "platform".into(),
-                    serde_json::Value::String("twitch".into()),
-                );
+                payload.insert("platform".into(), serde_json::Value::String(platform));
                 payload.insert("channel".into(), serde_json::Value::String(channel));
                 payload.insert(
                     "voice_id".into(),
@@ -1531,7 +2352,10 @@ fn app() -> Html {
                             }
                             status => {
                                 let body = resp.text().await.unwrap_or_default();
-                                status_state.set(format!("启动失败: {} {}", status, body));
+                                status_state.set(format!(
+                                    "启动失败: {}",
+                                    describe_api_error(status, &body)
+                                ));
                                 active_state.set(false);
                                 active_channel_state_async.set(None);
                                 stream_ready_state.set(false);
@@ -1582,6 +2406,18 @@ fn app() -> Html {
         Callback::from(move |_| detail_clip_state.set(None))
     };
 
+    let on_toggle_pin = {
+        let history_state = history_state.clone();
+        let detail_clip_state = detail_clip_state.clone();
+        Callback::from(move |clip: ClipHistoryItem| {
+            history_state.dispatch(HistoryAction::TogglePin(clip.id));
+            detail_clip_state.set(Some(ClipHistoryItem {
+                pinned: !clip.pinned,
+                ..clip
+            }));
+        })
+    };
+
     let detail_view = detail_clip
         .map(|clip| {
             let download_ext = clip
@@ -1600,6 +2436,12 @@ fn app() -> Html {
                 let clip = clip.clone();
                 Callback::from(move |_| on_copy_clip.emit(clip.clone()))
             };
+            let pin_cb = {
+                let on_toggle_pin = on_toggle_pin.clone();
+                let clip = clip.clone();
+                Callback::from(move |_| on_toggle_pin.emit(clip.clone()))
+            };
+            let pin_label = if clip.pinned { "取消收藏" } else { "收藏" };
             html! {
                 <div class="detail-overlay" onclick={on_close_detail.clone()}>
                     <div class="detail-panel" onclick={Callback::from(|event: MouseEvent| event.stop_propagation())}>
@@ -1621,6 +2463,42 @@ fn app() -> Html {
                                 <span class="label">{"采样率"}</span>
                                 <span>{format!("{} Hz", clip.sample_rate)}</span>
                             </div>
+                            {
+                                if let Some(step) = clip.nfe_step {
+                                    html! {
+                                        <div class="detail-line">
+                                            <span class="label">{"NFE 步数"}</span>
+                                            <span>{step}</span>
+                                        </div>
+                                    }
+                                } else {
+                                    Html::default()
+                                }
+                            }
+                            {
+                                if let (Some(peak), Some(rms)) = (clip.peak, clip.rms) {
+                                    html! {
+                                        <div class="detail-line">
+                                            <span class="label">{"电平"}</span>
+                                            <span>{format!("峰值 {peak:.2} · RMS {rms:.3}")}</span>
+                                        </div>
+                                    }
+                                } else {
+                                    Html::default()
+                                }
+                            }
+                            {
+                                if let Some(duration_ms) = clip.duration_ms {
+                                    html! {
+                                        <div class="detail-line">
+                                            <span class="label">{"时长"}</span>
+                                            <span>{format!("{:.1} 秒", f64::from(duration_ms) / 1000.0)}</span>
+                                        </div>
+                                    }
+                                } else {
+                                    Html::default()
+                                }
+                            }
                             <div class="detail-line">
                                 <span class="label">{"音频大小"}</span>
                                 <span>{format!("{:.1} KB", clip.waveform_len as f64 / 1024.0)}</span>
@@ -1633,6 +2511,7 @@ fn app() -> Html {
                         </div>
                         <footer class="detail-footer">
                             <button class="primary" onclick={copy_cb}>{"复制文本"}</button>
+                            <button class="ghost" onclick={pin_cb}>{pin_label}</button>
                             <a class="ghost" href={clip.audio_src.clone()} download={download_name}>{"下载音频"}</a>
                         </footer>
                     </div>
@@ -1641,13 +2520,50 @@ fn app() -> Html {
         })
         .unwrap_or(Html::default());
 
+    let on_skip_danmaku = {
+        let status_state = danmaku_status_state.clone();
+        let active_channel_state = danmaku_active_channel_state.clone();
+        let playing_state = danmaku_playing_state.clone();
+        Callback::from(move |_| {
+            let Some(channel) = (*active_channel_state).clone() else {
+                status_state.set("当前没有正在播报的频道".into());
+                return;
+            };
+            let packet_id = (*playing_state).clone();
+            let status_state = status_state.clone();
+            spawn_local(async move {
+                let mut url = format!(
+                    "{BACKEND_URL}/api/danmaku/skip/{}",
+                    js_sys::encode_uri_component(&channel)
+                );
+                if let Some(packet_id) = packet_id {
+                    url.push_str(&format!("?packet_id={packet_id}"));
+                }
+                match Request::post(&url).send().await {
+                    Ok(resp) if (200..300).contains(&resp.status()) => {}
+                    Ok(resp) => {
+                        let status_code = resp.status();
+                        let body = resp.text().await.unwrap_or_default();
+                        status_state.set(format!(
+                            "跳过失败: {}",
+                            describe_api_error(status_code, &body)
+                        ));
+                    }
+                    Err(err) => status_state.set(format!("跳过请求失败: {err}")),
+                }
+            });
+        })
+    };
+
     let on_stop_danmaku = {
         let active_state = danmaku_active_state.clone();
         let status_state = danmaku_status_state.clone();
         let log_state = danmaku_log_state.clone();
         let active_channel_state = danmaku_active_channel_state.clone();
         let audio_state = danmaku_audio_state.clone();
+        let playing_state = danmaku_playing_state.clone();
         let stream_ready_state = danmaku_stream_ready_state.clone();
+        let platform_state = danmaku_platform_state.clone();
         Callback::from(move |_| {
             if !*active_state {
                 status_state.set("当前没有正在播报的频道".into());
@@ -1660,11 +2576,13 @@ fn app() -> Html {
                 let _ = Url::revoke_object_url(&current);
             }
             audio_state.set(None);
+            playing_state.set(None);
             stream_ready_state.set(false);
 
             if let Some(channel) = current_channel.clone() {
                 status_state.set(format!("正在停止 {channel}..."));
                 let stop_channel = channel.clone();
+                let stop_platform = (*platform_state).clone();
                 let status_state_async = status_state.clone();
                 let log_state = log_state.clone();
                 let active_channel_state = active_channel_state.clone();
@@ -1672,7 +2590,7 @@ fn app() -> Html {
                 let stream_ready_state_async = stream_ready_state.clone();
                 spawn_local(async move {
                     let payload = serde_json::json!({
-                        "platform": "twitch",
+                        "platform": stop_platform,
                         "channel": stop_channel.clone(),
                     });
                     let request = Request::post(&format!("{BACKEND_URL}/api/danmaku/stop"))
@@ -1710,8 +2628,10 @@ fn app() -> Html {
                                     }
                                 } else {
                                     let body = resp.text().await.unwrap_or_default();
-                                    status_state_async
-                                        .set(format!("停止失败: {} {}", status_code, body));
+                                    status_state_async.set(format!(
+                                        "停止失败: {}",
+                                        describe_api_error(status_code, &body)
+                                    ));
                                     active_state_async.set(true);
                                     stream_ready_state_async.set(false);
                                 }
@@ -1766,6 +2686,11 @@ fn app() -> Html {
     let danmaku_logs = (*danmaku_log_state).clone();
     let danmaku_active = *danmaku_active_state;
     let danmaku_audio_src = (*danmaku_audio_state).clone();
+    let danmaku_playing = (*danmaku_playing_state).clone();
+    let on_danmaku_audio_ended = {
+        let playing_state = danmaku_playing_state.clone();
+        Callback::from(move |_: Event| playing_state.set(None))
+    };
     let danmaku_status = (*danmaku_status_state).clone();
     let danmaku_stream_ready = *danmaku_stream_ready_state;
     let selected_voice = (*selected_voice_state).clone().unwrap_or_default();
@@ -1775,9 +2700,21 @@ fn app() -> Html {
     for voice in &voices {
         if seen_labels.insert(voice.engine_label.clone()) {
             let label = voice.engine_label.clone();
+            let loading = health_info
+                .as_ref()
+                .is_some_and(|health| health.engine_loading(&voice.engine));
+            let cold = health_info
+                .as_ref()
+                .is_some_and(|health| health.engine_cold(&voice.engine));
             engine_options.push(EngineOption {
                 value: format!("tts:{label}"),
-                label: label.clone(),
+                label: if loading {
+                    format!("{label} (加载中)")
+                } else if cold {
+                    format!("{label} (未预热)")
+                } else {
+                    label.clone()
+                },
                 choice: EngineModelChoice::Tts {
                     engine_label: label,
                 },
@@ -1805,9 +2742,14 @@ fn app() -> Html {
             .iter()
             .any(|option| option.value == selected_engine_value)
     {
-        selected_engine_value = engine_options
-            .first()
-            .map(|option| option.value.clone())
+        let preferred_value = health_info
+            .as_ref()
+            .and_then(|health| health.default_engine.as_deref())
+            .and_then(|engine| voices.iter().find(|voice| voice.engine == engine))
+            .map(|voice| format!("tts:{}", voice.engine_label));
+        selected_engine_value = preferred_value
+            .filter(|value| engine_options.iter().any(|option| &option.value == value))
+            .or_else(|| engine_options.first().map(|option| option.value.clone()))
             .unwrap_or_default();
     }
     let selected_engine_option = engine_options
@@ -1827,6 +2769,26 @@ fn app() -> Html {
     };
     let voice_ready = !selected_voice.is_empty();
 
+    let engine_parameters: Option<Vec<EngineParameter>> = voices_for_engine
+        .first()
+        .and_then(|voice| {
+            engines_state
+                .iter()
+                .find(|info| info.engine == voice.engine)
+        })
+        .map(|info| info.parameters.clone());
+    let param_visible = |name: &str| {
+        engine_parameters
+            .as_ref()
+            .map_or(true, |params| params.iter().any(|param| param.name == name))
+    };
+    let nfe_placeholder = engine_parameters
+        .as_ref()
+        .and_then(|params| params.iter().find(|param| param.name == "nfe_step"))
+        .and_then(|param| param.default)
+        .map(|value| format!("默认 {value}"))
+        .unwrap_or_else(|| "默认 32".to_string());
+
     let voice_reference_detail_view = (*voice_reference_state).clone();
     let voice_reference_error_msg = (*voice_reference_error_state).clone();
     let voice_reference_notice_msg = (*voice_reference_notice_state).clone();
@@ -1854,16 +2816,16 @@ fn app() -> Html {
         } else if let Some(detail) = voice_reference_detail_view.clone() {
             let baseline_audio_link = if detail.baseline_audio_available {
                 Some(format!(
-                    "{BACKEND_URL}/api/voices/{}/reference/audio?source=baseline",
-                    detail.voice_id
+                    "{BACKEND_URL}/api/voices/{}/reference/audio?source=baseline&engine={}",
+                    detail.voice_id, detail.engine
                 ))
             } else {
                 None
             };
             let override_audio_link = if detail.override_audio_available {
                 Some(format!(
-                    "{BACKEND_URL}/api/voices/{}/reference/audio?source=override",
-                    detail.voice_id
+                    "{BACKEND_URL}/api/voices/{}/reference/audio?source=override&engine={}",
+                    detail.voice_id, detail.engine
                 ))
             } else {
                 None
@@ -1925,6 +2887,34 @@ fn app() -> Html {
                                 </span>
                                 <span class="status-chip subtle">{format!("更新时间 {updated_display}")}</span>
                             </div>
+                            {
+                                if detail.baseline_audio_available {
+                                    html! {
+                                        <ReferenceWaveformView
+                                            voice_id={detail.voice_id.clone()}
+                                            source="baseline"
+                                            label="默认音频波形"
+                                            revision={None::<String>}
+                                        />
+                                    }
+                                } else {
+                                    Html::default()
+                                }
+                            }
+                            {
+                                if detail.override_audio_available {
+                                    html! {
+                                        <ReferenceWaveformView
+                                            voice_id={detail.voice_id.clone()}
+                                            source="override"
+                                            label="自定义音频波形"
+                                            revision={detail.override_updated_at.clone()}
+                                        />
+                                    }
+                                } else {
+                                    Html::default()
+                                }
+                            }
                         </div>
                         <footer class="modal-card-footer link-footer">
                             {
@@ -1937,6 +2927,25 @@ fn app() -> Html {
                                     <a class="badge-link" href={link} target="_blank">{"下载自定义音频"}</a>
                                 }).unwrap_or(Html::default())
                             }
+                            {
+                                if detail.reference_clips.len() > 1 {
+                                    detail.reference_clips.iter().map(|clip| {
+                                        let link = format!(
+                                            "{BACKEND_URL}/api/voices/{}/reference/audio?source={}&index={}&engine={}",
+                                            detail.voice_id, clip.source, clip.index, detail.engine
+                                        );
+                                        let label = clip
+                                            .file_name
+                                            .clone()
+                                            .unwrap_or_else(|| format!("片段 {}", clip.index + 1));
+                                        html! {
+                                            <a class={classes!("badge-link", (!clip.available).then_some("muted"))} href={link} target="_blank">{label}</a>
+                                        }
+                                    }).collect::<Html>()
+                                } else {
+                                    Html::default()
+                                }
+                            }
                         </footer>
                     </section>
                     <section class="modal-card editor-card">
@@ -2058,43 +3067,61 @@ fn app() -> Html {
         html! {
             <div class="advanced-panel">
                 <div class="fields-grid">
-                    <label>
-                        {"语速 (speed)"}
-                        <input type="number" step="0.01" value={advanced_options.speed.clone()} oninput={speed_input.clone()} placeholder="默认 1.0" />
-                    </label>
-                    <label>
-                        {"目标响度 (target_rms)"}
-                        <input type="number" step="0.01" value={advanced_options.target_rms.clone()} oninput={target_rms_input.clone()} placeholder="默认 0.1" />
-                    </label>
-                    <label>
-                        {"交叉渐变 (cross_fade_duration)"}
-                        <input type="number" step="0.01" value={advanced_options.cross_fade_duration.clone()} oninput={cross_fade_input.clone()} placeholder="默认 0.15" />
-                    </label>
-                    <label>
-                        {"摇摆采样 (sway_sampling_coef)"}
-                        <input type="number" step="0.01" value={advanced_options.sway_sampling_coef.clone()} oninput={sway_input.clone()} placeholder="默认 -1" />
-                    </label>
-                    <label>
-                        {"CFG 强度"}
-                        <input type="number" step="0.1" value={advanced_options.cfg_strength.clone()} oninput={cfg_input.clone()} placeholder="默认 2.0" />
-                    </label>
-                    <label>
-                        {"NFE 步数"}
-                        <input type="number" value={advanced_options.nfe_step.clone()} oninput={nfe_input.clone()} placeholder="默认 32" />
-                    </label>
-                    <label>
-                        {"固定时长 (秒)"}
-                        <input type="number" step="0.05" value={advanced_options.fix_duration.clone()} oninput={fix_duration_input.clone()} placeholder="留空为自动" />
-                    </label>
-                    <label>
-                        {"随机种子"}
-                        <input type="number" value={advanced_options.seed.clone()} oninput={seed_input.clone()} placeholder="留空使用随机" />
-                    </label>
+                    if param_visible("speed") {
+                        <label>
+                            {"语速 (speed)"}
+                            <input type="number" step="0.01" value={advanced_options.speed.clone()} oninput={speed_input.clone()} placeholder="默认 1.0" />
+                        </label>
+                    }
+                    if param_visible("target_rms") {
+                        <label>
+                            {"目标响度 (target_rms)"}
+                            <input type="number" step="0.01" value={advanced_options.target_rms.clone()} oninput={target_rms_input.clone()} placeholder="默认 0.1" />
+                        </label>
+                    }
+                    if param_visible("cross_fade_duration") {
+                        <label>
+                            {"交叉渐变 (cross_fade_duration)"}
+                            <input type="number" step="0.01" value={advanced_options.cross_fade_duration.clone()} oninput={cross_fade_input.clone()} placeholder="默认 0.15" />
+                        </label>
+                    }
+                    if param_visible("sway_sampling_coef") {
+                        <label>
+                            {"摇摆采样 (sway_sampling_coef)"}
+                            <input type="number" step="0.01" value={advanced_options.sway_sampling_coef.clone()} oninput={sway_input.clone()} placeholder="默认 -1" />
+                        </label>
+                    }
+                    if param_visible("cfg_strength") {
+                        <label>
+                            {"CFG 强度"}
+                            <input type="number" step="0.1" value={advanced_options.cfg_strength.clone()} oninput={cfg_input.clone()} placeholder="默认 2.0" />
+                        </label>
+                    }
+                    if param_visible("nfe_step") {
+                        <label>
+                            {"NFE 步数"}
+                            <input type="number" value={advanced_options.nfe_step.clone()} oninput={nfe_input.clone()} placeholder={nfe_placeholder.clone()} />
+                        </label>
+                    }
+                    if param_visible("fix_duration") {
+                        <label>
+                            {"固定时长 (秒)"}
+                            <input type="number" step="0.05" value={advanced_options.fix_duration.clone()} oninput={fix_duration_input.clone()} placeholder="留空为自动" />
+                        </label>
+                    }
+                    if param_visible("seed") {
+                        <label>
+                            {"随机种子"}
+                            <input type="number" value={advanced_options.seed.clone()} oninput={seed_input.clone()} placeholder="留空使用随机" />
+                        </label>
+                    }
                 </div>
-                <label class="toggle">
-                    <input type="checkbox" checked={advanced_options.remove_silence} onchange={remove_silence_toggle} />
-                    <span>{"移除生成语音中的静音"}</span>
-                </label>
+                if param_visible("remove_silence") {
+                    <label class="toggle">
+                        <input type="checkbox" checked={advanced_options.remove_silence} onchange={remove_silence_toggle} />
+                        <span>{"移除生成语音中的静音"}</span>
+                    </label>
+                }
                 <button class="ghost" onclick={on_reset_advanced.clone()}>{"重置高级参数"}</button>
             </div>
         }
@@ -2132,6 +3159,9 @@ fn app() -> Html {
                     <button class="history-entry" type="button" onclick={detail_cb}>
                         <span class="history-time">{timestamp}</span>
                         <span class="history-preview">{summary}</span>
+                        if clip.pinned {
+                            <span class="history-pin" title="已收藏">{"★"}</span>
+                        }
                     </button>
                 </div>
             }
@@ -2213,15 +3243,36 @@ fn app() -> Html {
                         <header class="panel-heading">
                             <div>
                                 <h2>{"弹幕播报"}</h2>
-                                <span class="panel-sub">{"Twitch 聊天 → 实时语音"}</span>
+                                <span class="panel-sub">{"Twitch / Bilibili 聊天 → 实时语音"}</span>
                             </div>
                             <span class="panel-meta">{format!("日志 {}", danmaku_logs.len())}</span>
                         </header>
                         <div class="channel-form">
+                            <label class="field">
+                                <span>{"平台"}</span>
+                                <select
+                                    disabled={danmaku_active}
+                                    onchange={Callback::from({
+                                        let platform_state = danmaku_platform_state.clone();
+                                        move |event: Event| {
+                                            if let Some(select) = event.target_dyn_into::<HtmlSelectElement>() {
+                                                platform_state.set(select.value());
+                                            }
+                                        }
+                                    })}
+                                >
+                                    <option value="twitch" selected={*danmaku_platform_state == "twitch"}>{"Twitch"}</option>
+                                    <option value="bilibili" selected={*danmaku_platform_state == "bilibili"}>{"Bilibili"}</option>
+                                </select>
+                            </label>
                             <label class="field">
                                 <span>{"频道"}</span>
                                 <input
-                                    placeholder="例如：twitch.tv/example 或 example"
+                                    placeholder={if *danmaku_platform_state == "bilibili" {
+                                        "例如：live.bilibili.com/21452505 或 21452505"
+                                    } else {
+                                        "例如：twitch.tv/example 或 example"
+                                    }}
                                     value={(*danmaku_channel_state).clone()}
                                     oninput={Callback::from({
                                         let channel_state = danmaku_channel_state.clone();
@@ -2241,13 +3292,14 @@ fn app() -> Html {
                                 >
                                     { if danmaku_stream_ready { "正在播报" } else if danmaku_active { "连接中..." } else { "开始播报" } }
                                 </button>
+                                <button class="ghost" onclick={on_skip_danmaku} disabled={!danmaku_active}>{"跳过"}</button>
                                 <button class="ghost" onclick={on_stop_danmaku}>{"停止"}</button>
                             </div>
                         </div>
                         <div class="stream-status">{ danmaku_status }</div>
                         {
                             if let Some(src) = danmaku_audio_src {
-                                html! { <audio autoplay=true src={src} /> }
+                                html! { <audio autoplay=true src={src} onended={on_danmaku_audio_ended} /> }
                             } else {
                                 Html::default()
                             }
@@ -2261,8 +3313,14 @@ fn app() -> Html {
                                     .as_ref()
                                     .map(|value| format!("color: {}", value))
                                     .unwrap_or_default();
+                                let playing = entry.packet_id.is_some()
+                                    && entry.packet_id == danmaku_playing;
                                 html! {
-                                    <div class="log-line">
+                                    <div class={classes!(
+                                        "log-line",
+                                        playing.then_some("playing"),
+                                        entry.announcement.then_some("announcement")
+                                    )}>
                                         <span class="timestamp">{timestamp}</span>
                                         <span class="log-message" style={style}>{message}</span>
                                     </div>
@@ -2282,7 +3340,20 @@ fn app() -> Html {
                                     <span class="panel-meta">{page_label.clone()}</span>
                                     <button class="ghost compact" onclick={on_next_page.clone()} disabled={!has_next}>{"下一页"}</button>
                                 </div>
+                                <button class="ghost" onclick={on_export_history} disabled={history_len == 0}>{"导出历史"}</button>
+                                <button class="ghost" onclick={on_import_history_click}>{"导入历史"}</button>
+                                <input
+                                    type="file"
+                                    accept="application/json,.json"
+                                    hidden=true
+                                    ref={history_import_input}
+                                    onchange={on_import_history}
+                                />
                                 <button class="ghost" onclick={on_clear_history}>{"清空"}</button>
+                                <label class="toggle" title="历史只保留在当前页面，关闭页面即丢失">
+                                    <input type="checkbox" checked={*no_history_state} onchange={no_history_toggle} />
+                                    <span>{"不保存历史"}</span>
+                                </label>
                             </div>
                         </header>
                         {
@@ -2321,6 +3392,20 @@ fn app() -> Html {
                             />
                         </label>
 
+                        <div class="field-row">
+                            <label class="toggle" title="按弹幕播报的格式朗读，试听观众弹幕的实际效果">
+                                <input type="checkbox" checked={*simulate_danmaku_state} onchange={on_simulate_danmaku_toggle} />
+                                <span>{"模拟弹幕格式"}</span>
+                            </label>
+                            <input
+                                type="text"
+                                placeholder={DANMAKU_PREVIEW_USER}
+                                value={(*danmaku_user_state).clone()}
+                                oninput={on_danmaku_user_input}
+                                disabled={!*simulate_danmaku_state}
+                            />
+                        </div>
+
                         <div class="button-row">
                             <button onclick={on_submit.clone()} disabled={!voice_ready}>{"立即合成"}</button>
                             <button class={classes!("ghost", advanced_open.then_some("active"))} onclick={on_toggle_advanced.clone()}>
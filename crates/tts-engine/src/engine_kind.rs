@@ -17,6 +17,21 @@ impl EngineKind {
             EngineKind::Shimmy => "shimmy",
         }
     }
+
+    /// Human-readable name for UIs.
+    pub const fn label(&self) -> &'static str {
+        match self {
+            EngineKind::F5 => "F5-TTS",
+            EngineKind::IndexTts => "IndexTTS",
+            EngineKind::Shimmy => "Shimmy",
+        }
+    }
+
+    /// Whether voices on this engine need a transcript of their reference
+    /// clip. F5 conditions on it; IndexTTS and Shimmy ignore it.
+    pub const fn requires_reference_text(&self) -> bool {
+        matches!(self, EngineKind::F5)
+    }
 }
 
 impl fmt::Display for EngineKind {
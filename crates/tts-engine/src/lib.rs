@@ -4,7 +4,10 @@ use std::{
     hash::{Hash, Hasher},
     num::NonZeroUsize,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
 };
 
 use std::collections::hash_map::DefaultHasher;
@@ -15,28 +18,60 @@ use base64::engine::general_purpose::STANDARD as BASE64;
 use base64::Engine as _;
 use hound::{SampleFormat, WavSpec, WavWriter};
 use lru::LruCache;
+use numpy::ndarray::ArrayView2;
 use numpy::{PyArray1, PyArray2, PyArrayMethods, PyReadonlyArray1, PyReadonlyArray2};
 use once_cell::sync::Lazy;
 use parking_lot::{Mutex, RwLock};
 use pyo3::{
     prelude::PyAnyMethods,
-    types::{PyDict, PyList, PyModule, PyTuple},
+    types::{PyCFunction, PyDict, PyList, PyModule, PyTuple},
     IntoPy, Py, PyAny, PyResult, Python,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{Map as JsonMap, Value as JsonValue};
+use single_flight::SingleFlight;
+use synthesis_pool::SynthesisPool;
 use thiserror::Error;
-use tokio::task;
-use tracing::{debug, info, instrument};
+use tracing::{debug, info, instrument, warn};
 use uuid::Uuid;
 
 mod engine_kind;
+mod mock;
+mod params;
+mod single_flight;
+mod stitch;
+mod synthesis_pool;
 pub use engine_kind::EngineKind;
+pub use mock::{EngineType, MockEngine};
+pub use params::{ParameterKind, ParameterSpec};
+pub use stitch::{stitch_wav, wrap_wav, Stinger, StitchedAudio};
 
 static PYTHONPATH_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
 static PYTHONPATH_ENTRIES: Lazy<Mutex<HashSet<OsString>>> =
     Lazy::new(|| Mutex::new(HashSet::new()));
-const TARGET_SAMPLE_RATE: u32 = 24_000;
+/// Output rate unless the engine config sets `output_sample_rate`.
+const DEFAULT_OUTPUT_SAMPLE_RATE: u32 = 24_000;
+const MIN_OUTPUT_SAMPLE_RATE: u32 = 8_000;
+const MAX_OUTPUT_SAMPLE_RATE: u32 = 48_000;
+/// Python serializes each engine's model calls, so more threads than this
+/// would only sit waiting on the runtime lock.
+const MAX_SYNTHESIS_THREADS: usize = 16;
+/// NFE step used by F5 when neither the request nor the config sets one.
+const DEFAULT_NFE_STEP: u32 = 16;
+const DEFAULT_SILENCE_TRIM_THRESHOLD: f32 = 1e-3;
+// Anything louder than this is clearly speech, not breath noise or room tone.
+const MAX_SILENCE_TRIM_THRESHOLD: f32 = 0.05;
+/// Peak ceiling when normalizing output loudness.
+const NORMALIZE_PEAK_LIMIT: f32 = 0.98;
+/// Buffers quieter than this are treated as silence and never amplified.
+const NORMALIZE_SILENCE_RMS: f32 = 1e-4;
+/// Fade-in/out length applied to every clip unless the config sets `fade_ms`.
+const DEFAULT_FADE_MS: u32 = 5;
+/// Fade-out over a clip cut at `max_clip_secs`, long enough that the cut
+/// mid-word does not pop.
+const TRUNCATE_FADE_MS: u32 = 50;
+/// A 2D waveform with more channels than this is rejected as malformed.
+const MAX_WAVEFORM_CHANNELS: usize = 8;
 
 #[derive(Debug, Error)]
 pub enum TtsEngineError {
@@ -51,7 +86,10 @@ pub enum TtsEngineError {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct VoiceProfileConfig {
     pub id: String,
-    pub reference_audio: PathBuf,
+    /// One clip or a list of clips. Several clips are joined into a single
+    /// prompt, so `reference_text` should transcribe all of them in order.
+    #[serde(deserialize_with = "deserialize_reference_clips")]
+    pub reference_audio: Vec<PathBuf>,
     pub reference_text: String,
     #[serde(default)]
     pub language: Option<String>,
@@ -63,6 +101,9 @@ pub struct VoiceProfileConfig {
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct F5EngineConfig {
+    /// `mock` swaps the model for [`MockEngine`] in tests.
+    #[serde(default)]
+    pub engine_type: EngineType,
     pub model: String,
     #[serde(default)]
     pub ckpt_file: Option<PathBuf>,
@@ -80,12 +121,35 @@ pub struct F5EngineConfig {
     pub hf_cache_dir: Option<PathBuf>,
     #[serde(default)]
     pub default_nfe_step: Option<u32>,
+    #[serde(default)]
+    pub silence_trim_threshold: Option<f32>,
+    /// Linear fade at both ends of each clip, against clicks; 0 disables.
+    #[serde(default)]
+    pub fade_ms: Option<u32>,
+    #[serde(default)]
+    pub bit_depth: BitDepth,
+    /// Rate every clip is resampled to, 8000–48000 Hz.
+    #[serde(default = "default_output_sample_rate")]
+    pub output_sample_rate: u32,
+    /// Longest clip a single generation may return; unset means no limit.
+    #[serde(default)]
+    pub max_clip_secs: Option<f32>,
+    /// What happens to a clip longer than `max_clip_secs`.
+    #[serde(default)]
+    pub clip_overrun: ClipOverrun,
+    /// Dedicated threads running this engine's synthesis, kept apart from
+    /// tokio's shared blocking pool.
+    #[serde(default = "default_synthesis_threads")]
+    pub synthesis_threads: usize,
     pub python_package_path: PathBuf,
     pub voices: Vec<VoiceProfileConfig>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct IndexTtsEngineConfig {
+    /// `mock` swaps the model for [`MockEngine`] in tests.
+    #[serde(default)]
+    pub engine_type: EngineType,
     pub python_package_path: PathBuf,
     pub config_file: PathBuf,
     pub model_dir: PathBuf,
@@ -98,13 +162,92 @@ pub struct IndexTtsEngineConfig {
     #[serde(default)]
     pub use_deepspeed: Option<bool>,
     #[serde(default)]
+    pub silence_trim_threshold: Option<f32>,
+    /// Linear fade at both ends of each clip, against clicks; 0 disables.
+    #[serde(default)]
+    pub fade_ms: Option<u32>,
+    #[serde(default)]
+    pub bit_depth: BitDepth,
+    /// Rate every clip is resampled to, 8000–48000 Hz.
+    #[serde(default = "default_output_sample_rate")]
+    pub output_sample_rate: u32,
+    /// Longest clip a single generation may return; unset means no limit.
+    #[serde(default)]
+    pub max_clip_secs: Option<f32>,
+    /// What happens to a clip longer than `max_clip_secs`.
+    #[serde(default)]
+    pub clip_overrun: ClipOverrun,
+    /// Dedicated threads running this engine's synthesis, kept apart from
+    /// tokio's shared blocking pool.
+    #[serde(default = "default_synthesis_threads")]
+    pub synthesis_threads: usize,
+    /// Whether a `no_cache` request still stores its fresh clip, replacing
+    /// any cached one.
+    #[serde(default = "default_no_cache_refresh")]
+    pub no_cache_refresh: bool,
+    /// Keep multi-channel output as-is instead of downmixing it to mono.
+    #[serde(default)]
+    pub preserve_stereo: bool,
+    #[serde(default)]
     pub voices: Vec<IndexTtsVoiceConfig>,
 }
 
+/// Handling of clips that run past `max_clip_secs`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipOverrun {
+    /// Cut the clip at the limit with a short fade-out.
+    #[default]
+    Truncate,
+    /// Fail the request.
+    Reject,
+}
+
+fn default_no_cache_refresh() -> bool {
+    true
+}
+
+fn default_output_sample_rate() -> u32 {
+    DEFAULT_OUTPUT_SAMPLE_RATE
+}
+
+/// One thread can encode a finished clip while the next one renders.
+fn default_synthesis_threads() -> usize {
+    2
+}
+
+fn validate_synthesis_threads(threads: usize) -> Result<usize> {
+    anyhow::ensure!(
+        (1..=MAX_SYNTHESIS_THREADS).contains(&threads),
+        "synthesis_threads must be between 1 and {MAX_SYNTHESIS_THREADS}, got {threads}"
+    );
+    Ok(threads)
+}
+
+fn validate_output_sample_rate(rate: u32) -> Result<u32> {
+    anyhow::ensure!(
+        (MIN_OUTPUT_SAMPLE_RATE..=MAX_OUTPUT_SAMPLE_RATE).contains(&rate),
+        "output_sample_rate must be between {MIN_OUTPUT_SAMPLE_RATE} and {MAX_OUTPUT_SAMPLE_RATE} Hz, got {rate}"
+    );
+    Ok(rate)
+}
+
+fn validate_max_clip_secs(max_secs: Option<f32>) -> Result<Option<f32>> {
+    if let Some(secs) = max_secs {
+        anyhow::ensure!(
+            secs.is_finite() && secs > 0.0,
+            "max_clip_secs must be a positive number of seconds, got {secs}"
+        );
+    }
+    Ok(max_secs)
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct IndexTtsVoiceConfig {
     pub id: String,
-    pub reference_audio: PathBuf,
+    /// One clip or a list of clips, joined into a single speaker prompt.
+    #[serde(deserialize_with = "deserialize_reference_clips")]
+    pub reference_audio: Vec<PathBuf>,
     #[serde(default)]
     pub language: Option<String>,
     #[serde(default)]
@@ -121,6 +264,51 @@ pub struct IndexTtsVoiceConfig {
     pub preload: bool,
 }
 
+/// Sample width of the WAV clips an engine returns. Written as a number in
+/// the config (`bit_depth = 24`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "u16", into = "u16")]
+pub enum BitDepth {
+    #[default]
+    Pcm16,
+    /// For archival; roughly 1.5x the size of 16-bit clips.
+    Pcm24,
+}
+
+impl BitDepth {
+    pub const fn bits(self) -> u16 {
+        match self {
+            BitDepth::Pcm16 => 16,
+            BitDepth::Pcm24 => 24,
+        }
+    }
+}
+
+impl TryFrom<u16> for BitDepth {
+    type Error = String;
+
+    fn try_from(bits: u16) -> std::result::Result<Self, Self::Error> {
+        match bits {
+            16 => Ok(BitDepth::Pcm16),
+            24 => Ok(BitDepth::Pcm24),
+            other => Err(format!("unsupported bit depth {other}; use 16 or 24")),
+        }
+    }
+}
+
+impl From<BitDepth> for u16 {
+    fn from(depth: BitDepth) -> Self {
+        depth.bits()
+    }
+}
+
+/// One engine's voice declarations, as read from the config file.
+#[derive(Clone, Debug)]
+pub enum EngineVoices {
+    F5(Vec<VoiceProfileConfig>),
+    IndexTts(Vec<IndexTtsVoiceConfig>),
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct TtsRequest {
     pub text: String,
@@ -143,11 +331,55 @@ pub struct TtsRequest {
     pub remove_silence: Option<bool>,
     #[serde(default)]
     pub seed: Option<u64>,
+    /// Scale the finished audio to this RMS level, whatever the engine.
+    /// Unlike `target_rms`, which F5 applies to the reference clip.
+    #[serde(default)]
+    pub normalize_output_rms: Option<f32>,
+    /// Synthesize afresh even when the engine holds a cached clip.
+    #[serde(default)]
+    pub no_cache: bool,
+    /// IndexTTS only: lines rendered one after another into a single clip,
+    /// each with its own emotion prompt. Takes the place of `text`, which
+    /// should hold the lines joined for logging and estimates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segments: Option<Vec<TtsSegment>>,
+}
+
+/// One line of a segmented request. Unset emotion fields fall back to the
+/// voice's configured `emo_text` / `emo_alpha`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TtsSegment {
+    pub text: String,
+    #[serde(default)]
+    pub emo_text: Option<String>,
+    #[serde(default)]
+    pub emo_alpha: Option<f32>,
 }
 
+/// Where a segment of a segmented request landed in the returned clip.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SegmentTiming {
+    pub text: String,
+    pub start_ms: u32,
+    pub duration_ms: u32,
+}
+
+/// How far a long synthesis has got, in segments: the model's own sentence
+/// splits for plain IndexTTS requests, or the request's `segments`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SynthesisProgress {
+    pub completed: u32,
+    pub total: u32,
+}
+
+/// Receives [`SynthesisProgress`] updates. Called from the synthesis thread,
+/// so it must not block.
+pub type ProgressSink = Arc<dyn Fn(SynthesisProgress) + Send + Sync>;
+
 #[derive(Clone, Debug)]
 pub struct VoiceOverrideUpdate {
-    pub reference_audio: Option<PathBuf>,
+    /// Replaces the full set of active reference clips.
+    pub reference_audio: Option<Vec<PathBuf>>,
     pub reference_text: Option<String>,
 }
 
@@ -157,9 +389,35 @@ pub struct TtsResponse {
     pub sample_rate: u32,
     pub audio_base64: String,
     pub waveform_len: usize,
+    /// Clip length, `waveform_len` frames at `sample_rate`.
+    #[serde(default)]
+    pub duration_ms: u32,
     pub voice_id: String,
     pub engine: EngineKind,
     pub engine_label: String,
+    /// Reference transcript handed to the model, after any override or
+    /// baseline fallback. `None` when the engine does not take one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference_text_used: Option<String>,
+    /// Diffusion steps actually run. `None` for engines without an NFE knob.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nfe_step_used: Option<u32>,
+    /// Largest absolute sample value of the returned audio.
+    #[serde(default)]
+    pub peak: f32,
+    /// Root-mean-square level of the returned audio.
+    #[serde(default)]
+    pub rms: f32,
+    /// Set when the requested voice failed and a fallback voice produced the
+    /// audio instead; `voice_id` then names the fallback.
+    #[serde(default)]
+    pub fallback_used: bool,
+    /// Served from the engine's audio cache without running the model.
+    #[serde(default)]
+    pub cache_hit: bool,
+    /// Per-segment offsets when the request carried `segments`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment_timings: Option<Vec<SegmentTiming>>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -178,8 +436,82 @@ pub trait TtsEngine: Send + Sync {
     fn kind(&self) -> EngineKind;
     fn voice_descriptors(&self) -> Vec<VoiceDescriptor>;
     async fn synthesize(&self, request: TtsRequest) -> Result<TtsResponse>;
+    /// Like [`Self::synthesize`], reporting segment completions to `progress`
+    /// as they happen. Engines that render in one step never call it.
+    async fn synthesize_with_progress(
+        &self,
+        request: TtsRequest,
+        progress: ProgressSink,
+    ) -> Result<TtsResponse> {
+        let _ = progress;
+        self.synthesize(request).await
+    }
     fn apply_override(&self, voice_id: &str, update: VoiceOverrideUpdate) -> Result<()>;
-    fn resolve_reference(&self, voice_id: &str) -> Option<(PathBuf, Option<String>)>;
+    fn resolve_reference(&self, voice_id: &str) -> Option<(Vec<PathBuf>, Option<String>)>;
+    /// NFE step used when a request leaves `nfe_step` unset, or `None` if the
+    /// engine ignores it.
+    fn default_nfe_step(&self) -> Option<u32> {
+        None
+    }
+    /// Request fields this engine honors; anything else is ignored.
+    fn parameters(&self) -> Vec<ParameterSpec> {
+        Vec::new()
+    }
+    /// Replaces every voice with `voices` while the model stays loaded.
+    /// Overrides are lost with the old entries, so callers re-apply them.
+    /// Engines that only read voices at startup keep this default.
+    fn reload_voices(&self, voices: EngineVoices) -> Result<()> {
+        let _ = voices;
+        Err(anyhow!(
+            "engine '{}' cannot reload voices without a restart",
+            self.kind()
+        ))
+    }
+    /// Device the model was placed on and the GPU memory torch holds there,
+    /// or `None` for engines without a torch runtime. Takes the GIL.
+    fn gpu_stats(&self) -> Option<GpuStats> {
+        None
+    }
+}
+
+/// Model placement and torch's CUDA memory usage on that device.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct GpuStats {
+    /// Configured device; `None` lets the runtime pick (usually `cuda`).
+    pub device: Option<String>,
+    /// Bytes held by live tensors; `None` off CUDA or when torch can't say.
+    pub allocated_bytes: Option<u64>,
+    /// Bytes reserved by torch's caching allocator.
+    pub reserved_bytes: Option<u64>,
+}
+
+/// Reads `torch.cuda.memory_allocated`/`memory_reserved` for `device`.
+fn torch_gpu_stats(device: Option<&str>) -> GpuStats {
+    let target = device.unwrap_or("cuda");
+    let memory = if target.starts_with("cuda") {
+        Python::with_gil(|py| -> PyResult<Option<(u64, u64)>> {
+            let cuda = PyModule::import(py, "torch")?.getattr("cuda")?;
+            if !cuda.call_method0("is_available")?.extract::<bool>()? {
+                return Ok(None);
+            }
+            let allocated = cuda
+                .call_method1("memory_allocated", (target,))?
+                .extract()?;
+            let reserved = cuda.call_method1("memory_reserved", (target,))?.extract()?;
+            Ok(Some((allocated, reserved)))
+        })
+        .unwrap_or_else(|err| {
+            warn!(target = "ishowtts::tts_engine", device = target, %err, "failed to query torch GPU memory");
+            None
+        })
+    } else {
+        None
+    };
+    GpuStats {
+        device: device.map(str::to_string),
+        allocated_bytes: memory.map(|(allocated, _)| allocated),
+        reserved_bytes: memory.map(|(_, reserved)| reserved),
+    }
 }
 
 fn ensure_python_path(path: &Path) {
@@ -209,12 +541,45 @@ fn ensure_python_path(path: &Path) {
 #[derive(Clone)]
 pub struct F5Engine {
     inner: Arc<EngineInner>,
+    pool: SynthesisPool,
 }
 
 struct EngineInner {
-    runtime: Mutex<PythonRuntime>,
+    runtime: Mutex<Box<dyn F5Runtime>>,
     voices: RwLock<HashMap<String, VoiceProfileConfig>>,
     default_nfe_step: Option<u32>,
+    silence_trim_threshold: f32,
+    fade_ms: u32,
+    bit_depth: BitDepth,
+    output_sample_rate: u32,
+    max_clip_secs: Option<f32>,
+    clip_overrun: ClipOverrun,
+    device: Option<String>,
+}
+
+/// Knobs passed through to `F5TTS.infer`, with request defaults applied.
+#[derive(Clone, Debug)]
+struct F5InferParams {
+    target_rms: f32,
+    cross_fade_duration: f32,
+    sway_sampling_coef: f32,
+    cfg_strength: f32,
+    nfe_step: u32,
+    speed: f32,
+    fix_duration: Option<f32>,
+    remove_silence: bool,
+    seed: Option<u64>,
+}
+
+/// Produces raw F5 audio. [`PythonRuntime`] is the real implementation;
+/// tests substitute a deterministic one so no Python is needed.
+trait F5Runtime: Send {
+    fn run_infer(
+        &mut self,
+        voice: &VoiceProfileConfig,
+        text: &str,
+        params: &F5InferParams,
+    ) -> Result<(Vec<f32>, u32)>;
 }
 
 struct PythonRuntime {
@@ -224,13 +589,26 @@ struct PythonRuntime {
 #[derive(Clone)]
 pub struct IndexTtsEngine {
     inner: Arc<IndexEngineInner>,
+    pool: SynthesisPool,
 }
 
 struct IndexEngineInner {
     runtime: Mutex<IndexRuntime>,
     voices: RwLock<HashMap<String, IndexVoice>>,
     audio_cache: Mutex<LruCache<AudioCacheKey, AudioCacheEntry>>,
+    /// Cacheable requests currently rendering, so identical concurrent
+    /// requests wait for one inference instead of each running their own.
+    in_flight: SingleFlight<AudioCacheKey, AudioCacheEntry>,
     cache_epoch: u64,
+    silence_trim_threshold: f32,
+    fade_ms: u32,
+    bit_depth: BitDepth,
+    output_sample_rate: u32,
+    max_clip_secs: Option<f32>,
+    clip_overrun: ClipOverrun,
+    no_cache_refresh: bool,
+    preserve_stereo: bool,
+    device: Option<String>,
 }
 
 struct IndexRuntime {
@@ -240,7 +618,7 @@ struct IndexRuntime {
 #[derive(Clone)]
 struct IndexVoice {
     id: String,
-    reference_audio: PathBuf,
+    reference_audio: Vec<PathBuf>,
     language: Option<String>,
     reference_text: Option<String>,
     emo_audio: Option<PathBuf>,
@@ -255,6 +633,8 @@ struct AudioCacheEntry {
     audio_base64: Arc<String>,
     sample_rate: u32,
     waveform_len: usize,
+    peak: f32,
+    rms: f32,
 }
 
 #[derive(Clone, Hash, Eq, PartialEq)]
@@ -269,6 +649,9 @@ const AUDIO_CACHE_CAPACITY: usize = 512;
 
 impl F5Engine {
     pub fn new(config: F5EngineConfig) -> Result<Self> {
+        let output_sample_rate = validate_output_sample_rate(config.output_sample_rate)?;
+        let max_clip_secs = validate_max_clip_secs(config.max_clip_secs)?;
+        let synthesis_threads = validate_synthesis_threads(config.synthesis_threads)?;
         let python_package_path = config
             .python_package_path
             .canonicalize()
@@ -276,18 +659,7 @@ impl F5Engine {
 
         ensure_python_path(&python_package_path);
 
-        let mut voices = HashMap::new();
-        for profile in &config.voices {
-            let mut canonical = profile.clone();
-            canonical.reference_audio =
-                canonical.reference_audio.canonicalize().with_context(|| {
-                    format!(
-                        "failed to canonicalize reference audio for voice {}",
-                        profile.id
-                    )
-                })?;
-            voices.insert(canonical.id.clone(), canonical);
-        }
+        let voices = load_f5_voices(&config.voices)?;
 
         let runtime = Python::with_gil(|py| -> Result<PythonRuntime> {
             let f5_module = PyModule::import(py, "f5_tts.api")?;
@@ -301,10 +673,20 @@ impl F5Engine {
 
         Ok(Self {
             inner: Arc::new(EngineInner {
-                runtime: Mutex::new(runtime),
+                runtime: Mutex::new(Box::new(runtime)),
                 voices: RwLock::new(voices),
                 default_nfe_step: config.default_nfe_step,
+                silence_trim_threshold: resolve_silence_trim_threshold(
+                    config.silence_trim_threshold,
+                ),
+                fade_ms: config.fade_ms.unwrap_or(DEFAULT_FADE_MS),
+                bit_depth: config.bit_depth,
+                output_sample_rate,
+                max_clip_secs,
+                clip_overrun: config.clip_overrun,
+                device: config.device.clone(),
             }),
+            pool: SynthesisPool::new("f5", synthesis_threads)?,
         })
     }
 
@@ -346,16 +728,33 @@ impl F5Engine {
     #[instrument(skip(self))]
     pub async fn synthesize(&self, request: TtsRequest) -> Result<TtsResponse> {
         let inner = self.inner.clone();
-        task::spawn_blocking(move || inner.synthesize_blocking(request)).await?
+        self.pool
+            .run(move || inner.synthesize_blocking(request))
+            .await
+    }
+
+    /// The final samples [`Self::synthesize`] would encode, without the WAV
+    /// and base64 wrapping. With a fixed `seed` this is stable across runs,
+    /// which makes it suitable for golden-file comparisons.
+    pub async fn synthesize_pcm(&self, request: TtsRequest) -> Result<Vec<f32>> {
+        let inner = self.inner.clone();
+        self.pool
+            .run(move || {
+                let voice = inner.voice(&request.voice_id)?;
+                let params = inner.infer_params(&request);
+                inner
+                    .render_pcm(&voice, &request, &params)
+                    .map(|(samples, _)| samples)
+            })
+            .await
     }
 }
 
 impl IndexTtsEngine {
     pub fn new(config: IndexTtsEngineConfig) -> Result<Self> {
-        if config.voices.is_empty() {
-            anyhow::bail!("IndexTTS configuration must declare at least one voice profile");
-        }
-
+        let output_sample_rate = validate_output_sample_rate(config.output_sample_rate)?;
+        let max_clip_secs = validate_max_clip_secs(config.max_clip_secs)?;
+        let synthesis_threads = validate_synthesis_threads(config.synthesis_threads)?;
         let python_package_path = config
             .python_package_path
             .canonicalize()
@@ -371,44 +770,9 @@ impl IndexTtsEngine {
             .canonicalize()
             .context("failed to canonicalize IndexTTS model directory")?;
 
-        let mut voices = HashMap::new();
-        for voice in config.voices {
-            let reference_audio = voice.reference_audio.canonicalize().with_context(|| {
-                format!(
-                    "failed to canonicalize reference audio for IndexTTS voice {}",
-                    voice.id
-                )
-            })?;
-
-            let emo_audio = match voice.emo_audio {
-                Some(path) => Some(path.canonicalize().with_context(|| {
-                    format!(
-                        "failed to canonicalize emotion audio for IndexTTS voice {}",
-                        voice.id
-                    )
-                })?),
-                None => None,
-            };
-
-            let entry = IndexVoice {
-                id: voice.id.clone(),
-                reference_audio,
-                language: voice.language.clone(),
-                reference_text: voice.reference_text.clone(),
-                emo_audio,
-                emo_text: voice.emo_text.clone(),
-                emo_alpha: voice.emo_alpha,
-                engine_label: voice.engine_label.clone(),
-                version: 0,
-            };
-
-            if voices.insert(entry.id.clone(), entry).is_some() {
-                anyhow::bail!(
-                    "duplicate IndexTTS voice id '{}' detected in configuration",
-                    voice.id
-                );
-            }
-        }
+        let silence_trim_threshold = resolve_silence_trim_threshold(config.silence_trim_threshold);
+        let fade_ms = config.fade_ms.unwrap_or(DEFAULT_FADE_MS);
+        let voices = load_index_voices(config.voices)?;
 
         let model_dir_for_log = model_dir.clone();
         let runtime = Python::with_gil(|py| -> Result<IndexRuntime> {
@@ -447,10 +811,35 @@ impl IndexTtsEngine {
                 audio_cache: Mutex::new(LruCache::new(
                     NonZeroUsize::new(AUDIO_CACHE_CAPACITY).expect("cache capacity must be > 0"),
                 )),
+                in_flight: SingleFlight::default(),
                 cache_epoch: 0,
+                silence_trim_threshold,
+                fade_ms,
+                bit_depth: config.bit_depth,
+                output_sample_rate,
+                max_clip_secs,
+                clip_overrun: config.clip_overrun,
+                no_cache_refresh: config.no_cache_refresh,
+                preserve_stereo: config.preserve_stereo,
+                device: config.device.clone(),
             }),
+            pool: SynthesisPool::new("index_tts", synthesis_threads)?,
         })
     }
+
+    /// Like [`F5Engine::synthesize_pcm`]: interleaved samples before WAV
+    /// encoding. The audio cache is neither read nor filled.
+    pub async fn synthesize_pcm(&self, request: TtsRequest) -> Result<Vec<f32>> {
+        let inner = self.inner.clone();
+        self.pool
+            .run(move || {
+                let voice = inner.voice(&request.voice_id)?;
+                inner
+                    .render_pcm(&voice, &request, &request.text, None, false, None)
+                    .map(|(samples, _, _)| samples)
+            })
+            .await
+    }
 }
 
 #[async_trait]
@@ -486,7 +875,7 @@ impl TtsEngine for F5Engine {
             .ok_or_else(|| TtsEngineError::VoiceNotFound(voice_id.to_string()))?;
 
         if let Some(audio) = update.reference_audio {
-            let canonical = audio.canonicalize().with_context(|| {
+            let canonical = canonicalize_clips(&audio).with_context(|| {
                 format!("failed to canonicalize override audio for voice {voice_id}")
             })?;
             entry.reference_audio = canonical;
@@ -499,7 +888,7 @@ impl TtsEngine for F5Engine {
         Ok(())
     }
 
-    fn resolve_reference(&self, voice_id: &str) -> Option<(PathBuf, Option<String>)> {
+    fn resolve_reference(&self, voice_id: &str) -> Option<(Vec<PathBuf>, Option<String>)> {
         self.inner.voices.read().get(voice_id).map(|profile| {
             (
                 profile.reference_audio.clone(),
@@ -507,6 +896,27 @@ impl TtsEngine for F5Engine {
             )
         })
     }
+
+    fn default_nfe_step(&self) -> Option<u32> {
+        Some(self.inner.default_nfe_step())
+    }
+
+    fn parameters(&self) -> Vec<ParameterSpec> {
+        params::f5_parameters(self.inner.default_nfe_step())
+    }
+
+    fn reload_voices(&self, voices: EngineVoices) -> Result<()> {
+        let EngineVoices::F5(profiles) = voices else {
+            anyhow::bail!("F5 cannot load voices declared for another engine");
+        };
+        let voices = load_f5_voices(&profiles)?;
+        *self.inner.voices.write() = voices;
+        Ok(())
+    }
+
+    fn gpu_stats(&self) -> Option<GpuStats> {
+        Some(torch_gpu_stats(self.inner.device.as_deref()))
+    }
 }
 
 #[async_trait]
@@ -534,8 +944,55 @@ impl TtsEngine for IndexTtsEngine {
     }
 
     async fn synthesize(&self, request: TtsRequest) -> Result<TtsResponse> {
+        let voice = self.inner.voice(&request.voice_id)?;
+        if request.no_cache {
+            let inner = self.inner.clone();
+            return self
+                .pool
+                .run(move || inner.synthesize_blocking(request, None))
+                .await;
+        }
+        let Some(key) = self.inner.cache_key(&voice, &request) else {
+            let inner = self.inner.clone();
+            return self
+                .pool
+                .run(move || inner.synthesize_blocking(request, None))
+                .await;
+        };
+        if let Some(response) = self.inner.cached_response(&voice, &key, &request) {
+            return Ok(response);
+        }
+
         let inner = self.inner.clone();
-        task::spawn_blocking(move || inner.synthesize_blocking(request)).await?
+        let flight_voice = voice.clone();
+        let flight_key = key.clone();
+        let entry = self
+            .inner
+            .in_flight
+            .run(&self.pool, key, move || {
+                // The previous flight for this key may have filled the cache
+                // between our lookup and joining.
+                if let Some(entry) = inner.audio_cache.lock().get(&flight_key).cloned() {
+                    return Ok(entry);
+                }
+                inner.render_entry(&flight_voice, &request, Some(&flight_key), None)
+            })
+            .await
+            .map_err(|err| anyhow!("{err:#}"))?;
+        Ok(response_from_entry(&voice, &entry))
+    }
+
+    /// Skips the in-flight deduplication of [`Self::synthesize`], since a
+    /// joined flight would have nobody to report its progress to.
+    async fn synthesize_with_progress(
+        &self,
+        request: TtsRequest,
+        progress: ProgressSink,
+    ) -> Result<TtsResponse> {
+        let inner = self.inner.clone();
+        self.pool
+            .run(move || inner.synthesize_blocking(request, Some(&progress)))
+            .await
     }
 
     fn apply_override(&self, voice_id: &str, update: VoiceOverrideUpdate) -> Result<()> {
@@ -546,7 +1003,7 @@ impl TtsEngine for IndexTtsEngine {
                 .ok_or_else(|| anyhow!("IndexTTS voice '{}' not found", voice_id))?;
 
             if let Some(audio) = update.reference_audio {
-                let canonical = audio.canonicalize().with_context(|| {
+                let canonical = canonicalize_clips(&audio).with_context(|| {
                     format!("failed to canonicalize override audio for voice {voice_id}")
                 })?;
                 entry.reference_audio = canonical;
@@ -563,114 +1020,181 @@ impl TtsEngine for IndexTtsEngine {
         Ok(())
     }
 
-    fn resolve_reference(&self, voice_id: &str) -> Option<(PathBuf, Option<String>)> {
+    fn resolve_reference(&self, voice_id: &str) -> Option<(Vec<PathBuf>, Option<String>)> {
         self.inner
             .voices
             .read()
             .get(voice_id)
             .map(|voice| (voice.reference_audio.clone(), voice.reference_text.clone()))
     }
+
+    fn parameters(&self) -> Vec<ParameterSpec> {
+        params::index_tts_parameters()
+    }
+
+    fn reload_voices(&self, voices: EngineVoices) -> Result<()> {
+        let EngineVoices::IndexTts(configs) = voices else {
+            anyhow::bail!("IndexTTS cannot load voices declared for another engine");
+        };
+        let voices = load_index_voices(configs)?;
+        *self.inner.voices.write() = voices;
+        // Versions restart at 0, so cached clips could match a new voice
+        // that reuses an old id.
+        self.inner.audio_cache.lock().clear();
+        Ok(())
+    }
+
+    fn gpu_stats(&self) -> Option<GpuStats> {
+        Some(torch_gpu_stats(self.inner.device.as_deref()))
+    }
 }
 
 impl EngineInner {
-    fn synthesize_blocking(&self, request: TtsRequest) -> Result<TtsResponse> {
-        let voice = {
-            let voices = self.voices.read();
-            voices
-                .get(&request.voice_id)
-                .cloned()
-                .ok_or_else(|| TtsEngineError::VoiceNotFound(request.voice_id.clone()))?
-        };
+    fn default_nfe_step(&self) -> u32 {
+        self.default_nfe_step.unwrap_or(DEFAULT_NFE_STEP)
+    }
 
-        let target_rms = request.target_rms.unwrap_or(0.1);
-        let cross_fade_duration = request.cross_fade_duration.unwrap_or(0.15);
-        let sway = request.sway_sampling_coef.unwrap_or(-1.0);
-        let cfg_strength = request.cfg_strength.unwrap_or(2.0);
-        // Use configured default NFE step (default 16 for speed) or request override
-        let nfe_step = request
-            .nfe_step
-            .unwrap_or_else(|| self.default_nfe_step.unwrap_or(16));
-        let speed = request.speed.unwrap_or(1.0);
-        let fix_duration = request.fix_duration;
-        let remove_silence = request.remove_silence.unwrap_or(false);
-        let seed = request.seed;
+    fn voice(&self, voice_id: &str) -> Result<VoiceProfileConfig> {
+        let voices = self.voices.read();
+        let voice = voices
+            .get(voice_id)
+            .cloned()
+            .ok_or_else(|| TtsEngineError::VoiceNotFound(voice_id.to_string()))?;
+        Ok(voice)
+    }
 
-        let mut runtime = self.runtime.lock();
-        let (samples, sample_rate) = runtime.run_infer(
-            &voice,
-            &request.text,
-            target_rms,
-            cross_fade_duration,
-            sway,
-            cfg_strength,
-            nfe_step,
-            speed,
-            fix_duration,
-            remove_silence,
-            seed,
-        )?;
+    fn infer_params(&self, request: &TtsRequest) -> F5InferParams {
+        F5InferParams {
+            target_rms: request.target_rms.unwrap_or(0.1),
+            cross_fade_duration: request.cross_fade_duration.unwrap_or(0.15),
+            sway_sampling_coef: request.sway_sampling_coef.unwrap_or(-1.0),
+            cfg_strength: request.cfg_strength.unwrap_or(2.0),
+            nfe_step: request.nfe_step.unwrap_or_else(|| self.default_nfe_step()),
+            speed: request.speed.unwrap_or(1.0),
+            fix_duration: request.fix_duration,
+            remove_silence: request.remove_silence.unwrap_or(false),
+            seed: request.seed,
+        }
+    }
+
+    /// Runs inference and post-processing, returning samples at the output
+    /// sample rate.
+    fn render_pcm(
+        &self,
+        voice: &VoiceProfileConfig,
+        request: &TtsRequest,
+        params: &F5InferParams,
+    ) -> Result<(Vec<f32>, u32)> {
+        let (mut samples, mut sample_rate) =
+            self.runtime
+                .lock()
+                .run_infer(voice, &request.text, params)?;
+
+        if sample_rate != self.output_sample_rate {
+            samples = resample_linear(&samples, sample_rate, self.output_sample_rate);
+            sample_rate = self.output_sample_rate;
+        }
 
-        let mut sample_rate = sample_rate;
-        let mut samples = samples;
-        if sample_rate != TARGET_SAMPLE_RATE {
-            samples = resample_linear(&samples, sample_rate, TARGET_SAMPLE_RATE);
-            sample_rate = TARGET_SAMPLE_RATE;
+        // F5's own `remove_silence` varies between releases; trim here too so
+        // the result matches IndexTTS whatever the Python side did.
+        if params.remove_silence {
+            samples = trim_trailing_silence(&samples, self.silence_trim_threshold);
         }
 
-        let wav_bytes = encode_wav(&samples, sample_rate)?;
+        if let Some(target) = request.normalize_output_rms {
+            normalize_rms(&mut samples, target);
+        }
+
+        if limit_clip_duration(
+            &mut samples,
+            1,
+            sample_rate,
+            self.max_clip_secs,
+            self.clip_overrun,
+        )? {
+            warn!(
+                target = "ishowtts::tts_engine",
+                engine = %EngineKind::F5.as_str(),
+                voice = %voice.id,
+                max_clip_secs = self.max_clip_secs,
+                "clip exceeded max_clip_secs; truncated"
+            );
+        }
+
+        apply_fades(&mut samples, sample_rate, self.fade_ms);
+
+        Ok((samples, sample_rate))
+    }
+
+    fn synthesize_blocking(&self, request: TtsRequest) -> Result<TtsResponse> {
+        let voice = self.voice(&request.voice_id)?;
+        let params = self.infer_params(&request);
+        let nfe_step = params.nfe_step;
+        let (samples, sample_rate) = self.render_pcm(&voice, &request, &params)?;
+
+        let (peak, rms) = measure_levels(&samples);
+        let wav_bytes = encode_wav(&samples, sample_rate, 1, self.bit_depth)?;
         let encoded = BASE64.encode(&wav_bytes);
         let response = TtsResponse {
             request_id: Uuid::new_v4(),
             sample_rate,
             audio_base64: encoded,
             waveform_len: samples.len(),
+            duration_ms: clip_duration_ms(samples.len(), sample_rate),
             voice_id: voice.id.clone(),
             engine: EngineKind::F5,
             engine_label: voice
                 .engine_label
                 .clone()
                 .unwrap_or_else(|| EngineKind::F5.as_str().to_string()),
+            reference_text_used: Some(voice.reference_text.clone()),
+            nfe_step_used: Some(nfe_step),
+            peak,
+            rms,
+            fallback_used: false,
+            cache_hit: false,
+            segment_timings: None,
         };
+        debug!(
+            target = "ishowtts::tts_engine",
+            engine = %EngineKind::F5.as_str(),
+            voice = %voice.id,
+            reference_text_len = voice.reference_text.len(),
+            "f5 synthesis used reference text"
+        );
         Ok(response)
     }
 }
 
-impl PythonRuntime {
+impl F5Runtime for PythonRuntime {
     fn run_infer(
         &mut self,
         voice: &VoiceProfileConfig,
         text: &str,
-        target_rms: f32,
-        cross_fade_duration: f32,
-        sway_sampling_coef: f32,
-        cfg_strength: f32,
-        nfe_step: u32,
-        speed: f32,
-        fix_duration: Option<f32>,
-        remove_silence: bool,
-        seed: Option<u64>,
+        params: &F5InferParams,
     ) -> Result<(Vec<f32>, u32)> {
         Python::with_gil(|py| -> Result<(Vec<f32>, u32)> {
             let engine = self.engine.as_ref(py);
             let infer = engine.getattr("infer")?;
             let kwargs = PyDict::new(py);
-            kwargs.set_item("target_rms", target_rms)?;
-            kwargs.set_item("cross_fade_duration", cross_fade_duration)?;
-            kwargs.set_item("sway_sampling_coef", sway_sampling_coef)?;
-            kwargs.set_item("cfg_strength", cfg_strength)?;
-            kwargs.set_item("nfe_step", nfe_step)?;
-            kwargs.set_item("speed", speed)?;
-            if let Some(duration) = fix_duration {
+            kwargs.set_item("target_rms", params.target_rms)?;
+            kwargs.set_item("cross_fade_duration", params.cross_fade_duration)?;
+            kwargs.set_item("sway_sampling_coef", params.sway_sampling_coef)?;
+            kwargs.set_item("cfg_strength", params.cfg_strength)?;
+            kwargs.set_item("nfe_step", params.nfe_step)?;
+            kwargs.set_item("speed", params.speed)?;
+            if let Some(duration) = params.fix_duration {
                 kwargs.set_item("fix_duration", duration)?;
             }
-            kwargs.set_item("remove_silence", remove_silence)?;
-            if let Some(seed) = seed {
+            kwargs.set_item("remove_silence", params.remove_silence)?;
+            if let Some(seed) = params.seed {
                 kwargs.set_item("seed", seed)?;
             }
 
+            let reference_audio = reference_prompt_path(py, &voice.reference_audio)?;
             let result = infer.call(
                 (
-                    voice.reference_audio.as_os_str(),
+                    reference_audio.as_os_str(),
                     voice.reference_text.as_str(),
                     text,
                 ),
@@ -707,57 +1231,173 @@ impl PythonRuntime {
 }
 
 impl IndexEngineInner {
-    fn synthesize_blocking(&self, request: TtsRequest) -> Result<TtsResponse> {
-        let chars = request.text.chars().count();
-        let voice = {
-            let voices = self.voices.read();
-            voices
-                .get(&request.voice_id)
-                .cloned()
-                .ok_or_else(|| anyhow!("IndexTTS voice '{}' not found", request.voice_id))?
-        };
+    fn voice(&self, voice_id: &str) -> Result<IndexVoice> {
+        self.voices
+            .read()
+            .get(voice_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("IndexTTS voice '{}' not found", voice_id))
+    }
 
-        let normalized_text = normalize_text_for_cache(&request.text);
-        let cache_key = normalized_text
-            .as_ref()
-            .filter(|_| can_cache_request(&request))
-            .map(|text| {
-                let text_hash = hash_text(text);
-                AudioCacheKey::new(self.cache_epoch, &voice, text_hash)
-            });
+    /// Key under which `request` may be cached, or `None` when its
+    /// parameters make the output uncacheable.
+    fn cache_key(&self, voice: &IndexVoice, request: &TtsRequest) -> Option<AudioCacheKey> {
+        // IndexTTS has no NFE step (its `default_nfe_step` is `None`), so
+        // `nfe_step` never changes what it renders.
+        normalize_text_for_cache(&request.text)
+            .filter(|_| can_cache_request(request, None))
+            .map(|text| AudioCacheKey::new(self.cache_epoch, voice, hash_text(&text)))
+    }
 
-        if let Some(ref key) = cache_key {
-            let mut cache = self.audio_cache.lock();
-            if let Some(entry) = cache.get(key).cloned() {
-                drop(cache);
-                let response = TtsResponse {
-                    request_id: Uuid::new_v4(),
-                    sample_rate: entry.sample_rate,
-                    audio_base64: (*entry.audio_base64).clone(),
-                    waveform_len: entry.waveform_len,
-                    voice_id: voice.id.clone(),
-                    engine: EngineKind::IndexTts,
-                    engine_label: voice
-                        .engine_label
-                        .clone()
-                        .unwrap_or_else(|| EngineKind::IndexTts.as_str().to_string()),
-                };
-                info!(
-                    target = "ishowtts::tts_engine",
-                    engine = %EngineKind::IndexTts.as_str(),
-                    voice = %voice.id,
-                    chars,
-                    audio_cache_hit = true,
-                    "indextts audio cache hit"
-                );
+    fn cached_response(
+        &self,
+        voice: &IndexVoice,
+        key: &AudioCacheKey,
+        request: &TtsRequest,
+    ) -> Option<TtsResponse> {
+        let entry = self.audio_cache.lock().get(key).cloned()?;
+        info!(
+            target = "ishowtts::tts_engine",
+            engine = %EngineKind::IndexTts.as_str(),
+            voice = %voice.id,
+            chars = request.text.chars().count(),
+            audio_cache_hit = true,
+            "indextts audio cache hit"
+        );
+        let mut response = response_from_entry(voice, &entry);
+        response.cache_hit = true;
+        Some(response)
+    }
+
+    fn synthesize_blocking(
+        &self,
+        request: TtsRequest,
+        progress: Option<&ProgressSink>,
+    ) -> Result<TtsResponse> {
+        let voice = self.voice(&request.voice_id)?;
+        if let Some(segments) = request.segments.as_deref().filter(|s| !s.is_empty()) {
+            return self.render_segments(&voice, &request, segments, progress);
+        }
+        let mut cache_key = self.cache_key(&voice, &request);
+        if request.no_cache {
+            cache_key = cache_key.filter(|_| self.no_cache_refresh);
+        } else if let Some(ref key) = cache_key {
+            if let Some(response) = self.cached_response(&voice, key, &request) {
                 return Ok(response);
             }
         }
+        let entry = self.render_entry(&voice, &request, cache_key.as_ref(), progress)?;
+        Ok(response_from_entry(&voice, &entry))
+    }
+
+    /// Renders and encodes `request`, storing the clip under `cache_key`
+    /// when one is given.
+    fn render_entry(
+        &self,
+        voice: &IndexVoice,
+        request: &TtsRequest,
+        cache_key: Option<&AudioCacheKey>,
+        progress: Option<&ProgressSink>,
+    ) -> Result<AudioCacheEntry> {
+        let (samples, channels, sample_rate) = self.render_pcm(
+            voice,
+            request,
+            &request.text,
+            None,
+            cache_key.is_some(),
+            progress,
+        )?;
+
+        let (peak, rms) = measure_levels(&samples);
+        let wav_bytes = encode_wav(&samples, sample_rate, channels, self.bit_depth)?;
+        let entry = AudioCacheEntry {
+            audio_base64: Arc::new(BASE64.encode(&wav_bytes)),
+            sample_rate,
+            waveform_len: samples.len() / usize::from(channels),
+            peak,
+            rms,
+        };
+
+        if let Some(key) = cache_key {
+            self.audio_cache.lock().put(key.clone(), entry.clone());
+        }
+        Ok(entry)
+    }
+
+    /// Renders each segment with its own emotion prompt and joins them into
+    /// one clip. Segmented clips are never cached.
+    fn render_segments(
+        &self,
+        voice: &IndexVoice,
+        request: &TtsRequest,
+        segments: &[TtsSegment],
+        progress: Option<&ProgressSink>,
+    ) -> Result<TtsResponse> {
+        let mut samples = Vec::new();
+        let mut layout = None;
+        let mut timings = Vec::with_capacity(segments.len());
+        for (index, segment) in segments.iter().enumerate() {
+            let (pcm, channels, sample_rate) =
+                self.render_pcm(voice, request, &segment.text, Some(segment), false, None)?;
+            let first = *layout.get_or_insert((channels, sample_rate));
+            anyhow::ensure!(
+                first == (channels, sample_rate),
+                "segment audio format changed mid-request"
+            );
+            let start_frames = samples.len() / usize::from(channels);
+            timings.push(SegmentTiming {
+                text: segment.text.clone(),
+                start_ms: clip_duration_ms(start_frames, sample_rate),
+                duration_ms: clip_duration_ms(pcm.len() / usize::from(channels), sample_rate),
+            });
+            samples.extend(pcm);
+            if let Some(progress) = progress {
+                progress(SynthesisProgress {
+                    completed: index as u32 + 1,
+                    total: segments.len() as u32,
+                });
+            }
+        }
+        let (channels, sample_rate) = layout.context("segmented request has no segments")?;
 
+        let (peak, rms) = measure_levels(&samples);
+        let wav_bytes = encode_wav(&samples, sample_rate, channels, self.bit_depth)?;
+        let entry = AudioCacheEntry {
+            audio_base64: Arc::new(BASE64.encode(&wav_bytes)),
+            sample_rate,
+            waveform_len: samples.len() / usize::from(channels),
+            peak,
+            rms,
+        };
+        let mut response = response_from_entry(voice, &entry);
+        response.segment_timings = Some(timings);
+        Ok(response)
+    }
+
+    /// Runs inference on `text` and post-processing, returning interleaved
+    /// samples, the channel count and the output sample rate. `emotion`
+    /// overrides the voice's emotion prompt for this call; `progress` hears
+    /// about each of the model's sentence segments.
+    fn render_pcm(
+        &self,
+        voice: &IndexVoice,
+        request: &TtsRequest,
+        text: &str,
+        emotion: Option<&TtsSegment>,
+        audio_cache_stored: bool,
+        progress: Option<&ProgressSink>,
+    ) -> Result<(Vec<f32>, u16, u32)> {
+        let chars = text.chars().count();
         let mut runtime = self.runtime.lock();
-        let (mut samples, mut sample_rate, timings) = runtime.run_infer(&voice, &request.text)?;
+        let (mut samples, mut channels, mut sample_rate, timings) =
+            runtime.run_infer(voice, text, emotion, progress)?;
         drop(runtime);
 
+        if channels > 1 && !self.preserve_stereo {
+            samples = downmix_to_mono(&samples, channels);
+            channels = 1;
+        }
+
         if let Some(ref stats) = timings {
             let segment_count = stats
                 .get("segment_count")
@@ -776,7 +1416,6 @@ impl IndexEngineInner {
                 .and_then(JsonValue::as_f64)
                 .unwrap_or_default();
 
-            let audio_cache_stored = cache_key.is_some();
             info!(
                 target = "ishowtts::tts_engine",
                 engine = %EngineKind::IndexTts.as_str(),
@@ -793,40 +1432,39 @@ impl IndexEngineInner {
             );
         }
 
-        if sample_rate != TARGET_SAMPLE_RATE {
-            samples = resample_linear(&samples, sample_rate, TARGET_SAMPLE_RATE);
-            sample_rate = TARGET_SAMPLE_RATE;
+        if sample_rate != self.output_sample_rate {
+            samples =
+                resample_interleaved(&samples, channels, sample_rate, self.output_sample_rate);
+            sample_rate = self.output_sample_rate;
         }
 
         if request.remove_silence.unwrap_or(false) {
-            samples = trim_trailing_silence(&samples, 1e-3);
+            samples = trim_trailing_silence_frames(&samples, channels, self.silence_trim_threshold);
         }
 
-        let wav_bytes = encode_wav(&samples, sample_rate)?;
-        let encoded = BASE64.encode(&wav_bytes);
-
-        if let Some(ref key) = cache_key {
-            let entry = AudioCacheEntry {
-                audio_base64: Arc::new(encoded.clone()),
-                sample_rate,
-                waveform_len: samples.len(),
-            };
-            let mut cache = self.audio_cache.lock();
-            cache.put(key.clone(), entry);
+        if let Some(target) = request.normalize_output_rms {
+            normalize_rms(&mut samples, target);
         }
 
-        Ok(TtsResponse {
-            request_id: Uuid::new_v4(),
+        if limit_clip_duration(
+            &mut samples,
+            channels,
             sample_rate,
-            audio_base64: encoded,
-            waveform_len: samples.len(),
-            voice_id: voice.id.clone(),
-            engine: EngineKind::IndexTts,
-            engine_label: voice
-                .engine_label
-                .clone()
-                .unwrap_or_else(|| EngineKind::IndexTts.as_str().to_string()),
-        })
+            self.max_clip_secs,
+            self.clip_overrun,
+        )? {
+            warn!(
+                target = "ishowtts::tts_engine",
+                engine = %EngineKind::IndexTts.as_str(),
+                voice = %voice.id,
+                max_clip_secs = self.max_clip_secs,
+                "clip exceeded max_clip_secs; truncated"
+            );
+        }
+
+        apply_fades_frames(&mut samples, channels, sample_rate, self.fade_ms);
+
+        Ok((samples, channels, sample_rate))
     }
 
     fn invalidate_voice_cache(&self, voice_id: &str) {
@@ -861,8 +1499,16 @@ impl IndexRuntime {
         &mut self,
         voice: &IndexVoice,
         text: &str,
-    ) -> Result<(Vec<f32>, u32, Option<JsonValue>)> {
-        Python::with_gil(|py| -> Result<(Vec<f32>, u32, Option<JsonValue>)> {
+        emotion: Option<&TtsSegment>,
+        progress: Option<&ProgressSink>,
+    ) -> Result<(Vec<f32>, u16, u32, Option<JsonValue>)> {
+        let emo_alpha = emotion
+            .and_then(|segment| segment.emo_alpha)
+            .or(voice.emo_alpha);
+        let emo_text = emotion
+            .and_then(|segment| segment.emo_text.as_deref())
+            .or(voice.emo_text.as_deref());
+        Python::with_gil(|py| -> Result<(Vec<f32>, u16, u32, Option<JsonValue>)> {
             let engine = self.engine.as_ref(py);
             let infer = engine.getattr("infer")?;
 
@@ -870,18 +1516,59 @@ impl IndexRuntime {
             if let Some(ref emo_audio) = voice.emo_audio {
                 kwargs.set_item("emo_audio_prompt", emo_audio.as_os_str())?;
             }
-            if let Some(alpha) = voice.emo_alpha {
+            if let Some(alpha) = emo_alpha {
                 kwargs.set_item("emo_alpha", alpha)?;
             }
-            if let Some(ref emo_text) = voice.emo_text {
+            if let Some(emo_text) = emo_text {
                 kwargs.set_item("emo_text", emo_text)?;
                 kwargs.set_item("use_emo_text", true)?;
             }
             kwargs.set_item("verbose", false)?;
 
-            let args = (voice.reference_audio.as_os_str(), text, "");
+            let reference_audio = reference_prompt_path(py, &voice.reference_audio)?;
+            let args = (reference_audio.as_os_str(), text, "");
+
+            // IndexTTS reports "speech synthesis i/n..." through its Gradio
+            // progress hook as it starts each sentence segment.
+            let segment_total = Arc::new(AtomicU32::new(0));
+            if let Some(progress) = progress {
+                let progress = progress.clone();
+                let segment_total = segment_total.clone();
+                let hook = PyCFunction::new_closure(
+                    py,
+                    None,
+                    None,
+                    move |args: &PyTuple, kwargs: Option<&PyDict>| {
+                        let desc = kwargs
+                            .and_then(|kwargs| kwargs.get_item("desc").ok().flatten())
+                            .or_else(|| args.get_item(1).ok())
+                            .and_then(|desc| desc.extract::<String>().ok());
+                        if let Some((started, total)) =
+                            desc.as_deref().and_then(parse_segment_progress)
+                        {
+                            segment_total.store(total, Ordering::Relaxed);
+                            progress(SynthesisProgress {
+                                completed: started - 1,
+                                total,
+                            });
+                        }
+                    },
+                )?;
+                engine.setattr("gr_progress", hook)?;
+            }
 
-            let result = infer.call(args, Some(kwargs))?;
+            let result = infer.call(args, Some(kwargs));
+            if progress.is_some() {
+                engine.setattr("gr_progress", py.None())?;
+            }
+            let result = result?;
+            let total = segment_total.load(Ordering::Relaxed);
+            if let (Some(progress), true) = (progress, total > 0) {
+                progress(SynthesisProgress {
+                    completed: total,
+                    total,
+                });
+            }
             let tuple = result
                 .downcast::<PyTuple>()
                 .map_err(|err| anyhow!(err.to_string()))?;
@@ -906,22 +1593,204 @@ impl IndexRuntime {
                 None
             };
 
-            let waveform = extract_waveform(bound)?;
+            let (waveform, channels) = extract_waveform(bound)?;
+
+            Ok((waveform, channels, sr, timings))
+        })
+    }
+}
+
+const REFERENCE_CONCAT_SCRIPT: &str = r#"
+import torch
+import torchaudio
+
+
+def concat_references(paths, out_path, gap_secs=0.25):
+    target_sr = None
+    pieces = []
+    for path in paths:
+        wav, sr = torchaudio.load(path)
+        wav = wav.mean(dim=0, keepdim=True)
+        if target_sr is None:
+            target_sr = sr
+        elif sr != target_sr:
+            wav = torchaudio.functional.resample(wav, sr, target_sr)
+        if pieces:
+            pieces.append(torch.zeros(1, int(target_sr * gap_secs)))
+        pieces.append(wav)
+    torchaudio.save(out_path, torch.cat(pieces, dim=1), target_sr)
+"#;
+
+fn deserialize_reference_clips<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Vec<PathBuf>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(PathBuf),
+        Many(Vec<PathBuf>),
+    }
+
+    match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(path) => Ok(vec![path]),
+        OneOrMany::Many(paths) if paths.is_empty() => Err(serde::de::Error::custom(
+            "reference_audio must list at least one clip",
+        )),
+        OneOrMany::Many(paths) => Ok(paths),
+    }
+}
+
+/// F5 voices keyed by id, with their reference clips canonicalized.
+fn load_f5_voices(profiles: &[VoiceProfileConfig]) -> Result<HashMap<String, VoiceProfileConfig>> {
+    let mut voices = HashMap::new();
+    for profile in profiles {
+        let mut canonical = profile.clone();
+        canonical.reference_audio =
+            canonicalize_clips(&profile.reference_audio).with_context(|| {
+                format!(
+                    "failed to canonicalize reference audio for voice {}",
+                    profile.id
+                )
+            })?;
+        voices.insert(canonical.id.clone(), canonical);
+    }
+    Ok(voices)
+}
+
+/// IndexTTS voices keyed by id, with their clips canonicalized. Rejects an
+/// empty list and duplicate ids.
+fn load_index_voices(configs: Vec<IndexTtsVoiceConfig>) -> Result<HashMap<String, IndexVoice>> {
+    if configs.is_empty() {
+        anyhow::bail!("IndexTTS configuration must declare at least one voice profile");
+    }
+
+    let mut voices = HashMap::new();
+    for voice in configs {
+        let reference_audio = canonicalize_clips(&voice.reference_audio).with_context(|| {
+            format!(
+                "failed to canonicalize reference audio for IndexTTS voice {}",
+                voice.id
+            )
+        })?;
+
+        let emo_audio = match voice.emo_audio {
+            Some(path) => Some(path.canonicalize().with_context(|| {
+                format!(
+                    "failed to canonicalize emotion audio for IndexTTS voice {}",
+                    voice.id
+                )
+            })?),
+            None => None,
+        };
+
+        let entry = IndexVoice {
+            id: voice.id.clone(),
+            reference_audio,
+            language: voice.language.clone(),
+            reference_text: voice.reference_text.clone(),
+            emo_audio,
+            emo_text: voice.emo_text.clone(),
+            emo_alpha: voice.emo_alpha,
+            engine_label: voice.engine_label.clone(),
+            version: 0,
+        };
+
+        if voices.insert(entry.id.clone(), entry).is_some() {
+            anyhow::bail!(
+                "duplicate IndexTTS voice id '{}' detected in configuration",
+                voice.id
+            );
+        }
+    }
+    Ok(voices)
+}
 
-            Ok((waveform, sr, timings))
+fn canonicalize_clips(clips: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    anyhow::ensure!(!clips.is_empty(), "no reference clips given");
+    clips
+        .iter()
+        .map(|clip| {
+            clip.canonicalize()
+                .with_context(|| format!("reference clip {} is not readable", clip.display()))
         })
+        .collect()
+}
+
+/// Audio file handed to the model for `clips`: the clip itself when there is
+/// only one, otherwise a concatenation that is cached in the temp directory.
+/// The concatenation is written under a unique name and renamed into place,
+/// so concurrent first uses never read or write a half-written file.
+fn reference_prompt_path(py: Python<'_>, clips: &[PathBuf]) -> Result<PathBuf> {
+    match clips {
+        [] => Err(anyhow!("voice has no reference audio")),
+        [single] => Ok(single.clone()),
+        _ => {
+            let target = combined_reference_path(clips);
+            if !target.exists() {
+                if let Some(dir) = target.parent() {
+                    std::fs::create_dir_all(dir).with_context(|| {
+                        format!("failed to create reference cache dir {}", dir.display())
+                    })?;
+                }
+                let module = PyModule::from_code(
+                    py,
+                    REFERENCE_CONCAT_SCRIPT,
+                    "ishowtts_reference_concat.py",
+                    "ishowtts_reference_concat",
+                )?;
+                let paths: Vec<&std::ffi::OsStr> =
+                    clips.iter().map(|clip| clip.as_os_str()).collect();
+                // Keeps the `.wav` extension torchaudio picks the format from.
+                let partial = target.with_extension(format!("{}.partial.wav", Uuid::new_v4()));
+                let written = module
+                    .getattr("concat_references")
+                    .and_then(|concat| concat.call1((paths, partial.as_os_str())));
+                if let Err(err) = written {
+                    let _ = std::fs::remove_file(&partial);
+                    return Err(err.into());
+                }
+                std::fs::rename(&partial, &target).with_context(|| {
+                    format!("failed to move combined reference to {}", target.display())
+                })?;
+                debug!(
+                    target = "ishowtts::tts_engine",
+                    clips = clips.len(),
+                    path = %target.display(),
+                    "combined reference clips"
+                );
+            }
+            Ok(target)
+        }
+    }
+}
+
+/// Cache location for a clip combination. File size and mtime are part of the
+/// key so re-uploading a clip under the same name produces a fresh prompt.
+fn combined_reference_path(clips: &[PathBuf]) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    for clip in clips {
+        clip.hash(&mut hasher);
+        if let Ok(metadata) = std::fs::metadata(clip) {
+            metadata.len().hash(&mut hasher);
+            metadata.modified().ok().hash(&mut hasher);
+        }
     }
+    std::env::temp_dir()
+        .join("ishowtts-references")
+        .join(format!("{:016x}.wav", hasher.finish()))
 }
 
-fn extract_waveform(bound: &PyAny) -> Result<Vec<f32>> {
+/// Returns frame-interleaved samples and the channel count.
+fn extract_waveform(bound: &PyAny) -> Result<(Vec<f32>, u16)> {
     if let Ok(array) = bound.downcast::<PyArray2<i16>>() {
         let readonly: PyReadonlyArray2<i16> = array.readonly();
-        let view = readonly.as_array();
-        let mut waveform = Vec::with_capacity(view.len());
-        for &sample in view.iter() {
-            waveform.push(sample as f32 / i16::MAX as f32);
-        }
-        return Ok(waveform);
+        let scaled = readonly
+            .as_array()
+            .mapv(|sample| sample as f32 / i16::MAX as f32);
+        return interleave_channels(scaled.view());
     }
 
     if let Ok(array) = bound.downcast::<PyArray1<i16>>() {
@@ -931,22 +1800,17 @@ fn extract_waveform(bound: &PyAny) -> Result<Vec<f32>> {
         for &sample in slice {
             waveform.push(sample as f32 / i16::MAX as f32);
         }
-        return Ok(waveform);
+        return Ok((waveform, 1));
     }
 
     if let Ok(array) = bound.downcast::<PyArray1<f32>>() {
         let readonly: PyReadonlyArray1<f32> = array.readonly();
-        return Ok(readonly.as_slice()?.to_vec());
+        return Ok((readonly.as_slice()?.to_vec(), 1));
     }
 
     if let Ok(array) = bound.downcast::<PyArray2<f32>>() {
         let readonly: PyReadonlyArray2<f32> = array.readonly();
-        let view = readonly.as_array();
-        let mut waveform = Vec::with_capacity(view.len());
-        for &sample in view.iter() {
-            waveform.push(sample);
-        }
-        return Ok(waveform);
+        return interleave_channels(readonly.as_array());
     }
 
     if let Ok(array) = bound.downcast::<PyArray1<f64>>() {
@@ -956,7 +1820,7 @@ fn extract_waveform(bound: &PyAny) -> Result<Vec<f32>> {
             .iter()
             .map(|&sample| sample as f32)
             .collect();
-        return Ok(waveform);
+        return Ok((waveform, 1));
     }
 
     Err(anyhow!(
@@ -964,6 +1828,143 @@ fn extract_waveform(bound: &PyAny) -> Result<Vec<f32>> {
     ))
 }
 
+/// Flattens a 2D waveform into frame-interleaved samples. Runtimes return
+/// either `[frames, channels]` or `[channels, frames]`; the shorter axis is
+/// taken to be the channels.
+fn interleave_channels(view: ArrayView2<'_, f32>) -> Result<(Vec<f32>, u16)> {
+    let (rows, cols) = view.dim();
+    let view = if cols <= rows {
+        view
+    } else {
+        view.reversed_axes()
+    };
+    let channels = view.ncols();
+    if channels == 0 {
+        return Ok((Vec::new(), 1));
+    }
+    if channels > MAX_WAVEFORM_CHANNELS {
+        return Err(anyhow!(
+            "waveform of shape [{rows}, {cols}] does not look like audio"
+        ));
+    }
+    // Logical (row-major) iteration over [frames, channels] yields
+    // interleaved frames regardless of the source memory layout.
+    Ok((view.iter().copied().collect(), channels as u16))
+}
+
+/// Averages each interleaved frame down to a single sample.
+fn downmix_to_mono(samples: &[f32], channels: u16) -> Vec<f32> {
+    let channels = usize::from(channels.max(1));
+    if channels == 1 {
+        return samples.to_vec();
+    }
+    samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect()
+}
+
+/// [`resample_linear`] applied to each channel of interleaved audio.
+fn resample_interleaved(samples: &[f32], channels: u16, src_rate: u32, dst_rate: u32) -> Vec<f32> {
+    let channels = usize::from(channels.max(1));
+    if channels == 1 {
+        return resample_linear(samples, src_rate, dst_rate);
+    }
+    let resampled: Vec<Vec<f32>> = (0..channels)
+        .map(|channel| {
+            let plane: Vec<f32> = samples
+                .iter()
+                .skip(channel)
+                .step_by(channels)
+                .copied()
+                .collect();
+            resample_linear(&plane, src_rate, dst_rate)
+        })
+        .collect();
+    let frames = resampled.iter().map(Vec::len).min().unwrap_or(0);
+    let mut output = Vec::with_capacity(frames * channels);
+    for frame in 0..frames {
+        output.extend(resampled.iter().map(|plane| plane[frame]));
+    }
+    output
+}
+
+/// Ramps the first and last `fade_ms` of a mono clip linearly from and to
+/// silence, so clips played back to back do not click. Clips shorter than two
+/// fades get a fade over each half instead.
+fn apply_fades(samples: &mut [f32], sample_rate: u32, fade_ms: u32) {
+    apply_fades_frames(samples, 1, sample_rate, fade_ms);
+}
+
+/// [`apply_fades`] for interleaved audio, scaling whole frames.
+fn apply_fades_frames(samples: &mut [f32], channels: u16, sample_rate: u32, fade_ms: u32) {
+    let channels = usize::from(channels.max(1));
+    let frames = samples.len() / channels;
+    let fade_frames = (u64::from(sample_rate) * u64::from(fade_ms) / 1000) as usize;
+    let fade_frames = fade_frames.min(frames / 2);
+    if fade_frames == 0 {
+        return;
+    }
+    for i in 0..fade_frames {
+        let gain = i as f32 / fade_frames as f32;
+        let head = i * channels;
+        let tail = (frames - 1 - i) * channels;
+        for channel in 0..channels {
+            samples[head + channel] *= gain;
+            samples[tail + channel] *= gain;
+        }
+    }
+}
+
+/// Enforces `max_secs` on interleaved audio: a longer clip is cut with a
+/// [`TRUNCATE_FADE_MS`] fade-out, or refused, per `overrun`. Returns whether
+/// the clip was cut.
+fn limit_clip_duration(
+    samples: &mut Vec<f32>,
+    channels: u16,
+    sample_rate: u32,
+    max_secs: Option<f32>,
+    overrun: ClipOverrun,
+) -> Result<bool> {
+    let Some(max_secs) = max_secs else {
+        return Ok(false);
+    };
+    let channels = usize::from(channels.max(1));
+    let frames = samples.len() / channels;
+    let max_frames = (f64::from(max_secs) * f64::from(sample_rate)) as usize;
+    if frames <= max_frames {
+        return Ok(false);
+    }
+    if overrun == ClipOverrun::Reject {
+        anyhow::bail!(
+            "synthesized clip is {:.1} s, over max_clip_secs ({max_secs} s)",
+            frames as f64 / f64::from(sample_rate)
+        );
+    }
+    samples.truncate(max_frames * channels);
+    let fade_frames =
+        ((u64::from(sample_rate) * u64::from(TRUNCATE_FADE_MS) / 1000) as usize).min(max_frames);
+    for i in 0..fade_frames {
+        let gain = i as f32 / fade_frames as f32;
+        let frame = max_frames - 1 - i;
+        for sample in &mut samples[frame * channels..(frame + 1) * channels] {
+            *sample *= gain;
+        }
+    }
+    Ok(true)
+}
+
+/// [`trim_trailing_silence`] that never cuts an interleaved frame in half.
+fn trim_trailing_silence_frames(samples: &[f32], channels: u16, threshold: f32) -> Vec<f32> {
+    let channels = usize::from(channels.max(1));
+    let trimmed = trim_trailing_silence(samples, threshold);
+    if channels == 1 {
+        return trimmed;
+    }
+    let end = (trimmed.len().div_ceil(channels) * channels).min(samples.len());
+    samples[..end].to_vec()
+}
+
 fn py_any_to_json(value: &PyAny) -> Result<JsonValue> {
     if value.is_none() {
         return Ok(JsonValue::Null);
@@ -1008,6 +2009,31 @@ fn py_any_to_json(value: &PyAny) -> Result<JsonValue> {
     Ok(JsonValue::String(text))
 }
 
+fn response_from_entry(voice: &IndexVoice, entry: &AudioCacheEntry) -> TtsResponse {
+    TtsResponse {
+        request_id: Uuid::new_v4(),
+        sample_rate: entry.sample_rate,
+        audio_base64: (*entry.audio_base64).clone(),
+        waveform_len: entry.waveform_len,
+        duration_ms: clip_duration_ms(entry.waveform_len, entry.sample_rate),
+        voice_id: voice.id.clone(),
+        engine: EngineKind::IndexTts,
+        engine_label: voice
+            .engine_label
+            .clone()
+            .unwrap_or_else(|| EngineKind::IndexTts.as_str().to_string()),
+        // IndexTTS clones from the reference audio alone; the transcript is
+        // never passed to `infer`.
+        reference_text_used: None,
+        nfe_step_used: None,
+        peak: entry.peak,
+        rms: entry.rms,
+        fallback_used: false,
+        cache_hit: false,
+        segment_timings: None,
+    }
+}
+
 impl AudioCacheKey {
     fn new(epoch: u64, voice: &IndexVoice, text_hash: u64) -> Self {
         Self {
@@ -1019,26 +2045,81 @@ impl AudioCacheKey {
     }
 }
 
-fn encode_wav(samples: &[f32], sample_rate: u32) -> Result<Vec<u8>> {
+/// Length in milliseconds of `frames` frames at `sample_rate`.
+pub fn clip_duration_ms(frames: usize, sample_rate: u32) -> u32 {
+    if sample_rate == 0 {
+        return 0;
+    }
+    let ms = frames as u64 * 1000 / u64::from(sample_rate);
+    u32::try_from(ms).unwrap_or(u32::MAX)
+}
+
+/// Returns `(peak, rms)` of `samples`; both are zero for an empty buffer.
+fn measure_levels(samples: &[f32]) -> (f32, f32) {
+    if samples.is_empty() {
+        return (0.0, 0.0);
+    }
+    let (peak, sum_sq) = samples.iter().fold((0.0_f32, 0.0_f64), |(peak, sum), &s| {
+        (peak.max(s.abs()), sum + f64::from(s) * f64::from(s))
+    });
+    (peak, (sum_sq / samples.len() as f64).sqrt() as f32)
+}
+
+/// Scales `samples` to `target_rms`, lowering the gain when needed so the peak
+/// stays at or below [`NORMALIZE_PEAK_LIMIT`]. Silent buffers are left as-is.
+fn normalize_rms(samples: &mut [f32], target_rms: f32) {
+    if !target_rms.is_finite() || target_rms <= 0.0 {
+        return;
+    }
+    let (peak, rms) = measure_levels(samples);
+    if rms <= NORMALIZE_SILENCE_RMS {
+        return;
+    }
+    let gain = (target_rms / rms).min(NORMALIZE_PEAK_LIMIT / peak);
+    for sample in samples.iter_mut() {
+        *sample *= gain;
+    }
+}
+
+/// Encodes interleaved samples as integer PCM of `bit_depth` with
+/// `channels` channels.
+fn encode_wav(
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    bit_depth: BitDepth,
+) -> Result<Vec<u8>> {
     let spec = WavSpec {
-        channels: 1,
+        channels: channels.max(1),
         sample_rate,
-        bits_per_sample: 16,
+        bits_per_sample: bit_depth.bits(),
         sample_format: SampleFormat::Int,
     };
 
-    // Pre-allocate buffer: WAV header (44 bytes) + samples (2 bytes each)
-    let mut buffer = Vec::with_capacity(44 + samples.len() * 2);
+    // Pre-allocate buffer: WAV header (44 bytes, or 68 when hound writes
+    // WAVE_FORMAT_EXTENSIBLE for 24-bit or multichannel audio) + samples
+    let bytes_per_sample = usize::from(bit_depth.bits() / 8);
+    let mut buffer = Vec::with_capacity(68 + samples.len() * bytes_per_sample);
 
     {
         let mut cursor = std::io::Cursor::new(&mut buffer);
         let mut writer = WavWriter::new(&mut cursor, spec)?;
 
-        // Optimized: batch convert and write samples
-        for &sample in samples {
-            let clamped = sample.clamp(-1.0, 1.0);
-            let value = (clamped * i16::MAX as f32) as i16;
-            writer.write_sample(value)?;
+        match bit_depth {
+            BitDepth::Pcm16 => {
+                for &sample in samples {
+                    let clamped = sample.clamp(-1.0, 1.0);
+                    writer.write_sample((clamped * i16::MAX as f32) as i16)?;
+                }
+            }
+            BitDepth::Pcm24 => {
+                // hound packs 24-bit samples from an i32 into three bytes.
+                const I24_MAX: f32 = 8_388_607.0;
+                for &sample in samples {
+                    let clamped = sample.clamp(-1.0, 1.0);
+                    writer.write_sample((clamped * I24_MAX) as i32)?;
+                }
+            }
         }
         writer.finalize()?;
     }
@@ -1076,6 +2157,31 @@ fn resample_linear(input: &[f32], src_rate: u32, dst_rate: u32) -> Vec<f32> {
     output
 }
 
+fn resolve_silence_trim_threshold(configured: Option<f32>) -> f32 {
+    let Some(value) = configured else {
+        return DEFAULT_SILENCE_TRIM_THRESHOLD;
+    };
+    if !value.is_finite() || value <= 0.0 {
+        warn!(
+            target = "ishowtts::tts_engine",
+            configured = value,
+            fallback = DEFAULT_SILENCE_TRIM_THRESHOLD,
+            "invalid silence_trim_threshold; using default"
+        );
+        return DEFAULT_SILENCE_TRIM_THRESHOLD;
+    }
+    if value > MAX_SILENCE_TRIM_THRESHOLD {
+        warn!(
+            target = "ishowtts::tts_engine",
+            configured = value,
+            clamped = MAX_SILENCE_TRIM_THRESHOLD,
+            "silence_trim_threshold too high; clamping"
+        );
+        return MAX_SILENCE_TRIM_THRESHOLD;
+    }
+    value
+}
+
 fn trim_trailing_silence(samples: &[f32], threshold: f32) -> Vec<f32> {
     if samples.is_empty() {
         return Vec::new();
@@ -1088,12 +2194,29 @@ fn trim_trailing_silence(samples: &[f32], threshold: f32) -> Vec<f32> {
     }
 
     if end == 0 {
+        // The whole clip sits under the threshold. If it still carries audible
+        // signal the threshold is the problem, not the clip, so keep it intact.
+        let peak = samples.iter().fold(0.0_f32, |acc, s| acc.max(s.abs()));
+        if peak > DEFAULT_SILENCE_TRIM_THRESHOLD {
+            return samples.to_vec();
+        }
         return vec![0.0];
     }
 
     samples[..end].to_vec()
 }
 
+/// Parses the `i/n` out of an IndexTTS progress description such as
+/// `"speech synthesis 3/8..."`, where `i` is the 1-based segment starting.
+fn parse_segment_progress(desc: &str) -> Option<(u32, u32)> {
+    let rest = desc.strip_prefix("speech synthesis")?.trim_start();
+    let (started, rest) = rest.split_once('/')?;
+    let total: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    let started: u32 = started.trim().parse().ok()?;
+    let total: u32 = total.parse().ok()?;
+    (started >= 1 && started <= total).then_some((started, total))
+}
+
 fn normalize_text_for_cache(text: &str) -> Option<String> {
     let normalized = text.trim();
     if normalized.is_empty() {
@@ -1115,19 +2238,23 @@ fn float_matches(option: Option<f32>, default: f32) -> bool {
         .unwrap_or(true)
 }
 
-fn can_cache_request(request: &TtsRequest) -> bool {
+/// `default_nfe_step` is the engine's configured default, `None` when the
+/// engine ignores `nfe_step`.
+fn can_cache_request(request: &TtsRequest, default_nfe_step: Option<u32>) -> bool {
     float_matches(request.speed, 1.0)
         && float_matches(request.target_rms, 0.1)
         && float_matches(request.cross_fade_duration, 0.15)
         && float_matches(request.sway_sampling_coef, -1.0)
         && float_matches(request.cfg_strength, 2.0)
-        && match request.nfe_step {
-            None => true,
-            Some(step) => step == 16,
+        && match (request.nfe_step, default_nfe_step) {
+            (Some(step), Some(default)) => step == default,
+            _ => true,
         }
         && request.fix_duration.is_none()
         && !request.remove_silence.unwrap_or(false)
         && request.seed.is_none()
+        && request.normalize_output_rms.is_none()
+        && request.segments.is_none()
 }
 
 #[cfg(test)]
@@ -1138,10 +2265,401 @@ mod tests {
     fn test_encode_wav() {
         let sample_rate = 16000;
         let samples = vec![0.0_f32, 0.5, -0.5, 1.0, -1.0];
-        let encoded = encode_wav(&samples, sample_rate).unwrap();
+        let encoded = encode_wav(&samples, sample_rate, 1, BitDepth::Pcm16).unwrap();
         assert!(!encoded.is_empty());
         // RIFF header check
         assert_eq!(&encoded[0..4], b"RIFF");
         assert_eq!(&encoded[8..12], b"WAVE");
     }
+
+    #[test]
+    fn test_encode_wav_stereo_header() {
+        let samples = vec![0.0_f32, 0.5, -0.5, 1.0];
+        let encoded = encode_wav(&samples, 16000, 2, BitDepth::Pcm16).unwrap();
+        assert_eq!(u16::from_le_bytes([encoded[22], encoded[23]]), 2);
+        // Two frames of two 16-bit samples after the 44-byte header.
+        assert_eq!(encoded.len(), 44 + samples.len() * 2);
+    }
+
+    #[test]
+    fn test_encode_wav_24_bit() {
+        let samples = vec![0.0_f32, 0.5, -0.5, 1.0, -1.0, 2.0];
+        let pcm16 = encode_wav(&samples, 16000, 1, BitDepth::Pcm16).unwrap();
+        let pcm24 = encode_wav(&samples, 16000, 1, BitDepth::Pcm24).unwrap();
+        assert!(pcm24.len() > pcm16.len());
+
+        let reader = hound::WavReader::new(std::io::Cursor::new(pcm24)).unwrap();
+        assert_eq!(reader.spec().bits_per_sample, 24);
+        assert_eq!(reader.len() as usize, samples.len());
+        let decoded: Vec<i32> = reader
+            .into_samples::<i32>()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        // Out-of-range input clamps to full scale.
+        assert_eq!(decoded[3], 8_388_607);
+        assert_eq!(decoded[5], 8_388_607);
+        assert_eq!(decoded[4], -8_388_607);
+    }
+
+    #[test]
+    fn test_interleave_channels_frames_first() {
+        let waveform = numpy::ndarray::arr2(&[[0.1_f32, -0.1], [0.2, -0.2], [0.3, -0.3]]);
+        let (samples, channels) = interleave_channels(waveform.view()).unwrap();
+        assert_eq!(channels, 2);
+        assert_eq!(samples, vec![0.1, -0.1, 0.2, -0.2, 0.3, -0.3]);
+    }
+
+    #[test]
+    fn test_interleave_channels_channels_first() {
+        let waveform = numpy::ndarray::arr2(&[[0.1_f32, 0.2, 0.3], [-0.1, -0.2, -0.3]]);
+        let (samples, channels) = interleave_channels(waveform.view()).unwrap();
+        assert_eq!(channels, 2);
+        assert_eq!(samples, vec![0.1, -0.1, 0.2, -0.2, 0.3, -0.3]);
+
+        let mono = numpy::ndarray::arr2(&[[0.1_f32, 0.2, 0.3]]);
+        let (samples, channels) = interleave_channels(mono.view()).unwrap();
+        assert_eq!(channels, 1);
+        assert_eq!(samples, vec![0.1, 0.2, 0.3]);
+    }
+
+    #[test]
+    fn test_downmix_to_mono() {
+        let samples = vec![0.25_f32, 0.75, -1.0, 1.0, 0.5, 0.5];
+        assert_eq!(downmix_to_mono(&samples, 2), vec![0.5, 0.0, 0.5]);
+        assert_eq!(downmix_to_mono(&samples, 1), samples);
+    }
+
+    #[test]
+    fn test_measure_levels() {
+        assert_eq!(measure_levels(&[]), (0.0, 0.0));
+        let (peak, rms) = measure_levels(&[0.5, -0.5, 0.5, -1.0]);
+        assert_eq!(peak, 1.0);
+        assert!((rms - (1.75_f32 / 4.0).sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_normalize_rms() {
+        let mut quiet = vec![0.01_f32, -0.01, 0.01, -0.01];
+        normalize_rms(&mut quiet, 0.1);
+        let (_, rms) = measure_levels(&quiet);
+        assert!((rms - 0.1).abs() < 1e-5);
+
+        // A spiky buffer is limited by its peak rather than reaching the target.
+        let mut spiky = vec![0.5_f32, 0.0, 0.0, 0.0];
+        normalize_rms(&mut spiky, 0.5);
+        assert!((spiky[0] - NORMALIZE_PEAK_LIMIT).abs() < 1e-6);
+
+        let mut silent = vec![0.0_f32; 8];
+        normalize_rms(&mut silent, 0.1);
+        assert!(silent.iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn test_trim_trailing_silence() {
+        let samples = vec![0.2_f32, -0.3, 0.1, 0.0005, 0.0, 0.0];
+        assert_eq!(trim_trailing_silence(&samples, 1e-3), vec![0.2, -0.3, 0.1]);
+
+        // A threshold above the speech level must not wipe out the clip.
+        assert_eq!(trim_trailing_silence(&samples, 0.5), samples);
+
+        assert_eq!(trim_trailing_silence(&[0.0, 0.0001], 1e-3), vec![0.0]);
+    }
+
+    #[test]
+    fn test_apply_fades() {
+        let mut samples = vec![0.5_f32; 24_000];
+        apply_fades(&mut samples, 24_000, 5);
+        assert!(samples[0].abs() < 1e-6);
+        assert!(samples[samples.len() - 1].abs() < 1e-6);
+        // 5 ms at 24 kHz is 120 samples; past that the clip is untouched.
+        assert!(samples[60] > 0.2 && samples[60] < 0.3);
+        assert_eq!(samples[120], 0.5);
+        assert_eq!(samples[samples.len() - 121], 0.5);
+
+        let mut stereo = vec![0.5_f32; 2 * 1_000];
+        apply_fades_frames(&mut stereo, 2, 24_000, 5);
+        assert_eq!(&stereo[..2], &[0.0, 0.0]);
+        assert_eq!(&stereo[stereo.len() - 2..], &[0.0, 0.0]);
+
+        let mut untouched = vec![0.5_f32; 10];
+        apply_fades(&mut untouched, 24_000, 0);
+        assert_eq!(untouched, vec![0.5; 10]);
+    }
+
+    #[test]
+    fn test_limit_clip_duration() {
+        // Ten seconds of stereo at 1 kHz against a four-second limit.
+        let long = vec![0.5_f32; 2 * 10_000];
+        let mut samples = long.clone();
+        assert!(
+            limit_clip_duration(&mut samples, 2, 1_000, Some(4.0), ClipOverrun::Truncate).unwrap()
+        );
+        assert_eq!(samples.len(), 2 * 4_000);
+        assert_eq!(&samples[samples.len() - 2..], &[0.0, 0.0]);
+        // The 50 ms fade-out covers the last 50 frames only.
+        assert_eq!(samples[2 * (4_000 - 51)], 0.5);
+        assert!(samples[2 * (4_000 - 25)] < 0.5);
+
+        let mut rejected = long.clone();
+        assert!(
+            limit_clip_duration(&mut rejected, 2, 1_000, Some(4.0), ClipOverrun::Reject).is_err()
+        );
+
+        let mut unlimited = long.clone();
+        assert!(!limit_clip_duration(&mut unlimited, 2, 1_000, None, ClipOverrun::Reject).unwrap());
+        assert!(
+            !limit_clip_duration(&mut unlimited, 2, 1_000, Some(10.0), ClipOverrun::Reject)
+                .unwrap()
+        );
+        assert_eq!(unlimited, long);
+    }
+
+    #[test]
+    fn test_resolve_silence_trim_threshold() {
+        assert_eq!(
+            resolve_silence_trim_threshold(None),
+            DEFAULT_SILENCE_TRIM_THRESHOLD
+        );
+        assert_eq!(resolve_silence_trim_threshold(Some(0.01)), 0.01);
+        assert_eq!(
+            resolve_silence_trim_threshold(Some(0.8)),
+            MAX_SILENCE_TRIM_THRESHOLD
+        );
+        assert_eq!(
+            resolve_silence_trim_threshold(Some(-1.0)),
+            DEFAULT_SILENCE_TRIM_THRESHOLD
+        );
+    }
+    #[test]
+    fn test_reference_audio_accepts_one_or_many() {
+        let single: IndexTtsVoiceConfig = serde_json::from_value(serde_json::json!({
+            "id": "v",
+            "reference_audio": "a.wav"
+        }))
+        .unwrap();
+        assert_eq!(single.reference_audio, vec![PathBuf::from("a.wav")]);
+
+        let many: IndexTtsVoiceConfig = serde_json::from_value(serde_json::json!({
+            "id": "v",
+            "reference_audio": ["a.wav", "b.wav"]
+        }))
+        .unwrap();
+        assert_eq!(many.reference_audio.len(), 2);
+
+        let empty = serde_json::from_value::<IndexTtsVoiceConfig>(serde_json::json!({
+            "id": "v",
+            "reference_audio": []
+        }));
+        assert!(empty.is_err());
+    }
+
+    #[test]
+    fn test_combined_reference_path_depends_on_order() {
+        let forward = combined_reference_path(&[PathBuf::from("a.wav"), PathBuf::from("b.wav")]);
+        let reverse = combined_reference_path(&[PathBuf::from("b.wav"), PathBuf::from("a.wav")]);
+        assert_ne!(forward, reverse);
+        assert_eq!(
+            forward,
+            combined_reference_path(&[PathBuf::from("a.wav"), PathBuf::from("b.wav")])
+        );
+    }
+
+    #[test]
+    fn test_can_cache_request_nfe_step() {
+        let mut request: TtsRequest =
+            serde_json::from_value(serde_json::json!({ "text": "hi", "voice_id": "v" })).unwrap();
+        assert!(can_cache_request(&request, Some(7)));
+        request.nfe_step = Some(7);
+        assert!(can_cache_request(&request, Some(7)));
+        request.nfe_step = Some(16);
+        assert!(!can_cache_request(&request, Some(7)));
+        assert!(can_cache_request(&request, None));
+    }
+
+    #[test]
+    fn test_parse_segment_progress() {
+        assert_eq!(
+            parse_segment_progress("speech synthesis 3/8..."),
+            Some((3, 8))
+        );
+        assert_eq!(parse_segment_progress("speech synthesis 1/1"), Some((1, 1)));
+        assert_eq!(parse_segment_progress("speech synthesis 9/8..."), None);
+        assert_eq!(parse_segment_progress("saving audio..."), None);
+        assert_eq!(parse_segment_progress("text processing..."), None);
+    }
+
+    #[tokio::test]
+    async fn test_mock_engine_is_deterministic() {
+        let engine = MockEngine::new(
+            EngineKind::F5,
+            vec![(
+                VoiceDescriptor {
+                    id: "mock".to_string(),
+                    engine: EngineKind::F5,
+                    engine_label: "f5".to_string(),
+                    language: None,
+                    reference_text: Some("reference".to_string()),
+                },
+                vec![PathBuf::from("mock.wav")],
+            )],
+        );
+        let request: TtsRequest = serde_json::from_value(serde_json::json!({
+            "text": "twenty characters!!!",
+            "voice_id": "mock",
+        }))
+        .unwrap();
+
+        let first = engine.synthesize(request.clone()).await.unwrap();
+        let second = engine.synthesize(request).await.unwrap();
+        assert_eq!(first.audio_base64, second.audio_base64);
+        assert_eq!(first.sample_rate, DEFAULT_OUTPUT_SAMPLE_RATE);
+        // 20 chars at 50 ms each.
+        assert_eq!(first.waveform_len, DEFAULT_OUTPUT_SAMPLE_RATE as usize);
+        assert_eq!(first.nfe_step_used, Some(DEFAULT_NFE_STEP));
+        let wav = BASE64.decode(&first.audio_base64).unwrap();
+        assert_eq!(&wav[0..4], b"RIFF");
+
+        let missing: TtsRequest = serde_json::from_value(serde_json::json!({
+            "text": "hi",
+            "voice_id": "nope",
+        }))
+        .unwrap();
+        assert!(engine.synthesize(missing).await.is_err());
+    }
+
+    #[test]
+    fn test_mock_engine_reload_voices() {
+        let engine = MockEngine::new(EngineKind::F5, Vec::new());
+        let profile: VoiceProfileConfig = serde_json::from_value(serde_json::json!({
+            "id": "fresh",
+            "reference_audio": "fresh.wav",
+            "reference_text": "hello",
+        }))
+        .unwrap();
+
+        engine
+            .reload_voices(EngineVoices::F5(vec![profile]))
+            .unwrap();
+        let ids: Vec<String> = engine
+            .voice_descriptors()
+            .into_iter()
+            .map(|voice| voice.id)
+            .collect();
+        assert_eq!(ids, vec!["fresh".to_string()]);
+        assert!(engine
+            .reload_voices(EngineVoices::IndexTts(Vec::new()))
+            .is_err());
+    }
+
+    /// Deterministic stand-in for the Python runtime: a triangle tone plus
+    /// seeded noise at 16 kHz, so resampling runs too. Only basic float ops
+    /// are used, keeping the output identical across platforms.
+    struct StubF5Runtime;
+
+    impl F5Runtime for StubF5Runtime {
+        fn run_infer(
+            &mut self,
+            _voice: &VoiceProfileConfig,
+            text: &str,
+            params: &F5InferParams,
+        ) -> Result<(Vec<f32>, u32)> {
+            let mut state = params.seed.unwrap_or(0) ^ 0x9E37_79B9_7F4A_7C15;
+            let samples = (0..1_600 + text.len() * 10)
+                .map(|i| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    let noise = (state >> 40) as f32 / (1u64 << 24) as f32 - 0.5;
+                    let phase = (i % 80) as f32 / 80.0;
+                    let tone = if phase < 0.5 {
+                        4.0 * phase - 1.0
+                    } else {
+                        3.0 - 4.0 * phase
+                    };
+                    0.5 * tone + 0.1 * noise
+                })
+                .collect();
+            Ok((samples, 16_000))
+        }
+    }
+
+    fn stub_f5_engine() -> F5Engine {
+        let voice = VoiceProfileConfig {
+            id: "stub".to_string(),
+            reference_audio: vec![PathBuf::from("stub.wav")],
+            reference_text: "stub reference".to_string(),
+            language: None,
+            engine_label: None,
+            preload: false,
+        };
+        F5Engine {
+            inner: Arc::new(EngineInner {
+                runtime: Mutex::new(Box::new(StubF5Runtime)),
+                voices: RwLock::new(HashMap::from([(voice.id.clone(), voice)])),
+                default_nfe_step: None,
+                silence_trim_threshold: DEFAULT_SILENCE_TRIM_THRESHOLD,
+                // Keeps the golden file independent of the fade default.
+                fade_ms: 0,
+                bit_depth: BitDepth::Pcm16,
+                output_sample_rate: DEFAULT_OUTPUT_SAMPLE_RATE,
+                max_clip_secs: None,
+                clip_overrun: ClipOverrun::Truncate,
+                device: None,
+            }),
+            pool: SynthesisPool::new("stub", 1).unwrap(),
+        }
+    }
+
+    /// Set `ISHOWTTS_UPDATE_GOLDEN=1` to rewrite the golden file after an
+    /// intentional change to the post-processing chain.
+    #[tokio::test]
+    async fn test_f5_synthesize_pcm_matches_golden() {
+        let engine = stub_f5_engine();
+        let request: TtsRequest = serde_json::from_value(serde_json::json!({
+            "text": "golden sample",
+            "voice_id": "stub",
+            "seed": 42,
+            "normalize_output_rms": 0.1,
+        }))
+        .unwrap();
+
+        let first = engine.synthesize_pcm(request.clone()).await.unwrap();
+        let second = engine.synthesize_pcm(request).await.unwrap();
+        assert_eq!(first, second);
+
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/f5_seed42.f32le");
+        let bytes: Vec<u8> = first.iter().flat_map(|s| s.to_le_bytes()).collect();
+        if std::env::var_os("ISHOWTTS_UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, &bytes).unwrap();
+        }
+        let golden = std::fs::read(&path).unwrap();
+        assert!(bytes == golden, "PCM drifted from {}", path.display());
+    }
+
+    #[tokio::test]
+    async fn test_single_flight_shares_concurrent_work() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let flights = SingleFlight::<u64, u32>::default();
+        let pool = SynthesisPool::new("test", 2).unwrap();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let work = || {
+            let runs = runs.clone();
+            move || {
+                runs.fetch_add(1, Ordering::SeqCst);
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                Ok(7)
+            }
+        };
+
+        let (first, second) =
+            tokio::join!(flights.run(&pool, 1, work()), flights.run(&pool, 1, work()));
+        assert_eq!(first.unwrap(), 7);
+        assert_eq!(second.unwrap(), 7);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        // A finished flight is forgotten; the next caller runs the work again.
+        assert_eq!(flights.run(&pool, 1, work()).await.unwrap(), 7);
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
 }
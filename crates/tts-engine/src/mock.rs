@@ -0,0 +1,230 @@
+use std::{collections::HashMap, f32::consts::TAU, path::PathBuf};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    clip_duration_ms, encode_wav, measure_levels, normalize_rms, params, BitDepth, EngineKind,
+    EngineVoices, F5EngineConfig, IndexTtsEngineConfig, IndexTtsVoiceConfig, ParameterSpec,
+    TtsEngine, TtsEngineError, TtsRequest, TtsResponse, VoiceDescriptor, VoiceOverrideUpdate,
+    VoiceProfileConfig, DEFAULT_NFE_STEP, DEFAULT_OUTPUT_SAMPLE_RATE,
+};
+
+const MOCK_TONE_HZ: f32 = 440.0;
+const MOCK_AMPLITUDE: f32 = 0.3;
+const MOCK_SECS_PER_CHAR: f32 = 0.05;
+const MOCK_MIN_SECS: f32 = 0.2;
+const MOCK_MAX_SECS: f32 = 10.0;
+
+/// Which implementation backs an engine section of the config.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineType {
+    /// The real model, loaded through the embedded Python runtime.
+    #[default]
+    Python,
+    /// [`MockEngine`]: no Python, no GPU.
+    Mock,
+}
+
+#[derive(Clone)]
+struct MockVoice {
+    descriptor: VoiceDescriptor,
+    reference_audio: Vec<PathBuf>,
+}
+
+/// Stands in for an engine in tests. Every request yields a 440 Hz sine whose
+/// length follows the text, so responses are deterministic apart from
+/// `request_id`. Voices, overrides and parameters mirror the engine being
+/// replaced.
+pub struct MockEngine {
+    kind: EngineKind,
+    voices: RwLock<HashMap<String, MockVoice>>,
+}
+
+impl MockEngine {
+    pub fn new(kind: EngineKind, voices: Vec<(VoiceDescriptor, Vec<PathBuf>)>) -> Self {
+        Self {
+            kind,
+            voices: RwLock::new(voice_map(voices)),
+        }
+    }
+
+    /// Impersonates F5 with the voices declared in `config`.
+    pub fn from_f5_config(config: &F5EngineConfig) -> Self {
+        Self::new(EngineKind::F5, f5_voices(&config.voices))
+    }
+
+    /// Impersonates IndexTTS with the voices declared in `config`.
+    pub fn from_index_tts_config(config: &IndexTtsEngineConfig) -> Self {
+        Self::new(EngineKind::IndexTts, index_tts_voices(&config.voices))
+    }
+}
+
+fn voice_map(voices: Vec<(VoiceDescriptor, Vec<PathBuf>)>) -> HashMap<String, MockVoice> {
+    voices
+        .into_iter()
+        .map(|(descriptor, reference_audio)| {
+            (
+                descriptor.id.clone(),
+                MockVoice {
+                    descriptor,
+                    reference_audio,
+                },
+            )
+        })
+        .collect()
+}
+
+fn f5_voices(profiles: &[VoiceProfileConfig]) -> Vec<(VoiceDescriptor, Vec<PathBuf>)> {
+    profiles
+        .iter()
+        .map(|profile| {
+            let descriptor = VoiceDescriptor {
+                id: profile.id.clone(),
+                engine: EngineKind::F5,
+                engine_label: profile
+                    .engine_label
+                    .clone()
+                    .unwrap_or_else(|| EngineKind::F5.as_str().to_string()),
+                language: profile.language.clone(),
+                reference_text: Some(profile.reference_text.clone()),
+            };
+            (descriptor, profile.reference_audio.clone())
+        })
+        .collect()
+}
+
+fn index_tts_voices(configs: &[IndexTtsVoiceConfig]) -> Vec<(VoiceDescriptor, Vec<PathBuf>)> {
+    configs
+        .iter()
+        .map(|voice| {
+            let descriptor = VoiceDescriptor {
+                id: voice.id.clone(),
+                engine: EngineKind::IndexTts,
+                engine_label: voice
+                    .engine_label
+                    .clone()
+                    .unwrap_or_else(|| EngineKind::IndexTts.as_str().to_string()),
+                language: voice.language.clone(),
+                reference_text: voice.reference_text.clone(),
+            };
+            (descriptor, voice.reference_audio.clone())
+        })
+        .collect()
+}
+
+/// The mock waveform for `text`: a sine of 50 ms per character, clamped to
+/// 0.2–10 seconds.
+fn mock_waveform(text: &str) -> Vec<f32> {
+    let secs =
+        (text.chars().count() as f32 * MOCK_SECS_PER_CHAR).clamp(MOCK_MIN_SECS, MOCK_MAX_SECS);
+    let len = (secs * DEFAULT_OUTPUT_SAMPLE_RATE as f32) as usize;
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / DEFAULT_OUTPUT_SAMPLE_RATE as f32;
+            MOCK_AMPLITUDE * (TAU * MOCK_TONE_HZ * t).sin()
+        })
+        .collect()
+}
+
+#[async_trait]
+impl TtsEngine for MockEngine {
+    fn kind(&self) -> EngineKind {
+        self.kind
+    }
+
+    fn voice_descriptors(&self) -> Vec<VoiceDescriptor> {
+        self.voices
+            .read()
+            .values()
+            .map(|voice| voice.descriptor.clone())
+            .collect()
+    }
+
+    async fn synthesize(&self, request: TtsRequest) -> Result<TtsResponse> {
+        let voice = self
+            .voices
+            .read()
+            .get(&request.voice_id)
+            .cloned()
+            .ok_or_else(|| TtsEngineError::VoiceNotFound(request.voice_id.clone()))?;
+
+        let mut samples = mock_waveform(&request.text);
+        if let Some(target) = request.normalize_output_rms {
+            normalize_rms(&mut samples, target);
+        }
+        let (peak, rms) = measure_levels(&samples);
+        let wav_bytes = encode_wav(&samples, DEFAULT_OUTPUT_SAMPLE_RATE, 1, BitDepth::Pcm16)?;
+
+        Ok(TtsResponse {
+            request_id: Uuid::new_v4(),
+            sample_rate: DEFAULT_OUTPUT_SAMPLE_RATE,
+            audio_base64: BASE64.encode(&wav_bytes),
+            waveform_len: samples.len(),
+            duration_ms: clip_duration_ms(samples.len(), DEFAULT_OUTPUT_SAMPLE_RATE),
+            voice_id: voice.descriptor.id.clone(),
+            engine: self.kind,
+            engine_label: voice.descriptor.engine_label.clone(),
+            reference_text_used: voice.descriptor.reference_text.clone(),
+            nfe_step_used: self
+                .default_nfe_step()
+                .map(|default| request.nfe_step.unwrap_or(default)),
+            peak,
+            rms,
+            fallback_used: false,
+            cache_hit: false,
+            segment_timings: None,
+        })
+    }
+
+    fn apply_override(&self, voice_id: &str, update: VoiceOverrideUpdate) -> Result<()> {
+        let mut voices = self.voices.write();
+        let voice = voices
+            .get_mut(voice_id)
+            .ok_or_else(|| TtsEngineError::VoiceNotFound(voice_id.to_string()))?;
+        if let Some(audio) = update.reference_audio {
+            voice.reference_audio = audio;
+        }
+        if let Some(text) = update.reference_text {
+            voice.descriptor.reference_text = Some(text);
+        }
+        Ok(())
+    }
+
+    fn resolve_reference(&self, voice_id: &str) -> Option<(Vec<PathBuf>, Option<String>)> {
+        self.voices.read().get(voice_id).map(|voice| {
+            (
+                voice.reference_audio.clone(),
+                voice.descriptor.reference_text.clone(),
+            )
+        })
+    }
+
+    fn default_nfe_step(&self) -> Option<u32> {
+        (self.kind == EngineKind::F5).then_some(DEFAULT_NFE_STEP)
+    }
+
+    fn parameters(&self) -> Vec<ParameterSpec> {
+        match self.kind {
+            EngineKind::F5 => params::f5_parameters(DEFAULT_NFE_STEP),
+            EngineKind::IndexTts => params::index_tts_parameters(),
+            EngineKind::Shimmy => Vec::new(),
+        }
+    }
+
+    fn reload_voices(&self, voices: EngineVoices) -> Result<()> {
+        let voices = match (self.kind, voices) {
+            (EngineKind::F5, EngineVoices::F5(profiles)) => f5_voices(&profiles),
+            (EngineKind::IndexTts, EngineVoices::IndexTts(configs)) => index_tts_voices(&configs),
+            (kind, _) => anyhow::bail!("mock {kind} engine got voices for another engine"),
+        };
+        *self.voices.write() = voice_map(voices);
+        Ok(())
+    }
+}
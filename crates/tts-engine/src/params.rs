@@ -0,0 +1,135 @@
+use serde::Serialize;
+
+/// Value type of a [`TtsRequest`](crate::TtsRequest) tuning knob.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParameterKind {
+    Float,
+    Integer,
+    Boolean,
+}
+
+/// Describes one request field an engine honors, so clients can render
+/// controls without hardcoding per-engine knowledge.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ParameterSpec {
+    /// Field name in the synthesis request.
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub kind: ParameterKind,
+    /// Value used when the field is omitted; `None` means "unset".
+    pub default: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    pub description: &'static str,
+}
+
+impl ParameterSpec {
+    const fn float(
+        name: &'static str,
+        default: Option<f64>,
+        min: f64,
+        max: f64,
+        description: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            kind: ParameterKind::Float,
+            default,
+            min: Some(min),
+            max: Some(max),
+            description,
+        }
+    }
+
+    const fn boolean(name: &'static str, default: bool, description: &'static str) -> Self {
+        Self {
+            name,
+            kind: ParameterKind::Boolean,
+            default: Some(if default { 1.0 } else { 0.0 }),
+            min: None,
+            max: None,
+            description,
+        }
+    }
+}
+
+const REMOVE_SILENCE: ParameterSpec =
+    ParameterSpec::boolean("remove_silence", false, "Trim silence from the output");
+
+const NORMALIZE_OUTPUT_RMS: ParameterSpec = ParameterSpec::float(
+    "normalize_output_rms",
+    None,
+    0.01,
+    0.5,
+    "Scale the finished audio to this RMS level",
+);
+
+/// Knobs F5-TTS reads from a request. `default_nfe_step` comes from the
+/// engine config, so it is filled in per instance.
+pub(crate) fn f5_parameters(default_nfe_step: u32) -> Vec<ParameterSpec> {
+    vec![
+        ParameterSpec::float("speed", Some(1.0), 0.3, 2.0, "Speaking rate multiplier"),
+        ParameterSpec::float(
+            "target_rms",
+            Some(0.1),
+            0.01,
+            0.5,
+            "Loudness the reference clip is normalized to",
+        ),
+        ParameterSpec::float(
+            "cross_fade_duration",
+            Some(0.15),
+            0.0,
+            1.0,
+            "Seconds of cross-fade between generated batches",
+        ),
+        ParameterSpec::float(
+            "sway_sampling_coef",
+            Some(-1.0),
+            -1.0,
+            1.0,
+            "Sway sampling coefficient for the ODE solver",
+        ),
+        ParameterSpec::float(
+            "cfg_strength",
+            Some(2.0),
+            0.0,
+            10.0,
+            "Classifier-free guidance strength",
+        ),
+        ParameterSpec {
+            name: "nfe_step",
+            kind: ParameterKind::Integer,
+            default: Some(f64::from(default_nfe_step)),
+            min: Some(1.0),
+            max: Some(64.0),
+            description: "Diffusion steps; fewer is faster, more is cleaner",
+        },
+        ParameterSpec::float(
+            "fix_duration",
+            None,
+            0.5,
+            60.0,
+            "Force the total duration in seconds (reference included)",
+        ),
+        REMOVE_SILENCE,
+        ParameterSpec {
+            name: "seed",
+            kind: ParameterKind::Integer,
+            default: None,
+            min: Some(0.0),
+            max: None,
+            description: "Random seed for reproducible output",
+        },
+        NORMALIZE_OUTPUT_RMS,
+    ]
+}
+
+/// IndexTTS clones from the reference clip alone, so only the post-processing
+/// knobs apply.
+pub(crate) fn index_tts_parameters() -> Vec<ParameterSpec> {
+    vec![REMOVE_SILENCE, NORMALIZE_OUTPUT_RMS]
+}
@@ -0,0 +1,58 @@
+use std::{collections::HashMap, hash::Hash, sync::Arc};
+
+use anyhow::Result;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use parking_lot::Mutex;
+
+use crate::synthesis_pool::SynthesisPool;
+
+/// Outcome shared by every caller of one flight. Errors are behind an `Arc`
+/// because `Shared` needs a cloneable output.
+pub(crate) type Flight<V> = Shared<BoxFuture<'static, Result<V, Arc<anyhow::Error>>>>;
+
+/// Collapses concurrent blocking work for the same key into one run: callers
+/// that arrive while a key is in flight await the first caller's result.
+/// A key is forgotten as soon as its work finishes, so this never serves
+/// stale results; pair it with a cache for that.
+pub(crate) struct SingleFlight<K, V> {
+    in_flight: Arc<Mutex<HashMap<K, Flight<V>>>>,
+}
+
+impl<K, V> Default for SingleFlight<K, V> {
+    fn default() -> Self {
+        Self {
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<K, V> SingleFlight<K, V>
+where
+    K: Clone + Eq + Hash + Send + 'static,
+    V: Clone + Send + Sync + 'static,
+{
+    /// Joins the flight for `key`, starting `work` on `pool` when none is
+    /// running. `work` is dropped unused when a flight already exists.
+    pub(crate) fn run<F>(&self, pool: &SynthesisPool, key: K, work: F) -> Flight<V>
+    where
+        F: FnOnce() -> Result<V> + Send + 'static,
+    {
+        let mut in_flight = self.in_flight.lock();
+        if let Some(flight) = in_flight.get(&key) {
+            return flight.clone();
+        }
+
+        let registry = self.in_flight.clone();
+        let flight_key = key.clone();
+        let pool = pool.clone();
+        let flight = async move {
+            let outcome = pool.run(work).await;
+            registry.lock().remove(&flight_key);
+            outcome.map_err(Arc::new)
+        }
+        .boxed()
+        .shared();
+        in_flight.insert(key, flight.clone());
+        flight
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use hound::{SampleFormat, WavReader};
+use parking_lot::Mutex;
+
+use crate::{
+    downmix_to_mono, encode_wav, measure_levels, resample_interleaved, resample_linear, BitDepth,
+};
+
+/// Several WAV clips joined into one.
+#[derive(Clone, Debug)]
+pub struct StitchedAudio {
+    pub wav: Vec<u8>,
+    pub sample_rate: u32,
+    /// Length in frames (samples per channel).
+    pub frames: usize,
+    pub peak: f32,
+    pub rms: f32,
+}
+
+/// Joins WAV clips in order. A positive `gap_ms` inserts that much silence
+/// between clips; with no gap, neighbours overlap by `crossfade_ms` and are
+/// crossfaded linearly. Clips are converted to the first clip's sample rate
+/// and channel count, so clips from engines with different output formats
+/// can be joined.
+pub fn stitch_wav(clips: &[Vec<u8>], gap_ms: u32, crossfade_ms: u32) -> Result<StitchedAudio> {
+    let Some((first, rest)) = clips.split_first() else {
+        bail!("no clips to stitch");
+    };
+    let (mut joined, sample_rate, channels) = read_wav(first)?;
+    let bit_depth = WavReader::new(Cursor::new(first.as_slice()))?
+        .spec()
+        .bits_per_sample;
+    let bit_depth = BitDepth::try_from(bit_depth).unwrap_or_default();
+    let frame_len = usize::from(channels);
+    let gap = (u64::from(sample_rate) * u64::from(gap_ms) / 1000) as usize * frame_len;
+    let overlap = (u64::from(sample_rate) * u64::from(crossfade_ms) / 1000) as usize;
+
+    for clip in rest {
+        let (mut samples, rate, clip_channels) = read_wav(clip)?;
+        if clip_channels != channels {
+            let mono = downmix_to_mono(&samples, clip_channels);
+            samples = Vec::with_capacity(mono.len() * frame_len);
+            for sample in mono {
+                samples.resize(samples.len() + frame_len, sample);
+            }
+        }
+        if rate != sample_rate {
+            samples = resample_interleaved(&samples, channels, rate, sample_rate);
+        }
+
+        if gap > 0 {
+            joined.resize(joined.len() + gap, 0.0);
+            joined.extend_from_slice(&samples);
+            continue;
+        }
+        let overlap_frames = overlap
+            .min(joined.len() / frame_len)
+            .min(samples.len() / frame_len);
+        let start = joined.len() - overlap_frames * frame_len;
+        for frame in 0..overlap_frames {
+            let gain = (frame + 1) as f32 / (overlap_frames + 1) as f32;
+            for channel in 0..frame_len {
+                let index = frame * frame_len + channel;
+                let tail = &mut joined[start + index];
+                *tail = *tail * (1.0 - gain) + samples[index] * gain;
+            }
+        }
+        joined.extend_from_slice(&samples[overlap_frames * frame_len..]);
+    }
+
+    let (peak, rms) = measure_levels(&joined);
+    Ok(StitchedAudio {
+        wav: encode_wav(&joined, sample_rate, channels, bit_depth)?,
+        sample_rate,
+        frames: joined.len() / frame_len,
+        peak,
+        rms,
+    })
+}
+
+/// A short sound played around other clips, such as a chime before each
+/// chat readout. Decoded to mono once and resampled once per output rate.
+pub struct Stinger {
+    samples: Vec<f32>,
+    sample_rate: u32,
+    resampled: Mutex<HashMap<u32, Arc<Vec<f32>>>>,
+}
+
+impl Stinger {
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes =
+            std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        Self::from_wav(&bytes).with_context(|| format!("{} is not a usable WAV", path.display()))
+    }
+
+    pub fn from_wav(bytes: &[u8]) -> Result<Self> {
+        let (samples, sample_rate, channels) = read_wav(bytes)?;
+        let frame_len = usize::from(channels);
+        let samples: Vec<f32> = samples
+            .chunks(frame_len)
+            .map(|frame| frame.iter().sum::<f32>() / frame_len as f32)
+            .collect();
+        if samples.is_empty() {
+            bail!("stinger has no audio");
+        }
+        Ok(Self {
+            samples,
+            sample_rate,
+            resampled: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Mono samples at `sample_rate`.
+    pub fn samples_at(&self, sample_rate: u32) -> Arc<Vec<f32>> {
+        self.resampled
+            .lock()
+            .entry(sample_rate)
+            .or_insert_with(|| {
+                Arc::new(if sample_rate == self.sample_rate {
+                    self.samples.clone()
+                } else {
+                    resample_linear(&self.samples, self.sample_rate, sample_rate)
+                })
+            })
+            .clone()
+    }
+}
+
+/// `clip` with `prefix` played before it and `suffix` after it, keeping the
+/// clip's sample rate, channel count and bit depth.
+pub fn wrap_wav(
+    clip: &[u8],
+    prefix: Option<&Stinger>,
+    suffix: Option<&Stinger>,
+) -> Result<StitchedAudio> {
+    let (samples, sample_rate, channels) = read_wav(clip)?;
+    let bit_depth = WavReader::new(Cursor::new(clip))?.spec().bits_per_sample;
+    let bit_depth = BitDepth::try_from(bit_depth).unwrap_or_default();
+    let frame_len = usize::from(channels);
+    let interleave = |stinger: &Stinger, out: &mut Vec<f32>| {
+        for sample in stinger.samples_at(sample_rate).iter() {
+            out.resize(out.len() + frame_len, *sample);
+        }
+    };
+
+    let mut joined = Vec::with_capacity(samples.len());
+    if let Some(prefix) = prefix {
+        interleave(prefix, &mut joined);
+    }
+    joined.extend_from_slice(&samples);
+    if let Some(suffix) = suffix {
+        interleave(suffix, &mut joined);
+    }
+
+    let (peak, rms) = measure_levels(&joined);
+    Ok(StitchedAudio {
+        wav: encode_wav(&joined, sample_rate, channels, bit_depth)?,
+        sample_rate,
+        frames: joined.len() / frame_len,
+        peak,
+        rms,
+    })
+}
+
+/// Decodes a WAV clip to interleaved `f32` samples.
+fn read_wav(bytes: &[u8]) -> Result<(Vec<f32>, u32, u16)> {
+    let reader = WavReader::new(Cursor::new(bytes))?;
+    let spec = reader.spec();
+    let samples = match spec.sample_format {
+        SampleFormat::Float => reader
+            .into_samples::<f32>()
+            .collect::<Result<Vec<_>, _>>()?,
+        SampleFormat::Int => {
+            let scale = (1_i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .into_samples::<i32>()
+                .map(|sample| sample.map(|value| value as f32 / scale))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
+    Ok((samples, spec.sample_rate, spec.channels.max(1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stitch_wav() {
+        let first = encode_wav(&vec![0.5_f32; 1_000], 1_000, 1, BitDepth::Pcm16).unwrap();
+        let second = encode_wav(&vec![-0.5_f32; 500], 1_000, 1, BitDepth::Pcm16).unwrap();
+
+        // 100 ms of silence at 1 kHz between the clips.
+        let gapped = stitch_wav(&[first.clone(), second.clone()], 100, 0).unwrap();
+        assert_eq!(gapped.frames, 1_600);
+        assert_eq!(gapped.sample_rate, 1_000);
+
+        // A 50 ms crossfade overlaps 50 frames instead.
+        let crossfaded = stitch_wav(&[first.clone(), second], 0, 50).unwrap();
+        assert_eq!(crossfaded.frames, 1_450);
+        assert!((crossfaded.peak - 0.5).abs() < 1e-3);
+
+        assert!(stitch_wav(&[], 0, 0).is_err());
+    }
+
+    #[test]
+    fn test_stitch_wav_converts_to_the_first_clip_format() {
+        let mono = encode_wav(&vec![0.5_f32; 1_000], 1_000, 1, BitDepth::Pcm16).unwrap();
+        // 100 frames of left 0.5 / right -0.5 at 2 kHz.
+        let stereo: Vec<f32> = (0..200)
+            .map(|index| if index % 2 == 0 { 0.5 } else { -0.5 })
+            .collect();
+        let stereo = encode_wav(&stereo, 2_000, 2, BitDepth::Pcm16).unwrap();
+
+        // The stereo clip is downmixed to silence and halved to 50 frames.
+        let joined = stitch_wav(&[mono.clone(), stereo.clone()], 0, 0).unwrap();
+        assert_eq!((joined.sample_rate, joined.frames), (1_000, 1_050));
+
+        // Mono audio is copied to both channels of a stereo session.
+        let joined = stitch_wav(&[stereo, mono], 0, 0).unwrap();
+        assert_eq!((joined.sample_rate, joined.frames), (2_000, 2_100));
+        assert!((joined.peak - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_wrap_wav_with_stingers() {
+        let clip = encode_wav(&vec![0.25_f32; 400], 1_000, 2, BitDepth::Pcm16).unwrap();
+        let chime = encode_wav(&vec![0.5_f32; 200], 2_000, 1, BitDepth::Pcm16).unwrap();
+        let chime = Stinger::from_wav(&chime).unwrap();
+
+        // 200 frames at 2 kHz become 100 at the clip's 1 kHz, on both channels.
+        let wrapped = wrap_wav(&clip, Some(&chime), Some(&chime)).unwrap();
+        assert_eq!(wrapped.sample_rate, 1_000);
+        assert_eq!(wrapped.frames, 400);
+        assert!((wrapped.peak - 0.5).abs() < 1e-3);
+
+        let bare = wrap_wav(&clip, None, None).unwrap();
+        assert_eq!(bare.frames, 200);
+        assert!(Stinger::from_wav(b"not a wav").is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc},
+    thread,
+};
+
+use anyhow::{anyhow, Context, Result};
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Dedicated threads for an engine's blocking synthesis work. Model calls
+/// are serialized by the engine's runtime lock anyway, so running them on
+/// tokio's shared blocking pool only parks extra threads there and starves
+/// unrelated blocking tasks such as reference-audio reads.
+#[derive(Clone)]
+pub(crate) struct SynthesisPool {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl SynthesisPool {
+    /// Starts `threads` workers (at least one) named after `name`. They exit
+    /// once every handle to the pool is dropped.
+    pub(crate) fn new(name: &str, threads: usize) -> Result<Self> {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let queue = Arc::new(Mutex::new(queue));
+        for index in 0..threads.max(1) {
+            let queue = queue.clone();
+            thread::Builder::new()
+                .name(format!("{name}-synth-{index}"))
+                .spawn(move || loop {
+                    // The lock is released before the job runs.
+                    let job = queue.lock().recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => break,
+                    }
+                })
+                .with_context(|| format!("failed to start {name} synthesis thread"))?;
+        }
+        Ok(Self { jobs })
+    }
+
+    /// Runs `work` on the pool and waits for its result without blocking
+    /// the async runtime. A panic in `work` becomes an error and leaves the
+    /// worker alive.
+    pub(crate) async fn run<F, T>(&self, work: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (done, result) = oneshot::channel();
+        let job: Job = Box::new(move || {
+            let outcome = panic::catch_unwind(AssertUnwindSafe(work))
+                .unwrap_or_else(|_| Err(anyhow!("synthesis thread panicked")));
+            let _ = done.send(outcome);
+        });
+        self.jobs
+            .send(job)
+            .map_err(|_| anyhow!("synthesis pool has shut down"))?;
+        result
+            .await
+            .map_err(|_| anyhow!("synthesis pool dropped the job"))?
+    }
+}